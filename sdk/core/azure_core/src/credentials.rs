@@ -4,8 +4,13 @@
 //! Azure authentication and authorization.
 
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, fmt::Debug};
-use typespec_client_core::{fmt::SafeDebug, http::ClientMethodOptions, time::OffsetDateTime};
+use std::{borrow::Cow, fmt::Debug, sync::Arc};
+use typespec_client_core::{
+    fmt::SafeDebug,
+    http::{ClientMethodOptions, Method, Url},
+    time::{Duration, OffsetDateTime},
+    Value,
+};
 
 /// Represents a secret.
 ///
@@ -71,6 +76,11 @@ pub struct AccessToken {
     pub token: Secret,
     /// Gets the time when the provided token expires.
     pub expires_on: OffsetDateTime,
+    /// Gets the time when the token should be refreshed, if the token's issuer recommended one.
+    ///
+    /// When `None`, callers should refresh the token shortly before it expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_on: Option<OffsetDateTime>,
 }
 
 impl AccessToken {
@@ -82,8 +92,16 @@ impl AccessToken {
         Self {
             token: token.into(),
             expires_on,
+            refresh_on: None,
         }
     }
+
+    /// Returns the time at which the token should be refreshed: [`AccessToken::refresh_on`] if set,
+    /// otherwise 5 minutes before the token expires.
+    pub fn refresh_time(&self) -> OffsetDateTime {
+        self.refresh_on
+            .unwrap_or(self.expires_on - Duration::minutes(5))
+    }
 }
 
 /// Options for getting a token from a [`TokenCredential`]
@@ -91,6 +109,44 @@ impl AccessToken {
 pub struct TokenRequestOptions<'a> {
     /// Method options to be used when requesting a token.
     pub method_options: ClientMethodOptions<'a>,
+
+    /// Requests a proof-of-possession (PoP) token bound to an HTTP request instead of a bearer token.
+    ///
+    /// Credentials supporting PoP return a signed HTTP request (SHR), which callers send in an
+    /// `Authorization: PoP` header. Credentials that don't support PoP return an error.
+    pub pop: Option<PopTokenRequestOptions>,
+}
+
+/// Describes the HTTP request a proof-of-possession (PoP) token authorizes.
+#[derive(Clone, Debug)]
+pub struct PopTokenRequestOptions {
+    /// The key to which the token is bound and which signs the SHR.
+    pub key: Arc<dyn PopKey>,
+
+    /// The request's HTTP method.
+    pub method: Method,
+
+    /// The request's URL.
+    pub url: Url,
+
+    /// The nonce the resource server sent in its `WWW-Authenticate: PoP` challenge, if any.
+    pub nonce: Option<String>,
+}
+
+/// A key pair to which proof-of-possession (PoP) tokens are bound.
+pub trait PopKey: Send + Sync + Debug {
+    /// The key's ID, which is typically the base64url encoded SHA-256 thumbprint of its public
+    /// key (RFC 7638).
+    fn key_id(&self) -> &str;
+
+    /// The public key as a JSON Web Key (RFC 7517).
+    fn jwk(&self) -> Value;
+
+    /// The JWS algorithm with which [`PopKey::sign`] signs, for example "RS256".
+    fn algorithm(&self) -> &str;
+
+    /// Signs `data` with the private key.
+    fn sign(&self, data: &[u8]) -> crate::Result<Vec<u8>>;
 }
 
 /// Represents a credential capable of providing an OAuth token.
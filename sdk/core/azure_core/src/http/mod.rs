@@ -5,6 +5,7 @@
 
 pub mod headers;
 mod models;
+pub mod multipart;
 mod options;
 pub mod pager;
 mod pipeline;
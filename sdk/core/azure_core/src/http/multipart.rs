@@ -0,0 +1,301 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Encoding and decoding of `multipart/mixed` bodies, which batch operations use to carry several HTTP messages.
+
+use crate::{
+    error::{Error, ErrorKind},
+    http::{
+        headers::{HeaderName, HeaderValue, Headers},
+        Body, RawResponse, Request, StatusCode,
+    },
+    Bytes, Result,
+};
+
+const CRLF: &[u8] = b"\r\n";
+
+/// A part of a `multipart/mixed` body.
+#[derive(Clone, Debug, Default)]
+pub struct Part {
+    /// The headers of the part, such as `Content-Type` and `Content-ID`.
+    pub headers: Headers,
+
+    /// The content of the part.
+    pub body: Bytes,
+}
+
+impl Part {
+    /// Creates an `application/http` part containing `request`, as sent in batch requests.
+    ///
+    /// The request line contains only the path and query of the request URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request body is a stream rather than bytes.
+    pub fn from_request(request: &Request) -> Result<Self> {
+        let Body::Bytes(body) = request.body() else {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "a multipart request must have a body of bytes",
+            ));
+        };
+        let mut message = format!(
+            "{} {} HTTP/1.1\r\n",
+            request.method(),
+            request.path_and_query()
+        );
+        for (name, value) in request.headers().iter() {
+            if name.as_str() != "content-length" {
+                message.push_str(&format!("{}: {}\r\n", name.as_str(), value.as_str()));
+            }
+        }
+        message.push_str(&format!("content-length: {}\r\n\r\n", body.len()));
+        let mut message = message.into_bytes();
+        message.extend_from_slice(body);
+
+        let mut headers = Headers::new();
+        headers.insert("content-type", "application/http");
+        headers.insert("content-transfer-encoding", "binary");
+        Ok(Self {
+            headers,
+            body: message.into(),
+        })
+    }
+
+    /// Parses the `application/http` response this part contains, as returned for batch requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the part doesn't contain an HTTP response.
+    pub fn to_response(&self) -> Result<RawResponse> {
+        let (head, body) = split_head(&self.body);
+        let head =
+            std::str::from_utf8(head).map_err(|_| invalid("response headers aren't UTF-8"))?;
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| {
+                let mut fields = line.split_whitespace();
+                fields
+                    .next()
+                    .filter(|version| version.starts_with("HTTP/"))?;
+                fields.next()?.parse::<u16>().ok()
+            })
+            .ok_or_else(|| invalid("part doesn't start with an HTTP status line"))?;
+        let headers = parse_headers(lines)?;
+        Ok(RawResponse::from_bytes(
+            StatusCode::from(status),
+            headers,
+            Bytes::copy_from_slice(body),
+        ))
+    }
+}
+
+/// Returns the `Content-Type` of a `multipart/mixed` body with `boundary`.
+pub fn content_type(boundary: &str) -> String {
+    format!("multipart/mixed; boundary={boundary}")
+}
+
+/// Encodes `parts` as a `multipart/mixed` body delimited by `boundary`.
+pub fn encode(boundary: &str, parts: &[Part]) -> Bytes {
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        for (name, value) in part.headers.iter() {
+            body.extend_from_slice(format!("{}: {}\r\n", name.as_str(), value.as_str()).as_bytes());
+        }
+        body.extend_from_slice(CRLF);
+        body.extend_from_slice(&part.body);
+        body.extend_from_slice(CRLF);
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    body.into()
+}
+
+/// Decodes a `multipart/mixed` body with the given `Content-Type`, which names its boundary.
+///
+/// # Errors
+///
+/// Returns an error if `content_type` has no boundary or `body` isn't delimited by it.
+pub fn decode(content_type: &str, body: &[u8]) -> Result<Vec<Part>> {
+    let boundary = content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|parameter| parameter.trim().split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+        .filter(|boundary| !boundary.is_empty())
+        .ok_or_else(|| invalid(format!("{content_type} has no boundary")))?;
+    let delimiter = format!("--{boundary}");
+    let delimiter = delimiter.as_bytes();
+
+    let mut position =
+        find(body, delimiter, 0).ok_or_else(|| invalid("body doesn't contain the boundary"))?;
+    let mut parts = Vec::new();
+    loop {
+        let rest = &body[position + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        // Skip the rest of the delimiter line, which may contain whitespace.
+        let start = position + delimiter.len() + line_end(rest).unwrap_or(rest.len());
+        let next = find(body, delimiter, start)
+            .ok_or_else(|| invalid("body doesn't end with the closing boundary"))?;
+        // The line break preceding a delimiter belongs to it.
+        let mut content = &body[start..next];
+        content = content
+            .strip_suffix(CRLF)
+            .or_else(|| content.strip_suffix(b"\n"))
+            .unwrap_or(content);
+
+        let (head, part_body) = split_head(content);
+        let head = std::str::from_utf8(head).map_err(|_| invalid("part headers aren't UTF-8"))?;
+        parts.push(Part {
+            headers: parse_headers(head.lines())?,
+            body: Bytes::copy_from_slice(part_body),
+        });
+        position = next;
+    }
+}
+
+/// Splits an HTTP message or part into its head and body at the first empty line.
+fn split_head(message: &[u8]) -> (&[u8], &[u8]) {
+    if let Some(i) = find(message, b"\r\n\r\n", 0) {
+        return (&message[..i], &message[i + 4..]);
+    }
+    if let Some(i) = find(message, b"\n\n", 0) {
+        return (&message[..i], &message[i + 2..]);
+    }
+    (message, &[])
+}
+
+fn parse_headers<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Headers> {
+    let mut headers = Headers::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid(format!("invalid header {line}")))?;
+        headers.insert(
+            HeaderName::from(name.trim().to_string()),
+            HeaderValue::from(value.trim().to_string()),
+        );
+    }
+    Ok(headers)
+}
+
+/// Returns the index following the end of the first line of `data`.
+fn line_end(data: &[u8]) -> Option<usize> {
+    data.iter().position(|&b| b == b'\n').map(|i| i + 1)
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| i + from)
+}
+
+fn invalid(message: impl Into<std::borrow::Cow<'static, str>>) -> Error {
+    Error::with_message(ErrorKind::DataConversion, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Method, Url};
+
+    #[test]
+    fn encode_request() {
+        let mut request = Request::new(
+            Url::parse("https://account.blob.core.windows.net/container/blob?timeout=30").unwrap(),
+            Method::Delete,
+        );
+        request.insert_header("x-ms-date", "Thu, 14 Jun 2018 16:46:54 GMT");
+        let mut part = Part::from_request(&request).unwrap();
+        part.headers.insert("content-id", "0");
+
+        let body = encode("batch_1", &[part]);
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.starts_with("--batch_1\r\n"));
+        assert!(body.contains("content-type: application/http\r\n"));
+        assert!(body.contains("content-id: 0\r\n"));
+        assert!(body.contains(
+            "\r\n\r\nDELETE /container/blob?timeout=30 HTTP/1.1\r\nx-ms-date: Thu, 14 Jun 2018 16:46:54 GMT\r\ncontent-length: 0\r\n\r\n\r\n--batch_1--\r\n"
+        ));
+    }
+
+    #[test]
+    fn decode_responses() {
+        let body = "--batchresponse_1\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: 0\r\n\
+            \r\n\
+            HTTP/1.1 202 Accepted\r\n\
+            x-ms-delete-type-permanent: true\r\n\
+            \r\n\
+            \r\n\
+            --batchresponse_1\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: 1\r\n\
+            \r\n\
+            HTTP/1.1 404 The specified blob does not exist.\r\n\
+            x-ms-error-code: BlobNotFound\r\n\
+            Content-Type: application/xml\r\n\
+            \r\n\
+            <?xml version=\"1.0\" encoding=\"utf-8\"?>\r\n<Error><Code>BlobNotFound</Code></Error>\r\n\
+            --batchresponse_1--\r\n";
+
+        let parts = decode(
+            "multipart/mixed; boundary=\"batchresponse_1\"",
+            body.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(2, parts.len());
+        assert_eq!("1", parts[1].headers.get_str(&"content-id".into()).unwrap());
+
+        let response = parts[0].to_response().unwrap();
+        assert_eq!(StatusCode::Accepted, response.status());
+        assert_eq!(
+            "true",
+            response
+                .headers()
+                .get_str(&"x-ms-delete-type-permanent".into())
+                .unwrap()
+        );
+        assert!(response.body().is_empty());
+
+        let response = parts[1].to_response().unwrap();
+        assert_eq!(StatusCode::NotFound, response.status());
+        assert_eq!(
+            b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\r\n<Error><Code>BlobNotFound</Code></Error>",
+            response.body().as_ref()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut headers = Headers::new();
+        headers.insert("content-id", "7");
+        let parts = vec![
+            Part {
+                headers,
+                body: Bytes::from_static(b"first\r\nline"),
+            },
+            Part::default(),
+        ];
+        let body = encode("b", &parts);
+        let decoded = decode(&content_type("b"), &body).unwrap();
+        assert_eq!(2, decoded.len());
+        assert_eq!(parts[0].headers, decoded[0].headers);
+        assert_eq!(parts[0].body, decoded[0].body);
+        assert!(decoded[1].body.is_empty());
+    }
+
+    #[test]
+    fn invalid_bodies() {
+        assert!(decode("multipart/mixed", b"--b--").is_err());
+        assert!(decode(&content_type("b"), b"no boundary").is_err());
+        assert!(decode(&content_type("b"), b"--b\r\n\r\npart").is_err());
+        assert!(Part::default().to_response().is_err());
+    }
+}
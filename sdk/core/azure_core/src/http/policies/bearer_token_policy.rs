@@ -13,7 +13,7 @@ use async_lock::RwLock;
 use async_trait::async_trait;
 use std::sync::Arc;
 use typespec_client_core::http::{ClientMethodOptions, Context, Request};
-use typespec_client_core::time::{Duration, OffsetDateTime};
+use typespec_client_core::time::OffsetDateTime;
 
 /// Authentication policy for a bearer token.
 #[derive(Debug, Clone)]
@@ -71,6 +71,7 @@ impl Policy for BearerTokenAuthorizationPolicy {
                         method_options: ClientMethodOptions {
                             context: ctx.clone(),
                         },
+                        pop: None,
                     };
                     *access_token = Some(
                         self.credential
@@ -79,7 +80,7 @@ impl Policy for BearerTokenAuthorizationPolicy {
                     );
                 }
             }
-            Some(token) if should_refresh(&token.expires_on) => {
+            Some(token) if should_refresh(token) => {
                 // token is expired or within its refresh window. Upgrade the lock and
                 // acquire a new token, provided another thread hasn't already done so
                 let expires_on = token.expires_on;
@@ -92,6 +93,7 @@ impl Policy for BearerTokenAuthorizationPolicy {
                         method_options: ClientMethodOptions {
                             context: ctx.clone(),
                         },
+                        pop: None,
                     };
                     match self
                         .credential
@@ -131,8 +133,8 @@ impl Policy for BearerTokenAuthorizationPolicy {
     }
 }
 
-fn should_refresh(expires_on: &OffsetDateTime) -> bool {
-    *expires_on <= OffsetDateTime::now_utc() + Duration::minutes(5)
+fn should_refresh(token: &AccessToken) -> bool {
+    token.refresh_time() <= OffsetDateTime::now_utc()
 }
 
 #[cfg(test)]
@@ -281,20 +283,40 @@ mod tests {
         run_test(&[AccessToken {
             token: Secret::new("fake".to_string()),
             expires_on: OffsetDateTime::now_utc() + Duration::seconds(3600),
+            refresh_on: None,
         }])
         .await;
     }
 
+    #[tokio::test]
+    async fn refreshes_token_at_refresh_on() {
+        run_test(&[
+            AccessToken {
+                token: Secret::new("1".to_string()),
+                expires_on: OffsetDateTime::now_utc() + Duration::seconds(3600),
+                refresh_on: Some(OffsetDateTime::now_utc() - Duration::seconds(1)),
+            },
+            AccessToken {
+                token: Secret::new("2".to_string()),
+                expires_on: OffsetDateTime::now_utc() + Duration::seconds(3600),
+                refresh_on: Some(OffsetDateTime::now_utc() + Duration::seconds(1800)),
+            },
+        ])
+        .await;
+    }
+
     #[tokio::test]
     async fn refreshes_token() {
         run_test(&[
             AccessToken {
                 token: Secret::new("1".to_string()),
                 expires_on: OffsetDateTime::now_utc() - Duration::seconds(1),
+                refresh_on: None,
             },
             AccessToken {
                 token: Secret::new("2".to_string()),
                 expires_on: OffsetDateTime::now_utc() + Duration::seconds(3600),
+                refresh_on: None,
             },
         ])
         .await;
@@ -6,10 +6,12 @@
 mod bearer_token_policy;
 mod client_request_id;
 mod instrumentation;
+mod pop_token_policy;
 mod user_agent;
 
 pub use bearer_token_policy::BearerTokenAuthorizationPolicy;
 pub use client_request_id::*;
 pub use instrumentation::*;
+pub use pop_token_policy::PopTokenAuthorizationPolicy;
 pub use typespec_client_core::http::policies::*;
 pub use user_agent::*;
@@ -0,0 +1,270 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    credentials::{PopKey, PopTokenRequestOptions, TokenCredential, TokenRequestOptions},
+    http::{
+        headers::{AUTHORIZATION, WWW_AUTHENTICATE},
+        policies::{Policy, PolicyResult},
+        StatusCode,
+    },
+};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use std::sync::Arc;
+use typespec_client_core::http::{ClientMethodOptions, Context, Request};
+
+/// Authentication policy for a proof-of-possession (PoP) token.
+///
+/// For each request, the policy gets a PoP token bound to the request's method and URL from its
+/// credential and sends it in an `Authorization: PoP` header. When the resource server responds
+/// 401 with a `WWW-Authenticate: PoP` challenge containing a new nonce, the policy gets a token
+/// for that nonce and retries the request once. It includes the nonce in subsequent tokens.
+#[derive(Debug, Clone)]
+pub struct PopTokenAuthorizationPolicy {
+    credential: Arc<dyn TokenCredential>,
+    scopes: Vec<String>,
+    key: Arc<dyn PopKey>,
+    nonce: Arc<RwLock<Option<String>>>,
+}
+
+impl PopTokenAuthorizationPolicy {
+    /// Creates a new `PopTokenAuthorizationPolicy` binding tokens to `key`.
+    pub fn new<A, B>(credential: Arc<dyn TokenCredential>, scopes: A, key: Arc<dyn PopKey>) -> Self
+    where
+        A: IntoIterator<Item = B>,
+        B: Into<String>,
+    {
+        Self {
+            credential,
+            scopes: scopes.into_iter().map(|s| s.into()).collect(),
+            key,
+            nonce: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    async fn authorize(
+        &self,
+        ctx: &Context<'_>,
+        request: &mut Request,
+        nonce: Option<String>,
+    ) -> crate::Result<()> {
+        let scopes: Vec<&str> = self.scopes.iter().map(String::as_str).collect();
+        let options = TokenRequestOptions {
+            method_options: ClientMethodOptions {
+                context: ctx.clone(),
+            },
+            pop: Some(PopTokenRequestOptions {
+                key: self.key.clone(),
+                method: request.method(),
+                url: request.url().clone(),
+                nonce,
+            }),
+        };
+        let token = self.credential.get_token(&scopes, Some(options)).await?;
+        request.insert_header(AUTHORIZATION, format!("PoP {}", token.token.secret()));
+        Ok(())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Policy for PopTokenAuthorizationPolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        let nonce = self.nonce.read().await.clone();
+        self.authorize(ctx, request, nonce.clone()).await?;
+        let response = next[0].send(ctx, request, &next[1..]).await?;
+        if response.status() != StatusCode::Unauthorized {
+            return Ok(response);
+        }
+
+        let challenge_nonce = response
+            .headers()
+            .get_optional_str(&WWW_AUTHENTICATE)
+            .and_then(pop_nonce);
+        match challenge_nonce {
+            // retry only for a new nonce; the server rejected the token for some other reason if
+            // it sent the nonce the token already has
+            Some(challenge_nonce) if nonce.as_deref() != Some(challenge_nonce.as_str()) => {
+                *self.nonce.write().await = Some(challenge_nonce.clone());
+                self.authorize(ctx, request, Some(challenge_nonce)).await?;
+                next[0].send(ctx, request, &next[1..]).await
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+/// Returns the nonce from a `WWW-Authenticate` header's PoP challenge, if it has one.
+fn pop_nonce(header: &str) -> Option<String> {
+    // e.g. `Bearer authorization_uri="...", PoP nonce="..."`
+    let lowercase = header.to_ascii_lowercase();
+    let start = lowercase
+        .match_indices("pop ")
+        .map(|(i, _)| i)
+        .find(|&i| i == 0 || lowercase[..i].trim_end().ends_with(','))?;
+    let challenge = &header[start..];
+    let value_start = challenge.to_ascii_lowercase().find("nonce=\"")? + "nonce=\"".len();
+    let value = &challenge[value_start..];
+    let end = value.find('"')?;
+    Some(value[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        credentials::{AccessToken, Secret},
+        error::{Error, ErrorKind},
+        http::{
+            headers::Headers,
+            policies::{Policy, TransportPolicy},
+            Method, Request,
+        },
+        time::OffsetDateTime,
+        Bytes, Result, Value,
+    };
+    use azure_core_test::http::MockHttpClient;
+    use futures::FutureExt;
+    use std::sync::Mutex;
+    use typespec_client_core::{
+        http::{AsyncRawResponse, Transport},
+        time::Duration,
+    };
+
+    #[derive(Debug)]
+    struct MockKey;
+
+    impl PopKey for MockKey {
+        fn key_id(&self) -> &str {
+            "kid"
+        }
+
+        fn jwk(&self) -> Value {
+            Value::Null
+        }
+
+        fn algorithm(&self) -> &str {
+            "RS256"
+        }
+
+        fn sign(&self, _: &[u8]) -> Result<Vec<u8>> {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns tokens naming the nonce each was requested with.
+    #[derive(Debug, Default)]
+    struct MockCredential {
+        requests: Mutex<Vec<(Method, String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl TokenCredential for MockCredential {
+        async fn get_token(
+            &self,
+            _: &[&str],
+            options: Option<TokenRequestOptions<'_>>,
+        ) -> Result<AccessToken> {
+            let pop = options.and_then(|o| o.pop).ok_or_else(|| {
+                Error::with_message(ErrorKind::Credential, "expected a PoP request")
+            })?;
+            assert_eq!("kid", pop.key.key_id());
+            let token = format!("token-{}", pop.nonce.as_deref().unwrap_or("none"));
+            self.requests
+                .lock()
+                .unwrap()
+                .push((pop.method, pop.url.to_string(), pop.nonce));
+            Ok(AccessToken::new(
+                Secret::new(token),
+                OffsetDateTime::now_utc() + Duration::hours(1),
+            ))
+        }
+    }
+
+    fn transport(challenge: &'static str) -> Arc<dyn Policy> {
+        let client = MockHttpClient::new(move |req| {
+            async move {
+                let authz = req.headers().get_str(&AUTHORIZATION)?;
+                let (status, headers) = if authz == "PoP token-abc" {
+                    (StatusCode::Ok, Headers::new())
+                } else {
+                    let mut headers = Headers::new();
+                    headers.insert(WWW_AUTHENTICATE, challenge);
+                    (StatusCode::Unauthorized, headers)
+                };
+                Ok(AsyncRawResponse::from_bytes(status, headers, Bytes::new()))
+            }
+            .boxed()
+        });
+        Arc::new(TransportPolicy::new(Transport::new(Arc::new(client))))
+    }
+
+    #[tokio::test]
+    async fn nonce_challenge() {
+        let credential = Arc::new(MockCredential::default());
+        let policy =
+            PopTokenAuthorizationPolicy::new(credential.clone(), ["scope"], Arc::new(MockKey));
+        let transport = transport(r#"Bearer authorization_uri="https://login", PoP nonce="abc""#);
+
+        for _ in 0..2 {
+            let mut req = Request::new("https://localhost/path?q".parse().unwrap(), Method::Put);
+            let response = policy
+                .send(
+                    &Context::default(),
+                    &mut req,
+                    std::slice::from_ref(&transport),
+                )
+                .await
+                .expect("response");
+            assert_eq!(StatusCode::Ok, response.status());
+        }
+
+        // the first request should get a token without a nonce, then retry with the challenge's
+        // nonce, and the second request should use that nonce
+        let requests = credential.requests.lock().unwrap();
+        let nonces: Vec<_> = requests.iter().map(|r| r.2.as_deref()).collect();
+        assert_eq!(vec![None, Some("abc"), Some("abc")], nonces);
+        for (method, url, _) in requests.iter() {
+            assert_eq!(Method::Put, *method);
+            assert_eq!("https://localhost/path?q", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn unauthorized_without_new_nonce() {
+        for challenge in [r#"Bearer realm="""#, r#"PoP nonce="none""#] {
+            let credential = Arc::new(MockCredential::default());
+            let policy =
+                PopTokenAuthorizationPolicy::new(credential.clone(), ["scope"], Arc::new(MockKey));
+            let mut req = Request::new("https://localhost".parse().unwrap(), Method::Get);
+            *policy.nonce.write().await = Some("none".to_string());
+            let response = policy
+                .send(
+                    &Context::default(),
+                    &mut req,
+                    std::slice::from_ref(&transport(challenge)),
+                )
+                .await
+                .expect("response");
+            assert_eq!(StatusCode::Unauthorized, response.status());
+            assert_eq!(1, credential.requests.lock().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn parse_nonce() {
+        assert_eq!(Some("abc".to_string()), pop_nonce(r#"PoP nonce="abc""#));
+        assert_eq!(
+            Some("abc".to_string()),
+            pop_nonce(r#"Bearer realm="x", pop NONCE="abc", error="invalid_token""#)
+        );
+        assert_eq!(None, pop_nonce(r#"Bearer realm="pop nonce""#));
+        assert_eq!(None, pop_nonce("PoP"));
+    }
+}
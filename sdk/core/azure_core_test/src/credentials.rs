@@ -38,7 +38,7 @@ impl TokenCredential for MockCredential {
         let token: Secret = format!("TEST TOKEN {}", scopes.join(" ")).into();
         let expires_on = OffsetDateTime::now_utc().saturating_add(Duration::minutes(5));
 
-        Ok(AccessToken { token, expires_on })
+        Ok(AccessToken::new(token, expires_on))
     }
 }
 
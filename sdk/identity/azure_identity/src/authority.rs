@@ -0,0 +1,269 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{deserialize, entra_error};
+use async_lock::OnceCell;
+use azure_core::{
+    error::{ErrorKind, ResultExt},
+    http::{Context, Method, Pipeline, PipelineSendOptions, Request, Url},
+    Error, Result,
+};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Hosts of Microsoft Entra authorities, which don't need instance discovery.
+const KNOWN_AUTHORITY_HOSTS: &[&str] = &[
+    "login.chinacloudapi.cn",
+    "login.microsoft.com",
+    "login.microsoftonline.com",
+    "login.microsoftonline.us",
+    "login.windows.net",
+    "sts.windows.net",
+];
+
+const INSTANCE_DISCOVERY_ENDPOINT: &str =
+    "https://login.microsoftonline.com/common/discovery/instance";
+
+#[derive(Debug, Deserialize)]
+struct InstanceDiscoveryResponse {
+    tenant_discovery_endpoint: Option<String>,
+}
+
+/// The authority a confidential client credential authenticates with: a Microsoft Entra
+/// tenant or an AD FS instance.
+///
+/// Before the first token request, [`Authority::validate`] checks with Microsoft Entra instance
+/// discovery that an unknown authority host is a Microsoft Entra authority, unless the
+/// credential disabled instance discovery. AD FS authorities aren't validated.
+#[derive(Debug)]
+pub(crate) struct Authority {
+    host: Url,
+    tenant_id: String,
+    adfs: bool,
+    discover: bool,
+    validated: OnceCell<()>,
+}
+
+impl Authority {
+    /// Creates an authority for `tenant_id` at `host`. `host` is an AD FS authority when its path
+    /// begins with "/adfs", in which case `tenant_id` isn't part of the authority.
+    pub(crate) fn new(host: Url, tenant_id: &str, disable_instance_discovery: bool) -> Self {
+        let adfs = host
+            .path_segments()
+            .and_then(|mut segments| segments.next())
+            .is_some_and(|segment| segment.eq_ignore_ascii_case("adfs"));
+        let known = host
+            .host_str()
+            .is_some_and(|h| KNOWN_AUTHORITY_HOSTS.contains(&h.to_ascii_lowercase().as_str()));
+        Self {
+            host,
+            tenant_id: tenant_id.to_string(),
+            adfs,
+            discover: !(adfs || known || disable_instance_discovery),
+            validated: OnceCell::new(),
+        }
+    }
+
+    /// Returns the authority's token endpoint.
+    pub(crate) fn token_endpoint(&self) -> Result<Url> {
+        if self.adfs {
+            return self
+                .host
+                .join("/adfs/oauth2/token")
+                .with_context(ErrorKind::DataConversion, "invalid AD FS authority");
+        }
+        let tenant_id = &self.tenant_id;
+        self.host
+            .join(&format!("/{tenant_id}/oauth2/v2.0/token"))
+            .with_context_fn(ErrorKind::DataConversion, || {
+                format!("tenant_id '{tenant_id}' could not be URL encoded")
+            })
+    }
+
+    /// Validates the authority with instance discovery, once. Returns an error when Microsoft Entra
+    /// ID doesn't recognize the authority.
+    pub(crate) async fn validate(&self, pipeline: &Pipeline, ctx: &Context<'_>) -> Result<()> {
+        if !self.discover {
+            return Ok(());
+        }
+        self.validated
+            .get_or_try_init(|| self.discover(pipeline, ctx))
+            .await
+            .map(|_| ())
+    }
+
+    async fn discover(&self, pipeline: &Pipeline, ctx: &Context<'_>) -> Result<()> {
+        let authorize_endpoint = self
+            .host
+            .join(&format!("/{}/oauth2/v2.0/authorize", self.tenant_id))
+            .with_context(ErrorKind::DataConversion, "invalid authority")?;
+        let mut url = Url::parse(INSTANCE_DISCOVERY_ENDPOINT)?;
+        url.query_pairs_mut()
+            .append_pair("api-version", "1.1")
+            .append_pair("authorization_endpoint", authorize_endpoint.as_str());
+
+        debug!("validating authority {} with instance discovery", self.host);
+        let mut req = Request::new(url, Method::Get);
+        let rsp = pipeline
+            .send(
+                ctx,
+                &mut req,
+                Some(PipelineSendOptions {
+                    skip_checks: true,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+
+        let host = self.host.host_str().unwrap_or_default();
+        let message = || {
+            format!("Microsoft Entra ID doesn't recognize authority host {host}. If the authority is trustworthy, set disable_instance_discovery in the credential's options to skip this validation")
+        };
+        if !rsp.status().is_success() {
+            return Err(entra_error(rsp)).with_context_fn(ErrorKind::Credential, message);
+        }
+        let discovery: InstanceDiscoveryResponse = deserialize(&rsp)?;
+        if discovery.tenant_discovery_endpoint.is_none() {
+            return Err(Error::with_message_fn(ErrorKind::Credential, message));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use azure_core::{
+        http::{headers::Headers, AsyncRawResponse, ClientOptions, StatusCode, Transport},
+        Bytes,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn pipeline(responses: Vec<AsyncRawResponse>, requests: Arc<AtomicUsize>) -> Pipeline {
+        let sts = MockSts::new(
+            responses,
+            Some(Arc::new(move |req: &Request| {
+                requests.fetch_add(1, Ordering::SeqCst);
+                assert!(req.url().as_str().starts_with(INSTANCE_DISCOVERY_ENDPOINT));
+                let authorization_endpoint = req
+                    .url()
+                    .query_pairs()
+                    .find(|(k, _)| k == "authorization_endpoint")
+                    .map(|(_, v)| v.to_string());
+                assert_eq!(
+                    Some(format!(
+                        "https://login.contoso.local/{FAKE_TENANT_ID}/oauth2/v2.0/authorize"
+                    )),
+                    authorization_endpoint
+                );
+                Ok(())
+            })),
+        );
+        Pipeline::new(
+            None,
+            None,
+            ClientOptions {
+                transport: Some(Transport::new(Arc::new(sts))),
+                ..Default::default()
+            },
+            Vec::default(),
+            Vec::default(),
+            None,
+        )
+    }
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn adfs() {
+        for host in [
+            "https://adfs.contoso.local/adfs",
+            "https://adfs.contoso.local/adfs/",
+            "https://adfs.contoso.local/ADFS",
+        ] {
+            let authority = Authority::new(url(host), "adfs", false);
+            assert!(!authority.discover);
+            assert_eq!(
+                "https://adfs.contoso.local/adfs/oauth2/token",
+                authority.token_endpoint().unwrap().as_str()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn discovery() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let pipeline = pipeline(
+            vec![AsyncRawResponse::from_bytes(
+                StatusCode::Ok,
+                Headers::default(),
+                Bytes::from_static(
+                    br#"{"tenant_discovery_endpoint":"https://login.contoso.local/tenant/v2.0/.well-known/openid-configuration","api-version":"1.1","metadata":[]}"#,
+                ),
+            )],
+            requests.clone(),
+        );
+        let authority = Authority::new(url("https://login.contoso.local"), FAKE_TENANT_ID, false);
+        assert_eq!(
+            format!("https://login.contoso.local/{FAKE_TENANT_ID}/oauth2/v2.0/token"),
+            authority.token_endpoint().unwrap().as_str()
+        );
+
+        // the authority should be validated only once
+        for _ in 0..2 {
+            authority
+                .validate(&pipeline, &Context::default())
+                .await
+                .expect("valid authority");
+        }
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn discovery_disabled() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let pipeline = pipeline(Vec::new(), requests.clone());
+        for (host, disable) in [
+            ("https://login.contoso.local", true),
+            ("https://login.microsoftonline.com", false),
+            ("https://LOGIN.microsoftonline.us", false),
+        ] {
+            Authority::new(url(host), FAKE_TENANT_ID, disable)
+                .validate(&pipeline, &Context::default())
+                .await
+                .expect("no validation");
+        }
+        assert_eq!(0, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn invalid_instance() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let pipeline = pipeline(
+            vec![AsyncRawResponse::from_bytes(
+                StatusCode::BadRequest,
+                Headers::default(),
+                Bytes::from_static(
+                    br#"{"error":"invalid_instance","error_description":"AADSTS50049: Unknown or invalid instance.","error_codes":[50049]}"#,
+                ),
+            )],
+            requests.clone(),
+        );
+        let err = Authority::new(url("https://login.contoso.local"), FAKE_TENANT_ID, false)
+            .validate(&pipeline, &Context::default())
+            .await
+            .expect_err("invalid authority");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(
+            err.to_string().contains("disable_instance_discovery"),
+            "{err}"
+        );
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+}
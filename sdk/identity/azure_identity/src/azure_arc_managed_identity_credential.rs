@@ -0,0 +1,365 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::env::Env;
+use crate::{handle_msi_response, scopes_to_resource, ImdsId, TokenCache};
+use azure_core::{
+    credentials::{AccessToken, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    http::{
+        headers::{HeaderName, AUTHORIZATION},
+        request::Request,
+        ClientOptions, Method, Pipeline, PipelineSendOptions, StatusCode, Url,
+    },
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const ENDPOINT_ENV: &str = "IDENTITY_ENDPOINT";
+const API_VERSION: &str = "2020-06-01";
+const WWW_AUTHENTICATE: HeaderName = HeaderName::from_static("www-authenticate");
+/// The agent never writes a key larger than this; a larger file isn't a key.
+const MAX_KEY_FILE_SIZE: u64 = 4096;
+
+/// Authenticates the managed identity of an Azure Arc-enabled server.
+///
+/// The Arc agent challenges the first request for a token with the path of a file only
+/// administrators and members of the agent's group can read. The credential proves it can
+/// read that file by sending its contents with a second request.
+///
+/// See <https://learn.microsoft.com/azure/azure-arc/servers/managed-identity-authentication>
+#[derive(Debug)]
+pub(crate) struct AzureArcManagedIdentityCredential {
+    pipeline: Pipeline,
+    endpoint: Url,
+    /// The only directory the agent writes key files to. `None` on platforms the agent doesn't support.
+    key_directory: Option<PathBuf>,
+    cache: TokenCache,
+}
+
+impl AzureArcManagedIdentityCredential {
+    pub fn new(
+        id: ImdsId,
+        client_options: ClientOptions,
+        env: Env,
+    ) -> azure_core::Result<Arc<Self>> {
+        if !matches!(id, ImdsId::SystemAssigned) {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "Azure Arc doesn't support user-assigned identities",
+            ));
+        }
+        let endpoint = &env
+            .var(ENDPOINT_ENV)
+            .with_context_fn(ErrorKind::Credential, || {
+                format!(
+                    "azure arc credential requires {} environment variable",
+                    ENDPOINT_ENV
+                )
+            })?;
+        let endpoint = Url::parse(endpoint).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "azure arc credential {} environment variable must be a valid URL, but is '{endpoint}'",
+                ENDPOINT_ENV
+            )
+        })?;
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            client_options,
+            Vec::default(),
+            Vec::default(),
+            None,
+        );
+        Ok(Arc::new(Self {
+            pipeline,
+            endpoint,
+            key_directory: key_directory(&env),
+            cache: TokenCache::new(),
+        }))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let resource = scopes_to_resource(scopes)?;
+
+        let mut url = self.endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("api-version", API_VERSION)
+            .append_pair("resource", resource);
+
+        let mut req = Request::new(url, Method::Get);
+        req.insert_header("metadata", "true");
+
+        let options = options.unwrap_or_default();
+        let ctx = options.method_options.context.to_borrowed();
+        let send_options = || {
+            Some(PipelineSendOptions {
+                skip_checks: true,
+                ..Default::default()
+            })
+        };
+        let rsp = self.pipeline.send(&ctx, &mut req, send_options()).await?;
+        if rsp.status() != StatusCode::Unauthorized {
+            return handle_msi_response(rsp);
+        }
+
+        let challenge = rsp
+            .headers()
+            .get_optional_str(&WWW_AUTHENTICATE)
+            .unwrap_or_default();
+        let Some((_, path)) = challenge.split_once("realm=") else {
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!("Azure Arc responded with an unexpected challenge: '{challenge}'")
+            }));
+        };
+        let key = self.read_key(Path::new(path.trim()))?;
+
+        req.insert_header(AUTHORIZATION, format!("Basic {key}"));
+        let rsp = self.pipeline.send(&ctx, &mut req, send_options()).await?;
+        handle_msi_response(rsp)
+    }
+
+    /// Reads the key file named in the agent's challenge after verifying the agent could have written it.
+    fn read_key(&self, path: &Path) -> azure_core::Result<String> {
+        let Some(key_directory) = &self.key_directory else {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "Azure Arc managed identity isn't supported on this platform",
+            ));
+        };
+        if path.parent() != Some(key_directory.as_path()) {
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!(
+                    "Azure Arc challenge file '{}' isn't in the expected directory '{}'",
+                    path.display(),
+                    key_directory.display()
+                )
+            }));
+        }
+        if path.extension().and_then(|ext| ext.to_str()) != Some("key") {
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!(
+                    "Azure Arc challenge file '{}' doesn't have the expected '.key' extension",
+                    path.display()
+                )
+            }));
+        }
+        let metadata = fs::metadata(path).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "failed to read Azure Arc challenge file '{}'",
+                path.display()
+            )
+        })?;
+        if metadata.len() > MAX_KEY_FILE_SIZE {
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!(
+                    "Azure Arc challenge file '{}' is larger than {MAX_KEY_FILE_SIZE} bytes",
+                    path.display()
+                )
+            }));
+        }
+        fs::read_to_string(path).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "failed to read Azure Arc challenge file '{}'",
+                path.display()
+            )
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for AzureArcManagedIdentityCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        self.cache
+            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .await
+    }
+}
+
+fn key_directory(env: &Env) -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        Some(PathBuf::from("/var/opt/azcmagent/tokens"))
+    } else if cfg!(target_os = "windows") {
+        env.var("ProgramData")
+            .ok()
+            .map(|program_data| Path::new(&program_data).join("AzureConnectedMachineAgent\\Tokens"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{LIVE_TEST_RESOURCE, LIVE_TEST_SCOPES};
+    use azure_core::{
+        http::{headers::Headers, AsyncRawResponse, Transport},
+        Bytes, Uuid,
+    };
+    use azure_core_test::http::MockHttpClient;
+    use futures::FutureExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const ENDPOINT: &str = "http://localhost:40342/metadata/identity/oauth2/token";
+    const KEY: &str = "key file contents";
+
+    struct KeyDirectory(PathBuf);
+
+    impl KeyDirectory {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("azure_identity_test_{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).expect("create key directory");
+            Self(dir)
+        }
+    }
+
+    impl Drop for KeyDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn credential(
+        key_directory: &Path,
+        challenge: String,
+        requests: Arc<AtomicUsize>,
+    ) -> Arc<AzureArcManagedIdentityCredential> {
+        let mock_client = MockHttpClient::new(move |req| {
+            let challenge = challenge.clone();
+            let n = requests.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(Method::Get, req.method());
+                assert_eq!("true", req.headers().get_str(&"metadata".into()).unwrap());
+                let mut params: Vec<_> = req.url().query_pairs().into_owned().collect();
+                params.sort();
+                assert_eq!(
+                    vec![
+                        ("api-version".to_string(), API_VERSION.to_string()),
+                        ("resource".to_string(), LIVE_TEST_RESOURCE.to_string()),
+                    ],
+                    params
+                );
+                if n == 0 {
+                    assert!(req.headers().get_optional_str(&AUTHORIZATION).is_none());
+                    let mut headers = Headers::new();
+                    headers.insert(WWW_AUTHENTICATE, challenge);
+                    return Ok(AsyncRawResponse::from_bytes(
+                        StatusCode::Unauthorized,
+                        headers,
+                        Bytes::new(),
+                    ));
+                }
+                assert_eq!(
+                    format!("Basic {KEY}"),
+                    req.headers().get_str(&AUTHORIZATION).unwrap()
+                );
+                Ok(AsyncRawResponse::from_bytes(
+                    StatusCode::Ok,
+                    Headers::default(),
+                    Bytes::from(format!(
+                        r#"{{"access_token":"*","expires_on":"4102444800","resource":"{LIVE_TEST_RESOURCE}","token_type":"Bearer"}}"#
+                    )),
+                ))
+            }
+            .boxed()
+        });
+        let mut credential = AzureArcManagedIdentityCredential::new(
+            ImdsId::SystemAssigned,
+            ClientOptions {
+                transport: Some(Transport::new(Arc::new(mock_client))),
+                ..Default::default()
+            },
+            Env::from(&[(ENDPOINT_ENV, ENDPOINT)][..]),
+        )
+        .expect("credential");
+        Arc::get_mut(&mut credential).unwrap().key_directory = Some(key_directory.to_path_buf());
+        credential
+    }
+
+    async fn run_invalid_key_file_test(key_directory: &Path, path: &Path) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let credential = credential(
+            key_directory,
+            format!("Basic realm={}", path.display()),
+            requests.clone(),
+        );
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("invalid key file");
+        assert_eq!(ErrorKind::Credential, *err.kind());
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn challenge() {
+        let dir = KeyDirectory::new();
+        let path = dir.0.join("challenge.key");
+        fs::write(&path, KEY).unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let credential = credential(
+            &dir.0,
+            format!("Basic realm={}", path.display()),
+            requests.clone(),
+        );
+
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+
+        assert_eq!("*", token.token.secret());
+        assert_eq!(4102444800, token.expires_on.unix_timestamp());
+        assert_eq!(2, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn invalid_challenge() {
+        let dir = KeyDirectory::new();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let credential = credential(&dir.0, "Bearer".to_string(), requests.clone());
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("invalid challenge");
+        assert_eq!(ErrorKind::Credential, *err.kind());
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn key_file_outside_directory() {
+        let dir = KeyDirectory::new();
+        let other = KeyDirectory::new();
+        let path = other.0.join("challenge.key");
+        fs::write(&path, KEY).unwrap();
+        run_invalid_key_file_test(&dir.0, &path).await;
+    }
+
+    #[tokio::test]
+    async fn key_file_too_large() {
+        let dir = KeyDirectory::new();
+        let path = dir.0.join("challenge.key");
+        fs::write(&path, vec![b'a'; MAX_KEY_FILE_SIZE as usize + 1]).unwrap();
+        run_invalid_key_file_test(&dir.0, &path).await;
+    }
+
+    #[tokio::test]
+    async fn key_file_wrong_extension() {
+        let dir = KeyDirectory::new();
+        let path = dir.0.join("challenge.txt");
+        fs::write(&path, KEY).unwrap();
+        run_invalid_key_file_test(&dir.0, &path).await;
+    }
+}
@@ -135,8 +135,9 @@ impl TokenCredential for AzureCliCredential {
     async fn get_token(
         &self,
         scopes: &[&str],
-        _: Option<TokenRequestOptions<'_>>,
+        options: Option<TokenRequestOptions<'_>>,
     ) -> azure_core::Result<AccessToken> {
+        crate::pop::reject_pop(options.as_ref())?;
         if scopes.is_empty() {
             return Err(Error::new(
                 ErrorKind::Credential,
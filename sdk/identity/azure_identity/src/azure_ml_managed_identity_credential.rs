@@ -0,0 +1,142 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::env::Env;
+use crate::{handle_msi_response, scopes_to_resource, ImdsId, TokenCache};
+use azure_core::{
+    credentials::{AccessToken, Secret, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    http::{
+        headers::HeaderName, request::Request, ClientOptions, Method, Pipeline,
+        PipelineSendOptions, Url,
+    },
+};
+use std::sync::Arc;
+
+const ENDPOINT_ENV: &str = "MSI_ENDPOINT";
+const API_VERSION: &str = "2017-09-01";
+const SECRET_HEADER: HeaderName = HeaderName::from_static("secret");
+const SECRET_ENV: &str = "MSI_SECRET";
+/// Azure ML sets this to the client ID of the compute's system-assigned identity.
+const DEFAULT_CLIENT_ID_ENV: &str = "DEFAULT_IDENTITY_CLIENT_ID";
+
+/// Authenticates the managed identity of an Azure Machine Learning compute resource.
+///
+/// See <https://learn.microsoft.com/azure/machine-learning/how-to-identity-based-service-authentication>
+#[derive(Debug)]
+pub(crate) struct AzureMLManagedIdentityCredential {
+    pipeline: Pipeline,
+    endpoint: Url,
+    secret: Secret,
+    client_id: Option<String>,
+    cache: TokenCache,
+}
+
+impl AzureMLManagedIdentityCredential {
+    pub fn new(
+        id: ImdsId,
+        client_options: ClientOptions,
+        env: Env,
+    ) -> azure_core::Result<Arc<Self>> {
+        let client_id = match id {
+            ImdsId::SystemAssigned => env.var(DEFAULT_CLIENT_ID_ENV).ok(),
+            ImdsId::ClientId(client_id) => Some(client_id),
+            ImdsId::ObjectId(_) | ImdsId::MsiResId(_) => {
+                return Err(Error::with_message(
+                    ErrorKind::Credential,
+                    "Azure ML supports only client IDs for user-assigned identities. Use a client ID instead.",
+                ));
+            }
+        };
+        let endpoint = &env
+            .var(ENDPOINT_ENV)
+            .with_context_fn(ErrorKind::Credential, || {
+                format!(
+                    "azure ml credential requires {} environment variable",
+                    ENDPOINT_ENV
+                )
+            })?;
+        let endpoint = Url::parse(endpoint).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "azure ml credential {} environment variable must be a valid URL, but is '{endpoint}'",
+                ENDPOINT_ENV
+            )
+        })?;
+        let secret = env
+            .var(SECRET_ENV)
+            .with_context_fn(ErrorKind::Credential, || {
+                format!(
+                    "azure ml credential requires {} environment variable",
+                    SECRET_ENV
+                )
+            })?;
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            client_options,
+            Vec::default(),
+            Vec::default(),
+            None,
+        );
+        Ok(Arc::new(Self {
+            pipeline,
+            endpoint,
+            secret: secret.into(),
+            client_id,
+            cache: TokenCache::new(),
+        }))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let resource = scopes_to_resource(scopes)?;
+
+        let mut url = self.endpoint.clone();
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("api-version", API_VERSION)
+                .append_pair("resource", resource);
+            if let Some(client_id) = &self.client_id {
+                query.append_pair("clientid", client_id);
+            }
+        }
+
+        let mut req = Request::new(url, Method::Get);
+        req.insert_header("metadata", "true");
+        req.insert_header(SECRET_HEADER, self.secret.secret().to_string());
+
+        let options = options.unwrap_or_default();
+        let ctx = options.method_options.context.to_borrowed();
+        let rsp = self
+            .pipeline
+            .send(
+                &ctx,
+                &mut req,
+                Some(PipelineSendOptions {
+                    skip_checks: true,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+
+        handle_msi_response(rsp)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for AzureMLManagedIdentityCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        self.cache
+            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .await
+    }
+}
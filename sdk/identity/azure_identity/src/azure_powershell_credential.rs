@@ -0,0 +1,323 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+// cspell:ignore BSTR
+
+use crate::{
+    authentication_error,
+    env::Env,
+    process::{new_executor, shell_exec, Executor, OutputProcessor},
+    validate_scope, validate_tenant_id,
+};
+use azure_core::{
+    base64,
+    credentials::{AccessToken, Secret, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    json::from_json,
+    time::OffsetDateTime,
+};
+use serde::Deserialize;
+use std::{ffi::OsString, sync::Arc};
+use tracing::trace;
+
+/// Written to stderr by the token script when Az.Accounts isn't installed.
+const NO_AZ_ACCOUNT_MODULE: &str = "NoAzAccountModule";
+
+/// The JSON the token script writes to stdout.
+#[derive(Debug, Clone, Deserialize)]
+struct PowerShellTokenResponse {
+    #[serde(rename = "Token")]
+    pub access_token: Secret,
+    /// The token's expiry time in seconds since the epoch, a unix timestamp.
+    #[serde(rename = "ExpiresOn")]
+    pub expires_on: i64,
+}
+
+impl OutputProcessor for PowerShellTokenResponse {
+    fn credential_name() -> &'static str {
+        "AzurePowerShellCredential"
+    }
+
+    fn deserialize_token(stdout: &str) -> azure_core::Result<AccessToken> {
+        let response: Self = from_json(stdout)?;
+        let expires_on = OffsetDateTime::from_unix_timestamp(response.expires_on)
+            .with_context_fn(ErrorKind::DataConversion, || {
+                format!("unable to parse ExpiresOn '{}'", response.expires_on)
+            })?;
+        Ok(AccessToken::new(response.access_token, expires_on))
+    }
+
+    fn get_error_message(stderr: &str) -> Option<&str> {
+        if stderr.contains(NO_AZ_ACCOUNT_MODULE) {
+            Some("the Az.Accounts module (>= 2.2.0) isn't installed. Install it with `Install-Module -Name Az.Accounts`")
+        } else if stderr.contains("Connect-AzAccount") {
+            Some("please run `Connect-AzAccount` from PowerShell before using this credential")
+        } else {
+            None
+        }
+    }
+
+    fn tool_name() -> &'static str {
+        "pwsh"
+    }
+}
+
+/// Authenticates the identity logged in to [Azure PowerShell](https://learn.microsoft.com/powershell/azure/what-is-azure-powershell).
+///
+/// The credential runs `Get-AzAccessToken` in PowerShell 7 (`pwsh`) and requires the Az.Accounts module 2.2.0 or newer.
+#[derive(Debug)]
+pub struct AzurePowerShellCredential {
+    env: Env,
+    executor: Arc<dyn Executor>,
+    tenant_id: Option<String>,
+}
+
+/// Options for constructing an [`AzurePowerShellCredential`].
+#[derive(Clone, Debug, Default)]
+pub struct AzurePowerShellCredentialOptions {
+    /// An implementation of [`Executor`] to run commands asynchronously.
+    ///
+    /// If `None`, one is created using [`new_executor`]; alternatively,
+    /// you can supply your own implementation using a different asynchronous runtime.
+    pub executor: Option<Arc<dyn Executor>>,
+
+    /// Identifies the tenant the credential should authenticate in.
+    ///
+    /// Defaults to the tenant of Azure PowerShell's current context.
+    pub tenant_id: Option<String>,
+
+    #[cfg(test)]
+    pub(crate) env: Option<Env>,
+}
+
+impl AzurePowerShellCredential {
+    /// Create a new [`AzurePowerShellCredential`].
+    pub fn new(options: Option<AzurePowerShellCredentialOptions>) -> azure_core::Result<Arc<Self>> {
+        let options = options.unwrap_or_default();
+        if let Some(ref tenant_id) = options.tenant_id {
+            validate_tenant_id(tenant_id)?;
+        }
+        #[cfg(test)]
+        let env = options.env.unwrap_or_default();
+        #[cfg(not(test))]
+        let env = Env::default();
+        Ok(Arc::new(Self {
+            env,
+            executor: options.executor.unwrap_or(new_executor()),
+            tenant_id: options.tenant_id,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for AzurePowerShellCredential {
+    /// Requests a token from Azure PowerShell. This credential doesn't cache tokens, so every call runs PowerShell.
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        crate::pop::reject_pop(options.as_ref())?;
+        if scopes.len() != 1 {
+            return Err(Error::new(
+                ErrorKind::Credential,
+                "exactly one scope required",
+            ));
+        }
+        validate_scope(scopes[0])?;
+        let resource = scopes[0].strip_suffix("/.default").unwrap_or(scopes[0]);
+
+        let script = token_script(resource, self.tenant_id.as_deref());
+        trace!("running Azure PowerShell script: {script}");
+
+        let mut command =
+            OsString::from("pwsh -NoProfile -NonInteractive -OutputFormat Text -EncodedCommand ");
+        command.push(encode_command(&script));
+
+        shell_exec::<PowerShellTokenResponse>(self.executor.clone(), &self.env, &command)
+            .await
+            .map_err(|err| authentication_error(stringify!(AzurePowerShellCredential), err))
+    }
+}
+
+/// Returns a script that writes a token for `resource` to stdout as JSON.
+///
+/// Az.Accounts returns the token as a `SecureString` when `-AsSecureString` is set, which versions 2.17 through 4.x
+/// require to avoid a deprecation warning, and by default beginning with 5.0. The script converts it to plain text.
+fn token_script(resource: &str, tenant_id: Option<&str>) -> String {
+    let tenant_param = tenant_id
+        .map(|tenant_id| format!("$params['TenantId'] = '{tenant_id}'\n"))
+        .unwrap_or_default();
+    format!(
+        r#"$ErrorActionPreference = 'Stop'
+$mod = Import-Module Az.Accounts -MinimumVersion 2.2.0 -PassThru -ErrorAction SilentlyContinue
+if (-not $mod) {{
+    Write-Error '{NO_AZ_ACCOUNT_MODULE}'
+}}
+$params = @{{ ResourceUrl = '{resource}'; WarningAction = 'Ignore' }}
+{tenant_param}if ($mod.Version -ge [version]'2.17.0' -and $mod.Version -lt [version]'5.0.0') {{
+    $params['AsSecureString'] = $true
+}}
+$token = Get-AzAccessToken @params
+$plainToken = $token.Token
+if ($plainToken -is [System.Security.SecureString]) {{
+    $ssPtr = [System.Runtime.InteropServices.Marshal]::SecureStringToBSTR($plainToken)
+    try {{
+        $plainToken = [System.Runtime.InteropServices.Marshal]::PtrToStringBSTR($ssPtr)
+    }} finally {{
+        [System.Runtime.InteropServices.Marshal]::ZeroFreeBSTR($ssPtr)
+    }}
+}}
+@{{ Token = $plainToken; ExpiresOn = $token.ExpiresOn.ToUnixTimeSeconds() }} | ConvertTo-Json
+"#
+    )
+}
+
+/// Encodes a script for PowerShell's `-EncodedCommand` parameter, which takes base64-encoded UTF-16LE.
+/// This spares the script from the shell's quoting rules.
+fn encode_command(script: &str) -> String {
+    let utf16: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    base64::encode(utf16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        MockExecutor, FAKE_TENANT_ID, FAKE_TOKEN, LIVE_TEST_RESOURCE, LIVE_TEST_SCOPES,
+    };
+    use std::ffi::OsStr;
+    use time::UtcOffset;
+
+    const COMMAND: &str = "pwsh -NoProfile -NonInteractive -OutputFormat Text -EncodedCommand ";
+
+    fn decode_command(encoded: &str) -> String {
+        let bytes = base64::decode(encoded).expect("valid base64");
+        let utf16: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&utf16).expect("valid UTF-16")
+    }
+
+    async fn run_test(
+        exit_code: i32,
+        stdout: &str,
+        stderr: &str,
+        tenant_id: Option<String>,
+    ) -> azure_core::Result<AccessToken> {
+        let tenant_id_for_on_run = tenant_id.clone();
+        let system_root = "/dev/null";
+        let options = AzurePowerShellCredentialOptions {
+            env: Some(Env::from(&[("SYSTEMROOT", system_root)][..])),
+            executor: Some(MockExecutor::with_output(
+                exit_code,
+                stdout,
+                stderr,
+                Some(Arc::new(move |program: &OsStr, args: &[&OsStr]| {
+                    let args: Vec<String> = args
+                        .iter()
+                        .map(|arg| arg.to_string_lossy().to_string())
+                        .collect();
+                    let prefix = if cfg!(target_os = "windows") {
+                        assert_eq!(program.to_string_lossy(), "cmd");
+                        assert_eq!(args[0], "/C");
+                        format!("cd {system_root} && {COMMAND}")
+                    } else {
+                        assert_eq!(program, "/bin/sh");
+                        assert_eq!(args[0], "-c");
+                        format!("cd /bin && {COMMAND}")
+                    };
+                    let encoded = args[1]
+                        .strip_prefix(&prefix)
+                        .unwrap_or_else(|| panic!("unexpected command: {}", args[1]));
+                    let script = decode_command(encoded);
+                    assert!(script.contains(&format!("ResourceUrl = '{LIVE_TEST_RESOURCE}'")));
+                    if let Some(ref tenant_id) = tenant_id_for_on_run {
+                        assert!(script.contains(&format!("$params['TenantId'] = '{tenant_id}'")));
+                    } else {
+                        assert!(!script.contains("TenantId"));
+                    }
+                })),
+            )),
+            tenant_id,
+        };
+        let cred = AzurePowerShellCredential::new(Some(options))?;
+        cred.get_token(LIVE_TEST_SCOPES, None).await
+    }
+
+    #[test]
+    fn encoded_command() {
+        let script = "Write-Output 'é'";
+        assert_eq!(script, decode_command(&encode_command(script)));
+    }
+
+    #[tokio::test]
+    async fn error_includes_stderr() {
+        let stderr = "something went wrong";
+        let err = run_test(1, "stdout", stderr, None)
+            .await
+            .expect_err("expected error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(err.to_string().contains(stderr));
+    }
+
+    #[tokio::test]
+    async fn get_token_success() {
+        let stdout =
+            format!("{{\n  \"Token\": \"{FAKE_TOKEN}\",\n  \"ExpiresOn\": 2147483647\n}}\n");
+        let token = run_test(0, &stdout, "", None).await.expect("token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+        assert_eq!(2147483647, token.expires_on.unix_timestamp());
+        assert_eq!(UtcOffset::UTC, token.expires_on.offset());
+    }
+
+    #[tokio::test]
+    async fn module_missing() {
+        let stderr = format!("Write-Error: {NO_AZ_ACCOUNT_MODULE}");
+        let err = run_test(1, "", &stderr, None).await.expect_err("error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(err.to_string().contains("Install-Module -Name Az.Accounts"));
+    }
+
+    #[tokio::test]
+    async fn not_logged_in() {
+        let stderr = "Get-AzAccessToken: Run Connect-AzAccount to login.";
+        let err = run_test(1, "", stderr, None).await.expect_err("error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(err
+            .to_string()
+            .contains("please run `Connect-AzAccount` from PowerShell"));
+    }
+
+    #[tokio::test]
+    async fn program_not_found() {
+        let executor = MockExecutor::with_error(std::io::Error::from_raw_os_error(127));
+        let options = AzurePowerShellCredentialOptions {
+            executor: Some(executor),
+            ..Default::default()
+        };
+        let cred = AzurePowerShellCredential::new(Some(options)).expect("valid credential");
+        let err = cred
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("expected error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+    }
+
+    #[tokio::test]
+    async fn pwsh_not_found() {
+        let stderr = "'pwsh' is not recognized as an internal or external command";
+        let err = run_test(1, "", stderr, None).await.expect_err("error");
+        assert!(err.to_string().contains("pwsh not found on PATH"));
+    }
+
+    #[tokio::test]
+    async fn tenant_id() {
+        let stdout = format!(r#"{{"Token":"{FAKE_TOKEN}","ExpiresOn":2147483647}}"#);
+        let token = run_test(0, &stdout, "", Some(FAKE_TENANT_ID.to_string()))
+            .await
+            .expect("token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use async_lock::Mutex;
+use azure_core::{
+    async_runtime::get_async_runtime,
+    credentials::{AccessToken, TokenCredential, TokenRequestOptions},
+    error::ErrorKind,
+    http::ExponentialRetryOptions,
+    time::OffsetDateTime,
+    Error, Result,
+};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{debug, trace, warn};
+
+/// Options for constructing a new [`BackgroundRefreshCredential`].
+#[derive(Debug, Default)]
+pub struct BackgroundRefreshCredentialOptions {
+    /// How the credential retries a background refresh that failed. Retrying also stops when
+    /// the current token expires.
+    pub retry: ExponentialRetryOptions,
+}
+
+/// Wraps a [`TokenCredential`] to refresh its tokens in the background.
+///
+/// Once a token reaches its [`refresh_time`](AccessToken::refresh_time), the credential
+/// starts acquiring a new one from the wrapped credential and meanwhile continues returning
+/// the current token, so callers don't wait on token requests while the token is valid.
+/// It acquires a token synchronously only when it has none for the requested scopes or the
+/// current one has expired.
+///
+/// Requests for proof-of-possession tokens pass through to the wrapped credential.
+///
+/// The credential spawns refreshes with the [`AsyncRuntime`](azure_core::async_runtime::AsyncRuntime)
+/// returned by [`get_async_runtime`].
+#[derive(Debug)]
+pub struct BackgroundRefreshCredential {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    credential: Arc<dyn TokenCredential>,
+    retry: ExponentialRetryOptions,
+    tokens: Mutex<HashMap<Vec<String>, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    token: AccessToken,
+    refreshing: bool,
+}
+
+impl BackgroundRefreshCredential {
+    /// Create a new `BackgroundRefreshCredential`.
+    ///
+    /// # Arguments
+    /// - `credential`: The credential from which to acquire tokens.
+    /// - `options`: Options for configuring the credential. If `None` is provided, default options will be used.
+    pub fn new(
+        credential: Arc<dyn TokenCredential>,
+        options: Option<BackgroundRefreshCredentialOptions>,
+    ) -> Result<Arc<Self>> {
+        let options = options.unwrap_or_default();
+        Ok(Arc::new(Self {
+            shared: Arc::new(Shared {
+                credential,
+                retry: options.retry,
+                tokens: Mutex::new(HashMap::new()),
+            }),
+        }))
+    }
+}
+
+impl Shared {
+    /// Acquires a new token for `scopes`, retrying failures until the retry options are
+    /// exhausted or `expires_on` passes, then stores it for subsequent calls to `get_token`.
+    async fn refresh(self: Arc<Self>, scopes: Vec<String>, expires_on: OffsetDateTime) {
+        let scope_refs: Vec<&str> = scopes.iter().map(String::as_str).collect();
+        let start = OffsetDateTime::now_utc();
+        let mut delay = self.retry.initial_delay;
+        let mut retries = 0;
+        let token = loop {
+            match self.credential.get_token(&scope_refs, None).await {
+                Ok(token) => break Some(token),
+                Err(err) => {
+                    let now = OffsetDateTime::now_utc();
+                    if retries >= self.retry.max_retries
+                        || now - start + delay > self.retry.max_total_elapsed
+                        || now + delay >= expires_on
+                    {
+                        warn!("background token refresh failed: {err}");
+                        break None;
+                    }
+                    debug!("background token refresh failed, retrying in {delay}: {err}");
+                    get_async_runtime().sleep(delay).await;
+                    retries += 1;
+                    delay = delay.saturating_mul(2).min(self.retry.max_delay);
+                }
+            }
+        };
+
+        let mut tokens = self.tokens.lock().await;
+        if let Some(entry) = tokens.get_mut(&scopes) {
+            entry.refreshing = false;
+            if let Some(token) = token {
+                trace!("background token refresh succeeded");
+                entry.token = token;
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for BackgroundRefreshCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> Result<AccessToken> {
+        if scopes.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "no scopes specified",
+            ));
+        }
+        if options.as_ref().is_some_and(|o| o.pop.is_some()) {
+            // a PoP token is bound to a particular request, so there's nothing to refresh
+            return self.shared.credential.get_token(scopes, options).await;
+        }
+        let key: Vec<String> = scopes.iter().map(ToString::to_string).collect();
+        {
+            let mut tokens = self.shared.tokens.lock().await;
+            if let Some(entry) = tokens.get_mut(&key) {
+                let now = OffsetDateTime::now_utc();
+                if entry.token.expires_on > now {
+                    if !entry.refreshing && entry.token.refresh_time() <= now {
+                        trace!("starting background token refresh");
+                        entry.refreshing = true;
+                        let refresh = self
+                            .shared
+                            .clone()
+                            .refresh(key.clone(), entry.token.expires_on);
+                        // the task runs to completion after its handle is dropped
+                        drop(get_async_runtime().spawn(Box::pin(refresh)));
+                    }
+                    return Ok(entry.token.clone());
+                }
+            }
+        }
+
+        trace!("no valid token; acquiring one");
+        let token = self.shared.credential.get_token(scopes, options).await?;
+        self.shared
+            .tokens
+            .lock()
+            .await
+            .entry(key)
+            .and_modify(|entry| entry.token = token.clone())
+            .or_insert_with(|| Entry {
+                token: token.clone(),
+                refreshing: false,
+            });
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use azure_core::{credentials::Secret, time::Duration};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns tokens "1", "2", ... which need refreshing when `refresh` is true, failing
+    /// the first `failures` calls after the first.
+    #[derive(Debug)]
+    struct MockCredential {
+        calls: AtomicUsize,
+        failures: usize,
+        lifetime: Duration,
+        refresh: bool,
+    }
+
+    impl MockCredential {
+        fn new(lifetime: Duration, refresh: bool, failures: usize) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+                failures,
+                lifetime,
+                refresh,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCredential for MockCredential {
+        async fn get_token(
+            &self,
+            _: &[&str],
+            _: Option<TokenRequestOptions<'_>>,
+        ) -> Result<AccessToken> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call > 1 && call <= 1 + self.failures {
+                return Err(Error::with_message(ErrorKind::Credential, "failure"));
+            }
+            let now = OffsetDateTime::now_utc();
+            let mut token = AccessToken::new(Secret::new(call.to_string()), now + self.lifetime);
+            if self.refresh {
+                token.refresh_on = Some(now - Duration::seconds(1));
+            }
+            Ok(token)
+        }
+    }
+
+    fn options() -> Option<BackgroundRefreshCredentialOptions> {
+        Some(BackgroundRefreshCredentialOptions {
+            retry: ExponentialRetryOptions {
+                initial_delay: Duration::milliseconds(10),
+                max_delay: Duration::milliseconds(10),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Polls `credential` until it returns `expected`.
+    async fn wait_for(credential: &BackgroundRefreshCredential, expected: &str) {
+        for _ in 0..200 {
+            let token = credential.get_token(&["scope"], None).await.unwrap();
+            if token.token.secret() == expected {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("didn't get token '{expected}'");
+    }
+
+    #[tokio::test]
+    async fn refreshes_in_background() {
+        let mock = MockCredential::new(Duration::hours(1), true, 0);
+        let credential = BackgroundRefreshCredential::new(mock.clone(), options()).unwrap();
+
+        let token = credential.get_token(&["scope"], None).await.unwrap();
+        assert_eq!("1", token.token.secret());
+
+        // this call starts a refresh but returns the current token
+        let token = credential.get_token(&["scope"], None).await.unwrap();
+        assert_eq!("1", token.token.secret());
+
+        wait_for(&credential, "2").await;
+    }
+
+    #[tokio::test]
+    async fn retries_background_refresh() {
+        let mock = MockCredential::new(Duration::hours(1), true, 2);
+        let credential = BackgroundRefreshCredential::new(mock.clone(), options()).unwrap();
+
+        credential.get_token(&["scope"], None).await.unwrap();
+        // calls 2 and 3 fail, then the refresh retries and gets token 4
+        wait_for(&credential, "4").await;
+    }
+
+    #[tokio::test]
+    async fn no_background_refresh_before_refresh_time() {
+        let mock = MockCredential::new(Duration::hours(1), false, 0);
+        let credential = BackgroundRefreshCredential::new(mock.clone(), None).unwrap();
+
+        for _ in 0..3 {
+            let token = credential.get_token(&["scope"], None).await.unwrap();
+            assert_eq!("1", token.token.secret());
+        }
+        assert_eq!(1, mock.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn expired_token() {
+        let mock = MockCredential::new(Duration::seconds(-1), false, 0);
+        let credential = BackgroundRefreshCredential::new(mock.clone(), None).unwrap();
+
+        for i in 1..4 {
+            let token = credential.get_token(&["scope"], None).await.unwrap();
+            assert_eq!(i.to_string(), token.token.secret());
+        }
+    }
+}
@@ -1,15 +1,41 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use async_lock::RwLock;
-use azure_core::credentials::{AccessToken, TokenRequestOptions};
-use azure_core::time::{Duration, OffsetDateTime};
+use async_lock::{Mutex, RwLock};
+use azure_core::credentials::{AccessToken, Secret, TokenRequestOptions};
+use azure_core::time::OffsetDateTime;
 use std::collections::HashMap;
 use std::future::Future;
+use std::sync::Arc;
 use tracing::trace;
 
+/// Tokens are keyed by a partition, which is empty unless a credential acquires tokens for
+/// multiple identities, and scopes.
+type CacheKey = (String, Vec<String>);
+
+/// A token cache that persists access and refresh tokens so that processes can share them and
+/// reuse them across restarts.
+///
+/// Creating one requires the `persistent_cache` feature. Without it, the `token_cache` option of
+/// each credential can only be `None`.
+#[cfg(not(feature = "persistent_cache"))]
 #[derive(Debug)]
-pub(crate) struct TokenCache(RwLock<HashMap<Vec<String>, AccessToken>>);
+pub struct PersistentTokenCache {
+    _private: (),
+}
+
+#[derive(Debug)]
+pub(crate) struct TokenCache {
+    tokens: RwLock<HashMap<CacheKey, AccessToken>>,
+    // a lock for each key whose token is being fetched, so that concurrent requests for a key
+    // fetch one token without holding up requests for other keys
+    fetching: std::sync::Mutex<HashMap<CacheKey, Arc<Mutex<()>>>>,
+    // credentials authenticating users store a refresh token here so they can
+    // acquire access tokens for additional scopes without user interaction
+    refresh_token: RwLock<Option<Secret>>,
+    #[cfg(feature = "persistent_cache")]
+    persistence: Option<crate::persistent_cache::Persistence>,
+}
 
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) trait MaybeSend: Send {}
@@ -23,7 +49,80 @@ impl<T> MaybeSend for T {}
 
 impl TokenCache {
     pub(crate) fn new() -> Self {
-        Self(RwLock::new(HashMap::new()))
+        Self {
+            tokens: RwLock::new(HashMap::new()),
+            fetching: std::sync::Mutex::new(HashMap::new()),
+            refresh_token: RwLock::new(None),
+            #[cfg(feature = "persistent_cache")]
+            persistence: None,
+        }
+    }
+
+    /// Creates a cache that also stores tokens in `cache`, if it isn't `None`, so that they
+    /// outlive the credential.
+    #[cfg(feature = "persistent_cache")]
+    pub(crate) fn persistent(
+        cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+        authority_host: &azure_core::http::Url,
+        client_id: &str,
+        tenant_id: &str,
+    ) -> Self {
+        Self {
+            persistence: cache.map(|cache| {
+                crate::persistent_cache::Persistence::new(
+                    cache,
+                    authority_host,
+                    client_id,
+                    tenant_id,
+                )
+            }),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a cache in memory only, because a [`PersistentTokenCache`](crate::PersistentTokenCache)
+    /// can't be created without the `persistent_cache` feature.
+    #[cfg(not(feature = "persistent_cache"))]
+    pub(crate) fn persistent(
+        _cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+        _authority_host: &azure_core::http::Url,
+        _client_id: &str,
+        _tenant_id: &str,
+    ) -> Self {
+        Self::new()
+    }
+
+    /// Gets the most recently stored refresh token, if any.
+    pub(crate) async fn refresh_token(&self) -> Option<Secret> {
+        let refresh_token = self.refresh_token.read().await.clone();
+        #[cfg(feature = "persistent_cache")]
+        if refresh_token.is_none() {
+            if let Some(persistence) = &self.persistence {
+                return persistence.refresh_token().await;
+            }
+        }
+        refresh_token
+    }
+
+    /// Stores a refresh token. `None` leaves any previously stored refresh token in place because
+    /// Entra ID doesn't always return a new refresh token when redeeming one.
+    pub(crate) async fn set_refresh_token(&self, refresh_token: Option<Secret>) {
+        if let Some(refresh_token) = refresh_token {
+            #[cfg(feature = "persistent_cache")]
+            if let Some(persistence) = &self.persistence {
+                persistence.set_refresh_token(&refresh_token).await;
+            }
+            *self.refresh_token.write().await = Some(refresh_token);
+        }
+    }
+
+    /// Removes the stored refresh token, for example because Entra ID rejected it.
+    pub(crate) async fn clear_refresh_token(&self) {
+        *self.refresh_token.write().await = None;
+        #[cfg(feature = "persistent_cache")]
+        if let Some(persistence) = &self.persistence {
+            persistence.clear_refresh_token().await;
+        }
     }
 
     pub(crate) async fn get_token<'a, C, F>(
@@ -36,31 +135,145 @@ impl TokenCache {
         C: FnOnce(&'a [&'a str], Option<TokenRequestOptions<'a>>) -> F + MaybeSend,
         F: Future<Output = azure_core::Result<AccessToken>> + MaybeSend,
     {
-        let token_cache = self.0.read().await;
-        let scopes_owned = scopes.iter().map(ToString::to_string).collect::<Vec<_>>();
-        if let Some(token) = token_cache.get(&scopes_owned) {
-            if !should_refresh(token) {
-                trace!("returning cached token");
-                return Ok(token.clone());
-            }
+        self.get_partitioned_token("", scopes, options, callback)
+            .await
+    }
+
+    /// Like [`TokenCache::get_token`] but caches tokens separately for each `partition`, for
+    /// example to keep tokens acquired on behalf of different users apart.
+    ///
+    /// Returns an error when `options` request a proof-of-possession token. Credentials supporting
+    /// those call [`TokenCache::get_pop_token`] instead.
+    pub(crate) async fn get_partitioned_token<'a, C, F>(
+        &self,
+        partition: &str,
+        scopes: &'a [&'a str],
+        options: Option<TokenRequestOptions<'a>>,
+        callback: C,
+    ) -> azure_core::Result<AccessToken>
+    where
+        C: FnOnce(&'a [&'a str], Option<TokenRequestOptions<'a>>) -> F + MaybeSend,
+        F: Future<Output = azure_core::Result<AccessToken>> + MaybeSend,
+    {
+        crate::pop::reject_pop(options.as_ref())?;
+        self.get_cached_token(partition, scopes, options, callback)
+            .await
+    }
+
+    /// Like [`TokenCache::get_token`] but, when `options` request a proof-of-possession (PoP)
+    /// token, caches the token `callback` returns separately for each PoP key and returns a
+    /// signed HTTP request for the PoP request. `callback` must request a PoP token when
+    /// `options` request one.
+    pub(crate) async fn get_pop_token<'a, C, F>(
+        &self,
+        scopes: &'a [&'a str],
+        options: Option<TokenRequestOptions<'a>>,
+        callback: C,
+    ) -> azure_core::Result<AccessToken>
+    where
+        C: FnOnce(&'a [&'a str], Option<TokenRequestOptions<'a>>) -> F + MaybeSend,
+        F: Future<Output = azure_core::Result<AccessToken>> + MaybeSend,
+    {
+        let Some(pop) = options.as_ref().and_then(|o| o.pop.clone()) else {
+            return self.get_cached_token("", scopes, options, callback).await;
+        };
+        let partition = format!("pop|{}", pop.key.key_id());
+        let token = self
+            .get_cached_token(&partition, scopes, options, callback)
+            .await?;
+        crate::pop::signed_http_request(&token, &pop)
+    }
+
+    async fn get_cached_token<'a, C, F>(
+        &self,
+        partition: &str,
+        scopes: &'a [&'a str],
+        options: Option<TokenRequestOptions<'a>>,
+        callback: C,
+    ) -> azure_core::Result<AccessToken>
+    where
+        C: FnOnce(&'a [&'a str], Option<TokenRequestOptions<'a>>) -> F + MaybeSend,
+        F: Future<Output = azure_core::Result<AccessToken>> + MaybeSend,
+    {
+        let key = (
+            partition.to_string(),
+            scopes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        );
+        if let Some(token) = self.cached_token(&key).await {
+            trace!("returning cached token");
+            return Ok(token);
         }
 
-        // otherwise, drop the read lock and get a write lock to refresh the token
-        drop(token_cache);
-        let mut token_cache = self.0.write().await;
+        // otherwise, take the key's lock so only one request fetches its token
+        let lock = self
+            .fetching
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let guard = lock.lock().await;
+        let result = self.fetch_token(key, scopes, options, callback).await;
+        drop(guard);
+        drop(lock);
+        // forget the locks no request is holding or waiting on
+        self.fetching
+            .lock()
+            .unwrap()
+            .retain(|_, lock| Arc::strong_count(lock) > 1);
+        result
+    }
+
+    /// Returns the token cached for `key`, unless it should be refreshed.
+    async fn cached_token(&self, key: &CacheKey) -> Option<AccessToken> {
+        self.tokens
+            .read()
+            .await
+            .get(key)
+            .filter(|token| !should_refresh(token))
+            .cloned()
+    }
+
+    /// Gets the token for `key` from `callback` and caches it. The caller must hold the key's lock.
+    async fn fetch_token<'a, C, F>(
+        &self,
+        key: CacheKey,
+        scopes: &'a [&'a str],
+        options: Option<TokenRequestOptions<'a>>,
+        callback: C,
+    ) -> azure_core::Result<AccessToken>
+    where
+        C: FnOnce(&'a [&'a str], Option<TokenRequestOptions<'a>>) -> F + MaybeSend,
+        F: Future<Output = azure_core::Result<AccessToken>> + MaybeSend,
+    {
+        // check again in case another request fetched the token while we were waiting on the lock
+        if let Some(token) = self.cached_token(&key).await {
+            trace!("returning token that was updated while waiting on the lock");
+            return Ok(token);
+        }
 
-        // check again in case another thread refreshed the token while we were
-        // waiting on the write lock
-        if let Some(token) = token_cache.get(&scopes_owned) {
-            if !should_refresh(token) {
-                trace!("returning token that was updated while waiting on write lock");
-                return Ok(token.clone());
+        #[cfg(feature = "persistent_cache")]
+        if let Some(persistence) = &self.persistence {
+            if let Some(token) = persistence.access_token(&key.0, &key.1).await {
+                if !should_refresh(&token) {
+                    trace!("returning token from the persistent cache");
+                    self.tokens.write().await.insert(key, token.clone());
+                    return Ok(token);
+                }
             }
         }
 
         trace!("token cache miss");
         let token = callback(scopes, options).await?;
-        token_cache.insert(scopes_owned, token.clone());
+        #[cfg(feature = "persistent_cache")]
+        if let Some(persistence) = &self.persistence {
+            persistence.set_access_token(&key.0, &key.1, &token).await;
+        }
+        // evict expired tokens so the cache doesn't grow without bound when partitions come and go
+        let now = OffsetDateTime::now_utc();
+        let mut token_cache = self.tokens.write().await;
+        token_cache.retain(|_, token| token.expires_on > now);
+        token_cache.insert(key, token.clone());
         Ok(token)
     }
 }
@@ -72,13 +285,12 @@ impl Default for TokenCache {
 }
 
 fn should_refresh(token: &AccessToken) -> bool {
-    token.expires_on <= OffsetDateTime::now_utc() + Duration::seconds(300)
+    token.refresh_time() <= OffsetDateTime::now_utc()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use async_lock::Mutex;
     use azure_core::{
         credentials::Secret,
         time::{Duration, OffsetDateTime},
@@ -114,6 +326,7 @@ mod tests {
                     *call_count
                 )),
                 expires_on: self.token.expires_on,
+                refresh_on: self.token.refresh_on,
             })
         }
     }
@@ -184,4 +397,147 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_refresh_on() -> azure_core::Result<()> {
+        let resource = &[STORAGE_TOKEN_SCOPE];
+        let access_token = "test-token";
+        let mut token_response = AccessToken::new(
+            Secret::new(access_token),
+            OffsetDateTime::now_utc() + Duration::hours(1),
+        );
+        token_response.refresh_on = Some(OffsetDateTime::now_utc() - Duration::seconds(1));
+
+        let mock_credential = MockCredential::new(token_response);
+
+        let cache = TokenCache::new();
+
+        // the token hasn't expired but should be refreshed because its refresh_on has passed
+        for i in 1..3 {
+            let token = cache
+                .get_token(resource, None, |s, o| mock_credential.get_token(s, o))
+                .await?;
+            assert_eq!(
+                token.token.secret(),
+                format!("{}-{}:{}", resource.join(" "), access_token, i)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_partitions() -> azure_core::Result<()> {
+        let resource = &[STORAGE_TOKEN_SCOPE];
+        let expires_on = OffsetDateTime::now_utc() + Duration::seconds(3600);
+        let mock_credential = MockCredential::new(AccessToken::new("test-token", expires_on));
+
+        let cache = TokenCache::new();
+
+        // each partition caches its own token for the same scopes
+        for (partition, expected) in [("a", 1), ("b", 2), ("a", 1), ("", 3), ("b", 2)] {
+            let token = cache
+                .get_partitioned_token(partition, resource, None, |s, o| {
+                    mock_credential.get_token(s, o)
+                })
+                .await?;
+            assert_eq!(
+                token.token.secret(),
+                format!("{}-test-token:{expected}", resource.join(" "))
+            );
+        }
+        let token = cache
+            .get_token(resource, None, |s, o| mock_credential.get_token(s, o))
+            .await?;
+        assert_eq!(
+            token.token.secret(),
+            format!("{}-test-token:3", resource.join(" "))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_doesnt_block_other_keys() -> azure_core::Result<()> {
+        let resource = &[STORAGE_TOKEN_SCOPE];
+        let expires_on = OffsetDateTime::now_utc() + Duration::seconds(3600);
+        let mock_credential = MockCredential::new(AccessToken::new("test-token", expires_on));
+        let (fetched, on_fetched) = futures::channel::oneshot::channel();
+
+        let cache = TokenCache::new();
+
+        // partition "a" gets its token only after partition "b" gets one while a's is being fetched
+        let a = cache.get_partitioned_token("a", resource, None, |s, o| async {
+            on_fetched.await.expect("b's token was fetched");
+            mock_credential.get_token(s, o).await
+        });
+        let b = async {
+            let token = cache
+                .get_partitioned_token("b", resource, None, |s, o| mock_credential.get_token(s, o))
+                .await;
+            fetched.send(()).expect("a is waiting");
+            token
+        };
+        let (a, b) = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            futures::join!(a, b)
+        })
+        .await
+        .expect("fetching a's token doesn't block b");
+        assert_eq!(
+            a?.token.secret(),
+            format!("{}-test-token:2", resource.join(" "))
+        );
+        assert_eq!(
+            b?.token.secret(),
+            format!("{}-test-token:1", resource.join(" "))
+        );
+        assert!(cache.fetching.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pop() -> azure_core::Result<()> {
+        use crate::pop::tests::{pop_options, verify_shr};
+
+        let resource = &[STORAGE_TOKEN_SCOPE];
+        let expires_on = OffsetDateTime::now_utc() + Duration::seconds(3600);
+        let mock_credential = MockCredential::new(AccessToken::new("test-token", expires_on));
+        let pop_request = |nonce| {
+            Some(TokenRequestOptions {
+                pop: Some(pop_options(nonce)),
+                ..Default::default()
+            })
+        };
+
+        let cache = TokenCache::new();
+
+        // credentials that don't support PoP get tokens from get_token or get_partitioned_token
+        cache
+            .get_token(resource, pop_request(None), |s, o| {
+                mock_credential.get_token(s, o)
+            })
+            .await
+            .expect_err("PoP isn't supported");
+
+        // the PoP token is cached apart from bearer tokens and each call signs a new request
+        let expected = format!("{}-test-token:1", resource.join(" "));
+        for nonce in [None, Some("nonce")] {
+            let token = cache
+                .get_pop_token(resource, pop_request(nonce), |s, o| {
+                    mock_credential.get_token(s, o)
+                })
+                .await?;
+            verify_shr(token.token.secret(), &expected, nonce);
+        }
+        let token = cache
+            .get_pop_token(resource, None, |s, o| mock_credential.get_token(s, o))
+            .await?;
+        assert_eq!(
+            token.token.secret(),
+            format!("{}-test-token:2", resource.join(" "))
+        );
+
+        Ok(())
+    }
 }
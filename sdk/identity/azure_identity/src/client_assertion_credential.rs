@@ -43,6 +43,10 @@ pub struct ClientAssertionCredentialOptions {
 
     /// Options for the credential's HTTP pipeline.
     pub client_options: ClientOptions,
+
+    /// A persistent cache in which the credential stores tokens, so they can be shared with other
+    /// processes and reused after the credential is dropped. See [`PersistentTokenCache`](crate::PersistentTokenCache).
+    pub token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
@@ -89,6 +93,8 @@ impl<C: ClientAssertion> ClientAssertionCredential<C> {
             .with_context_fn(ErrorKind::DataConversion, || {
                 format!("tenant_id {tenant_id} could not be URL encoded")
             })?;
+        let cache =
+            TokenCache::persistent(options.token_cache, &authority_host, &client_id, &tenant_id);
         let pipeline = Pipeline::new(
             option_env!("CARGO_PKG_NAME"),
             option_env!("CARGO_PKG_VERSION"),
@@ -102,7 +108,7 @@ impl<C: ClientAssertion> ClientAssertionCredential<C> {
             client_id,
             assertion,
             endpoint,
-            cache: TokenCache::new(),
+            cache,
             pipeline,
         })
     }
@@ -122,14 +128,20 @@ impl<C: ClientAssertion> ClientAssertionCredential<C> {
             .assertion
             .secret(Some(options.method_options.to_owned()))
             .await?;
-        let encoded: String = form_urlencoded::Serializer::new(String::new())
-            .append_pair("client_assertion", assertion.as_str())
-            .append_pair("client_assertion_type", ASSERTION_TYPE)
-            .append_pair("client_id", self.client_id.as_str())
-            .append_pair("grant_type", "client_credentials")
-            .append_pair("scope", &scopes.join(" "))
-            .finish();
-        req.set_body(encoded);
+        let body = {
+            let mut encoded = form_urlencoded::Serializer::new(String::new());
+            encoded
+                .append_pair("client_assertion", assertion.as_str())
+                .append_pair("client_assertion_type", ASSERTION_TYPE)
+                .append_pair("client_id", self.client_id.as_str())
+                .append_pair("grant_type", "client_credentials")
+                .append_pair("scope", &scopes.join(" "));
+            if let Some(pop) = &options.pop {
+                encoded.extend_pairs(crate::pop::token_request_params(pop));
+            }
+            encoded.finish()
+        };
+        req.set_body(body);
 
         let ctx = options.method_options.context.to_borrowed();
         let res = self
@@ -157,7 +169,7 @@ impl<C: ClientAssertion> TokenCredential for ClientAssertionCredential<C> {
         options: Option<TokenRequestOptions<'_>>,
     ) -> azure_core::Result<AccessToken> {
         self.cache
-            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .get_pop_token(scopes, options, |s, o| self.get_token_impl(s, o))
             .await
             .map_err(|err| crate::authentication_error(self.name, err))
     }
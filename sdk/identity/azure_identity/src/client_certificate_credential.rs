@@ -3,7 +3,7 @@
 
 use crate::{
     authentication_error, env::Env, get_authority_host, validate_not_empty, validate_tenant_id,
-    TokenCache,
+    Authority, TokenCache,
 };
 use azure_core::{
     base64,
@@ -18,23 +18,44 @@ use azure_core::{
     Uuid,
 };
 
-// cspell:ignore pkey
-use openssl::{
-    error::ErrorStack,
-    hash::MessageDigest,
-    pkcs12::Pkcs12,
-    pkey::{Id, PKey, Private},
-    sign::Signer,
-    x509::X509,
+use p12_keystore::KeyStore;
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs1v15,
+    pkcs8::{DecodePrivateKey, DecodePublicKey, EncryptedPrivateKeyInfo},
+    pss,
+    rand_core::OsRng,
+    signature::{RandomizedSigner, SignatureEncoding, Signer},
+    RsaPrivateKey, RsaPublicKey,
 };
-use std::sync::Arc;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::{fmt, sync::Arc};
 use url::form_urlencoded;
+use x509_cert::{
+    der::{pem, Decode, Encode},
+    Certificate,
+};
 
 /// Refresh time to use in seconds.
 const DEFAULT_REFRESH_TIME: i64 = 300;
 
 const AZURE_CLIENT_SEND_CERTIFICATE_CHAIN_ENV_KEY: &str = "AZURE_CLIENT_SEND_CERTIFICATE_CHAIN";
 
+/// A private key, the DER encoding of its certificate, and the DER encoding of any other certificates in the chain.
+type ParsedCertificate = (RsaPrivateKey, Vec<u8>, Option<Vec<Vec<u8>>>);
+
+/// The algorithm with which a [`ClientCertificateCredential`] signs client assertions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CertificateSigningAlgorithm {
+    /// RSASSA-PKCS1-v1_5 with SHA-256.
+    #[default]
+    Rs256,
+
+    /// RSASSA-PSS with SHA-256.
+    Ps256,
+}
+
 /// Provides options to configure how the Identity library makes authentication
 /// requests to Azure Active Directory.
 #[derive(Clone, Debug, Default)]
@@ -42,9 +63,28 @@ pub struct ClientCertificateCredentialOptions {
     /// Options for the credential's HTTP pipeline.
     pub client_options: ClientOptions,
 
-    /// The password for the certificate.
+    /// The password for the certificate, or for the private key of a PEM certificate.
     pub password: Option<Secret>,
 
+    /// Whether to include the certificate chain in client assertions (the `x5c` header), which
+    /// subject name/issuer authentication requires.
+    ///
+    /// The credential also sends the chain when environment variable `AZURE_CLIENT_SEND_CERTIFICATE_CHAIN`
+    /// is "1" or "true".
+    pub send_certificate_chain: bool,
+
+    /// The algorithm with which to sign client assertions. The default is [`CertificateSigningAlgorithm::Rs256`].
+    pub signing_algorithm: CertificateSigningAlgorithm,
+
+    /// Whether to skip validating the authority host with Microsoft Entra instance discovery
+    /// before requesting a token. Set this when authenticating with a private cloud or other
+    /// authority Microsoft Entra ID doesn't know. AD FS authorities aren't validated.
+    pub disable_instance_discovery: bool,
+
+    /// A persistent cache in which the credential stores tokens, so they can be shared with other
+    /// processes and reused after the credential is dropped. See [`PersistentTokenCache`](crate::PersistentTokenCache).
+    pub token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+
     #[cfg(test)]
     pub(crate) env: Option<Env>,
 }
@@ -52,20 +92,44 @@ pub struct ClientCertificateCredentialOptions {
 /// Enables authentication to Azure Active Directory using a client certificate that
 /// was generated for an App Registration.
 ///
-/// In order to use subject name validation `send_cert_chain` option must be set to true
-/// The certificate is expected to be in base64 encoded PKCS12 format.
-#[derive(Debug)]
+/// The certificate may be PEM, containing the certificate, any intermediate certificates and
+/// the private key, which may be encrypted, or base64 encoded PKCS12. Only RSA keys are supported.
+///
+/// In order to use subject name/issuer authentication, set the `send_certificate_chain` option.
 pub struct ClientCertificateCredential {
     client_id: String,
-    key: PKey<Private>,
+    key: RsaPrivateKey,
+    algorithm: CertificateSigningAlgorithm,
+    authority: Authority,
     endpoint: Url,
     pipeline: Pipeline,
     header: String,
     cache: TokenCache,
 }
 
+impl fmt::Debug for ClientCertificateCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // omits the private key
+        f.debug_struct("ClientCertificateCredential")
+            .field("client_id", &self.client_id)
+            .field("algorithm", &self.algorithm)
+            .field("authority", &self.authority)
+            .field("endpoint", &self.endpoint)
+            .field("pipeline", &self.pipeline)
+            .field("header", &self.header)
+            .field("cache", &self.cache)
+            .finish_non_exhaustive()
+    }
+}
+
 impl ClientCertificateCredential {
     /// Create a new `ClientCertificateCredential`.
+    ///
+    /// # Arguments
+    /// - `tenant_id`: The Azure Active Directory tenant (directory) ID of the service principal.
+    /// - `client_id`: The client (application) ID of the service principal.
+    /// - `certificate`: A PEM certificate with its private key, or a base64 encoded PKCS12 certificate.
+    /// - `options`: Options for configuring the credential. If `None` is provided, default options will be used.
     pub fn new(
         tenant_id: String,
         client_id: String,
@@ -77,51 +141,43 @@ impl ClientCertificateCredential {
 
         let options = options.unwrap_or_default();
 
-        let cert_bytes = base64::decode(certificate.into().secret())
-            .with_context_fn(ErrorKind::Credential, || {
-                "failed to decode base64 certificate data"
-            })?;
-
-        let (key, cert, ca_chain) = parse_certificate(&cert_bytes, options.password.as_ref())?;
-        let thumbprint = cert
-            .digest(MessageDigest::sha1())
-            .with_context(ErrorKind::Credential, "failed to compute thumbprint")?
-            .to_vec();
-        let thumbprint = base64::encode(thumbprint);
+        let certificate = certificate.into();
+        let (key, cert, ca_chain) = if is_pem(certificate.secret()) {
+            parse_pem(certificate.secret(), options.password.as_ref())?
+        } else {
+            let cert_bytes = base64::decode(certificate.secret())
+                .with_context_fn(ErrorKind::Credential, || {
+                    "failed to decode base64 certificate data"
+                })?;
+            parse_certificate(&cert_bytes, options.password.as_ref())?
+        };
 
         #[cfg(test)]
         let env = options.env.unwrap_or_default();
         #[cfg(not(test))]
         let env = Env::default();
 
-        let send_x5c = env
-            .var(AZURE_CLIENT_SEND_CERTIFICATE_CHAIN_ENV_KEY)
-            .map(|s| s == "1" || s.eq_ignore_ascii_case("true"))
-            .unwrap_or(false);
-        let header = if send_x5c {
-            let base_signature = get_encoded_cert(&cert)?;
-            let x5c = match &ca_chain {
-                Some(chain) => {
-                    let chain = chain
-                        .iter()
-                        .map(get_encoded_cert)
-                        .collect::<azure_core::Result<Vec<String>>>()?
-                        .join(",");
-                    format!("{base_signature},{chain}")
-                }
-                None => base_signature,
-            };
-            format!(r#"{{"alg":"RS256","typ":"JWT","x5c":[{x5c}],"x5t":"{thumbprint}"}}"#)
-        } else {
-            format!(r#"{{"alg":"RS256","typ":"JWT","x5t":"{thumbprint}"}}"#)
-        };
+        let send_x5c = options.send_certificate_chain
+            || env
+                .var(AZURE_CLIENT_SEND_CERTIFICATE_CHAIN_ENV_KEY)
+                .map(|s| s == "1" || s.eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
+        let header = header(
+            &cert,
+            send_x5c.then_some(ca_chain.as_deref().unwrap_or_default()),
+            options.signing_algorithm,
+        )?;
 
         let authority_host = get_authority_host(None, options.client_options.cloud.as_deref())?;
-        let endpoint = authority_host
-            .join(&format!("/{tenant_id}/oauth2/v2.0/token"))
-            .with_context_fn(ErrorKind::DataConversion, || {
-                format!("tenant_id '{tenant_id}' could not be URL encoded")
-            })?;
+        let authority = Authority::new(
+            authority_host.clone(),
+            &tenant_id,
+            options.disable_instance_discovery,
+        );
+        let endpoint = authority.token_endpoint()?;
+
+        let cache =
+            TokenCache::persistent(options.token_cache, &authority_host, &client_id, &tenant_id);
 
         let pipeline = Pipeline::new(
             option_env!("CARGO_PKG_NAME"),
@@ -135,28 +191,49 @@ impl ClientCertificateCredential {
         Ok(Arc::new(ClientCertificateCredential {
             client_id,
             key,
+            algorithm: options.signing_algorithm,
+            authority,
             endpoint,
             pipeline,
             header: ClientCertificateCredential::as_jwt_part(header.as_bytes()),
-            cache: TokenCache::new(),
+            cache,
         }))
     }
 
-    fn sign(jwt: &str, pkey: &PKey<Private>) -> Result<Vec<u8>, ErrorStack> {
-        let mut signer = Signer::new(MessageDigest::sha256(), pkey)?;
-        signer.update(jwt.as_bytes())?;
-        signer.sign_to_vec()
+    fn sign(
+        jwt: &str,
+        key: &RsaPrivateKey,
+        algorithm: CertificateSigningAlgorithm,
+    ) -> Result<Vec<u8>, rsa::signature::Error> {
+        match algorithm {
+            CertificateSigningAlgorithm::Rs256 => pkcs1v15::SigningKey::<Sha256>::new(key.clone())
+                .try_sign(jwt.as_bytes())
+                .map(|signature| signature.to_vec()),
+            // the salt is as long as the digest, as JWS requires
+            CertificateSigningAlgorithm::Ps256 => {
+                pss::BlindedSigningKey::<Sha256>::new(key.clone())
+                    .try_sign_with_rng(&mut OsRng, jwt.as_bytes())
+                    .map(|signature| signature.to_vec())
+            }
+        }
     }
 
     fn as_jwt_part(part: &[u8]) -> String {
         base64::encode_url_safe(part)
     }
 
-    async fn get_token_impl(
-        &self,
-        scopes: &[&str],
-        options: Option<TokenRequestOptions<'_>>,
-    ) -> azure_core::Result<AccessToken> {
+    /// The client ID of the application this credential authenticates.
+    pub(crate) fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// The token endpoint, which is the audience of the credential's client assertions.
+    pub(crate) fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
+    /// Creates a client assertion signed with the certificate's private key.
+    pub(crate) fn client_assertion(&self) -> azure_core::Result<String> {
         let uuid = Uuid::new_v4();
         let current_time = OffsetDateTime::now_utc().unix_timestamp();
         let expiry_time = current_time + DEFAULT_REFRESH_TIME;
@@ -167,10 +244,19 @@ impl ClientCertificateCredential {
         let payload = ClientCertificateCredential::as_jwt_part(payload.as_bytes());
 
         let jwt = format!("{}.{}", self.header, payload);
-        let signature = ClientCertificateCredential::sign(&jwt, &self.key)
+        let signature = ClientCertificateCredential::sign(&jwt, &self.key, self.algorithm)
             .with_context(ErrorKind::Credential, "failed to sign JWT")?;
         let sig = ClientCertificateCredential::as_jwt_part(&signature);
-        let client_assertion = format!("{}.{}", jwt, sig);
+        Ok(format!("{}.{}", jwt, sig))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let client_assertion = self.client_assertion()?;
+        let options = options.unwrap_or_default();
 
         let encoded = {
             let mut encoded = &mut form_urlencoded::Serializer::new(String::new());
@@ -183,6 +269,9 @@ impl ClientCertificateCredential {
                 )
                 .append_pair("client_assertion", client_assertion.as_str())
                 .append_pair("grant_type", "client_credentials");
+            if let Some(pop) = &options.pop {
+                encoded = encoded.extend_pairs(crate::pop::token_request_params(pop));
+            }
             encoded.finish()
         };
 
@@ -193,8 +282,8 @@ impl ClientCertificateCredential {
         );
         req.set_body(encoded);
 
-        let options = options.unwrap_or_default();
         let ctx = options.method_options.context.to_borrowed();
+        self.authority.validate(&self.pipeline, &ctx).await?;
         let rsp = self
             .pipeline
             .send(
@@ -211,56 +300,181 @@ impl ClientCertificateCredential {
     }
 }
 
-/// Parse a base64-encoded PKCS12 certificate into key, certificate, and optional CA chain.
+/// Whether `certificate` is PEM rather than base64 encoded PKCS12.
+fn is_pem(certificate: &str) -> bool {
+    certificate.contains("-----BEGIN ")
+}
+
+/// Parse a PKCS12 certificate into key, certificate, and optional CA chain.
 fn parse_certificate(
     cert_bytes: &[u8],
     password: Option<&Secret>,
-) -> azure_core::Result<(PKey<Private>, X509, Option<Vec<X509>>)> {
-    let pkcs12 = Pkcs12::from_der(cert_bytes).with_context(
-        ErrorKind::Credential,
-        "deserializing PKCS12 from DER failed",
-    )?;
-    let parsed = pkcs12
-        .parse2(password.map(|p| p.secret()).unwrap_or(""))
-        .with_context(ErrorKind::Credential, "PKCS12 parsing failed")?;
-    let key = parsed.pkey.ok_or_else(|| {
+) -> azure_core::Result<ParsedCertificate> {
+    let keystore =
+        KeyStore::from_pkcs12(cert_bytes, password.map(|p| p.secret()).unwrap_or_default())
+            .with_context(ErrorKind::Credential, "PKCS12 parsing failed")?;
+    let (_, key_chain) = keystore.private_key_chain().ok_or_else(|| {
         Error::with_message(
             ErrorKind::Credential,
-            "PKCS12 bundle contains no private key",
+            "PKCS12 bundle contains no private key with a certificate",
         )
     })?;
-    if key.id() != Id::RSA {
-        return Err(Error::with_message(
-            ErrorKind::Credential,
-            "only RSA private keys are supported",
-        ));
-    }
-    let cert = parsed.cert.ok_or_else(|| {
+    let key = RsaPrivateKey::from_pkcs8_der(key_chain.key()).map_err(|_| unsupported_key())?;
+    // the chain starts with the key's certificate
+    let mut certs = key_chain.chain().iter().map(|cert| cert.as_der().to_vec());
+    let cert = certs.next().ok_or_else(|| {
         Error::with_message(
             ErrorKind::Credential,
             "PKCS12 bundle contains no certificate",
         )
     })?;
-    let ca_chain = parsed.ca.and_then(|stack| {
-        let certs: Vec<X509> = stack.into_iter().collect();
-        if certs.is_empty() {
-            None
-        } else {
-            Some(certs)
-        }
-    });
+    let ca_chain: Vec<Vec<u8>> = certs.collect();
+    let ca_chain = if ca_chain.is_empty() {
+        None
+    } else {
+        Some(ca_chain)
+    };
 
     Ok((key, cert, ca_chain))
 }
 
-fn get_encoded_cert(cert: &X509) -> azure_core::Result<String> {
-    Ok(format!(
-        "\"{}\"",
-        base64::encode(
-            cert.to_pem()
-                .with_context(ErrorKind::Credential, "PEM encoding failed")?
+/// Parse PEM containing a private key and certificates into key, the key's certificate, and
+/// optional CA chain. The key may be encrypted with `password`.
+fn parse_pem(pem: &str, password: Option<&Secret>) -> azure_core::Result<ParsedCertificate> {
+    let mut key = None;
+    let mut certs = Vec::new();
+    for (label, der) in pem_blocks(pem)? {
+        match label.as_str() {
+            "CERTIFICATE" => certs.push(der),
+            "PRIVATE KEY" => {
+                key = Some(RsaPrivateKey::from_pkcs8_der(&der).map_err(|_| unsupported_key())?);
+            }
+            "ENCRYPTED PRIVATE KEY" => {
+                let password = password.map(|p| p.secret()).unwrap_or_default();
+                let decrypted = EncryptedPrivateKeyInfo::try_from(der.as_slice())
+                    .and_then(|info| info.decrypt(password))
+                    .with_context(
+                        ErrorKind::Credential,
+                        "the PEM certificate's private key couldn't be decrypted",
+                    )?;
+                key = Some(
+                    RsaPrivateKey::from_pkcs8_der(decrypted.as_bytes())
+                        .map_err(|_| unsupported_key())?,
+                );
+            }
+            "RSA PRIVATE KEY" => {
+                key = Some(RsaPrivateKey::from_pkcs1_der(&der).with_context(
+                    ErrorKind::Credential,
+                    "failed to parse the PEM certificate's private key",
+                )?);
+            }
+            _ => {}
+        }
+    }
+    let key = key.ok_or_else(|| {
+        Error::with_message(
+            ErrorKind::Credential,
+            "PEM certificate contains no private key",
         )
-    ))
+    })?;
+
+    let public_key = key.to_public_key();
+    let position = certs
+        .iter()
+        .position(|cert| certificate_public_key(cert).is_some_and(|k| k == public_key))
+        .ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Credential,
+                "PEM certificate contains no certificate for the private key",
+            )
+        })?;
+    let cert = certs.remove(position);
+    let ca_chain = if certs.is_empty() { None } else { Some(certs) };
+
+    Ok((key, cert, ca_chain))
+}
+
+/// Returns the label and decoded contents of each block in `pem`, ignoring text between blocks.
+fn pem_blocks(pem: &str) -> azure_core::Result<Vec<(String, Vec<u8>)>> {
+    const BEGIN: &str = "-----BEGIN ";
+    let mut blocks = Vec::new();
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        rest = &rest[start..];
+        let label = rest[BEGIN.len()..]
+            .split_once("-----")
+            .map(|(label, _)| label)
+            .unwrap_or_default();
+        let end_boundary = format!("-----END {label}-----");
+        let end = rest
+            .find(&end_boundary)
+            .map(|end| end + end_boundary.len())
+            .ok_or_else(|| {
+                Error::with_message_fn(ErrorKind::Credential, || {
+                    format!("PEM certificate has no end to its {label} block")
+                })
+            })?;
+        let (label, der) = pem::decode_vec(&rest.as_bytes()[..end]).map_err(|err| {
+            Error::with_message_fn(ErrorKind::Credential, || {
+                format!("failed to decode the PEM certificate's {label} block: {err}")
+            })
+        })?;
+        blocks.push((label.to_string(), der));
+        rest = &rest[end..];
+    }
+    Ok(blocks)
+}
+
+/// Returns the RSA public key of a DER encoded certificate, or `None` if it has another kind of key.
+fn certificate_public_key(cert: &[u8]) -> Option<RsaPublicKey> {
+    let cert = Certificate::from_der(cert).ok()?;
+    let spki = cert.tbs_certificate.subject_public_key_info.to_der().ok()?;
+    RsaPublicKey::from_public_key_der(&spki).ok()
+}
+
+fn unsupported_key() -> Error {
+    Error::with_message(
+        ErrorKind::Credential,
+        "failed to parse the private key. Only RSA private keys are supported",
+    )
+}
+
+/// Returns the JSON header of the credential's client assertions. `chain` is the certificate
+/// chain, excluding `cert`, to send in the `x5c` header or `None` to omit that header. Certificates
+/// are DER encoded.
+fn header(
+    cert: &[u8],
+    chain: Option<&[Vec<u8>]>,
+    algorithm: CertificateSigningAlgorithm,
+) -> azure_core::Result<String> {
+    // Entra ID identifies the certificate by its SHA-1 thumbprint for RS256 and by its SHA-256
+    // thumbprint for PS256
+    let (alg, thumbprint_header, thumbprint) = match algorithm {
+        CertificateSigningAlgorithm::Rs256 => ("RS256", "x5t", Sha1::digest(cert).to_vec()),
+        CertificateSigningAlgorithm::Ps256 => ("PS256", "x5t#S256", Sha256::digest(cert).to_vec()),
+    };
+    let thumbprint = base64::encode(thumbprint);
+
+    match chain {
+        Some(chain) => {
+            let x5c = std::iter::once(cert)
+                .chain(chain.iter().map(Vec::as_slice))
+                .map(get_encoded_cert)
+                .collect::<Vec<String>>()
+                .join(",");
+            Ok(format!(
+                r#"{{"alg":"{alg}","typ":"JWT","x5c":[{x5c}],"{thumbprint_header}":"{thumbprint}"}}"#
+            ))
+        }
+        None => Ok(format!(
+            r#"{{"alg":"{alg}","typ":"JWT","{thumbprint_header}":"{thumbprint}"}}"#
+        )),
+    }
+}
+
+/// Encodes a DER encoded certificate for the `x5c` header, which is a JSON array of base64 encoded DER certificates.
+fn get_encoded_cert(cert: &[u8]) -> String {
+    format!("\"{}\"", base64::encode(cert))
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
@@ -278,7 +492,7 @@ impl TokenCredential for ClientCertificateCredential {
             ));
         }
         self.cache
-            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .get_pop_token(scopes, options, |s, o| self.get_token_impl(s, o))
             .await
             .map_err(|err| authentication_error(stringify!(ClientCertificateCredential), err))
     }
@@ -296,7 +510,11 @@ mod tests {
         },
         Bytes,
     };
-    use openssl::{pkey::Public, sign::Verifier};
+    use rsa::{
+        pkcs1::EncodeRsaPrivateKey,
+        pkcs8::{pkcs5, EncodePrivateKey, LineEnding, PrivateKeyInfo},
+        signature::Verifier,
+    };
     use std::{
         collections::HashMap,
         sync::{Arc, LazyLock},
@@ -311,23 +529,30 @@ mod tests {
         base64::encode(pfx)
     });
 
+    /// Returns the test certificate's key and certificate.
+    fn test_key_and_cert() -> (RsaPrivateKey, Vec<u8>) {
+        let pfx = base64::decode(TEST_CERT.as_str()).expect("base64 encoding");
+        let (key, cert, _) = parse_certificate(&pfx, None).expect("valid certificate");
+        (key, cert)
+    }
+
     #[derive(Debug, Clone)]
     struct VerifyAssertionPolicy {
-        public_key: PKey<Public>,
+        public_key: RsaPublicKey,
         cert_der: Vec<u8>,
         expect_x5c: bool,
+        algorithm: CertificateSigningAlgorithm,
     }
 
     impl VerifyAssertionPolicy {
-        fn new(certificate: String, expect_x5c: bool) -> Self {
-            let pfx = base64::decode(certificate).expect("base64 encoding");
-            let (_, cert, _) = parse_certificate(&pfx, None).expect("valid certificate");
-            let public_key = cert.public_key().expect("public key");
-            let cert_der = cert.to_der().expect("valid certificate");
+        fn new(expect_x5c: bool, algorithm: CertificateSigningAlgorithm) -> Self {
+            let (_, cert_der) = test_key_and_cert();
+            let public_key = certificate_public_key(&cert_der).expect("public key");
             Self {
                 public_key,
                 cert_der,
                 expect_x5c,
+                algorithm,
             }
         }
     }
@@ -380,14 +605,9 @@ mod tests {
                 let x5c_cert_base64 = x5c_array[0]
                     .as_str()
                     .expect("x5c certificate should be a string");
-                let x5c_cert_pem = base64::decode(x5c_cert_base64)
+                let x5c_der = base64::decode(x5c_cert_base64)
                     .expect("x5c certificate should be valid base64");
-                let x5c_cert =
-                    X509::from_pem(&x5c_cert_pem).expect("x5c certificate should be valid PEM");
-
-                let x5c_der = x5c_cert
-                    .to_der()
-                    .expect("x5c certificate should convert to DER");
+                Certificate::from_der(&x5c_der).expect("x5c certificate should be valid DER");
                 assert_eq!(
                     self.cert_der, x5c_der,
                     "the first certificate in x5c should match the certificate provided to ClientCertificateCredential::new()"
@@ -399,21 +619,38 @@ mod tests {
                 );
             }
 
+            let (alg, thumbprint_header) = match self.algorithm {
+                CertificateSigningAlgorithm::Rs256 => ("RS256", "x5t"),
+                CertificateSigningAlgorithm::Ps256 => ("PS256", "x5t#S256"),
+            };
+            assert_eq!(Some(alg), header_json["alg"].as_str());
+            assert!(
+                header_json.get(thumbprint_header).is_some(),
+                "header should contain {thumbprint_header}"
+            );
+
             let signature_bytes =
                 base64::decode_url_safe(signature).expect("signature should be base64url encoded");
 
-            let mut verifier =
-                Verifier::new(openssl::hash::MessageDigest::sha256(), &self.public_key)
-                    .expect("verifier creation should succeed");
-            verifier
-                .update(format!("{header}.{payload}").as_bytes())
-                .expect("verifier update should succeed");
-            let verified = verifier
-                .verify(&signature_bytes)
-                .expect("verification should complete");
-
+            let signed = format!("{header}.{payload}");
+            let verified = match self.algorithm {
+                CertificateSigningAlgorithm::Rs256 => {
+                    pkcs1v15::VerifyingKey::<Sha256>::new(self.public_key.clone()).verify(
+                        signed.as_bytes(),
+                        &pkcs1v15::Signature::try_from(signature_bytes.as_slice())
+                            .expect("valid signature"),
+                    )
+                }
+                CertificateSigningAlgorithm::Ps256 => {
+                    pss::VerifyingKey::<Sha256>::new(self.public_key.clone()).verify(
+                        signed.as_bytes(),
+                        &pss::Signature::try_from(signature_bytes.as_slice())
+                            .expect("valid signature"),
+                    )
+                }
+            };
             assert!(
-                verified,
+                verified.is_ok(),
                 "JWT signature should verify with the certificate's public key"
             );
 
@@ -438,6 +675,7 @@ mod tests {
                         cloud: Some(Arc::new(cloud)),
                         ..Default::default()
                     },
+                    disable_instance_discovery: true,
                     ..Default::default()
                 }),
             )
@@ -524,8 +762,8 @@ mod tests {
                 client_options: ClientOptions {
                     transport: Some(Transport::new(Arc::new(sts))),
                     per_try_policies: vec![Arc::new(VerifyAssertionPolicy::new(
-                        TEST_CERT.to_string(),
                         false,
+                        CertificateSigningAlgorithm::Rs256,
                     ))],
                     ..Default::default()
                 },
@@ -591,6 +829,143 @@ mod tests {
         .expect_err("no scopes provided");
     }
 
+    /// Gets a token with `certificate`, verifying the client assertion.
+    async fn run_assertion_test(
+        certificate: String,
+        options: ClientCertificateCredentialOptions,
+        expect_x5c: bool,
+    ) {
+        let sts = MockSts::new(
+            vec![token_response()],
+            Some(Arc::new(is_valid_request(
+                FAKE_PUBLIC_CLOUD_AUTHORITY.to_string(),
+                None,
+            ))),
+        );
+        let algorithm = options.signing_algorithm;
+        let credential = ClientCertificateCredential::new(
+            FAKE_TENANT_ID.to_string(),
+            FAKE_CLIENT_ID.to_string(),
+            certificate,
+            Some(ClientCertificateCredentialOptions {
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(sts))),
+                    per_try_policies: vec![Arc::new(VerifyAssertionPolicy::new(
+                        expect_x5c, algorithm,
+                    ))],
+                    ..Default::default()
+                },
+                ..options
+            }),
+        )
+        .expect("valid credential");
+
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+    }
+
+    #[tokio::test]
+    async fn pem() {
+        let (key, cert) = test_key_and_cert();
+        // the key may precede or follow the certificate and be PKCS#8 or PKCS#1
+        let pkcs1_key = key.to_pkcs1_pem(LineEnding::LF).unwrap().to_string();
+        let key = key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string();
+        let cert = pem::encode_string("CERTIFICATE", LineEnding::LF, &cert).unwrap();
+        for pem in [
+            format!("{key}{cert}"),
+            format!("{cert}{key}"),
+            format!("{cert}{pkcs1_key}"),
+        ] {
+            run_assertion_test(pem, ClientCertificateCredentialOptions::default(), false).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn pem_encrypted_key() {
+        let (key, cert) = test_key_and_cert();
+        // PBKDF2 like OpenSSL, because the default scrypt parameters are slow in debug builds
+        let params =
+            pkcs5::pbes2::Parameters::pbkdf2_sha256_aes256cbc(2048, &[1; 16], &[2; 16]).unwrap();
+        let key = PrivateKeyInfo::try_from(key.to_pkcs8_der().unwrap().as_bytes())
+            .unwrap()
+            .encrypt_with_params(params, "password")
+            .unwrap()
+            .to_pem("ENCRYPTED PRIVATE KEY", LineEnding::LF)
+            .unwrap();
+        let pem = format!(
+            "{}{}",
+            pem::encode_string("CERTIFICATE", LineEnding::LF, &cert).unwrap(),
+            key.as_str()
+        );
+
+        for password in [None, Some("wrong")] {
+            ClientCertificateCredential::new(
+                FAKE_TENANT_ID.to_string(),
+                FAKE_CLIENT_ID.to_string(),
+                pem.clone(),
+                Some(ClientCertificateCredentialOptions {
+                    password: password.map(Secret::from),
+                    ..Default::default()
+                }),
+            )
+            .expect_err("missing or wrong password");
+        }
+
+        run_assertion_test(
+            pem,
+            ClientCertificateCredentialOptions {
+                password: Some(Secret::from("password")),
+                ..Default::default()
+            },
+            false,
+        )
+        .await;
+    }
+
+    #[test]
+    fn pem_without_matching_certificate() {
+        let (key, _) = test_key_and_cert();
+        let key = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let err = ClientCertificateCredential::new(
+            FAKE_TENANT_ID.to_string(),
+            FAKE_CLIENT_ID.to_string(),
+            key.to_string(),
+            None,
+        )
+        .expect_err("no certificate");
+        assert!(err.to_string().contains("no certificate"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn ps256() {
+        run_assertion_test(
+            TEST_CERT.to_string(),
+            ClientCertificateCredentialOptions {
+                signing_algorithm: CertificateSigningAlgorithm::Ps256,
+                send_certificate_chain: true,
+                ..Default::default()
+            },
+            true,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn send_certificate_chain() {
+        run_assertion_test(
+            TEST_CERT.to_string(),
+            ClientCertificateCredentialOptions {
+                send_certificate_chain: true,
+                ..Default::default()
+            },
+            true,
+        )
+        .await;
+    }
+
     #[tokio::test]
     async fn sni() {
         let sts = MockSts::new(
@@ -608,8 +983,8 @@ mod tests {
                 client_options: ClientOptions {
                     transport: Some(Transport::new(Arc::new(sts))),
                     per_try_policies: vec![Arc::new(VerifyAssertionPolicy::new(
-                        TEST_CERT.to_string(),
                         true,
+                        CertificateSigningAlgorithm::Rs256,
                     ))],
                     ..Default::default()
                 },
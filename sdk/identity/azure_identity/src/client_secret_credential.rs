@@ -1,13 +1,13 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use crate::{authentication_error, get_authority_host, TokenCache};
+use crate::{authentication_error, get_authority_host, Authority, TokenCache};
 use azure_core::credentials::TokenRequestOptions;
 use azure_core::http::PipelineSendOptions;
 use azure_core::Result;
 use azure_core::{
     credentials::{AccessToken, Secret, TokenCredential},
-    error::{ErrorKind, ResultExt},
+    error::ErrorKind,
     http::{
         headers::{self, content_type},
         ClientOptions, Method, Pipeline, Request, Url,
@@ -22,11 +22,21 @@ use url::form_urlencoded;
 pub struct ClientSecretCredentialOptions {
     /// Options for constructing credentials.
     pub client_options: ClientOptions,
+
+    /// Whether to skip validating the authority host with Microsoft Entra instance discovery
+    /// before requesting a token. Set this when authenticating with a private cloud or other
+    /// authority Microsoft Entra ID doesn't know. AD FS authorities aren't validated.
+    pub disable_instance_discovery: bool,
+
+    /// A persistent cache in which the credential stores tokens, so they can be shared with other
+    /// processes and reused after the credential is dropped. See [`PersistentTokenCache`](crate::PersistentTokenCache).
+    pub token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
 }
 
 /// Authenticates an application with a client secret.
 #[derive(Debug)]
 pub struct ClientSecretCredential {
+    authority: Authority,
     cache: TokenCache,
     client_id: String,
     endpoint: Url,
@@ -55,11 +65,15 @@ impl ClientSecretCredential {
 
         let options = options.unwrap_or_default();
         let authority_host = get_authority_host(None, options.client_options.cloud.as_deref())?;
-        let endpoint = authority_host
-            .join(&format!("/{tenant_id}/oauth2/v2.0/token"))
-            .with_context_fn(ErrorKind::DataConversion, || {
-                format!("tenant_id '{tenant_id}' could not be URL encoded")
-            })?;
+        let authority = Authority::new(
+            authority_host.clone(),
+            tenant_id,
+            options.disable_instance_discovery,
+        );
+        let endpoint = authority.token_endpoint()?;
+
+        let cache =
+            TokenCache::persistent(options.token_cache, &authority_host, &client_id, tenant_id);
 
         let pipeline = Pipeline::new(
             option_env!("CARGO_PKG_NAME"),
@@ -71,7 +85,8 @@ impl ClientSecretCredential {
         );
 
         Ok(Arc::new(Self {
-            cache: TokenCache::new(),
+            authority,
+            cache,
             client_id,
             endpoint,
             pipeline,
@@ -89,16 +104,22 @@ impl ClientSecretCredential {
             headers::CONTENT_TYPE,
             content_type::APPLICATION_X_WWW_FORM_URLENCODED,
         );
-        let body = form_urlencoded::Serializer::new(String::new())
-            .append_pair("client_id", &self.client_id)
-            .append_pair("client_secret", self.secret.secret())
-            .append_pair("grant_type", "client_credentials")
-            .append_pair("scope", &scopes.join(" "))
-            .finish();
+        let options = options.unwrap_or_default();
+        let body = {
+            let mut body = form_urlencoded::Serializer::new(String::new());
+            body.append_pair("client_id", &self.client_id)
+                .append_pair("client_secret", self.secret.secret())
+                .append_pair("grant_type", "client_credentials")
+                .append_pair("scope", &scopes.join(" "));
+            if let Some(pop) = &options.pop {
+                body.extend_pairs(crate::pop::token_request_params(pop));
+            }
+            body.finish()
+        };
         req.set_body(body);
 
-        let options = options.unwrap_or_default();
         let ctx = options.method_options.context.to_borrowed();
+        self.authority.validate(&self.pipeline, &ctx).await?;
         let res = self
             .pipeline
             .send(
@@ -130,7 +151,7 @@ impl TokenCredential for ClientSecretCredential {
             ));
         }
         self.cache
-            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .get_pop_token(scopes, options, |s, o| self.get_token_impl(s, o))
             .await
             .map_err(|err| authentication_error(stringify!(ClientSecretCredential), err))
     }
@@ -141,6 +162,7 @@ mod tests {
     use super::*;
     use crate::tests::*;
     use azure_core::{
+        cloud::{CloudConfiguration, CustomConfiguration},
         http::{headers::Headers, AsyncRawResponse, RawResponse, StatusCode, Transport},
         Bytes, Result,
     };
@@ -179,6 +201,8 @@ mod tests {
                         cloud: Some(Arc::new(cloud)),
                         ..Default::default()
                     },
+                    disable_instance_discovery: true,
+                    ..Default::default()
                 }),
             )
             .expect("valid credential");
@@ -190,6 +214,42 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn adfs() {
+        let mut config = CustomConfiguration::default();
+        config.authority_host = "https://adfs.contoso.local/adfs/".to_string();
+        let sts = MockSts::new(
+            vec![token_response()],
+            Some(Arc::new(|req: &Request| {
+                assert_eq!(
+                    "https://adfs.contoso.local/adfs/oauth2/token",
+                    req.url().as_str()
+                );
+                Ok(())
+            })),
+        );
+        let credential = ClientSecretCredential::new(
+            "adfs",
+            FAKE_CLIENT_ID.to_string(),
+            FAKE_SECRET.into(),
+            Some(ClientSecretCredentialOptions {
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(sts))),
+                    cloud: Some(Arc::new(CloudConfiguration::Custom(config))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .expect("valid credential");
+
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+    }
+
     #[tokio::test]
     async fn get_token_error() {
         let body = Bytes::from(
@@ -215,6 +275,7 @@ mod tests {
                     transport: Some(Transport::new(Arc::new(sts))),
                     ..Default::default()
                 },
+                ..Default::default()
             }),
         )
         .expect("valid credential");
@@ -264,6 +325,7 @@ mod tests {
                     transport: Some(Transport::new(Arc::new(sts))),
                     ..Default::default()
                 },
+                ..Default::default()
             }),
         )
         .expect("valid credential");
@@ -292,6 +354,130 @@ mod tests {
         assert_eq!(token.expires_on, cached_token.expires_on);
     }
 
+    #[tokio::test]
+    async fn pop() {
+        use crate::pop::tests::{pop_options, verify_shr};
+        use azure_core::http::Body;
+        use std::{collections::HashMap, sync::Mutex};
+
+        let token_types = Arc::new(Mutex::new(Vec::new()));
+        let token_types_clone = token_types.clone();
+        let sts = MockSts::new(
+            vec![token_response(), token_response()],
+            Some(Arc::new(move |req: &Request| {
+                let body = match req.body() {
+                    Body::Bytes(bytes) => bytes.to_vec(),
+                    _ => panic!("unexpected body type"),
+                };
+                let params: HashMap<String, String> =
+                    form_urlencoded::parse(&body).into_owned().collect();
+                if params.get("token_type").map(String::as_str) == Some("pop") {
+                    assert_eq!(
+                        Some(azure_core::base64::encode_url_safe(r#"{"kid":"key-id"}"#)),
+                        params.get("req_cnf").cloned()
+                    );
+                } else {
+                    assert!(!params.contains_key("req_cnf"));
+                }
+                token_types_clone
+                    .lock()
+                    .unwrap()
+                    .push(params.get("token_type").cloned());
+                Ok(())
+            })),
+        );
+        let credential = ClientSecretCredential::new(
+            FAKE_TENANT_ID,
+            FAKE_CLIENT_ID.to_string(),
+            FAKE_SECRET.into(),
+            Some(ClientSecretCredentialOptions {
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(sts))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .expect("valid credential");
+
+        // the second call should sign a new request with the cached PoP token
+        for nonce in [None, Some("nonce")] {
+            let token = credential
+                .get_token(
+                    LIVE_TEST_SCOPES,
+                    Some(TokenRequestOptions {
+                        pop: Some(pop_options(nonce)),
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .expect("PoP token");
+            verify_shr(token.token.secret(), FAKE_TOKEN, nonce);
+        }
+
+        // the cached PoP token isn't a bearer token
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("bearer token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+        assert_eq!(
+            vec![Some("pop".to_string()), None],
+            *token_types.lock().unwrap()
+        );
+    }
+
+    #[cfg(feature = "persistent_cache")]
+    #[tokio::test]
+    async fn persistent_cache() {
+        use crate::{
+            persistent_cache::tests::{test_key, MemoryStorage},
+            PersistentTokenCache,
+        };
+
+        let cache =
+            PersistentTokenCache::new(Arc::new(MemoryStorage::default()), &test_key()).unwrap();
+        let new_credential = |client_id: &str, responses| {
+            ClientSecretCredential::new(
+                FAKE_TENANT_ID,
+                client_id.to_string(),
+                FAKE_SECRET.into(),
+                Some(ClientSecretCredentialOptions {
+                    client_options: ClientOptions {
+                        transport: Some(Transport::new(Arc::new(MockSts::new(responses, None)))),
+                        ..Default::default()
+                    },
+                    token_cache: Some(cache.clone()),
+                    ..Default::default()
+                }),
+            )
+            .expect("valid credential")
+        };
+
+        let token = new_credential(FAKE_CLIENT_ID, vec![token_response()])
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+
+        // another credential for the same application should get the token from the cache
+        // (its sts will return an error if it sends a request)
+        let cached_token = new_credential(FAKE_CLIENT_ID, vec![])
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("cached token");
+        assert_eq!(token.token.secret(), cached_token.token.secret());
+        assert_eq!(
+            token.expires_on.unix_timestamp(),
+            cached_token.expires_on.unix_timestamp()
+        );
+
+        // but not a credential for a different application
+        new_credential("other-client", vec![])
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("no cached token");
+    }
+
     #[test]
     fn invalid_tenant_id() {
         ClientSecretCredential::new(
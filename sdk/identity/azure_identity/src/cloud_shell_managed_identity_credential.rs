@@ -0,0 +1,119 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::env::Env;
+use crate::{handle_msi_response, scopes_to_resource, ImdsId, TokenCache};
+use azure_core::{
+    credentials::{AccessToken, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    http::{
+        headers::{self, content_type},
+        request::Request,
+        ClientOptions, Method, Pipeline, PipelineSendOptions, Url,
+    },
+};
+use std::sync::Arc;
+use url::form_urlencoded;
+
+const ENDPOINT_ENV: &str = "MSI_ENDPOINT";
+
+/// Authenticates the identity of the user signed in to Azure Cloud Shell.
+///
+/// See <https://learn.microsoft.com/azure/cloud-shell/msi-authorization>
+#[derive(Debug)]
+pub(crate) struct CloudShellManagedIdentityCredential {
+    pipeline: Pipeline,
+    endpoint: Url,
+    cache: TokenCache,
+}
+
+impl CloudShellManagedIdentityCredential {
+    pub fn new(
+        id: ImdsId,
+        client_options: ClientOptions,
+        env: Env,
+    ) -> azure_core::Result<Arc<Self>> {
+        if !matches!(id, ImdsId::SystemAssigned) {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "Cloud Shell doesn't support user-assigned identities",
+            ));
+        }
+        let endpoint = &env
+            .var(ENDPOINT_ENV)
+            .with_context_fn(ErrorKind::Credential, || {
+                format!(
+                    "cloud shell credential requires {} environment variable",
+                    ENDPOINT_ENV
+                )
+            })?;
+        let endpoint = Url::parse(endpoint).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "cloud shell credential {} environment variable must be a valid URL, but is '{endpoint}'",
+                ENDPOINT_ENV
+            )
+        })?;
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            client_options,
+            Vec::default(),
+            Vec::default(),
+            None,
+        );
+        Ok(Arc::new(Self {
+            pipeline,
+            endpoint,
+            cache: TokenCache::new(),
+        }))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let resource = scopes_to_resource(scopes)?;
+
+        let mut req = Request::new(self.endpoint.clone(), Method::Post);
+        req.insert_header("metadata", "true");
+        req.insert_header(
+            headers::CONTENT_TYPE,
+            content_type::APPLICATION_X_WWW_FORM_URLENCODED,
+        );
+        let encoded: String = form_urlencoded::Serializer::new(String::new())
+            .append_pair("resource", resource)
+            .finish();
+        req.set_body(encoded);
+
+        let options = options.unwrap_or_default();
+        let ctx = options.method_options.context.to_borrowed();
+        let rsp = self
+            .pipeline
+            .send(
+                &ctx,
+                &mut req,
+                Some(PipelineSendOptions {
+                    skip_checks: true,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+
+        handle_msi_response(rsp)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for CloudShellManagedIdentityCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        self.cache
+            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .await
+    }
+}
@@ -3,7 +3,8 @@
 
 use crate::{
     AzureCliCredential, AzureCliCredentialOptions, AzureDeveloperCliCredential,
-    AzureDeveloperCliCredentialOptions, Executor,
+    AzureDeveloperCliCredentialOptions, AzurePowerShellCredential,
+    AzurePowerShellCredentialOptions, Executor,
 };
 use azure_core::{
     credentials::{AccessToken, TokenCredential, TokenRequestOptions},
@@ -30,6 +31,7 @@ pub struct DeveloperToolsCredentialOptions {
 ///
 /// * [`AzureCliCredential`]
 /// * [`AzureDeveloperCliCredential`]
+/// * [`AzurePowerShellCredential`]
 ///
 /// `DeveloperToolsCredential` uses the first credential that provides a token for all subsequent token requests. It never tries the others again.
 pub struct DeveloperToolsCredential {
@@ -53,6 +55,10 @@ impl DeveloperToolsCredential {
                 ..Default::default()
             }))?,
             AzureDeveloperCliCredential::new(Some(AzureDeveloperCliCredentialOptions {
+                executor: options.executor.clone(),
+                ..Default::default()
+            }))?,
+            AzurePowerShellCredential::new(Some(AzurePowerShellCredentialOptions {
                 executor: options.executor,
                 ..Default::default()
             }))?,
@@ -170,10 +176,10 @@ mod tests {
         ) -> azure_core::Result<AccessToken> {
             self.call_count.fetch_add(1, Ordering::SeqCst);
             if self.succeed {
-                Ok(AccessToken {
-                    token: self.id.clone().into(),
-                    expires_on: (SystemTime::now() + Duration::from_secs(3600)).into(),
-                })
+                Ok(AccessToken::new(
+                    self.id.clone(),
+                    (SystemTime::now() + Duration::from_secs(3600)).into(),
+                ))
             } else {
                 Err(Error::with_message_fn(ErrorKind::Credential, || {
                     format!("{} failed", self.id)
@@ -244,7 +250,7 @@ mod tests {
             .expect_err("expected error");
         assert!(err.to_string().contains("something went wrong"));
         assert_eq!(
-            2,
+            3,
             executor.call_count(),
             "Executor should have been called once for each inner credential"
         );
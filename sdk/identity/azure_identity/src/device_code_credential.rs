@@ -0,0 +1,478 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    authentication_error, deserialize, public_client::user_scopes, EntraIdErrorResponse,
+    PublicClient,
+};
+use azure_core::{
+    credentials::{AccessToken, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind},
+    http::ClientOptions,
+    time::{Duration, OffsetDateTime},
+};
+use serde::Deserialize;
+use std::{fmt, sync::Arc};
+use tracing::trace;
+use url::form_urlencoded;
+
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const DEFAULT_POLLING_INTERVAL: i64 = 5;
+// RFC 8628 section 3.5: "the interval MUST be increased by 5 seconds for this and all subsequent requests"
+const SLOW_DOWN_INCREMENT: i64 = 5;
+
+/// Information the user needs to complete device code authentication.
+#[derive(Clone, Debug)]
+pub struct DeviceCodeInfo {
+    /// The code the user should enter at [`Self::verification_uri`].
+    pub user_code: String,
+
+    /// The URL at which the user should enter [`Self::user_code`].
+    pub verification_uri: String,
+
+    /// Instructions for the user, as provided by Entra ID.
+    pub message: String,
+
+    /// The time after which the user code is no longer valid.
+    pub expires_on: OffsetDateTime,
+}
+
+/// A callback that presents a [`DeviceCodeInfo`] to the user.
+pub type DeviceCodePrompt = Arc<dyn Fn(&DeviceCodeInfo) + Send + Sync>;
+
+/// Options for constructing a new [`DeviceCodeCredential`].
+#[derive(Clone, Default)]
+pub struct DeviceCodeCredentialOptions {
+    /// The client (application) ID of an app registration that allows public client flows.
+    ///
+    /// Defaults to the client ID of the Azure CLI.
+    pub client_id: Option<String>,
+
+    /// The tenant in which the user should authenticate.
+    ///
+    /// Defaults to "organizations", which allows work and school accounts from any tenant.
+    pub tenant_id: Option<String>,
+
+    /// Presents the device code to the user.
+    ///
+    /// If `None`, the credential prints the message Entra ID provides to stdout.
+    pub user_prompt: Option<DeviceCodePrompt>,
+
+    /// Options for the credential's HTTP pipeline.
+    pub client_options: ClientOptions,
+
+    /// A persistent cache in which the credential stores tokens, so they can be shared with other
+    /// processes and reused after the credential is dropped. See [`PersistentTokenCache`](crate::PersistentTokenCache).
+    pub token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+}
+
+impl fmt::Debug for DeviceCodeCredentialOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceCodeCredentialOptions")
+            .field("client_id", &self.client_id)
+            .field("tenant_id", &self.tenant_id)
+            .field("client_options", &self.client_options)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Authenticates a user with the [device code flow](https://learn.microsoft.com/entra/identity-platform/v2-oauth2-device-code).
+///
+/// The credential asks the user to visit a URL on any device with a browser and enter a code. This
+/// makes it suitable for authenticating users on systems without a browser, such as a remote server
+/// reached over SSH.
+///
+/// After the user authenticates, the credential uses the refresh token it received to acquire tokens
+/// for other scopes, so the user is prompted only once per credential instance.
+pub struct DeviceCodeCredential {
+    client: PublicClient,
+    user_prompt: DeviceCodePrompt,
+}
+
+impl fmt::Debug for DeviceCodeCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceCodeCredential")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: i64,
+    #[serde(default)]
+    interval: Option<i64>,
+    #[serde(default)]
+    message: String,
+}
+
+impl DeviceCodeCredential {
+    /// Creates a new `DeviceCodeCredential`.
+    ///
+    /// # Arguments
+    /// * `options`: Options for configuring the credential. If `None` is provided, default options will be used.
+    pub fn new(options: Option<DeviceCodeCredentialOptions>) -> azure_core::Result<Arc<Self>> {
+        let options = options.unwrap_or_default();
+        let client =
+            PublicClient::new(options.client_id, options.tenant_id, options.client_options)?;
+        let client = client.with_token_cache(options.token_cache);
+
+        Ok(Arc::new(Self {
+            client,
+            user_prompt: options
+                .user_prompt
+                .unwrap_or_else(|| Arc::new(|info: &DeviceCodeInfo| println!("{}", info.message))),
+        }))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let options = options.unwrap_or_default();
+        let ctx = options.method_options.context.to_borrowed();
+
+        if let Some(token) = self.client.redeem_refresh_token(&ctx, scopes).await {
+            return Ok(token);
+        }
+
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &self.client.client_id)
+            .append_pair("scope", &user_scopes(scopes))
+            .finish();
+        let res = self
+            .client
+            .send_form(&ctx, self.client.endpoint("devicecode"), body)
+            .await?;
+        if !res.status().is_success() {
+            return Err(crate::entra_error(res));
+        }
+        let device_code: DeviceCodeResponse = deserialize(&res)?;
+        let expires_on = OffsetDateTime::now_utc() + Duration::seconds(device_code.expires_in);
+        (self.user_prompt)(&DeviceCodeInfo {
+            user_code: device_code.user_code,
+            verification_uri: device_code.verification_uri,
+            message: device_code.message,
+            expires_on,
+        });
+
+        let mut interval = device_code.interval.unwrap_or(DEFAULT_POLLING_INTERVAL);
+        loop {
+            azure_core::sleep(Duration::seconds(interval)).await;
+            if OffsetDateTime::now_utc() >= expires_on {
+                return Err(Error::with_message(
+                    ErrorKind::Credential,
+                    "the device code expired before the user completed authentication",
+                ));
+            }
+
+            let body = form_urlencoded::Serializer::new(String::new())
+                .append_pair("client_id", &self.client.client_id)
+                .append_pair("device_code", &device_code.device_code)
+                .append_pair("grant_type", DEVICE_CODE_GRANT_TYPE)
+                .finish();
+            let res = self
+                .client
+                .send_form(&ctx, self.client.endpoint("token"), body)
+                .await?;
+            if !res.status().is_success() {
+                let error: EntraIdErrorResponse = deserialize(&res).unwrap_or_default();
+                if let Some(next) = next_polling_interval(&error.error, interval) {
+                    trace!("device code authentication is pending ({})", error.error);
+                    interval = next;
+                    continue;
+                }
+            }
+
+            return self.client.handle_token_response(res).await;
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for DeviceCodeCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        if scopes.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "no scopes specified",
+            ));
+        }
+        self.client
+            .cache
+            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .await
+            .map_err(|err| authentication_error(stringify!(DeviceCodeCredential), err))
+    }
+}
+
+/// Returns the interval in seconds to wait before polling again, or `None` when the
+/// error indicates authentication failed and the credential should stop polling.
+fn next_polling_interval(error: &str, interval: i64) -> Option<i64> {
+    match error {
+        "authorization_pending" => Some(interval),
+        "slow_down" => Some(interval + SLOW_DOWN_INCREMENT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use azure_core::{
+        http::{headers::Headers, AsyncRawResponse, Body, Method, Request, StatusCode, Transport},
+        Bytes,
+    };
+    use std::{
+        collections::HashMap,
+        str,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    const FAKE_DEVICE_CODE: &str = "fake-device-code";
+    const FAKE_REFRESH_TOKEN: &str = "fake-refresh-token";
+    const FAKE_USER_CODE: &str = "FAKE-CODE";
+
+    fn body_params(req: &Request) -> HashMap<String, String> {
+        let body = match req.body() {
+            Body::Bytes(bytes) => str::from_utf8(bytes.as_ref()).unwrap(),
+            _ => panic!("unexpected body type"),
+        };
+        form_urlencoded::parse(body.as_bytes())
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn device_code_response(interval: i64) -> AsyncRawResponse {
+        AsyncRawResponse::from_bytes(
+            StatusCode::Ok,
+            Headers::default(),
+            Bytes::from(format!(
+                r#"{{"device_code":"{FAKE_DEVICE_CODE}","user_code":"{FAKE_USER_CODE}","verification_uri":"https://microsoft.com/devicelogin","expires_in":900,"interval":{interval},"message":"To sign in, enter the code {FAKE_USER_CODE}"}}"#
+            )),
+        )
+    }
+
+    fn error_response(error: &str) -> AsyncRawResponse {
+        AsyncRawResponse::from_bytes(
+            StatusCode::BadRequest,
+            Headers::default(),
+            Bytes::from(format!(
+                r#"{{"error":"{error}","error_description":"{error} description","error_codes":[70016]}}"#
+            )),
+        )
+    }
+
+    fn user_token_response(token: &str) -> AsyncRawResponse {
+        AsyncRawResponse::from_bytes(
+            StatusCode::Ok,
+            Headers::default(),
+            Bytes::from(format!(
+                r#"{{"access_token":"{token}","expires_in":3600,"token_type":"Bearer","refresh_token":"{FAKE_REFRESH_TOKEN}"}}"#
+            )),
+        )
+    }
+
+    fn new_credential(sts: MockSts, prompts: Arc<AtomicUsize>) -> Arc<DeviceCodeCredential> {
+        DeviceCodeCredential::new(Some(DeviceCodeCredentialOptions {
+            tenant_id: Some(FAKE_TENANT_ID.to_string()),
+            client_id: Some(FAKE_CLIENT_ID.to_string()),
+            user_prompt: Some(Arc::new(move |info: &DeviceCodeInfo| {
+                assert_eq!(FAKE_USER_CODE, info.user_code);
+                assert_eq!("https://microsoft.com/devicelogin", info.verification_uri);
+                assert!(info.message.contains(FAKE_USER_CODE));
+                prompts.fetch_add(1, Ordering::SeqCst);
+            })),
+            client_options: ClientOptions {
+                transport: Some(Transport::new(Arc::new(sts))),
+                ..Default::default()
+            },
+            ..Default::default()
+        }))
+        .expect("valid credential")
+    }
+
+    #[tokio::test]
+    async fn authenticates_and_redeems_refresh_token() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let requests_clone = requests.clone();
+        let sts = MockSts::new(
+            vec![
+                device_code_response(0),
+                error_response("authorization_pending"),
+                user_token_response("first"),
+                user_token_response("second"),
+            ],
+            Some(Arc::new(move |req: &Request| {
+                assert_eq!(Method::Post, req.method());
+                requests_clone
+                    .lock()
+                    .unwrap()
+                    .push((req.url().to_string(), body_params(req)));
+                Ok(())
+            })),
+        );
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let credential = new_credential(sts, prompts.clone());
+
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        assert_eq!("first", token.token.secret());
+        assert_eq!(1, prompts.load(Ordering::SeqCst));
+
+        // the credential should use its refresh token instead of prompting again
+        let token = credential
+            .get_token(&["https://vault.azure.net/.default"], None)
+            .await
+            .expect("token");
+        assert_eq!("second", token.token.secret());
+        assert_eq!(1, prompts.load(Ordering::SeqCst));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(4, requests.len());
+        let (url, params) = &requests[0];
+        assert_eq!(
+            &format!("{FAKE_PUBLIC_CLOUD_AUTHORITY}/oauth2/v2.0/devicecode"),
+            url
+        );
+        assert_eq!(FAKE_CLIENT_ID, params["client_id"]);
+        assert_eq!(
+            format!("{} offline_access", LIVE_TEST_SCOPES.join(" ")),
+            params["scope"]
+        );
+        for (url, params) in &requests[1..3] {
+            assert_eq!(
+                &format!("{FAKE_PUBLIC_CLOUD_AUTHORITY}/oauth2/v2.0/token"),
+                url
+            );
+            assert_eq!(DEVICE_CODE_GRANT_TYPE, params["grant_type"]);
+            assert_eq!(FAKE_DEVICE_CODE, params["device_code"]);
+        }
+        let (_, params) = &requests[3];
+        assert_eq!("refresh_token", params["grant_type"]);
+        assert_eq!(FAKE_REFRESH_TOKEN, params["refresh_token"]);
+        assert_eq!(
+            "https://vault.azure.net/.default offline_access",
+            params["scope"]
+        );
+    }
+
+    #[cfg(feature = "persistent_cache")]
+    #[tokio::test]
+    async fn persistent_refresh_token() {
+        use crate::{
+            persistent_cache::tests::{test_key, MemoryStorage},
+            PersistentTokenCache,
+        };
+
+        let cache =
+            PersistentTokenCache::new(Arc::new(MemoryStorage::default()), &test_key()).unwrap();
+        let new_credential = |responses, prompts: Arc<AtomicUsize>| {
+            DeviceCodeCredential::new(Some(DeviceCodeCredentialOptions {
+                tenant_id: Some(FAKE_TENANT_ID.to_string()),
+                client_id: Some(FAKE_CLIENT_ID.to_string()),
+                user_prompt: Some(Arc::new(move |_: &DeviceCodeInfo| {
+                    prompts.fetch_add(1, Ordering::SeqCst);
+                })),
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(MockSts::new(responses, None)))),
+                    ..Default::default()
+                },
+                token_cache: Some(cache.clone()),
+            }))
+            .expect("valid credential")
+        };
+
+        let prompts = Arc::new(AtomicUsize::new(0));
+        new_credential(
+            vec![device_code_response(0), user_token_response("first")],
+            prompts.clone(),
+        )
+        .get_token(LIVE_TEST_SCOPES, None)
+        .await
+        .expect("token");
+        assert_eq!(1, prompts.load(Ordering::SeqCst));
+
+        // a new credential, as in another process, should redeem the persisted refresh token
+        // instead of prompting the user
+        let token = new_credential(vec![user_token_response("second")], prompts.clone())
+            .get_token(&["https://vault.azure.net/.default"], None)
+            .await
+            .expect("token");
+        assert_eq!("second", token.token.secret());
+        assert_eq!(1, prompts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn authentication_declined() {
+        let sts = MockSts::new(
+            vec![
+                device_code_response(0),
+                error_response("authorization_declined"),
+            ],
+            None,
+        );
+        let credential = new_credential(sts, Arc::new(AtomicUsize::new(0)));
+
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("authentication error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(
+            err.to_string()
+                .contains("authorization_declined description"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn prompts_when_refresh_token_is_rejected() {
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let sts = MockSts::new(
+            vec![
+                device_code_response(0),
+                user_token_response("first"),
+                error_response("invalid_grant"),
+                device_code_response(0),
+                user_token_response("second"),
+            ],
+            None,
+        );
+        let credential = new_credential(sts, prompts.clone());
+
+        credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        let token = credential
+            .get_token(&["https://vault.azure.net/.default"], None)
+            .await
+            .expect("token");
+        assert_eq!("second", token.token.secret());
+        assert_eq!(2, prompts.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn polling_interval() {
+        assert_eq!(Some(5), next_polling_interval("authorization_pending", 5));
+        assert_eq!(Some(10), next_polling_interval("slow_down", 5));
+        assert_eq!(None, next_polling_interval("expired_token", 5));
+        assert_eq!(None, next_polling_interval("", 5));
+    }
+}
@@ -1,13 +1,13 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use crate::{env::Env, TokenCache, UserAssignedId};
+use crate::{env::Env, refresh_on, TokenCache, UserAssignedId};
 use azure_core::{
     credentials::{AccessToken, Secret, TokenCredential, TokenRequestOptions},
     error::{Error, ErrorKind},
     http::{
         headers::HeaderName, request::Request, ClientOptions, Method, Pipeline, PipelineOptions,
-        PipelineSendOptions, StatusCode, Url,
+        PipelineSendOptions, RawResponse, StatusCode, Url,
     },
     json::from_json,
     time::OffsetDateTime,
@@ -138,35 +138,7 @@ impl ImdsManagedIdentityCredential {
             )
             .await?;
 
-        let status = rsp.status();
-        if !status.is_success() {
-            let message = match status {
-                StatusCode::BadRequest => {
-                    "The requested identity has not been assigned to this resource".to_string()
-                }
-                StatusCode::BadGateway | StatusCode::GatewayTimeout => {
-                    "The request failed due to a gateway error".to_string()
-                }
-                _ => {
-                    let body = String::from_utf8_lossy(rsp.body());
-                    format!("The request failed: {body}")
-                }
-            };
-            return Err(Error::new(
-                ErrorKind::HttpResponse {
-                    error_code: None,
-                    raw_response: Some(Box::new(rsp)),
-                    status,
-                },
-                message,
-            ));
-        }
-
-        let token_response: MsiTokenResponse = from_json(rsp.into_body())?;
-        Ok(AccessToken::new(
-            token_response.access_token,
-            token_response.expires_on,
-        ))
+        handle_msi_response(rsp)
     }
 }
 
@@ -184,20 +156,84 @@ impl TokenCredential for ImdsManagedIdentityCredential {
     }
 }
 
+/// Converts a managed identity endpoint's token response to an [`AccessToken`], or its
+/// error response to an [`Error`] carrying the response.
+pub(crate) fn handle_msi_response(rsp: RawResponse) -> azure_core::Result<AccessToken> {
+    let status = rsp.status();
+    if !status.is_success() {
+        let message = match status {
+            StatusCode::BadRequest => {
+                "The requested identity has not been assigned to this resource".to_string()
+            }
+            StatusCode::BadGateway | StatusCode::GatewayTimeout => {
+                "The request failed due to a gateway error".to_string()
+            }
+            _ => {
+                let body = String::from_utf8_lossy(rsp.body());
+                format!("The request failed: {body}")
+            }
+        };
+        return Err(Error::new(
+            ErrorKind::HttpResponse {
+                error_code: None,
+                raw_response: Some(Box::new(rsp)),
+                status,
+            },
+            message,
+        ));
+    }
+
+    let token_response: MsiTokenResponse = from_json(rsp.into_body())?;
+    let now = OffsetDateTime::now_utc();
+    let mut token = AccessToken::new(token_response.access_token, token_response.expires_on);
+    token.refresh_on = refresh_on(
+        now,
+        token_response.expires_on - now,
+        token_response.refresh_in,
+    );
+    Ok(token)
+}
+
+/// A number of seconds, or a Unix timestamp, which most sources send as a string but Service
+/// Fabric sends as a number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber {
+    String(String),
+    Number(i64),
+}
+
+impl StringOrNumber {
+    fn into_i64<E: de::Error>(self) -> std::result::Result<i64, E> {
+        match self {
+            StringOrNumber::String(v) => v.parse::<i64>().map_err(de::Error::custom),
+            StringOrNumber::Number(v) => Ok(v),
+        }
+    }
+}
+
 fn expires_on_string<'de, D>(deserializer: D) -> std::result::Result<OffsetDateTime, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let v = String::deserialize(deserializer)?;
-    let as_i64 = v.parse::<i64>().map_err(de::Error::custom)?;
+    let as_i64 = StringOrNumber::deserialize(deserializer)?.into_i64()?;
     OffsetDateTime::from_unix_timestamp(as_i64).map_err(de::Error::custom)
 }
 
+fn refresh_in_string<'de, D>(deserializer: D) -> std::result::Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<StringOrNumber>::deserialize(deserializer)?
+        .map(StringOrNumber::into_i64)
+        .transpose()
+}
+
 /// Convert a `AADv2` scope to an `AADv1` resource
 ///
 /// Directly based on the `azure-sdk-for-python` implementation:
 /// ref: <https://github.com/Azure/azure-sdk-for-python/blob/d6aeefef46c94b056419613f1a5cc9eaa3af0d22/sdk/identity/azure-identity/azure/identity/_internal/__init__.py#L22>
-fn scopes_to_resource<'a>(scopes: &'a [&'a str]) -> azure_core::Result<&'a str> {
+pub(crate) fn scopes_to_resource<'a>(scopes: &'a [&'a str]) -> azure_core::Result<&'a str> {
     if scopes.len() != 1 {
         return Err(Error::with_message(
             ErrorKind::Credential,
@@ -215,7 +251,7 @@ fn scopes_to_resource<'a>(scopes: &'a [&'a str]) -> azure_core::Result<&'a str>
     Ok(scope.strip_suffix("/.default").unwrap_or(*scope))
 }
 
-// NOTE: expires_on is a String version of unix epoch time, not an integer.
+// NOTE: expires_on is usually a String version of unix epoch time, not an integer.
 // https://learn.microsoft.com/azure/app-service/overview-managed-identity?tabs=dotnet#rest-protocol-examples
 #[derive(Debug, Clone, Deserialize)]
 #[allow(unused)]
@@ -223,6 +259,8 @@ struct MsiTokenResponse {
     pub access_token: Secret,
     #[serde(deserialize_with = "expires_on_string")]
     pub expires_on: OffsetDateTime,
+    #[serde(default, deserialize_with = "refresh_in_string")]
+    pub refresh_in: Option<i64>,
     pub token_type: String,
     pub resource: String,
 }
@@ -230,6 +268,7 @@ struct MsiTokenResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use azure_core::{http::headers::Headers, time::Duration, Bytes};
     use time::macros::datetime;
 
     #[derive(Debug, Deserialize)]
@@ -246,4 +285,46 @@ mod tests {
         assert_eq!(expected, parsed.date);
         Ok(())
     }
+
+    #[test]
+    fn check_expires_on_number() -> azure_core::Result<()> {
+        let as_number = r#"{"date": 1586984735}"#;
+        let expected = datetime!(2020-4-15 21:5:35 UTC);
+        let parsed: TestExpires = from_json(as_number)?;
+        assert_eq!(expected, parsed.date);
+        Ok(())
+    }
+
+    #[test]
+    fn msi_response_refresh_in() -> azure_core::Result<()> {
+        let expires_on = OffsetDateTime::now_utc() + Duration::hours(1);
+        for refresh_in in [r#""600""#, "600"] {
+            let body = format!(
+                r#"{{"access_token":"*","expires_on":"{}","refresh_in":{refresh_in},"resource":"r","token_type":"Bearer"}}"#,
+                expires_on.unix_timestamp()
+            );
+            let now = OffsetDateTime::now_utc();
+            let token = handle_msi_response(RawResponse::from_bytes(
+                StatusCode::Ok,
+                Headers::new(),
+                Bytes::from(body),
+            ))?;
+            let refresh_on = token.refresh_on.expect("refresh_on");
+            assert!(refresh_on >= now + Duration::seconds(600));
+            assert!(refresh_on < now + Duration::seconds(610));
+        }
+
+        // without refresh_in, a token valid for less than two hours isn't refreshed early
+        let body = format!(
+            r#"{{"access_token":"*","expires_on":"{}","resource":"r","token_type":"Bearer"}}"#,
+            expires_on.unix_timestamp()
+        );
+        let token = handle_msi_response(RawResponse::from_bytes(
+            StatusCode::Ok,
+            Headers::new(),
+            Bytes::from(body),
+        ))?;
+        assert_eq!(None, token.refresh_on);
+        Ok(())
+    }
 }
@@ -0,0 +1,708 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+// cspell:ignore rundll32
+
+use crate::{
+    authentication_error,
+    process::{new_executor, Executor},
+    public_client::user_scopes,
+    PublicClient,
+};
+use azure_core::{
+    base64,
+    credentials::{AccessToken, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    http::{ClientOptions, Url},
+    time::Duration,
+    Uuid,
+};
+use futures::channel::oneshot;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{mpsc, Arc},
+    thread,
+    time::Instant,
+};
+use tracing::{debug, trace};
+
+const DEFAULT_TIMEOUT: Duration = Duration::minutes(5);
+const RESPONSE_HTML: &str =
+    "<html><body>Authentication complete. You can close this window.</body></html>";
+const ERROR_HTML: &str =
+    "<html><body>Authentication failed. You can close this window.</body></html>";
+
+/// Options for constructing a new [`InteractiveBrowserCredential`].
+#[derive(Clone, Debug, Default)]
+pub struct InteractiveBrowserCredentialOptions {
+    /// The client (application) ID of an app registration that allows public client flows and
+    /// has the redirect URI "http://localhost".
+    ///
+    /// Defaults to the client ID of the Azure CLI.
+    pub client_id: Option<String>,
+
+    /// The tenant in which the user should authenticate.
+    ///
+    /// Defaults to "organizations", which allows work and school accounts from any tenant.
+    pub tenant_id: Option<String>,
+
+    /// A username the login page should suggest to the user, for example "user@contoso.com".
+    pub login_hint: Option<String>,
+
+    /// The URI to which Entra ID redirects the browser after authentication. Its host must be
+    /// "localhost" or "127.0.0.1" and its scheme "http".
+    ///
+    /// Defaults to "http://localhost". When the URI has no port, the credential listens on a
+    /// port chosen by the operating system.
+    pub redirect_uri: Option<String>,
+
+    /// How long to wait for the user to complete authentication. Defaults to 5 minutes.
+    pub timeout: Option<Duration>,
+
+    /// An implementation of [`Executor`] used to open the system browser.
+    ///
+    /// If `None`, one is created using [`new_executor`]. When the browser can't be opened,
+    /// the credential prints the authorization URL to stdout.
+    pub executor: Option<Arc<dyn Executor>>,
+
+    /// Options for the credential's HTTP pipeline.
+    pub client_options: ClientOptions,
+
+    /// A persistent cache in which the credential stores tokens, so they can be shared with other
+    /// processes and reused after the credential is dropped. See [`PersistentTokenCache`](crate::PersistentTokenCache).
+    pub token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+}
+
+/// Authenticates a user through the system browser with the
+/// [authorization code flow](https://learn.microsoft.com/entra/identity-platform/v2-oauth2-auth-code-flow)
+/// and [PKCE](https://datatracker.ietf.org/doc/html/rfc7636).
+///
+/// The credential opens the Entra ID login page in the browser and listens on a loopback address
+/// for the redirect carrying the authorization code, which it then redeems for an access token.
+///
+/// After the user authenticates, the credential uses the refresh token it received to acquire tokens
+/// for other scopes, so the user is prompted only once per credential instance.
+#[derive(Debug)]
+pub struct InteractiveBrowserCredential {
+    client: PublicClient,
+    executor: Arc<dyn Executor>,
+    login_hint: Option<String>,
+    redirect_uri: String,
+    timeout: Duration,
+}
+
+impl InteractiveBrowserCredential {
+    /// Creates a new `InteractiveBrowserCredential`.
+    ///
+    /// # Arguments
+    /// * `options`: Options for configuring the credential. If `None` is provided, default options will be used.
+    pub fn new(
+        options: Option<InteractiveBrowserCredentialOptions>,
+    ) -> azure_core::Result<Arc<Self>> {
+        let options = options.unwrap_or_default();
+        let redirect_uri = options
+            .redirect_uri
+            .unwrap_or_else(|| "http://localhost".to_string());
+        let url = Url::parse(&redirect_uri).with_context_fn(ErrorKind::Credential, || {
+            format!("invalid redirect URI '{redirect_uri}'")
+        })?;
+        if url.scheme() != "http" || !matches!(url.host_str(), Some("localhost" | "127.0.0.1")) {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                format!("redirect URI must be an http URI on localhost or 127.0.0.1, but is '{redirect_uri}'"),
+            ));
+        }
+
+        let client =
+            PublicClient::new(options.client_id, options.tenant_id, options.client_options)?;
+        let client = client.with_token_cache(options.token_cache);
+
+        Ok(Arc::new(Self {
+            client,
+            executor: options.executor.unwrap_or_else(new_executor),
+            login_hint: options.login_hint,
+            redirect_uri,
+            timeout: options.timeout.unwrap_or(DEFAULT_TIMEOUT),
+        }))
+    }
+
+    async fn get_token_impl(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let options = options.unwrap_or_default();
+        let ctx = options.method_options.context.to_borrowed();
+
+        if let Some(token) = self.client.redeem_refresh_token(&ctx, scopes).await {
+            return Ok(token);
+        }
+
+        // new() validated the redirect URI
+        let port = Url::parse(&self.redirect_uri)
+            .ok()
+            .and_then(|url| url.port())
+            .unwrap_or(0);
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).with_context(
+            ErrorKind::Credential,
+            "failed to start a listener for the redirect",
+        )?;
+        let redirect_uri = redirect_uri(&self.redirect_uri, &listener)?;
+
+        let pkce = Pkce::new();
+        let state = Uuid::new_v4().to_string();
+        let scope = user_scopes(scopes);
+        let mut authorize_url = self.client.endpoint("authorize");
+        authorize_url
+            .query_pairs_mut()
+            .append_pair("client_id", &self.client.client_id)
+            .append_pair("response_type", "code")
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("scope", &scope)
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", &state)
+            .append_pair("prompt", "select_account");
+        if let Some(login_hint) = &self.login_hint {
+            authorize_url
+                .query_pairs_mut()
+                .append_pair("login_hint", login_hint);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let deadline = Instant::now() + self.timeout.unsigned_abs();
+        thread::spawn(move || tx.send(receive_redirect(listener, deadline)));
+
+        self.open_browser(&authorize_url).await;
+
+        let params = rx
+            .await
+            .map_err(|err| {
+                Error::with_error(
+                    ErrorKind::Credential,
+                    err,
+                    "canceled waiting for the redirect",
+                )
+            })?
+            .with_context(ErrorKind::Credential, "failed to receive the redirect")?;
+        // Only a redirect answering this authorization request can report its outcome, including an error.
+        if params.get("state") != Some(&state) {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "the redirect's state parameter doesn't match the authorization request",
+            ));
+        }
+        if let Some(error) = params.get("error") {
+            let description = params.get("error_description").map_or("", String::as_str);
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!("authorization failed: {error} {description}")
+            }));
+        }
+        let code = params.get("code").ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Credential,
+                "the redirect didn't include an authorization code",
+            )
+        })?;
+
+        self.client
+            .redeem(
+                &ctx,
+                &[
+                    ("code", code),
+                    ("code_verifier", pkce.verifier.as_str()),
+                    ("grant_type", "authorization_code"),
+                    ("redirect_uri", &redirect_uri),
+                    ("scope", &scope),
+                ],
+            )
+            .await
+    }
+
+    async fn open_browser(&self, url: &Url) {
+        let (program, args): (&str, Vec<&str>) = if cfg!(windows) {
+            (
+                "rundll32",
+                vec!["url.dll,FileProtocolHandler", url.as_str()],
+            )
+        } else if cfg!(target_os = "macos") {
+            ("open", vec![url.as_str()])
+        } else {
+            ("xdg-open", vec![url.as_str()])
+        };
+        let args: Vec<&OsStr> = args.iter().map(OsStr::new).collect();
+        match self.executor.run(OsStr::new(program), &args).await {
+            Ok(output) if output.status.success() => trace!("opened the system browser"),
+            result => {
+                debug!("failed to open the system browser: {result:?}");
+                println!("To sign in, open this URL in a browser: {url}");
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for InteractiveBrowserCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        if scopes.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "no scopes specified",
+            ));
+        }
+        self.client
+            .cache
+            .get_token(scopes, options, |s, o| self.get_token_impl(s, o))
+            .await
+            .map_err(|err| authentication_error(stringify!(InteractiveBrowserCredential), err))
+    }
+}
+
+/// A PKCE code verifier and its S256 challenge.
+struct Pkce {
+    verifier: String,
+    challenge: String,
+}
+
+impl Pkce {
+    fn new() -> Self {
+        // two v4 UUIDs provide 244 random bits and encode to 43 characters, the minimum verifier length
+        let mut random = Vec::with_capacity(32);
+        random.extend_from_slice(Uuid::new_v4().as_bytes());
+        random.extend_from_slice(Uuid::new_v4().as_bytes());
+        let verifier = base64::encode_url_safe(random);
+        let challenge = base64::encode_url_safe(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+/// Returns the redirect URI to send Entra ID, including the port on which `listener` is listening.
+fn redirect_uri(configured: &str, listener: &TcpListener) -> azure_core::Result<String> {
+    let mut uri =
+        Url::parse(configured).with_context(ErrorKind::Credential, "invalid redirect URI")?;
+    if uri.port().is_some() {
+        return Ok(configured.to_string());
+    }
+    let port = listener
+        .local_addr()
+        .with_context(
+            ErrorKind::Credential,
+            "failed to get the listener's address",
+        )?
+        .port();
+    uri.set_port(Some(port))
+        .map_err(|_| Error::with_message(ErrorKind::Credential, "invalid redirect URI"))?;
+    let trim_slash = uri.path() == "/" && !configured.ends_with('/');
+    let uri = uri.to_string();
+    // "http://localhost" is a common registered redirect URI; Url adds a path to it
+    if trim_slash {
+        return Ok(uri.trim_end_matches('/').to_string());
+    }
+    Ok(uri)
+}
+
+/// Accepts connections until one carries an authorization response or the deadline passes,
+/// returning the response's query parameters.
+///
+/// Each connection is read on its own thread, so a connection the browser opens without sending a request doesn't
+/// delay the redirect. Errors on a connection are logged and the connection dropped.
+fn receive_redirect(
+    listener: TcpListener,
+    deadline: Instant,
+) -> io::Result<HashMap<String, String>> {
+    listener.set_nonblocking(true)?;
+    let (sender, receiver) = mpsc::channel();
+    loop {
+        if let Ok(params) = receiver.try_recv() {
+            return Ok(params);
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let sender = sender.clone();
+                thread::spawn(move || match handle_connection(stream) {
+                    Ok(Some(params)) => {
+                        let _ = sender.send(params);
+                    }
+                    Ok(None) => {}
+                    Err(err) => debug!("dropped a connection to the redirect listener: {err}"),
+                });
+            }
+            Err(err) => {
+                if err.kind() != io::ErrorKind::WouldBlock {
+                    debug!("failed to accept a connection to the redirect listener: {err}");
+                }
+                if Instant::now() >= deadline {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "timed out waiting for the user to authenticate",
+                    ));
+                }
+                thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+/// Reads an HTTP request and returns its query parameters if they're an authorization response.
+fn handle_connection(mut stream: TcpStream) -> io::Result<Option<HashMap<String, String>>> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(10)))?;
+
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    // e.g. "GET /?code=...&state=... HTTP/1.1"
+    let request = String::from_utf8_lossy(&request);
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default();
+    let params: HashMap<String, String> = Url::parse("http://localhost")
+        .and_then(|base| base.join(target))
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+
+    if !params.contains_key("code") && !params.contains_key("error") {
+        // probably the browser requesting something like favicon.ico
+        stream.write_all(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )?;
+        return Ok(None);
+    }
+
+    let body = if params.contains_key("code") {
+        RESPONSE_HTML
+    } else {
+        ERROR_HTML
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(Some(params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use azure_core::{
+        http::{headers::Headers, AsyncRawResponse, Body, Method, Request, StatusCode, Transport},
+        Bytes,
+    };
+    use std::{
+        str,
+        sync::{Arc, Mutex},
+    };
+    use url::form_urlencoded;
+
+    const FAKE_CODE: &str = "fake-code";
+    const FAKE_LOGIN_HINT: &str = "user@contoso.com";
+    const FAKE_REFRESH_TOKEN: &str = "fake-refresh-token";
+
+    fn user_token_response(token: &str) -> AsyncRawResponse {
+        AsyncRawResponse::from_bytes(
+            StatusCode::Ok,
+            Headers::default(),
+            Bytes::from(format!(
+                r#"{{"access_token":"{token}","expires_in":3600,"token_type":"Bearer","refresh_token":"{FAKE_REFRESH_TOKEN}"}}"#
+            )),
+        )
+    }
+
+    /// Simulates the browser: extracts the redirect URI from the authorization URL and sends
+    /// it the given query, returning the authorization URL's query parameters.
+    fn redirect(
+        authorize_url: &str,
+        query: impl Fn(&HashMap<String, String>) -> String,
+    ) -> HashMap<String, String> {
+        let url = Url::parse(authorize_url).expect("valid authorization URL");
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let redirect_uri = Url::parse(&params["redirect_uri"]).expect("valid redirect URI");
+        let mut stream =
+            TcpStream::connect(("127.0.0.1", redirect_uri.port().expect("port"))).expect("connect");
+        write!(
+            stream,
+            "GET /?{} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            query(&params)
+        )
+        .expect("write request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read response");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        params
+    }
+
+    #[tokio::test]
+    async fn authenticates_and_redeems_refresh_token() {
+        let authorize_params = Arc::new(Mutex::new(HashMap::new()));
+        let authorize_params_clone = authorize_params.clone();
+        let executor = MockExecutor::with_output(
+            0,
+            "",
+            "",
+            Some(Arc::new(move |_: &OsStr, args: &[&OsStr]| {
+                let url = args.last().unwrap().to_string_lossy().to_string();
+                // record the parameters before redirecting so they're available when get_token returns
+                *authorize_params_clone.lock().unwrap() = Url::parse(&url)
+                    .expect("valid authorization URL")
+                    .query_pairs()
+                    .into_owned()
+                    .collect();
+                thread::spawn(move || {
+                    redirect(&url, |params| {
+                        format!("code={FAKE_CODE}&state={}", params["state"])
+                    })
+                });
+            })),
+        );
+
+        let token_requests = Arc::new(Mutex::new(Vec::new()));
+        let token_requests_clone = token_requests.clone();
+        let sts = MockSts::new(
+            vec![user_token_response("first"), user_token_response("second")],
+            Some(Arc::new(move |req: &Request| {
+                assert_eq!(Method::Post, req.method());
+                assert_eq!(
+                    format!("{FAKE_PUBLIC_CLOUD_AUTHORITY}/oauth2/v2.0/token"),
+                    req.url().to_string()
+                );
+                let body = match req.body() {
+                    Body::Bytes(bytes) => str::from_utf8(bytes.as_ref()).unwrap().to_string(),
+                    _ => panic!("unexpected body type"),
+                };
+                let params: HashMap<String, String> = form_urlencoded::parse(body.as_bytes())
+                    .into_owned()
+                    .collect();
+                token_requests_clone.lock().unwrap().push(params);
+                Ok(())
+            })),
+        );
+
+        let credential =
+            InteractiveBrowserCredential::new(Some(InteractiveBrowserCredentialOptions {
+                client_id: Some(FAKE_CLIENT_ID.to_string()),
+                tenant_id: Some(FAKE_TENANT_ID.to_string()),
+                login_hint: Some(FAKE_LOGIN_HINT.to_string()),
+                executor: Some(executor.clone()),
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(sts))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+            .expect("valid credential");
+
+        let token = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        assert_eq!("first", token.token.secret());
+
+        // the credential should use its refresh token instead of opening the browser again
+        let token = credential
+            .get_token(&["https://vault.azure.net/.default"], None)
+            .await
+            .expect("token");
+        assert_eq!("second", token.token.secret());
+        assert_eq!(1, executor.call_count());
+
+        let authorize_params = authorize_params.lock().unwrap();
+        assert_eq!(FAKE_CLIENT_ID, authorize_params["client_id"]);
+        assert_eq!("code", authorize_params["response_type"]);
+        assert_eq!("S256", authorize_params["code_challenge_method"]);
+        assert_eq!(FAKE_LOGIN_HINT, authorize_params["login_hint"]);
+        assert!(authorize_params["redirect_uri"].starts_with("http://localhost:"));
+        assert!(!authorize_params["redirect_uri"].ends_with('/'));
+
+        let token_requests = token_requests.lock().unwrap();
+        assert_eq!(2, token_requests.len());
+        let params = &token_requests[0];
+        assert_eq!("authorization_code", params["grant_type"]);
+        assert_eq!(FAKE_CODE, params["code"]);
+        assert_eq!(authorize_params["redirect_uri"], params["redirect_uri"]);
+        assert_eq!(
+            authorize_params["code_challenge"],
+            base64::encode_url_safe(Sha256::digest(params["code_verifier"].as_bytes()))
+        );
+        let params = &token_requests[1];
+        assert_eq!("refresh_token", params["grant_type"]);
+        assert_eq!(FAKE_REFRESH_TOKEN, params["refresh_token"]);
+    }
+
+    #[test]
+    fn receive_redirect_ignores_idle_and_broken_connections() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("bind");
+        let port = listener.local_addr().expect("address").port();
+        let started = Instant::now();
+        let receiver =
+            thread::spawn(move || receive_redirect(listener, started + Duration::seconds(30)));
+
+        // a preconnected socket the browser never sends a request on
+        let _idle = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+        // a connection closed before its request is complete
+        let mut broken = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+        broken.write_all(b"GET /?code=").expect("write request");
+        drop(broken);
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+        write!(
+            stream,
+            "GET /?code={FAKE_CODE}&state=state HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        )
+        .expect("write request");
+        let params = receiver.join().unwrap().expect("authorization response");
+        assert_eq!(FAKE_CODE, params["code"]);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn authorization_error() {
+        let executor = MockExecutor::with_output(
+            0,
+            "",
+            "",
+            Some(Arc::new(move |_: &OsStr, args: &[&OsStr]| {
+                let url = args.last().unwrap().to_string_lossy().to_string();
+                thread::spawn(move || {
+                    redirect(&url, |params| {
+                        format!(
+                            "error=access_denied&error_description=the+user+canceled&state={}",
+                            params["state"]
+                        )
+                    })
+                });
+            })),
+        );
+        let credential =
+            InteractiveBrowserCredential::new(Some(InteractiveBrowserCredentialOptions {
+                executor: Some(executor),
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(MockSts::new(vec![], None)))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+            .expect("valid credential");
+
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("authorization error");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        assert!(
+            err.to_string().contains("access_denied the user canceled"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn state_mismatch() {
+        let executor = MockExecutor::with_output(
+            0,
+            "",
+            "",
+            Some(Arc::new(move |_: &OsStr, args: &[&OsStr]| {
+                let url = args.last().unwrap().to_string_lossy().to_string();
+                thread::spawn(move || redirect(&url, |_| "code=code&state=wrong".to_string()));
+            })),
+        );
+        let credential =
+            InteractiveBrowserCredential::new(Some(InteractiveBrowserCredentialOptions {
+                executor: Some(executor),
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(MockSts::new(vec![], None)))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+            .expect("valid credential");
+
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("state mismatch");
+        assert!(err.to_string().contains("state"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn authorization_error_state_mismatch() {
+        let executor = MockExecutor::with_output(
+            0,
+            "",
+            "",
+            Some(Arc::new(move |_: &OsStr, args: &[&OsStr]| {
+                let url = args.last().unwrap().to_string_lossy().to_string();
+                thread::spawn(move || {
+                    redirect(&url, |_| "error=access_denied&state=wrong".to_string())
+                });
+            })),
+        );
+        let credential =
+            InteractiveBrowserCredential::new(Some(InteractiveBrowserCredentialOptions {
+                executor: Some(executor),
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(MockSts::new(vec![], None)))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+            .expect("valid credential");
+
+        let err = credential
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("state mismatch");
+        assert!(err.to_string().contains("state"), "{err}");
+        assert!(!err.to_string().contains("access_denied"), "{err}");
+    }
+
+    #[test]
+    fn invalid_redirect_uri() {
+        for uri in ["https://localhost", "http://contoso.com", "not a URL"] {
+            InteractiveBrowserCredential::new(Some(InteractiveBrowserCredentialOptions {
+                redirect_uri: Some(uri.to_string()),
+                ..Default::default()
+            }))
+            .expect_err(uri);
+        }
+    }
+
+    #[test]
+    fn redirect_uri_port() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        for (configured, expected) in [
+            ("http://localhost", format!("http://localhost:{port}")),
+            ("http://localhost/", format!("http://localhost:{port}/")),
+            (
+                "http://127.0.0.1/path",
+                format!("http://127.0.0.1:{port}/path"),
+            ),
+            ("http://localhost:8400", "http://localhost:8400".to_string()),
+        ] {
+            assert_eq!(expected, redirect_uri(configured, &listener).unwrap());
+        }
+    }
+}
@@ -6,23 +6,39 @@
 #![warn(missing_docs)]
 
 mod app_service_managed_identity_credential;
+mod authority;
+mod azure_arc_managed_identity_credential;
 #[cfg(not(target_arch = "wasm32"))]
 mod azure_cli_credential;
 #[cfg(not(target_arch = "wasm32"))]
 mod azure_developer_cli_credential;
+mod azure_ml_managed_identity_credential;
 mod azure_pipelines_credential;
+#[cfg(not(target_arch = "wasm32"))]
+mod azure_powershell_credential;
+mod background_refresh_credential;
 mod cache;
 mod client_assertion_credential;
 #[cfg(feature = "client_certificate")]
 mod client_certificate_credential;
 mod client_secret_credential;
+mod cloud_shell_managed_identity_credential;
 #[cfg(not(target_arch = "wasm32"))]
 mod developer_tools_credential;
+mod device_code_credential;
 mod env;
 mod imds_managed_identity_credential;
+#[cfg(not(target_arch = "wasm32"))]
+mod interactive_browser_credential;
 mod managed_identity_credential;
+mod on_behalf_of_credential;
+#[cfg(feature = "persistent_cache")]
+mod persistent_cache;
+mod pop;
 #[cfg(not(target_arch = "wasm32"))]
 mod process;
+mod public_client;
+mod service_fabric_managed_identity_credential;
 mod virtual_machine_managed_identity_credential;
 mod workload_identity_credential;
 
@@ -31,26 +47,47 @@ pub use azure_cli_credential::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use azure_developer_cli_credential::*;
 pub use azure_pipelines_credential::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use azure_powershell_credential::*;
+pub use background_refresh_credential::*;
+#[cfg(not(feature = "persistent_cache"))]
+pub use cache::PersistentTokenCache;
 pub use client_assertion_credential::*;
 #[cfg(feature = "client_certificate")]
 pub use client_certificate_credential::*;
 pub use client_secret_credential::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use developer_tools_credential::*;
+pub use device_code_credential::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use interactive_browser_credential::*;
 pub use managed_identity_credential::*;
+pub use on_behalf_of_credential::*;
+#[cfg(all(feature = "persistent_cache", not(target_arch = "wasm32")))]
+pub use persistent_cache::FileTokenCacheStorage;
+#[cfg(feature = "persistent_cache")]
+pub use persistent_cache::{PersistentTokenCache, TokenCacheStorage};
+#[cfg(feature = "client_certificate")]
+pub use pop::RsaPopKey;
 #[cfg(not(target_arch = "wasm32"))]
 pub use process::{new_executor, Executor};
 pub use workload_identity_credential::*;
 
 pub(crate) use app_service_managed_identity_credential::*;
+pub(crate) use authority::Authority;
+pub(crate) use azure_arc_managed_identity_credential::*;
+pub(crate) use azure_ml_managed_identity_credential::*;
 pub(crate) use cache::TokenCache;
+pub(crate) use cloud_shell_managed_identity_credential::*;
 pub(crate) use imds_managed_identity_credential::*;
+pub(crate) use public_client::PublicClient;
+pub(crate) use service_fabric_managed_identity_credential::*;
 pub(crate) use virtual_machine_managed_identity_credential::*;
 
 use crate::env::Env;
 use azure_core::{
     cloud::CloudConfiguration,
-    credentials::AccessToken,
+    credentials::{AccessToken, Secret},
     error::ErrorKind,
     http::{RawResponse, Url},
     time::{Duration, OffsetDateTime},
@@ -62,6 +99,7 @@ use std::borrow::Cow;
 #[derive(Debug, Default, Deserialize)]
 #[serde(default)]
 struct EntraIdErrorResponse {
+    error: String,
     error_codes: Vec<i32>,
     error_description: String,
 }
@@ -74,7 +112,9 @@ struct EntraIdTokenResponse {
     // (real values are unsigned)
     expires_in: i64,
     ext_expires_in: i64,
+    refresh_in: Option<i64>,
     access_token: String,
+    refresh_token: Option<Secret>,
 }
 
 fn deserialize<T>(res: &RawResponse) -> Result<T>
@@ -85,16 +125,53 @@ where
 }
 
 fn handle_entra_response(response: RawResponse) -> Result<AccessToken> {
-    let status = response.status();
-    if status.is_success() {
+    handle_entra_user_response(response).map(|(token, _)| token)
+}
+
+/// Like [`handle_entra_response`] but also returns the refresh token, if any, Entra ID
+/// included in the response. Credentials authenticating users need this to acquire
+/// tokens for additional scopes without prompting the user again.
+fn handle_entra_user_response(response: RawResponse) -> Result<(AccessToken, Option<Secret>)> {
+    if response.status().is_success() {
         let token_response: EntraIdTokenResponse = deserialize(&response)?;
-        return Ok(AccessToken::new(
+        let now = OffsetDateTime::now_utc();
+        let mut token = AccessToken::new(
             token_response.access_token,
-            OffsetDateTime::now_utc() + Duration::seconds(token_response.expires_in),
-        ));
+            now + Duration::seconds(token_response.expires_in),
+        );
+        token.refresh_on = refresh_on(
+            now,
+            Duration::seconds(token_response.expires_in),
+            token_response.refresh_in,
+        );
+        return Ok((token, token_response.refresh_token));
     }
 
-    let error_response: EntraIdErrorResponse = deserialize(&response)?;
+    Err(entra_error(response))
+}
+
+/// Returns when a token acquired at `now` should be refreshed. Like MSAL, this prefers the
+/// service's `refresh_in` hint and otherwise refreshes tokens valid for at least two hours
+/// at half their lifetime. `None` means refresh shortly before the token expires.
+pub(crate) fn refresh_on(
+    now: OffsetDateTime,
+    lifetime: Duration,
+    refresh_in: Option<i64>,
+) -> Option<OffsetDateTime> {
+    match refresh_in {
+        Some(refresh_in) if refresh_in > 0 => Some(now + Duration::seconds(refresh_in)),
+        _ if lifetime >= Duration::hours(2) => Some(now + lifetime / 2),
+        _ => None,
+    }
+}
+
+/// Converts an Entra ID error response to an [`Error`] carrying the response.
+fn entra_error(response: RawResponse) -> Error {
+    let status = response.status();
+    let error_response: EntraIdErrorResponse = match deserialize(&response) {
+        Ok(error_response) => error_response,
+        Err(err) => return err,
+    };
     let error_code = if error_response.error_codes.is_empty() {
         None
     } else {
@@ -107,14 +184,14 @@ fn handle_entra_response(response: RawResponse) -> Result<AccessToken> {
                 .join(","),
         )
     };
-    Err(Error::new(
+    Error::new(
         ErrorKind::HttpResponse {
             status,
             error_code,
             raw_response: Some(Box::new(response)),
         },
         error_response.error_description,
-    ))
+    )
 }
 
 fn validate_not_empty<C>(value: &str, message: C) -> Result<()>
@@ -170,9 +247,13 @@ fn authentication_error(credential_name: &str, err: Error) -> Error {
         stringify!(AzureCliCredential) => "#azure-cli",
         stringify!(AzureDeveloperCliCredential) => "#azd",
         stringify!(AzurePipelinesCredential) => "#apc",
+        stringify!(AzurePowerShellCredential) => "#azure-pwsh",
         stringify!(ClientCertificateCredential) => "#client-cert",
         stringify!(ClientSecretCredential) => "#client-secret",
+        stringify!(DeviceCodeCredential) => "#device-code",
+        stringify!(InteractiveBrowserCredential) => "#interactive-browser",
         stringify!(ManagedIdentityCredential) => "#managed-id",
+        stringify!(OnBehalfOfCredential) => "#obo",
         stringify!(WorkloadIdentityCredential) => "#workload",
         _ => "",
     };
@@ -473,4 +554,19 @@ mod tests {
         let err = get_authority_host(None, Some(&cloud)).unwrap_err();
         assert!(err.to_string().contains("HTTPS"));
     }
+
+    #[test]
+    fn refresh_on_policy() {
+        let now = OffsetDateTime::now_utc();
+        assert_eq!(
+            refresh_on(now, Duration::hours(1), Some(600)),
+            Some(now + Duration::seconds(600))
+        );
+        assert_eq!(
+            refresh_on(now, Duration::hours(24), None),
+            Some(now + Duration::hours(12))
+        );
+        assert_eq!(refresh_on(now, Duration::hours(1), None), None);
+        assert_eq!(refresh_on(now, Duration::hours(1), Some(0)), None);
+    }
 }
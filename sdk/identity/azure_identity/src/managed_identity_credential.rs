@@ -2,7 +2,9 @@
 // Licensed under the MIT License.
 
 use crate::{
-    authentication_error, env::Env, AppServiceManagedIdentityCredential, ImdsId,
+    authentication_error, env::Env, AppServiceManagedIdentityCredential,
+    AzureArcManagedIdentityCredential, AzureMLManagedIdentityCredential,
+    CloudShellManagedIdentityCredential, ImdsId, ServiceFabricManagedIdentityCredential,
     VirtualMachineManagedIdentityCredential,
 };
 use azure_core::credentials::{AccessToken, TokenCredential, TokenRequestOptions};
@@ -21,7 +23,12 @@ pub enum UserAssignedId {
     ResourceId(String),
 }
 
-/// Authenticates a managed identity from Azure App Service or an Azure Virtual Machine.
+/// Authenticates a managed identity from Azure App Service, Azure Arc, Azure Cloud Shell, Azure Machine Learning,
+/// Service Fabric, or an Azure Virtual Machine.
+///
+/// The credential detects its hosting environment from environment variables the environment sets. Not every
+/// environment supports user-assigned identities: Azure Arc, Cloud Shell and Service Fabric support only the
+/// system-assigned identity, and Azure Machine Learning accepts only client IDs.
 #[derive(Debug)]
 pub struct ManagedIdentityCredential {
     credential: Arc<dyn TokenCredential>,
@@ -74,14 +81,20 @@ impl ManagedIdentityCredential {
                 }
                 AppServiceManagedIdentityCredential::new(id, options.client_options, env)?
             }
+            ManagedIdentitySource::AzureArc => {
+                AzureArcManagedIdentityCredential::new(id, options.client_options, env)?
+            }
+            ManagedIdentitySource::AzureML => {
+                AzureMLManagedIdentityCredential::new(id, options.client_options, env)?
+            }
+            ManagedIdentitySource::CloudShell => {
+                CloudShellManagedIdentityCredential::new(id, options.client_options, env)?
+            }
             ManagedIdentitySource::Imds => {
                 VirtualMachineManagedIdentityCredential::new(id, options.client_options, env)?
             }
-            _ => {
-                return Err(azure_core::Error::with_message_fn(
-                    azure_core::error::ErrorKind::Credential,
-                    || format!("{} managed identity isn't supported", source.as_str()),
-                ));
+            ManagedIdentitySource::ServiceFabric => {
+                ServiceFabricManagedIdentityCredential::new(id, options.client_options, env)?
             }
         };
 
@@ -182,6 +195,7 @@ mod tests {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     const EXPIRES_ON: &str = "EXPIRES_ON";
+    const SERVICE_FABRIC_THUMBPRINT: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
 
     async fn run_deployed_test(
         authority: &str,
@@ -233,7 +247,31 @@ mod tests {
                     (IDENTITY_HEADER, "secret"),
                 ][..],
             ),
-            other => panic!("unsupported managed identity source {:?}", other),
+            ManagedIdentitySource::AzureArc => Env::from(
+                &[
+                    (IDENTITY_ENDPOINT, "http://localhost:40342"),
+                    (IMDS_ENDPOINT, "http://localhost:40342"),
+                ][..],
+            ),
+            ManagedIdentitySource::AzureML => Env::from(
+                &[
+                    (MSI_ENDPOINT, "http://localhost:46808/msi/token"),
+                    (MSI_SECRET, "secret"),
+                ][..],
+            ),
+            ManagedIdentitySource::CloudShell => {
+                Env::from(&[(MSI_ENDPOINT, "http://localhost:50342/oauth2/token")][..])
+            }
+            ManagedIdentitySource::ServiceFabric => Env::from(
+                &[
+                    (
+                        IDENTITY_ENDPOINT,
+                        "https://localhost:2377/metadata/identity/oauth2/token",
+                    ),
+                    (IDENTITY_HEADER, "secret"),
+                    (IDENTITY_SERVER_THUMBPRINT, SERVICE_FABRIC_THUMBPRINT),
+                ][..],
+            ),
         };
         let options = ManagedIdentityCredentialOptions {
             client_options: ClientOptions {
@@ -316,6 +354,7 @@ mod tests {
                     expected.headers().iter().for_each(|(k, v)| {
                         assert_eq!(actual.headers().get_str(k).unwrap(), v.as_str())
                     });
+                    assert_eq!(Bytes::from(expected.body()), Bytes::from(actual.body()));
 
                     Ok(AsyncRawResponse::from_bytes(
                         StatusCode::Ok,
@@ -345,20 +384,18 @@ mod tests {
         }
     }
 
-    fn run_unsupported_source_test(env: Env, expected_source: ManagedIdentitySource) {
-        let actual_source = get_source(&env);
-        assert_eq!(
-            std::mem::discriminant(&actual_source),
-            std::mem::discriminant(&expected_source)
-        );
-        let result = ManagedIdentityCredential::new(Some(ManagedIdentityCredentialOptions {
-            env,
-            ..Default::default()
-        }));
-        assert!(
-            matches!(result, Err(ref e) if *e.kind() == azure_core::error::ErrorKind::Credential),
-            "Expected constructor error"
-        );
+    fn run_user_assigned_unsupported_test(env: Env, ids: &[UserAssignedId]) {
+        for id in ids {
+            let result = ManagedIdentityCredential::new(Some(ManagedIdentityCredentialOptions {
+                env: env.clone(),
+                user_assigned_id: Some(id.clone()),
+                ..Default::default()
+            }));
+            assert!(
+                matches!(result, Err(ref e) if *e.kind() == azure_core::error::ErrorKind::Credential),
+                "Expected constructor error for {id:?}"
+            );
+        }
     }
 
     #[recorded::test(live)]
@@ -469,32 +506,169 @@ mod tests {
         );
     }
 
-    #[test]
-    fn arc() {
-        run_unsupported_source_test(
+    // the challenge handshake is tested in azure_arc_managed_identity_credential
+    #[tokio::test]
+    async fn arc() {
+        let endpoint = "http://localhost:40342/metadata/identity/oauth2/token";
+        let mut model = Request::new(endpoint.parse().unwrap(), Method::Get);
+        model.insert_header("metadata", "true");
+        model.url_mut().query_pairs_mut().extend_pairs([
+            ("api-version", "2020-06-01"),
+            ("resource", LIVE_TEST_RESOURCE),
+        ]);
+        run_supported_source_test(
             Env::from(
                 &[
-                    (IDENTITY_ENDPOINT, "http://localhost"),
-                    (IMDS_ENDPOINT, "..."),
+                    (IDENTITY_ENDPOINT, endpoint),
+                    (IMDS_ENDPOINT, "http://localhost:40342"),
                 ][..],
             ),
+            None,
             ManagedIdentitySource::AzureArc,
-        );
+            model,
+            format!(
+                r#"{{"access_token":"*","expires_on":"{}","resource":"{}","token_type":"Bearer"}}"#,
+                EXPIRES_ON, LIVE_TEST_RESOURCE
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn arc_error_response() {
+        run_error_response_test(ManagedIdentitySource::AzureArc).await
     }
 
     #[test]
-    fn azure_ml() {
-        run_unsupported_source_test(
-            Env::from(&[(MSI_ENDPOINT, "..."), (MSI_SECRET, "...")][..]),
+    fn arc_user_assigned() {
+        run_user_assigned_unsupported_test(
+            Env::from(
+                &[
+                    (IDENTITY_ENDPOINT, "http://localhost:40342"),
+                    (IMDS_ENDPOINT, "http://localhost:40342"),
+                ][..],
+            ),
+            &[
+                UserAssignedId::ClientId("client".to_string()),
+                UserAssignedId::ObjectId("object".to_string()),
+                UserAssignedId::ResourceId("resource".to_string()),
+            ],
+        );
+    }
+
+    async fn run_azure_ml_test(
+        env: &[(&str, &str)],
+        options: Option<ManagedIdentityCredentialOptions>,
+        client_id: Option<&str>,
+    ) {
+        let endpoint = "http://localhost:46808/msi/token";
+        let secret = "msi secret";
+        let mut model = Request::new(endpoint.parse().unwrap(), Method::Get);
+        model.insert_header("secret", secret);
+        let mut params = Vec::from([
+            ("api-version", "2017-09-01"),
+            ("resource", LIVE_TEST_RESOURCE),
+        ]);
+        if let Some(client_id) = client_id {
+            params.push(("clientid", client_id));
+        }
+        model.url_mut().query_pairs_mut().extend_pairs(params);
+        let mut env = env.to_vec();
+        env.extend([(MSI_ENDPOINT, endpoint), (MSI_SECRET, secret)]);
+        run_supported_source_test(
+            Env::from(&env[..]),
+            options,
             ManagedIdentitySource::AzureML,
+            model,
+            format!(
+                r#"{{"access_token":"*","expires_on":"{}","resource":"{}","token_type":"Bearer"}}"#,
+                EXPIRES_ON, LIVE_TEST_RESOURCE
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn azure_ml() {
+        run_azure_ml_test(&[], None, None).await;
+    }
+
+    #[tokio::test]
+    async fn azure_ml_client_id() {
+        run_azure_ml_test(
+            &[("DEFAULT_IDENTITY_CLIENT_ID", "default client ID")],
+            Some(ManagedIdentityCredentialOptions {
+                user_assigned_id: Some(UserAssignedId::ClientId("expected client ID".to_string())),
+                ..Default::default()
+            }),
+            Some("expected client ID"),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn azure_ml_default_client_id() {
+        run_azure_ml_test(
+            &[("DEFAULT_IDENTITY_CLIENT_ID", "default client ID")],
+            None,
+            Some("default client ID"),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn azure_ml_error_response() {
+        run_error_response_test(ManagedIdentitySource::AzureML).await
+    }
+
+    #[test]
+    fn azure_ml_user_assigned() {
+        run_user_assigned_unsupported_test(
+            Env::from(&[(MSI_ENDPOINT, "http://localhost"), (MSI_SECRET, "...")][..]),
+            &[
+                UserAssignedId::ObjectId("object".to_string()),
+                UserAssignedId::ResourceId("resource".to_string()),
+            ],
         );
     }
 
+    #[tokio::test]
+    async fn cloudshell() {
+        let endpoint = "http://localhost:50342/oauth2/token";
+        let mut model = Request::new(endpoint.parse().unwrap(), Method::Post);
+        model.insert_header("metadata", "true");
+        model.insert_header("content-type", "application/x-www-form-urlencoded");
+        model.set_body(format!(
+            "resource={}",
+            url::form_urlencoded::byte_serialize(LIVE_TEST_RESOURCE.as_bytes()).collect::<String>()
+        ));
+        run_supported_source_test(
+            Env::from(&[(MSI_ENDPOINT, endpoint)][..]),
+            None,
+            ManagedIdentitySource::CloudShell,
+            model,
+            format!(
+                r#"{{"access_token":"*","expires_on":"{}","resource":"{}","token_type":"Bearer"}}"#,
+                EXPIRES_ON, LIVE_TEST_RESOURCE
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn cloudshell_error_response() {
+        run_error_response_test(ManagedIdentitySource::CloudShell).await
+    }
+
     #[test]
-    fn cloudshell() {
-        run_unsupported_source_test(
+    fn cloudshell_user_assigned() {
+        run_user_assigned_unsupported_test(
             Env::from(&[(MSI_ENDPOINT, "http://localhost")][..]),
-            ManagedIdentitySource::CloudShell,
+            &[
+                UserAssignedId::ClientId("client".to_string()),
+                UserAssignedId::ObjectId("object".to_string()),
+                UserAssignedId::ResourceId("resource".to_string()),
+            ],
         );
     }
 
@@ -613,17 +787,91 @@ mod tests {
         }
     }
 
-    #[test]
-    fn service_fabric() {
-        run_unsupported_source_test(
+    #[tokio::test]
+    async fn service_fabric() {
+        let endpoint = "https://localhost:2377/metadata/identity/oauth2/token";
+        let secret = "identity header";
+        let mut model = Request::new(endpoint.parse().unwrap(), Method::Get);
+        model.insert_header("secret", secret);
+        model.url_mut().query_pairs_mut().extend_pairs([
+            ("api-version", "2019-07-01-preview"),
+            ("resource", LIVE_TEST_RESOURCE),
+        ]);
+        run_supported_source_test(
             Env::from(
                 &[
-                    (IDENTITY_ENDPOINT, "http://localhost"),
+                    (IDENTITY_ENDPOINT, endpoint),
+                    (IDENTITY_HEADER, secret),
+                    (IDENTITY_SERVER_THUMBPRINT, SERVICE_FABRIC_THUMBPRINT),
+                ][..],
+            ),
+            None,
+            ManagedIdentitySource::ServiceFabric,
+            model,
+            // Service Fabric sends expires_on as a number
+            format!(
+                r#"{{"access_token":"*","expires_on":{},"resource":"{}","token_type":"Bearer"}}"#,
+                EXPIRES_ON, LIVE_TEST_RESOURCE
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn service_fabric_error_response() {
+        run_error_response_test(ManagedIdentitySource::ServiceFabric).await
+    }
+
+    #[test]
+    fn service_fabric_invalid_thumbprint() {
+        let result = ManagedIdentityCredential::new(Some(ManagedIdentityCredentialOptions {
+            env: Env::from(
+                &[
+                    (IDENTITY_ENDPOINT, "https://localhost:2377"),
                     (IDENTITY_HEADER, "..."),
                     (IDENTITY_SERVER_THUMBPRINT, "..."),
                 ][..],
             ),
-            ManagedIdentitySource::ServiceFabric,
+            ..Default::default()
+        }));
+        assert!(
+            matches!(result, Err(ref e) if *e.kind() == azure_core::error::ErrorKind::Credential),
+            "Expected constructor error"
+        );
+    }
+
+    #[cfg(not(feature = "service_fabric"))]
+    #[test]
+    fn service_fabric_requires_transport() {
+        let err = ManagedIdentityCredential::new(Some(ManagedIdentityCredentialOptions {
+            env: Env::from(
+                &[
+                    (IDENTITY_ENDPOINT, "https://localhost:2377"),
+                    (IDENTITY_HEADER, "..."),
+                    (IDENTITY_SERVER_THUMBPRINT, SERVICE_FABRIC_THUMBPRINT),
+                ][..],
+            ),
+            ..Default::default()
+        }))
+        .expect_err("no transport trusts the certificate");
+        assert!(err.to_string().contains("service_fabric feature"), "{err}");
+    }
+
+    #[test]
+    fn service_fabric_user_assigned() {
+        run_user_assigned_unsupported_test(
+            Env::from(
+                &[
+                    (IDENTITY_ENDPOINT, "https://localhost:2377"),
+                    (IDENTITY_HEADER, "..."),
+                    (IDENTITY_SERVER_THUMBPRINT, SERVICE_FABRIC_THUMBPRINT),
+                ][..],
+            ),
+            &[
+                UserAssignedId::ClientId("client".to_string()),
+                UserAssignedId::ObjectId("object".to_string()),
+                UserAssignedId::ResourceId("resource".to_string()),
+            ],
         );
     }
 }
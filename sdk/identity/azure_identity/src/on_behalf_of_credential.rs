@@ -0,0 +1,506 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+#[cfg(feature = "client_certificate")]
+use crate::ClientCertificateCredential;
+use crate::{
+    authentication_error, get_authority_host, validate_not_empty, validate_tenant_id,
+    ClientAssertion, TokenCache,
+};
+use azure_core::{
+    base64,
+    credentials::{AccessToken, Secret, TokenCredential, TokenRequestOptions},
+    error::{Error, ErrorKind, ResultExt},
+    http::{
+        headers::{self, content_type},
+        ClientOptions, Method, Pipeline, PipelineSendOptions, Request, Url,
+    },
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use url::form_urlencoded;
+
+const ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// How an [`OnBehalfOfCredential`] authenticates the application.
+#[derive(Clone, Debug)]
+pub enum ClientAuthentication {
+    /// A client secret that was generated for the application.
+    Secret(Secret),
+
+    /// A certificate registered for the application. The credential must be for the same
+    /// tenant and client as the [`OnBehalfOfCredential`].
+    ///
+    /// The [`OnBehalfOfCredential`] uses it only to sign client assertions.
+    #[cfg(feature = "client_certificate")]
+    Certificate(Arc<ClientCertificateCredential>),
+
+    /// A callback that supplies a signed client assertion, for example a
+    /// [`ManagedIdentityCredential`](crate::ManagedIdentityCredential) token for a federated identity credential.
+    Assertion(Arc<dyn ClientAssertion>),
+}
+
+/// Options for constructing a new [`OnBehalfOfCredential`].
+#[derive(Debug, Default)]
+pub struct OnBehalfOfCredentialOptions {
+    /// Options for the credential's HTTP pipeline.
+    pub client_options: ClientOptions,
+}
+
+/// Authenticates a middle-tier application as the user who called it, with the
+/// [on-behalf-of flow](https://learn.microsoft.com/entra/identity-platform/v2-oauth2-on-behalf-of-flow).
+///
+/// An `OnBehalfOfCredential` represents the application and is intended to be long-lived. For each
+/// incoming request, call [`OnBehalfOfCredential::for_user`] with the caller's access token to get a
+/// [`TokenCredential`] that acquires tokens as that user. Tokens are cached per user assertion, so
+/// requests from the same user share tokens.
+///
+/// # Examples
+///
+/// ```no_run
+/// use azure_core::credentials::TokenCredential;
+/// use azure_identity::{ClientAuthentication, OnBehalfOfCredential};
+///
+/// # async fn example(incoming_token: String) -> azure_core::Result<()> {
+/// let credential = OnBehalfOfCredential::new(
+///     "tenant-id",
+///     "client-id".to_string(),
+///     ClientAuthentication::Secret("client secret".into()),
+///     None,
+/// )?;
+///
+/// // for each request, exchange the caller's token for one to call a downstream API as the caller
+/// let user_credential = credential.for_user(incoming_token);
+/// let token = user_credential
+///     .get_token(&["https://vault.azure.net/.default"], None)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OnBehalfOfCredential {
+    authentication: ClientAuthentication,
+    cache: TokenCache,
+    client_id: String,
+    endpoint: Url,
+    pipeline: Pipeline,
+}
+
+impl OnBehalfOfCredential {
+    /// Creates a new `OnBehalfOfCredential`.
+    ///
+    /// # Arguments
+    /// - `tenant_id`: The Microsoft Entra tenant (directory) ID of the application.
+    /// - `client_id`: The client (application) ID of the application.
+    /// - `authentication`: How the credential authenticates the application.
+    /// - `options`: Options for configuring the credential. If `None` is provided, default options will be used.
+    pub fn new(
+        tenant_id: &str,
+        client_id: String,
+        authentication: ClientAuthentication,
+        options: Option<OnBehalfOfCredentialOptions>,
+    ) -> azure_core::Result<Arc<Self>> {
+        validate_tenant_id(tenant_id)?;
+        validate_not_empty(&client_id, "no client ID specified")?;
+
+        let options = options.unwrap_or_default();
+        let authority_host = get_authority_host(None, options.client_options.cloud.as_deref())?;
+        let endpoint = authority_host
+            .join(&format!("/{tenant_id}/oauth2/v2.0/token"))
+            .with_context_fn(ErrorKind::DataConversion, || {
+                format!("tenant_id '{tenant_id}' could not be URL encoded")
+            })?;
+
+        match &authentication {
+            ClientAuthentication::Secret(secret) => {
+                validate_not_empty(secret.secret(), "no secret specified")?
+            }
+            #[cfg(feature = "client_certificate")]
+            ClientAuthentication::Certificate(certificate) => {
+                // the certificate credential's assertions are valid only for its own client and token endpoint
+                if certificate.client_id() != client_id || certificate.endpoint() != &endpoint {
+                    return Err(Error::with_message(
+                        ErrorKind::Credential,
+                        "the certificate credential must have the same tenant and client IDs as the OnBehalfOfCredential",
+                    ));
+                }
+            }
+            ClientAuthentication::Assertion(_) => {}
+        }
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options,
+            Vec::default(),
+            Vec::default(),
+            None,
+        );
+
+        Ok(Arc::new(Self {
+            authentication,
+            cache: TokenCache::new(),
+            client_id,
+            endpoint,
+            pipeline,
+        }))
+    }
+
+    /// Returns a credential that acquires tokens on behalf of the user who presented `user_assertion`.
+    ///
+    /// # Arguments
+    /// - `user_assertion`: The access token the user sent to the application.
+    pub fn for_user(
+        self: &Arc<Self>,
+        user_assertion: impl Into<Secret>,
+    ) -> Arc<UserAssertionCredential> {
+        let user_assertion = user_assertion.into();
+        // key the cache with a hash so it doesn't hold on to users' tokens
+        let partition = base64::encode(Sha256::digest(user_assertion.secret().as_bytes()));
+        Arc::new(UserAssertionCredential {
+            credential: self.clone(),
+            partition,
+            user_assertion,
+        })
+    }
+
+    async fn get_token_impl(
+        &self,
+        user_assertion: &Secret,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        let options = options.unwrap_or_default();
+
+        // (name, value) of the application's credential
+        let client_credential = match &self.authentication {
+            ClientAuthentication::Secret(secret) => ("client_secret", secret.secret().to_string()),
+            #[cfg(feature = "client_certificate")]
+            ClientAuthentication::Certificate(certificate) => {
+                ("client_assertion", certificate.client_assertion()?)
+            }
+            ClientAuthentication::Assertion(assertion) => (
+                "client_assertion",
+                assertion
+                    .secret(Some(options.method_options.to_owned()))
+                    .await?,
+            ),
+        };
+
+        let body = {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("assertion", user_assertion.secret())
+                .append_pair("client_id", &self.client_id)
+                .append_pair(client_credential.0, &client_credential.1)
+                .append_pair("grant_type", JWT_BEARER_GRANT_TYPE)
+                .append_pair("requested_token_use", "on_behalf_of")
+                .append_pair("scope", &scopes.join(" "));
+            if client_credential.0 == "client_assertion" {
+                form.append_pair("client_assertion_type", ASSERTION_TYPE);
+            }
+            form.finish()
+        };
+
+        let mut req = Request::new(self.endpoint.clone(), Method::Post);
+        req.insert_header(
+            headers::CONTENT_TYPE,
+            content_type::APPLICATION_X_WWW_FORM_URLENCODED,
+        );
+        req.set_body(body);
+
+        let ctx = options.method_options.context.to_borrowed();
+        let res = self
+            .pipeline
+            .send(
+                &ctx,
+                &mut req,
+                Some(PipelineSendOptions {
+                    skip_checks: true,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+
+        crate::handle_entra_response(res)
+    }
+}
+
+/// Acquires tokens on behalf of a user. Get one from [`OnBehalfOfCredential::for_user`].
+#[derive(Debug)]
+pub struct UserAssertionCredential {
+    credential: Arc<OnBehalfOfCredential>,
+    partition: String,
+    user_assertion: Secret,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for UserAssertionCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        if scopes.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "no scopes specified",
+            ));
+        }
+        self.credential
+            .cache
+            .get_partitioned_token(&self.partition, scopes, options, |s, o| {
+                self.credential.get_token_impl(&self.user_assertion, s, o)
+            })
+            .await
+            .map_err(|err| authentication_error(stringify!(OnBehalfOfCredential), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use azure_core::{
+        http::{
+            headers::Headers, AsyncRawResponse, Body, ClientMethodOptions, StatusCode, Transport,
+        },
+        Bytes,
+    };
+    use std::{
+        collections::HashMap,
+        str,
+        sync::{Arc, Mutex},
+    };
+
+    const FAKE_ASSERTION: &str = "fake-client-assertion";
+    const FAKE_SECRET: &str = "fake-secret";
+
+    fn user_token_response(token: &str) -> AsyncRawResponse {
+        AsyncRawResponse::from_bytes(
+            StatusCode::Ok,
+            Headers::default(),
+            Bytes::from(format!(
+                r#"{{"access_token":"{token}","expires_in":3600,"token_type":"Bearer"}}"#
+            )),
+        )
+    }
+
+    type RequestParams = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    /// Returns a MockSts that records each request's form parameters.
+    fn mock_sts(responses: Vec<AsyncRawResponse>) -> (MockSts, RequestParams) {
+        let requests = RequestParams::default();
+        let requests_clone = requests.clone();
+        let sts = MockSts::new(
+            responses,
+            Some(Arc::new(move |req: &Request| {
+                assert_eq!(Method::Post, req.method());
+                assert_eq!(
+                    format!("{FAKE_PUBLIC_CLOUD_AUTHORITY}/oauth2/v2.0/token"),
+                    req.url().to_string()
+                );
+                let body = match req.body() {
+                    Body::Bytes(bytes) => str::from_utf8(bytes.as_ref()).unwrap().to_string(),
+                    _ => panic!("unexpected body type"),
+                };
+                requests_clone.lock().unwrap().push(
+                    form_urlencoded::parse(body.as_bytes())
+                        .into_owned()
+                        .collect(),
+                );
+                Ok(())
+            })),
+        );
+        (sts, requests)
+    }
+
+    fn credential(authentication: ClientAuthentication, sts: MockSts) -> Arc<OnBehalfOfCredential> {
+        OnBehalfOfCredential::new(
+            FAKE_TENANT_ID,
+            FAKE_CLIENT_ID.to_string(),
+            authentication,
+            Some(OnBehalfOfCredentialOptions {
+                client_options: ClientOptions {
+                    transport: Some(Transport::new(Arc::new(sts))),
+                    ..Default::default()
+                },
+            }),
+        )
+        .expect("valid credential")
+    }
+
+    fn assert_obo_request(params: &HashMap<String, String>, user_assertion: &str) {
+        assert_eq!(user_assertion, params["assertion"]);
+        assert_eq!(FAKE_CLIENT_ID, params["client_id"]);
+        assert_eq!(JWT_BEARER_GRANT_TYPE, params["grant_type"]);
+        assert_eq!("on_behalf_of", params["requested_token_use"]);
+        assert_eq!(LIVE_TEST_SCOPES.join(" "), params["scope"]);
+    }
+
+    #[tokio::test]
+    async fn client_secret() {
+        let (sts, requests) = mock_sts(vec![token_response()]);
+        let credential = credential(ClientAuthentication::Secret(FAKE_SECRET.into()), sts);
+
+        let token = credential
+            .for_user("user-assertion")
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+        assert_eq!(FAKE_TOKEN, token.token.secret());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        assert_obo_request(&requests[0], "user-assertion");
+        assert_eq!(FAKE_SECRET, requests[0]["client_secret"]);
+        assert!(!requests[0].contains_key("client_assertion"));
+    }
+
+    #[derive(Debug)]
+    struct MockAssertion;
+
+    #[async_trait::async_trait]
+    impl ClientAssertion for MockAssertion {
+        async fn secret(&self, _: Option<ClientMethodOptions<'_>>) -> azure_core::Result<String> {
+            Ok(FAKE_ASSERTION.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn client_assertion() {
+        let (sts, requests) = mock_sts(vec![token_response()]);
+        let credential = credential(
+            ClientAuthentication::Assertion(Arc::new(MockAssertion)),
+            sts,
+        );
+
+        credential
+            .for_user("user-assertion")
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+
+        let requests = requests.lock().unwrap();
+        assert_obo_request(&requests[0], "user-assertion");
+        assert_eq!(FAKE_ASSERTION, requests[0]["client_assertion"]);
+        assert_eq!(ASSERTION_TYPE, requests[0]["client_assertion_type"]);
+        assert!(!requests[0].contains_key("client_secret"));
+    }
+
+    #[cfg(feature = "client_certificate")]
+    #[tokio::test]
+    async fn client_certificate() {
+        let pfx = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/certificate.pfx"
+        ))
+        .expect("failed to read test certificate");
+        let certificate = ClientCertificateCredential::new(
+            FAKE_TENANT_ID.to_string(),
+            FAKE_CLIENT_ID.to_string(),
+            base64::encode(pfx),
+            None,
+        )
+        .expect("valid certificate credential");
+
+        let (sts, requests) = mock_sts(vec![token_response()]);
+        let credential = credential(ClientAuthentication::Certificate(certificate.clone()), sts);
+        credential
+            .for_user("user-assertion")
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect("token");
+
+        let requests = requests.lock().unwrap();
+        assert_obo_request(&requests[0], "user-assertion");
+        assert_eq!(ASSERTION_TYPE, requests[0]["client_assertion_type"]);
+        assert_eq!(3, requests[0]["client_assertion"].split('.').count());
+
+        // the certificate credential must be for the same application
+        OnBehalfOfCredential::new(
+            FAKE_TENANT_ID,
+            "other-client".to_string(),
+            ClientAuthentication::Certificate(certificate),
+            None,
+        )
+        .expect_err("mismatched client ID");
+    }
+
+    #[tokio::test]
+    async fn cache_per_user_assertion() {
+        let (sts, requests) = mock_sts(vec![
+            user_token_response("user-1-token"),
+            user_token_response("user-2-token"),
+        ]);
+        let credential = credential(ClientAuthentication::Secret(FAKE_SECRET.into()), sts);
+
+        for (user, expected) in [
+            ("user-1", "user-1-token"),
+            ("user-2", "user-2-token"),
+            ("user-1", "user-1-token"),
+            ("user-2", "user-2-token"),
+        ] {
+            // a new credential for each request, as in a web API
+            let token = credential
+                .for_user(user)
+                .get_token(LIVE_TEST_SCOPES, None)
+                .await
+                .expect("token");
+            assert_eq!(expected, token.token.secret());
+        }
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(2, requests.len());
+        assert_obo_request(&requests[0], "user-1");
+        assert_obo_request(&requests[1], "user-2");
+    }
+
+    #[tokio::test]
+    async fn error_response() {
+        let (sts, _) = mock_sts(vec![AsyncRawResponse::from_bytes(
+            StatusCode::BadRequest,
+            Headers::default(),
+            Bytes::from(
+                r#"{"error":"invalid_grant","error_description":"AADSTS50013: Assertion failed signature validation.","error_codes":[50013]}"#,
+            ),
+        )]);
+        let credential = credential(ClientAuthentication::Secret(FAKE_SECRET.into()), sts);
+
+        let err = credential
+            .for_user("user-assertion")
+            .get_token(LIVE_TEST_SCOPES, None)
+            .await
+            .expect_err("error response");
+        assert!(matches!(err.kind(), ErrorKind::Credential));
+        let message = err.to_string();
+        assert!(message.contains("OnBehalfOfCredential"), "{message}");
+        assert!(message.contains("AADSTS50013"), "{message}");
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        OnBehalfOfCredential::new(
+            "not a tenant",
+            FAKE_CLIENT_ID.to_string(),
+            ClientAuthentication::Secret(FAKE_SECRET.into()),
+            None,
+        )
+        .expect_err("invalid tenant");
+        OnBehalfOfCredential::new(
+            FAKE_TENANT_ID,
+            String::new(),
+            ClientAuthentication::Secret(FAKE_SECRET.into()),
+            None,
+        )
+        .expect_err("empty client ID");
+        OnBehalfOfCredential::new(
+            FAKE_TENANT_ID,
+            FAKE_CLIENT_ID.to_string(),
+            ClientAuthentication::Secret("".into()),
+            None,
+        )
+        .expect_err("empty secret");
+    }
+}
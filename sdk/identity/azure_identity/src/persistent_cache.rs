@@ -0,0 +1,657 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use azure_core::{
+    base64,
+    credentials::{AccessToken, Secret},
+    error::{Error, ErrorKind, ResultExt},
+    http::Url,
+    json,
+    time::OffsetDateTime,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, sync::Arc};
+use tracing::{trace, warn};
+
+const NONCE_LEN: usize = 12;
+
+/// Stores the encrypted entries of a [`PersistentTokenCache`].
+///
+/// Implement this trait to keep tokens somewhere other than the file system, for example in a
+/// database shared by several hosts. Implementations may be used by multiple processes at once.
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+pub trait TokenCacheStorage: Send + Sync + fmt::Debug {
+    /// Reads the entry with the given key, returning `None` if it doesn't exist.
+    ///
+    /// Keys are hex-encoded hashes, so they're safe to use as file names.
+    async fn read(&self, key: &str) -> azure_core::Result<Option<Vec<u8>>>;
+
+    /// Creates or replaces the entry with the given key.
+    async fn write(&self, key: &str, data: &[u8]) -> azure_core::Result<()>;
+
+    /// Deletes the entry with the given key. Deleting an entry that doesn't exist isn't an error.
+    async fn delete(&self, key: &str) -> azure_core::Result<()>;
+}
+
+/// A token cache that persists access and refresh tokens in a [`TokenCacheStorage`] so that
+/// processes can share them and reuse them across restarts.
+///
+/// Entries are encrypted with AES-256-GCM using a key supplied by the application and are keyed by
+/// authority, client ID, tenant ID and scopes. Entries that can't be decrypted, for example because
+/// the key changed, are ignored.
+///
+/// Persistent caching is opt-in. To use it, pass the same `PersistentTokenCache` in the `token_cache`
+/// option of each credential that should share it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use azure_identity::{
+///     ClientSecretCredential, ClientSecretCredentialOptions, FileTokenCacheStorage,
+///     PersistentTokenCache,
+/// };
+/// use std::sync::Arc;
+///
+/// # fn example(key: azure_core::credentials::Secret) -> azure_core::Result<()> {
+/// let storage = FileTokenCacheStorage::new("/var/cache/my-app/tokens");
+/// let cache = PersistentTokenCache::new(Arc::new(storage), &key)?;
+/// let credential = ClientSecretCredential::new(
+///     "tenant-id",
+///     "client-id".to_string(),
+///     "client secret".into(),
+///     Some(ClientSecretCredentialOptions {
+///         token_cache: Some(cache),
+///         ..Default::default()
+///     }),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PersistentTokenCache {
+    cipher: Aes256Gcm,
+    storage: Arc<dyn TokenCacheStorage>,
+}
+
+impl fmt::Debug for PersistentTokenCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistentTokenCache")
+            .field("storage", &self.storage)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PersistentTokenCache {
+    /// Creates a new `PersistentTokenCache`.
+    ///
+    /// # Arguments
+    /// - `storage`: Where to store the cache's entries.
+    /// - `key`: A base64-encoded 256-bit key with which to encrypt entries. Keep it secret, for
+    ///   example in a platform keychain or Azure Key Vault.
+    pub fn new(storage: Arc<dyn TokenCacheStorage>, key: &Secret) -> azure_core::Result<Arc<Self>> {
+        let key = base64::decode(key.secret()).with_context(
+            ErrorKind::Credential,
+            "the token cache key isn't valid base64",
+        )?;
+        if key.len() != 32 {
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "the token cache key must be 256 bits",
+            ));
+        }
+        Ok(Arc::new(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+            storage,
+        }))
+    }
+
+    async fn get(&self, key: &str) -> Option<Entry> {
+        let storage_key = storage_key(key);
+        let data = match self.storage.read(&storage_key).await {
+            Ok(data) => data?,
+            Err(err) => {
+                warn!("failed to read from the persistent token cache: {err}");
+                return None;
+            }
+        };
+        if data.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .inspect_err(|_| {
+                trace!("ignoring a persistent token cache entry that couldn't be decrypted")
+            })
+            .ok()?;
+        json::from_json(plaintext).ok()
+    }
+
+    async fn set(&self, key: &str, entry: &Entry) {
+        let result = async {
+            let plaintext = json::to_json(entry)?;
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = self
+                .cipher
+                .encrypt(
+                    &nonce,
+                    Payload {
+                        msg: &plaintext,
+                        aad: key.as_bytes(),
+                    },
+                )
+                .map_err(|_| {
+                    Error::with_message(ErrorKind::Credential, "failed to encrypt a token")
+                })?;
+            let mut data = nonce.to_vec();
+            data.extend_from_slice(&ciphertext);
+            self.storage.write(&storage_key(key), &data).await
+        }
+        .await;
+        if let Err(err) = result {
+            warn!("failed to write to the persistent token cache: {err}");
+        }
+    }
+
+    async fn remove(&self, key: &str) {
+        if let Err(err) = self.storage.delete(&storage_key(key)).await {
+            warn!("failed to delete from the persistent token cache: {err}");
+        }
+    }
+}
+
+/// Hashes a cache key so storage never sees scopes or client IDs and can use keys as file names.
+fn storage_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    secret: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_on: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_on: Option<i64>,
+}
+
+/// A credential's view of a [`PersistentTokenCache`], keyed by the credential's authority, client and tenant.
+#[derive(Debug)]
+pub(crate) struct Persistence {
+    cache: Arc<PersistentTokenCache>,
+    prefix: String,
+}
+
+impl Persistence {
+    pub(crate) fn new(
+        cache: Arc<PersistentTokenCache>,
+        authority_host: &Url,
+        client_id: &str,
+        tenant_id: &str,
+    ) -> Self {
+        let authority = authority_host.host_str().unwrap_or_default();
+        Self {
+            cache,
+            prefix: format!("{authority}|{client_id}|{tenant_id}"),
+        }
+    }
+
+    fn access_token_key(&self, partition: &str, scopes: &[String]) -> String {
+        format!(
+            "{}|{partition}|access_token|{}",
+            self.prefix,
+            scopes.join(" ")
+        )
+    }
+
+    fn refresh_token_key(&self) -> String {
+        format!("{}|refresh_token", self.prefix)
+    }
+
+    pub(crate) async fn access_token(
+        &self,
+        partition: &str,
+        scopes: &[String],
+    ) -> Option<AccessToken> {
+        let entry = self
+            .cache
+            .get(&self.access_token_key(partition, scopes))
+            .await?;
+        let expires_on = OffsetDateTime::from_unix_timestamp(entry.expires_on?).ok()?;
+        let mut token = AccessToken::new(entry.secret, expires_on);
+        token.refresh_on = entry
+            .refresh_on
+            .and_then(|r| OffsetDateTime::from_unix_timestamp(r).ok());
+        Some(token)
+    }
+
+    pub(crate) async fn set_access_token(
+        &self,
+        partition: &str,
+        scopes: &[String],
+        token: &AccessToken,
+    ) {
+        let entry = Entry {
+            secret: token.token.secret().to_string(),
+            expires_on: Some(token.expires_on.unix_timestamp()),
+            refresh_on: token.refresh_on.map(OffsetDateTime::unix_timestamp),
+        };
+        self.cache
+            .set(&self.access_token_key(partition, scopes), &entry)
+            .await;
+    }
+
+    pub(crate) async fn refresh_token(&self) -> Option<Secret> {
+        let entry = self.cache.get(&self.refresh_token_key()).await?;
+        Some(Secret::new(entry.secret))
+    }
+
+    pub(crate) async fn set_refresh_token(&self, refresh_token: &Secret) {
+        let entry = Entry {
+            secret: refresh_token.secret().to_string(),
+            expires_on: None,
+            refresh_on: None,
+        };
+        self.cache.set(&self.refresh_token_key(), &entry).await;
+    }
+
+    pub(crate) async fn clear_refresh_token(&self) {
+        self.cache.remove(&self.refresh_token_key()).await;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileTokenCacheStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file {
+    use super::TokenCacheStorage;
+    use azure_core::{
+        error::{Error, ErrorKind, ResultExt},
+        time::Duration,
+        Uuid,
+    };
+    use std::{
+        fs::{self, File, OpenOptions},
+        io::{self, Write},
+        path::{Path, PathBuf},
+        time::{Instant, SystemTime},
+    };
+
+    /// How long to wait for another process to release a lock.
+    const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+    /// Locks older than this were probably left behind by a process that exited while holding them.
+    const STALE_LOCK_AGE: std::time::Duration = std::time::Duration::from_secs(30);
+
+    /// A [`TokenCacheStorage`] that stores each entry in a file in a directory.
+    ///
+    /// The directory and files are created readable only by the current user on Unix. Writes replace
+    /// files atomically, and processes coordinate through lock files so concurrent processes can share
+    /// the directory.
+    #[derive(Clone, Debug)]
+    pub struct FileTokenCacheStorage {
+        directory: PathBuf,
+    }
+
+    impl FileTokenCacheStorage {
+        /// Creates a new `FileTokenCacheStorage` that stores entries in `directory`, creating it if necessary.
+        pub fn new(directory: impl Into<PathBuf>) -> Self {
+            Self {
+                directory: directory.into(),
+            }
+        }
+
+        fn path(&self, key: &str, extension: &str) -> PathBuf {
+            self.directory.join(format!("{key}.{extension}"))
+        }
+
+        fn create_directory(&self) -> azure_core::Result<()> {
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder
+                .create(&self.directory)
+                .with_context_fn(ErrorKind::Io, || {
+                    format!("failed to create {}", self.directory.display())
+                })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCacheStorage for FileTokenCacheStorage {
+        async fn read(&self, key: &str) -> azure_core::Result<Option<Vec<u8>>> {
+            if !self.directory.exists() {
+                return Ok(None);
+            }
+            let _lock = FileLock::acquire(self.path(key, "lock")).await?;
+            match fs::read(self.path(key, "bin")) {
+                Ok(data) => Ok(Some(data)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(Error::with_error(
+                    ErrorKind::Io,
+                    err,
+                    "failed to read a token cache file",
+                )),
+            }
+        }
+
+        async fn write(&self, key: &str, data: &[u8]) -> azure_core::Result<()> {
+            self.create_directory()?;
+            let _lock = FileLock::acquire(self.path(key, "lock")).await?;
+            // write a temporary file and rename it so readers never see a partial entry
+            let temp = self.path(key, &format!("{}.tmp", Uuid::new_v4()));
+            let result = create_private(&temp)
+                .and_then(|mut file| file.write_all(data).and_then(|_| file.sync_all()))
+                .and_then(|_| fs::rename(&temp, self.path(key, "bin")));
+            if result.is_err() {
+                let _ = fs::remove_file(&temp);
+            }
+            result.with_context(ErrorKind::Io, "failed to write a token cache file")
+        }
+
+        async fn delete(&self, key: &str) -> azure_core::Result<()> {
+            if !self.directory.exists() {
+                return Ok(());
+            }
+            let _lock = FileLock::acquire(self.path(key, "lock")).await?;
+            match fs::remove_file(self.path(key, "bin")) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Error::with_error(
+                    ErrorKind::Io,
+                    err,
+                    "failed to delete a token cache file",
+                )),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// Creates a new file readable and writable only by the current user.
+    fn create_private(path: &Path) -> io::Result<File> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)
+    }
+
+    /// An exclusive lock held by creating a file, which works across processes and platforms.
+    /// Dropping the lock deletes the file.
+    #[derive(Debug)]
+    struct FileLock {
+        path: PathBuf,
+    }
+
+    impl FileLock {
+        async fn acquire(path: PathBuf) -> azure_core::Result<Self> {
+            let start = Instant::now();
+            loop {
+                match create_private(&path) {
+                    Ok(_) => return Ok(Self { path }),
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                        if is_stale(&path) {
+                            let _ = fs::remove_file(&path);
+                            continue;
+                        }
+                        if start.elapsed() >= LOCK_TIMEOUT {
+                            return Err(Error::with_message_fn(ErrorKind::Io, || {
+                                format!("timed out waiting for lock {}", path.display())
+                            }));
+                        }
+                        azure_core::sleep(Duration::milliseconds(10)).await;
+                    }
+                    Err(err) => {
+                        return Err(Error::with_error_fn(ErrorKind::Io, err, || {
+                            format!("failed to create lock {}", path.display())
+                        }))
+                    }
+                }
+            }
+        }
+    }
+
+    impl Drop for FileLock {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    fn is_stale(path: &Path) -> bool {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > STALE_LOCK_AGE)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::{sync::Arc, time::Duration as StdDuration};
+
+        fn temp_storage() -> FileTokenCacheStorage {
+            FileTokenCacheStorage::new(
+                std::env::temp_dir().join(format!("azure_identity_test_{}", Uuid::new_v4())),
+            )
+        }
+
+        #[tokio::test]
+        async fn read_write_delete() {
+            let storage = temp_storage();
+            assert_eq!(None, storage.read("key").await.unwrap());
+            storage
+                .delete("key")
+                .await
+                .expect("deleting a missing entry");
+
+            storage.write("key", b"data").await.unwrap();
+            assert_eq!(Some(b"data".to_vec()), storage.read("key").await.unwrap());
+            storage.write("key", b"new data").await.unwrap();
+            assert_eq!(
+                Some(b"new data".to_vec()),
+                storage.read("key").await.unwrap()
+            );
+
+            storage.delete("key").await.unwrap();
+            assert_eq!(None, storage.read("key").await.unwrap());
+
+            // only the directory should remain; no lock or temporary files
+            assert_eq!(0, fs::read_dir(&storage.directory).unwrap().count());
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                storage.write("key", b"data").await.unwrap();
+                let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+                assert_eq!(0o700, mode(&storage.directory));
+                assert_eq!(0o600, mode(&storage.path("key", "bin")));
+            }
+
+            fs::remove_dir_all(&storage.directory).unwrap();
+        }
+
+        #[tokio::test]
+        async fn concurrent_writers() {
+            let storage = Arc::new(temp_storage());
+            let tasks: Vec<_> = (0..8)
+                .map(|i| {
+                    let storage = storage.clone();
+                    tokio::spawn(async move {
+                        storage
+                            .write("key", format!("data {i}").as_bytes())
+                            .await
+                            .unwrap()
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+            let data = storage.read("key").await.unwrap().unwrap();
+            assert!(String::from_utf8(data).unwrap().starts_with("data "));
+            fs::remove_dir_all(&storage.directory).unwrap();
+        }
+
+        #[tokio::test]
+        async fn stale_lock() {
+            let storage = temp_storage();
+            storage.create_directory().unwrap();
+            let lock = storage.path("key", "lock");
+            let file = File::create(&lock).unwrap();
+            file.set_modified(SystemTime::now() - STALE_LOCK_AGE - StdDuration::from_secs(1))
+                .unwrap();
+
+            storage
+                .write("key", b"data")
+                .await
+                .expect("stale lock should be removed");
+            assert!(!lock.exists());
+            fs::remove_dir_all(&storage.directory).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{collections::HashMap, sync::Mutex};
+
+    pub(crate) fn test_key() -> Secret {
+        Secret::new(base64::encode([7u8; 32]))
+    }
+
+    /// In-memory storage that can be shared by credentials in a test.
+    #[derive(Debug, Default)]
+    pub(crate) struct MemoryStorage {
+        pub(crate) entries: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCacheStorage for MemoryStorage {
+        async fn read(&self, key: &str) -> azure_core::Result<Option<Vec<u8>>> {
+            Ok(self.entries.lock().unwrap().get(key).cloned())
+        }
+
+        async fn write(&self, key: &str, data: &[u8]) -> azure_core::Result<()> {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> azure_core::Result<()> {
+            self.entries.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    fn persistence(cache: Arc<PersistentTokenCache>, client_id: &str) -> Persistence {
+        Persistence::new(
+            cache,
+            &Url::parse("https://login.microsoftonline.com").unwrap(),
+            client_id,
+            "tenant",
+        )
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = PersistentTokenCache::new(storage.clone(), &test_key()).unwrap();
+        let persistence = persistence(cache, "client");
+        let scopes = vec!["scope".to_string()];
+
+        assert!(persistence.access_token("", &scopes).await.is_none());
+        assert!(persistence.refresh_token().await.is_none());
+
+        let expires_on = OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap();
+        let refresh_on = OffsetDateTime::from_unix_timestamp(1_999_990_000).unwrap();
+        let mut access_token = AccessToken::new("access", expires_on);
+        access_token.refresh_on = Some(refresh_on);
+        persistence
+            .set_access_token("", &scopes, &access_token)
+            .await;
+        persistence.set_refresh_token(&Secret::new("refresh")).await;
+
+        let token = persistence.access_token("", &scopes).await.unwrap();
+        assert_eq!("access", token.token.secret());
+        assert_eq!(expires_on, token.expires_on);
+        assert_eq!(Some(refresh_on), token.refresh_on);
+        assert_eq!(
+            "refresh",
+            persistence.refresh_token().await.unwrap().secret()
+        );
+        assert!(persistence.access_token("other", &scopes).await.is_none());
+        assert!(persistence
+            .access_token("", &["other".to_string()])
+            .await
+            .is_none());
+
+        // entries are encrypted and their keys hashed
+        for (key, data) in storage.entries.lock().unwrap().iter() {
+            assert_eq!(64, key.len());
+            let data = String::from_utf8_lossy(data);
+            assert!(!data.contains("access") && !data.contains("refresh"));
+        }
+
+        persistence.clear_refresh_token().await;
+        assert!(persistence.refresh_token().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn keyed_by_client() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = PersistentTokenCache::new(storage, &test_key()).unwrap();
+        persistence(cache.clone(), "client")
+            .set_refresh_token(&Secret::new("refresh"))
+            .await;
+        assert!(persistence(cache, "other client")
+            .refresh_token()
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn wrong_key() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = PersistentTokenCache::new(storage.clone(), &test_key()).unwrap();
+        persistence(cache, "client")
+            .set_refresh_token(&Secret::new("refresh"))
+            .await;
+
+        let other_key = Secret::new(base64::encode([8u8; 32]));
+        let cache = PersistentTokenCache::new(storage.clone(), &other_key).unwrap();
+        assert!(persistence(cache, "client").refresh_token().await.is_none());
+
+        // corrupt entries are ignored too
+        for data in storage.entries.lock().unwrap().values_mut() {
+            data.truncate(5);
+        }
+        let cache = PersistentTokenCache::new(storage, &test_key()).unwrap();
+        assert!(persistence(cache, "client").refresh_token().await.is_none());
+    }
+
+    #[test]
+    fn invalid_key() {
+        let storage = Arc::new(MemoryStorage::default());
+        PersistentTokenCache::new(storage.clone(), &Secret::new("not base64!"))
+            .expect_err("invalid base64");
+        PersistentTokenCache::new(storage, &Secret::new(base64::encode([0u8; 16])))
+            .expect_err("128-bit key");
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Proof-of-possession (PoP) token support. Microsoft Entra ID binds a PoP token to the key in
+//! the token request's `req_cnf` parameter, and the client presents the token in a signed HTTP
+//! request (SHR) binding it to a particular HTTP request.
+
+use azure_core::{
+    base64,
+    credentials::{AccessToken, PopTokenRequestOptions, TokenRequestOptions},
+    error::{Error, ErrorKind},
+    json::to_json,
+    time::OffsetDateTime,
+    Result, Value,
+};
+use serde::Serialize;
+
+/// Returns an error when `options` request a PoP token, for credentials that can't get one.
+pub(crate) fn reject_pop(options: Option<&TokenRequestOptions<'_>>) -> Result<()> {
+    if options.is_some_and(|o| o.pop.is_some()) {
+        return Err(Error::with_message(
+            ErrorKind::Credential,
+            "this credential doesn't support proof-of-possession tokens",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the parameters with which a token request asks for a PoP token bound to `pop.key`.
+pub(crate) fn token_request_params(pop: &PopTokenRequestOptions) -> [(&'static str, String); 2] {
+    let req_cnf = format!(r#"{{"kid":"{}"}}"#, pop.key.key_id());
+    [
+        ("token_type", "pop".to_string()),
+        ("req_cnf", base64::encode_url_safe(req_cnf)),
+    ]
+}
+
+#[derive(Serialize)]
+struct ShrHeader<'a> {
+    alg: &'a str,
+    kid: &'a str,
+    typ: &'static str,
+}
+
+#[derive(Serialize)]
+struct ShrPayload<'a> {
+    at: &'a str,
+    ts: i64,
+    m: &'a str,
+    u: String,
+    p: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<&'a str>,
+    cnf: Cnf,
+}
+
+#[derive(Serialize)]
+struct Cnf {
+    jwk: Value,
+}
+
+/// Signs an SHR presenting `token`, a PoP token, for the request `pop` describes. The returned
+/// token's secret is the SHR.
+pub(crate) fn signed_http_request(
+    token: &AccessToken,
+    pop: &PopTokenRequestOptions,
+) -> Result<AccessToken> {
+    let header = ShrHeader {
+        alg: pop.key.algorithm(),
+        kid: pop.key.key_id(),
+        typ: "pop",
+    };
+    let host = pop.url.host_str().unwrap_or_default();
+    let payload = ShrPayload {
+        at: token.token.secret(),
+        ts: OffsetDateTime::now_utc().unix_timestamp(),
+        m: pop.method.as_ref(),
+        u: match pop.url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        },
+        p: pop.url.path(),
+        nonce: pop.nonce.as_deref(),
+        cnf: Cnf { jwk: pop.key.jwk() },
+    };
+    let jwt = format!(
+        "{}.{}",
+        base64::encode_url_safe(to_json(&header)?),
+        base64::encode_url_safe(to_json(&payload)?)
+    );
+    let signature = pop.key.sign(jwt.as_bytes())?;
+    let shr = format!("{jwt}.{}", base64::encode_url_safe(signature));
+
+    let mut pop_token = AccessToken::new(shr, token.expires_on);
+    pop_token.refresh_on = token.refresh_on;
+    Ok(pop_token)
+}
+
+#[cfg(feature = "client_certificate")]
+pub use rsa_key::RsaPopKey;
+
+#[cfg(feature = "client_certificate")]
+mod rsa_key {
+    use azure_core::{
+        base64,
+        credentials::PopKey,
+        error::{ErrorKind, ResultExt},
+        json::from_json,
+        Result, Value,
+    };
+    use rsa::{
+        pkcs1v15::SigningKey,
+        rand_core::OsRng,
+        signature::{SignatureEncoding, Signer},
+        traits::PublicKeyParts,
+        RsaPrivateKey,
+    };
+    use sha2::{Digest, Sha256};
+    use std::{fmt, sync::Arc};
+
+    /// An RSA [`PopKey`], which signs with RS256.
+    ///
+    /// Requires the `client_certificate` feature.
+    pub struct RsaPopKey {
+        key: SigningKey<Sha256>,
+        jwk: Value,
+        key_id: String,
+    }
+
+    impl fmt::Debug for RsaPopKey {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // omits the private key
+            f.debug_struct("RsaPopKey")
+                .field("jwk", &self.jwk)
+                .field("key_id", &self.key_id)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl RsaPopKey {
+        /// Generates a new 2048-bit key.
+        pub fn generate() -> Result<Arc<Self>> {
+            let rsa = RsaPrivateKey::new(&mut OsRng, 2048)
+                .with_context(ErrorKind::Credential, "failed to generate RSA key")?;
+            // RFC 7638 thumbprints hash the required members in lexicographic order without whitespace
+            let jwk = format!(
+                r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+                base64::encode_url_safe(rsa.e().to_bytes_be()),
+                base64::encode_url_safe(rsa.n().to_bytes_be())
+            );
+            let key_id = base64::encode_url_safe(Sha256::digest(jwk.as_bytes()));
+            Ok(Arc::new(Self {
+                key: SigningKey::new(rsa),
+                jwk: from_json(jwk)?,
+                key_id,
+            }))
+        }
+    }
+
+    impl PopKey for RsaPopKey {
+        fn key_id(&self) -> &str {
+            &self.key_id
+        }
+
+        fn jwk(&self) -> Value {
+            self.jwk.clone()
+        }
+
+        fn algorithm(&self) -> &str {
+            "RS256"
+        }
+
+        fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            self.key
+                .try_sign(data)
+                .map(|signature| signature.to_vec())
+                .with_context(ErrorKind::Credential, "failed to sign")
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use azure_core::{
+        credentials::{PopKey, Secret},
+        http::{Method, Url},
+        time::Duration,
+    };
+    use std::sync::Arc;
+
+    /// A [`PopKey`] whose signature is the signed data reversed.
+    #[derive(Debug)]
+    pub(crate) struct MockPopKey;
+
+    impl PopKey for MockPopKey {
+        fn key_id(&self) -> &str {
+            "key-id"
+        }
+
+        fn jwk(&self) -> Value {
+            Value::String("jwk".to_string())
+        }
+
+        fn algorithm(&self) -> &str {
+            "RS256"
+        }
+
+        fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.iter().rev().copied().collect())
+        }
+    }
+
+    pub(crate) fn pop_options(nonce: Option<&str>) -> PopTokenRequestOptions {
+        PopTokenRequestOptions {
+            key: Arc::new(MockPopKey),
+            method: Method::Post,
+            url: Url::parse("https://contoso.com:8443/path?query").unwrap(),
+            nonce: nonce.map(ToString::to_string),
+        }
+    }
+
+    /// Verifies `shr` is an SHR for [`pop_options`] presenting `access_token`.
+    pub(crate) fn verify_shr(shr: &str, access_token: &str, nonce: Option<&str>) {
+        let parts: Vec<&str> = shr.split('.').collect();
+        let [header, payload, signature] = parts.as_slice() else {
+            panic!("SHR should have 3 parts");
+        };
+        let signed = format!("{header}.{payload}");
+        assert_eq!(
+            MockPopKey.sign(signed.as_bytes()).unwrap(),
+            base64::decode_url_safe(signature).unwrap()
+        );
+
+        let header: Value =
+            azure_core::json::from_json(base64::decode_url_safe(header).unwrap()).unwrap();
+        assert_eq!(
+            r#"{"alg":"RS256","kid":"key-id","typ":"pop"}"#,
+            header.to_string()
+        );
+        let payload: Value =
+            azure_core::json::from_json(base64::decode_url_safe(payload).unwrap()).unwrap();
+        assert_eq!(access_token, payload["at"]);
+        assert_eq!("POST", payload["m"]);
+        assert_eq!("contoso.com:8443", payload["u"]);
+        assert_eq!("/path", payload["p"]);
+        assert_eq!(nonce, payload["nonce"].as_str());
+        assert_eq!("jwk", payload["cnf"]["jwk"]);
+        assert!(payload["ts"].as_i64().is_some());
+    }
+
+    #[test]
+    fn req_cnf() {
+        let [token_type, req_cnf] = token_request_params(&pop_options(None));
+        assert_eq!(("token_type", "pop".to_string()), token_type);
+        assert_eq!("req_cnf", req_cnf.0);
+        assert_eq!(
+            br#"{"kid":"key-id"}"#.to_vec(),
+            base64::decode_url_safe(req_cnf.1).unwrap()
+        );
+    }
+
+    #[test]
+    fn shr() {
+        let mut token = AccessToken::new(
+            Secret::new("at"),
+            OffsetDateTime::now_utc() + Duration::hours(1),
+        );
+        token.refresh_on = Some(OffsetDateTime::now_utc());
+        for nonce in [None, Some("nonce")] {
+            let shr = signed_http_request(&token, &pop_options(nonce)).unwrap();
+            assert_eq!(token.expires_on, shr.expires_on);
+            assert_eq!(token.refresh_on, shr.refresh_on);
+            verify_shr(shr.token.secret(), "at", nonce);
+        }
+    }
+
+    #[cfg(feature = "client_certificate")]
+    #[test]
+    fn rsa_pop_key() {
+        use ::rsa::{
+            pkcs1v15::{Signature, VerifyingKey},
+            sha2::Sha256,
+            signature::Verifier,
+            BigUint, RsaPublicKey,
+        };
+
+        let key = RsaPopKey::generate().unwrap();
+        let jwk = key.jwk();
+        assert_eq!("RSA", jwk["kty"]);
+        assert_eq!(43, key.key_id().len());
+
+        let signature = key.sign(b"data").unwrap();
+        let public_key = RsaPublicKey::new(
+            BigUint::from_bytes_be(&base64::decode_url_safe(jwk["n"].as_str().unwrap()).unwrap()),
+            BigUint::from_bytes_be(&base64::decode_url_safe(jwk["e"].as_str().unwrap()).unwrap()),
+        )
+        .unwrap();
+        VerifyingKey::<Sha256>::new(public_key)
+            .verify(b"data", &Signature::try_from(signature.as_slice()).unwrap())
+            .unwrap();
+    }
+}
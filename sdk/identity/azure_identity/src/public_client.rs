@@ -0,0 +1,183 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{get_authority_host, validate_not_empty, validate_tenant_id, TokenCache};
+use azure_core::{
+    credentials::AccessToken,
+    error::{ErrorKind, ResultExt},
+    http::{
+        headers::{self, content_type},
+        ClientOptions, Context, Method, Pipeline, PipelineSendOptions, RawResponse, Request, Url,
+    },
+};
+use tracing::debug;
+use url::form_urlencoded;
+
+/// The client ID of the Azure CLI, which is the default client ID for user authentication.
+pub(crate) const DEVELOPER_SIGN_ON_CLIENT_ID: &str = "04b07795-8ddb-461a-bbee-02f9e1bf7b46";
+/// The default tenant for user authentication. It allows work and school accounts from any tenant.
+pub(crate) const ORGANIZATIONS_TENANT_ID: &str = "organizations";
+
+/// Functionality shared by credentials that authenticate users through a public client application.
+///
+/// The client stores refresh tokens in its [`TokenCache`] so that after a user authenticates once,
+/// the credential can acquire tokens for other scopes without user interaction.
+#[derive(Debug)]
+pub(crate) struct PublicClient {
+    pub(crate) cache: TokenCache,
+    pub(crate) client_id: String,
+    authority: Url,
+    pipeline: Pipeline,
+    tenant_id: String,
+}
+
+impl PublicClient {
+    pub(crate) fn new(
+        client_id: Option<String>,
+        tenant_id: Option<String>,
+        client_options: ClientOptions,
+    ) -> azure_core::Result<Self> {
+        let client_id = client_id.unwrap_or_else(|| DEVELOPER_SIGN_ON_CLIENT_ID.to_string());
+        validate_not_empty(&client_id, "no client ID specified")?;
+        let tenant_id = tenant_id.unwrap_or_else(|| ORGANIZATIONS_TENANT_ID.to_string());
+        validate_tenant_id(&tenant_id)?;
+
+        let authority_host = get_authority_host(None, client_options.cloud.as_deref())?;
+        let authority = authority_host
+            .join(&format!("/{tenant_id}/"))
+            .with_context_fn(ErrorKind::DataConversion, || {
+                format!("tenant_id '{tenant_id}' could not be URL encoded")
+            })?;
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            client_options,
+            Vec::default(),
+            Vec::default(),
+            None,
+        );
+
+        Ok(Self {
+            cache: TokenCache::new(),
+            client_id,
+            authority,
+            pipeline,
+            tenant_id,
+        })
+    }
+
+    /// Stores tokens in a persistent cache as well as memory.
+    pub(crate) fn with_token_cache(
+        mut self,
+        token_cache: Option<std::sync::Arc<crate::PersistentTokenCache>>,
+    ) -> Self {
+        self.cache = TokenCache::persistent(
+            token_cache,
+            &self.authority,
+            &self.client_id,
+            &self.tenant_id,
+        );
+        self
+    }
+
+    /// Returns the URL of an OAuth 2.0 endpoint of the authority e.g. "token" or "devicecode".
+    pub(crate) fn endpoint(&self, name: &str) -> Url {
+        // the authority ends with a slash and name is a relative path, so join can't fail
+        self.authority
+            .join(&format!("oauth2/v2.0/{name}"))
+            .expect("valid endpoint URL")
+    }
+
+    /// POSTs a form to the given endpoint.
+    pub(crate) async fn send_form(
+        &self,
+        ctx: &Context<'_>,
+        endpoint: Url,
+        body: String,
+    ) -> azure_core::Result<RawResponse> {
+        let mut req = Request::new(endpoint, Method::Post);
+        req.insert_header(
+            headers::CONTENT_TYPE,
+            content_type::APPLICATION_X_WWW_FORM_URLENCODED,
+        );
+        req.set_body(body);
+        self.pipeline
+            .send(
+                ctx,
+                &mut req,
+                Some(PipelineSendOptions {
+                    skip_checks: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+    }
+
+    /// Redeems a grant at the token endpoint, storing any refresh token in the response.
+    ///
+    /// `params` must contain the grant's parameters; this method adds the client ID.
+    pub(crate) async fn redeem(
+        &self,
+        ctx: &Context<'_>,
+        params: &[(&str, &str)],
+    ) -> azure_core::Result<AccessToken> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &self.client_id)
+            .extend_pairs(params)
+            .finish();
+        let res = self.send_form(ctx, self.endpoint("token"), body).await?;
+        self.handle_token_response(res).await
+    }
+
+    /// Converts a token endpoint response to an [`AccessToken`], storing any refresh token in the response.
+    pub(crate) async fn handle_token_response(
+        &self,
+        res: RawResponse,
+    ) -> azure_core::Result<AccessToken> {
+        let (token, refresh_token) = crate::handle_entra_user_response(res)?;
+        self.cache.set_refresh_token(refresh_token).await;
+        Ok(token)
+    }
+
+    /// Acquires a token for the given scopes with a stored refresh token.
+    ///
+    /// Returns `None` when there is no stored refresh token or Entra ID rejected it, in which case
+    /// the credential must authenticate the user again.
+    pub(crate) async fn redeem_refresh_token(
+        &self,
+        ctx: &Context<'_>,
+        scopes: &[&str],
+    ) -> Option<AccessToken> {
+        let refresh_token = self.cache.refresh_token().await?;
+        let params = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.secret()),
+            ("scope", &user_scopes(scopes)),
+        ];
+        match self.redeem(ctx, &params).await {
+            Ok(token) => Some(token),
+            Err(err) => {
+                // the refresh token may have expired or been revoked; the user must authenticate again
+                debug!("refresh token redemption failed: {err}");
+                self.cache.clear_refresh_token().await;
+                None
+            }
+        }
+    }
+}
+
+/// Adds "offline_access" to the requested scopes so Entra ID returns a refresh token.
+pub(crate) fn user_scopes(scopes: &[&str]) -> String {
+    let mut scopes = scopes.to_vec();
+    if !scopes.contains(&"offline_access") {
+        scopes.push("offline_access");
+    }
+    scopes.join(" ")
+}
+
+#[test]
+fn test_user_scopes() {
+    assert_eq!("a b offline_access", user_scopes(&["a", "b"]));
+    assert_eq!("offline_access a", user_scopes(&["offline_access", "a"]));
+}
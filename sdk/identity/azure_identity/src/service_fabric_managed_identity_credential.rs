@@ -0,0 +1,233 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::env::Env;
+use crate::{ImdsId, ImdsManagedIdentityCredential};
+use azure_core::credentials::{AccessToken, TokenCredential, TokenRequestOptions};
+use azure_core::error::{Error, ErrorKind, ResultExt};
+use azure_core::http::headers::HeaderName;
+use azure_core::http::{ClientOptions, Transport, Url};
+use std::sync::Arc;
+
+const ENDPOINT_ENV: &str = "IDENTITY_ENDPOINT";
+const API_VERSION: &str = "2019-07-01-preview";
+const SECRET_HEADER: HeaderName = HeaderName::from_static("secret");
+const SECRET_ENV: &str = "IDENTITY_HEADER";
+const THUMBPRINT_ENV: &str = "IDENTITY_SERVER_THUMBPRINT";
+
+/// Authenticates the managed identity of a Service Fabric application.
+///
+/// Service Fabric's token endpoint presents a self-signed certificate whose SHA-1 thumbprint
+/// Service Fabric sets in `IDENTITY_SERVER_THUMBPRINT`. Unless the application configures
+/// `ClientOptions::transport`, the credential builds a transport that trusts only that certificate,
+/// which requires the `service_fabric` feature.
+///
+/// See <https://learn.microsoft.com/azure/service-fabric/how-to-managed-identity-service-fabric-app-code>
+#[derive(Debug)]
+pub(crate) struct ServiceFabricManagedIdentityCredential {
+    credential: ImdsManagedIdentityCredential,
+}
+
+impl ServiceFabricManagedIdentityCredential {
+    pub fn new(
+        id: ImdsId,
+        client_options: ClientOptions,
+        env: Env,
+    ) -> azure_core::Result<Arc<Self>> {
+        if !matches!(id, ImdsId::SystemAssigned) {
+            // Service Fabric applications select a user-assigned identity in their
+            // manifest; the token endpoint doesn't accept one at runtime
+            return Err(Error::with_message(
+                ErrorKind::Credential,
+                "Service Fabric doesn't support specifying a user-assigned identity at runtime. Configure the identity in the application manifest instead.",
+            ));
+        }
+        let endpoint = &env
+            .var(ENDPOINT_ENV)
+            .with_context_fn(ErrorKind::Credential, || {
+                format!(
+                    "service fabric credential requires {} environment variable",
+                    ENDPOINT_ENV
+                )
+            })?;
+        let endpoint = Url::parse(endpoint).with_context_fn(ErrorKind::Credential, || {
+            format!(
+                "service fabric credential {} environment variable must be a valid URL, but is '{endpoint}'",
+                ENDPOINT_ENV
+            )
+        })?;
+        let thumbprint = env.var(THUMBPRINT_ENV).unwrap_or_default();
+        if thumbprint.len() != 40 || !thumbprint.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::with_message_fn(ErrorKind::Credential, || {
+                format!(
+                    "service fabric credential {} environment variable must be a SHA-1 certificate thumbprint, but is '{thumbprint}'",
+                    THUMBPRINT_ENV
+                )
+            }));
+        }
+        let client_options = match client_options.transport {
+            // the application's transport is responsible for trusting the certificate
+            Some(_) => client_options,
+            None => ClientOptions {
+                transport: Some(pinned_transport(&thumbprint)?),
+                ..client_options
+            },
+        };
+        Ok(Arc::new(Self {
+            credential: ImdsManagedIdentityCredential::new(
+                endpoint,
+                API_VERSION,
+                SECRET_HEADER,
+                SECRET_ENV,
+                id,
+                client_options,
+                None,
+                env,
+            ),
+        }))
+    }
+}
+
+/// Returns a transport that trusts only the certificate with SHA-1 `thumbprint`, a validated hex string.
+#[cfg(feature = "service_fabric")]
+fn pinned_transport(thumbprint: &str) -> azure_core::Result<Transport> {
+    use rustls::{crypto::ring, ClientConfig};
+
+    let provider = Arc::new(ring::default_provider());
+    let verifier = ThumbprintVerifier::new(thumbprint, &provider)?;
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .with_context(ErrorKind::Credential, "failed to configure TLS")?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    let client = reqwest::Client::builder()
+        .use_preconfigured_tls(config)
+        .build()
+        .with_context(
+            ErrorKind::Credential,
+            "failed to create the service fabric credential's HTTP client",
+        )?;
+    Ok(Transport::new(Arc::new(client)))
+}
+
+#[cfg(not(feature = "service_fabric"))]
+fn pinned_transport(_thumbprint: &str) -> azure_core::Result<Transport> {
+    Err(Error::with_message_fn(ErrorKind::Credential, || {
+        format!(
+            "service fabric credential requires the service_fabric feature to trust the certificate in {}, or a ClientOptions::transport that trusts it",
+            THUMBPRINT_ENV
+        )
+    }))
+}
+
+/// Trusts only the server certificate with a given SHA-1 thumbprint, whatever its issuer and name.
+#[cfg(feature = "service_fabric")]
+#[derive(Debug)]
+struct ThumbprintVerifier {
+    thumbprint: Vec<u8>,
+    algorithms: rustls::crypto::WebPkiSupportedAlgorithms,
+}
+
+#[cfg(feature = "service_fabric")]
+impl ThumbprintVerifier {
+    fn new(
+        thumbprint: &str,
+        provider: &rustls::crypto::CryptoProvider,
+    ) -> azure_core::Result<Self> {
+        let thumbprint = (0..thumbprint.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(thumbprint.get(i..i + 2).unwrap_or_default(), 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .with_context(ErrorKind::Credential, "invalid certificate thumbprint")?;
+        Ok(Self {
+            thumbprint,
+            algorithms: provider.signature_verification_algorithms,
+        })
+    }
+}
+
+#[cfg(feature = "service_fabric")]
+impl rustls::client::danger::ServerCertVerifier for ThumbprintVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        use sha1::{Digest, Sha1};
+
+        if Sha1::digest(end_entity.as_ref()).as_slice() != self.thumbprint {
+            return Err(rustls::Error::General(format!(
+                "the server certificate doesn't match {THUMBPRINT_ENV}"
+            )));
+        }
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl TokenCredential for ServiceFabricManagedIdentityCredential {
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken> {
+        self.credential.get_token(scopes, options).await
+    }
+}
+
+#[cfg(all(test, feature = "service_fabric"))]
+mod tests {
+    use super::*;
+    use rustls::{
+        client::danger::ServerCertVerifier,
+        pki_types::{CertificateDer, ServerName, UnixTime},
+    };
+
+    #[test]
+    fn thumbprint_verifier() {
+        // SHA-1 of "certificate"
+        let verifier = ThumbprintVerifier::new(
+            "735AD571C189D7BA84464BF4A9F1D2280175B128",
+            &rustls::crypto::ring::default_provider(),
+        )
+        .unwrap();
+        let verify = |cert: &'static [u8]| {
+            verifier.verify_server_cert(
+                &CertificateDer::from(cert),
+                &[],
+                &ServerName::try_from("localhost").unwrap(),
+                &[],
+                UnixTime::now(),
+            )
+        };
+        assert!(verify(b"certificate").is_ok());
+        assert!(verify(b"another certificate").is_err());
+    }
+}
@@ -830,6 +830,7 @@ impl Receiver {
         let access_token = azure_core::credentials::AccessToken {
             token: azure_core::credentials::Secret::new("placeholder-token".to_string()),
             expires_on: time::OffsetDateTime::now_utc() + Duration::seconds(3600),
+            refresh_on: None,
         };
 
         let management_client = azure_core_amqp::AmqpManagement::new(
@@ -0,0 +1,470 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! An incremental decoder of [Avro object container files](https://avro.apache.org/docs/1.11.1/specification/#object-container-files),
+//! the format in which the service streams the results of a blob query.
+
+use azure_core::{
+    error::{Error, ErrorKind},
+    Result,
+};
+use serde_json::Value as Json;
+use std::collections::{HashMap, VecDeque};
+
+const MAGIC: &[u8] = b"Obj\x01";
+const SYNC_LENGTH: usize = 16;
+
+/// A decoded Avro datum.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    /// A record: its full name and its fields.
+    Record(String, HashMap<String, Value>),
+    Enum(String),
+    Array(Vec<Value>),
+    Map(HashMap<String, Value>),
+    Fixed(Vec<u8>),
+}
+
+impl Value {
+    pub(crate) fn into_long(self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(value.into()),
+            Value::Long(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// An Avro schema, whose named types are referred to by full name once they're defined.
+#[derive(Clone, Debug)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record {
+        name: String,
+        fields: Vec<(String, Schema)>,
+    },
+    Enum {
+        symbols: Vec<String>,
+    },
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Fixed {
+        size: usize,
+    },
+    Named(String),
+}
+
+/// Parses schemas, collecting the named types they define.
+#[derive(Default)]
+struct SchemaParser {
+    names: HashMap<String, Schema>,
+}
+
+impl SchemaParser {
+    fn parse(&mut self, json: &Json, namespace: &str) -> Result<Schema> {
+        match json {
+            Json::String(name) => self.parse_name(name, namespace),
+            Json::Array(branches) => Ok(Schema::Union(
+                branches
+                    .iter()
+                    .map(|branch| self.parse(branch, namespace))
+                    .collect::<Result<_>>()?,
+            )),
+            Json::Object(object) => {
+                let type_name = object
+                    .get("type")
+                    .ok_or_else(|| invalid_schema("a type has no type"))?;
+                let Json::String(type_name) = type_name else {
+                    return self.parse(type_name, namespace);
+                };
+                let full_name = || -> Result<(String, String)> {
+                    let name = object
+                        .get("name")
+                        .and_then(Json::as_str)
+                        .ok_or_else(|| invalid_schema("a named type has no name"))?;
+                    let namespace = object
+                        .get("namespace")
+                        .and_then(Json::as_str)
+                        .unwrap_or(namespace);
+                    let full_name = full_name(name, namespace);
+                    let namespace = full_name
+                        .rsplit_once('.')
+                        .map(|(namespace, _)| namespace.to_string())
+                        .unwrap_or_default();
+                    Ok((full_name, namespace))
+                };
+                let schema = match type_name.as_str() {
+                    "record" | "error" => {
+                        let (name, namespace) = full_name()?;
+                        let fields = object
+                            .get("fields")
+                            .and_then(Json::as_array)
+                            .ok_or_else(|| invalid_schema("a record has no fields"))?
+                            .iter()
+                            .map(|field| {
+                                let name = field
+                                    .get("name")
+                                    .and_then(Json::as_str)
+                                    .ok_or_else(|| invalid_schema("a field has no name"))?;
+                                let schema = field
+                                    .get("type")
+                                    .ok_or_else(|| invalid_schema("a field has no type"))?;
+                                Ok((name.to_string(), self.parse(schema, &namespace)?))
+                            })
+                            .collect::<Result<_>>()?;
+                        self.define(name.clone(), Schema::Record { name, fields })
+                    }
+                    "enum" => {
+                        let (name, _) = full_name()?;
+                        let symbols = object
+                            .get("symbols")
+                            .and_then(Json::as_array)
+                            .ok_or_else(|| invalid_schema("an enum has no symbols"))?
+                            .iter()
+                            .map(|symbol| symbol.as_str().map(String::from))
+                            .collect::<Option<_>>()
+                            .ok_or_else(|| invalid_schema("an enum symbol isn't a string"))?;
+                        self.define(name, Schema::Enum { symbols })
+                    }
+                    "fixed" => {
+                        let (name, _) = full_name()?;
+                        let size = object
+                            .get("size")
+                            .and_then(Json::as_u64)
+                            .ok_or_else(|| invalid_schema("a fixed type has no size"))?;
+                        self.define(
+                            name,
+                            Schema::Fixed {
+                                size: size as usize,
+                            },
+                        )
+                    }
+                    "array" => Schema::Array(Box::new(
+                        self.parse(
+                            object
+                                .get("items")
+                                .ok_or_else(|| invalid_schema("an array has no items"))?,
+                            namespace,
+                        )?,
+                    )),
+                    "map" => Schema::Map(Box::new(
+                        self.parse(
+                            object
+                                .get("values")
+                                .ok_or_else(|| invalid_schema("a map has no values"))?,
+                            namespace,
+                        )?,
+                    )),
+                    name => self.parse_name(name, namespace)?,
+                };
+                Ok(schema)
+            }
+            _ => Err(invalid_schema("a type must be a string, array or object")),
+        }
+    }
+
+    /// Parses a primitive type or a reference to a named type.
+    fn parse_name(&self, name: &str, namespace: &str) -> Result<Schema> {
+        Ok(match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            name => {
+                let full_name = full_name(name, namespace);
+                if self.names.contains_key(&full_name) {
+                    Schema::Named(full_name)
+                } else if self.names.contains_key(name) {
+                    Schema::Named(name.to_string())
+                } else {
+                    return Err(invalid_schema(&format!("unknown type {name}")));
+                }
+            }
+        })
+    }
+
+    fn define(&mut self, name: String, schema: Schema) -> Schema {
+        self.names.insert(name, schema.clone());
+        schema
+    }
+}
+
+fn full_name(name: &str, namespace: &str) -> String {
+    if name.contains('.') || namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{namespace}.{name}")
+    }
+}
+
+fn invalid_schema(message: &str) -> Error {
+    Error::with_message(
+        ErrorKind::DataConversion,
+        format!("invalid Avro schema: {message}"),
+    )
+}
+
+fn invalid_data() -> Error {
+    Error::with_message(ErrorKind::DataConversion, "invalid Avro data")
+}
+
+/// Reads the binary encoding of Avro data, returning `None` if it ends first.
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    /// Reads a zigzag-encoded variable-length integer.
+    fn long(&mut self) -> Option<i64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        None
+    }
+
+    /// Reads a length-prefixed byte sequence.
+    fn sized_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = usize::try_from(self.long()?).ok()?;
+        self.bytes(len)
+    }
+
+    /// Reads the number of items of the next block of an array or map, skipping its size in bytes if it's given.
+    fn block_count(&mut self) -> Option<usize> {
+        let count = self.long()?;
+        if count < 0 {
+            self.long()?;
+        }
+        usize::try_from(count.unsigned_abs()).ok()
+    }
+}
+
+/// The header of an object container file.
+struct Header {
+    schema: Schema,
+    names: HashMap<String, Schema>,
+    sync: [u8; SYNC_LENGTH],
+}
+
+impl Header {
+    /// Parses the header, returning `None` if it's incomplete and the number of bytes it occupies otherwise.
+    fn parse(data: &[u8]) -> Result<Option<(Self, usize)>> {
+        let mut cursor = Cursor::new(data);
+        let Some(magic) = cursor.bytes(MAGIC.len()) else {
+            return Ok(None);
+        };
+        if magic != MAGIC {
+            return Err(Error::with_message(
+                ErrorKind::DataConversion,
+                "response isn't an Avro object container file",
+            ));
+        }
+        let mut metadata = HashMap::new();
+        loop {
+            let Some(count) = cursor.block_count() else {
+                return Ok(None);
+            };
+            if count == 0 {
+                break;
+            }
+            for _ in 0..count {
+                let (Some(key), Some(value)) = (cursor.sized_bytes(), cursor.sized_bytes()) else {
+                    return Ok(None);
+                };
+                metadata.insert(String::from_utf8_lossy(key).into_owned(), value);
+            }
+        }
+        let Some(sync) = cursor.bytes(SYNC_LENGTH) else {
+            return Ok(None);
+        };
+
+        if let Some(codec) = metadata.get("avro.codec") {
+            if *codec != b"null" {
+                return Err(Error::with_message(
+                    ErrorKind::DataConversion,
+                    format!("unsupported Avro codec {}", String::from_utf8_lossy(codec)),
+                ));
+            }
+        }
+        let schema = metadata
+            .get("avro.schema")
+            .ok_or_else(|| invalid_schema("the file has no schema"))?;
+        let schema: Json = serde_json::from_slice(schema)
+            .map_err(|error| Error::new(ErrorKind::DataConversion, error))?;
+        let mut parser = SchemaParser::default();
+        let schema = parser.parse(&schema, "")?;
+        Ok(Some((
+            Self {
+                schema,
+                names: parser.names,
+                sync: sync.try_into().expect("sync marker is 16 bytes"),
+            },
+            cursor.position,
+        )))
+    }
+
+    fn read(&self, schema: &Schema, cursor: &mut Cursor<'_>) -> Option<Value> {
+        Some(match schema {
+            Schema::Null => Value::Null,
+            Schema::Boolean => Value::Boolean(cursor.bytes(1)?[0] != 0),
+            Schema::Int => Value::Int(i32::try_from(cursor.long()?).ok()?),
+            Schema::Long => Value::Long(cursor.long()?),
+            Schema::Float => Value::Float(f32::from_le_bytes(cursor.bytes(4)?.try_into().ok()?)),
+            Schema::Double => Value::Double(f64::from_le_bytes(cursor.bytes(8)?.try_into().ok()?)),
+            Schema::Bytes => Value::Bytes(cursor.sized_bytes()?.to_vec()),
+            Schema::String => {
+                Value::String(String::from_utf8(cursor.sized_bytes()?.to_vec()).ok()?)
+            }
+            Schema::Record { name, fields } => {
+                let mut values = HashMap::with_capacity(fields.len());
+                for (field, schema) in fields {
+                    values.insert(field.clone(), self.read(schema, cursor)?);
+                }
+                Value::Record(name.clone(), values)
+            }
+            Schema::Enum { symbols } => {
+                Value::Enum(symbols.get(usize::try_from(cursor.long()?).ok()?)?.clone())
+            }
+            Schema::Array(items) => {
+                let mut values = Vec::new();
+                loop {
+                    let count = cursor.block_count()?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        values.push(self.read(items, cursor)?);
+                    }
+                }
+                Value::Array(values)
+            }
+            Schema::Map(values_schema) => {
+                let mut values = HashMap::new();
+                loop {
+                    let count = cursor.block_count()?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        let key = String::from_utf8(cursor.sized_bytes()?.to_vec()).ok()?;
+                        values.insert(key, self.read(values_schema, cursor)?);
+                    }
+                }
+                Value::Map(values)
+            }
+            Schema::Union(branches) => {
+                let branch = branches.get(usize::try_from(cursor.long()?).ok()?)?;
+                self.read(branch, cursor)?
+            }
+            Schema::Fixed { size } => Value::Fixed(cursor.bytes(*size)?.to_vec()),
+            Schema::Named(name) => self.read(self.names.get(name)?, cursor)?,
+        })
+    }
+}
+
+/// Decodes the objects of an object container file as its bytes arrive.
+#[derive(Default)]
+pub(crate) struct Decoder {
+    buffer: Vec<u8>,
+    header: Option<Header>,
+    values: VecDeque<Value>,
+}
+
+impl Decoder {
+    /// Appends the next bytes of the file.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns whether every byte pushed has been decoded.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.values.is_empty()
+    }
+
+    /// Returns the next object, or `None` if more bytes are needed to decode it.
+    pub(crate) fn next(&mut self) -> Result<Option<Value>> {
+        loop {
+            if let Some(value) = self.values.pop_front() {
+                return Ok(Some(value));
+            }
+            let Some(header) = &self.header else {
+                let Some((header, len)) = Header::parse(&self.buffer)? else {
+                    return Ok(None);
+                };
+                self.header = Some(header);
+                self.buffer.drain(..len);
+                continue;
+            };
+            if self.buffer.is_empty() {
+                return Ok(None);
+            }
+
+            // A block holds the number of objects it contains and their size, then the objects and the sync marker.
+            let mut cursor = Cursor::new(&self.buffer);
+            let (Some(count), Some(size)) = (cursor.long(), cursor.long()) else {
+                return Ok(None);
+            };
+            let (Ok(count), Ok(size)) = (usize::try_from(count), usize::try_from(size)) else {
+                return Err(invalid_data());
+            };
+            let (Some(block), Some(sync)) = (cursor.bytes(size), cursor.bytes(SYNC_LENGTH)) else {
+                return Ok(None);
+            };
+            if sync != header.sync {
+                return Err(Error::with_message(
+                    ErrorKind::DataConversion,
+                    "Avro block doesn't end with the file's sync marker",
+                ));
+            }
+            let mut block_cursor = Cursor::new(block);
+            for _ in 0..count {
+                let value = header
+                    .read(&header.schema, &mut block_cursor)
+                    .ok_or_else(invalid_data)?;
+                self.values.push_back(value);
+            }
+            if block_cursor.position != block.len() {
+                return Err(invalid_data());
+            }
+            let len = cursor.position;
+            self.buffer.drain(..len);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Blob batches, which delete or set the tier of up to 256 blobs with a single request.
+
+use crate::{
+    generated::clients::BlobClient as GeneratedBlobClient,
+    models::{AccessTier, BlobClientDeleteOptions, BlobClientSetTierOptions},
+    pipeline::BatchSubRequest,
+};
+use azure_core::{
+    error::{CheckSuccessOptions, Error, ErrorKind},
+    fmt::SafeDebug,
+    http::{
+        check_success,
+        headers::{CONTENT_TYPE, VERSION},
+        multipart::{self, Part},
+        Context, Method, NoFormat, Pipeline, PipelineSendOptions, Request, Response, Url,
+    },
+    Result,
+};
+use uuid::Uuid;
+
+const MAX_OPERATIONS: usize = 256;
+
+/// A batch of up to 256 operations on blobs, submitted with a single request by
+/// [`BlobServiceClient::submit_batch()`](crate::BlobServiceClient::submit_batch) or
+/// [`BlobContainerClient::submit_batch()`](crate::BlobContainerClient::submit_batch).
+///
+/// All operations in a batch must be of the same kind.
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobBatch<'a> {
+    operations: Vec<BatchOperation<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum BatchOperation<'a> {
+    Delete {
+        container_name: String,
+        blob_name: String,
+        options: BlobClientDeleteOptions<'a>,
+    },
+    SetTier {
+        container_name: String,
+        blob_name: String,
+        tier: AccessTier,
+        options: BlobClientSetTierOptions<'a>,
+    },
+}
+
+impl BatchOperation<'_> {
+    pub(crate) fn container_name(&self) -> &str {
+        match self {
+            BatchOperation::Delete { container_name, .. }
+            | BatchOperation::SetTier { container_name, .. } => container_name,
+        }
+    }
+
+    fn blob_name(&self) -> &str {
+        match self {
+            BatchOperation::Delete { blob_name, .. }
+            | BatchOperation::SetTier { blob_name, .. } => blob_name,
+        }
+    }
+}
+
+impl<'a> BlobBatch<'a> {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an operation that deletes a blob, or its snapshots, as [`BlobClient::delete()`](crate::BlobClient::delete) does.
+    ///
+    /// # Arguments
+    ///
+    /// * `container_name` - The name of the container.
+    /// * `blob_name` - The name of the blob.
+    /// * `options` - Optional configuration for the operation.
+    pub fn delete_blob(
+        &mut self,
+        container_name: &str,
+        blob_name: &str,
+        options: Option<BlobClientDeleteOptions<'a>>,
+    ) -> Result<()> {
+        self.push(BatchOperation::Delete {
+            container_name: container_name.into(),
+            blob_name: blob_name.into(),
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    /// Adds an operation that sets the tier of a blob, as [`BlobClient::set_tier()`](crate::BlobClient::set_tier) does.
+    ///
+    /// # Arguments
+    ///
+    /// * `container_name` - The name of the container.
+    /// * `blob_name` - The name of the blob.
+    /// * `tier` - The tier to be set on the blob.
+    /// * `options` - Optional configuration for the operation.
+    pub fn set_blob_tier(
+        &mut self,
+        container_name: &str,
+        blob_name: &str,
+        tier: AccessTier,
+        options: Option<BlobClientSetTierOptions<'a>>,
+    ) -> Result<()> {
+        self.push(BatchOperation::SetTier {
+            container_name: container_name.into(),
+            blob_name: blob_name.into(),
+            tier,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the number of operations in the batch.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns whether the batch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub(crate) fn operations(&self) -> &[BatchOperation<'a>] {
+        &self.operations
+    }
+
+    fn push(&mut self, operation: BatchOperation<'a>) -> Result<()> {
+        if self.operations.len() == MAX_OPERATIONS {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!("a batch may contain at most {MAX_OPERATIONS} operations"),
+            ));
+        }
+        if self.operations.first().is_some_and(|first| {
+            std::mem::discriminant(first) != std::mem::discriminant(&operation)
+        }) {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "all operations in a batch must be of the same kind",
+            ));
+        }
+        self.operations.push(operation);
+        Ok(())
+    }
+}
+
+/// Submits `batch` to `url`, which has the batch query parameters, returning the result of each operation in order.
+///
+/// Each operation is built by a [`GeneratedBlobClient`] returned by `blob_client`, whose pipeline authorizes it and
+/// captures it instead of sending it; see [`BatchSubRequest`].
+pub(crate) async fn submit_batch(
+    pipeline: &Pipeline,
+    version: &str,
+    url: Url,
+    batch: BlobBatch<'_>,
+    blob_client: impl Fn(&str, &str) -> GeneratedBlobClient,
+    ctx: &Context<'_>,
+) -> Result<Vec<Result<Response<(), NoFormat>>>> {
+    if batch.is_empty() {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            "a batch must contain at least one operation",
+        ));
+    }
+
+    let mut parts = Vec::with_capacity(batch.len());
+    let mut success_codes: Vec<&'static [u16]> = Vec::with_capacity(batch.len());
+    for (index, operation) in batch.operations.into_iter().enumerate() {
+        let client = blob_client(operation.container_name(), operation.blob_name());
+        let sub_request = BatchSubRequest::default();
+        match operation {
+            BatchOperation::Delete { mut options, .. } => {
+                options.method_options.context = options
+                    .method_options
+                    .context
+                    .with_value(sub_request.clone());
+                client.delete(Some(options)).await?;
+                success_codes.push(&[202]);
+            }
+            BatchOperation::SetTier {
+                tier, mut options, ..
+            } => {
+                options.method_options.context = options
+                    .method_options
+                    .context
+                    .with_value(sub_request.clone());
+                client.set_tier(tier, Some(options)).await?;
+                success_codes.push(&[200, 202]);
+            }
+        }
+        let request = sub_request.take().ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Other,
+                "the client can't submit batches; create it with a constructor of BlobServiceClient or BlobContainerClient",
+            )
+        })?;
+        let mut part = Part::from_request(&request)?;
+        part.headers.insert("content-id", index.to_string());
+        parts.push(part);
+    }
+
+    let boundary = format!("batch_{}", Uuid::new_v4());
+    let body = multipart::encode(&boundary, &parts);
+    let mut request = Request::new(url, Method::Post);
+    request.insert_header("content-length", body.len().to_string());
+    request.insert_header(CONTENT_TYPE, multipart::content_type(&boundary));
+    request.insert_header(VERSION, version.to_string());
+    request.set_body(body);
+    let response = pipeline
+        .send(
+            ctx,
+            &mut request,
+            Some(PipelineSendOptions {
+                check_success: CheckSuccessOptions {
+                    success_codes: &[202],
+                },
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    let content_type = response.headers().get_str(&CONTENT_TYPE)?;
+    let parts = multipart::decode(content_type, response.body())?;
+    let mut results = Vec::with_capacity(success_codes.len());
+    for (index, success_codes) in success_codes.into_iter().enumerate() {
+        // Responses are matched to requests by Content-ID, falling back to their order.
+        let content_id = index.to_string();
+        let part = parts
+            .iter()
+            .find(|part| part.headers.get_optional_str(&"content-id".into()) == Some(&content_id))
+            .or_else(|| parts.get(index))
+            .ok_or_else(|| {
+                Error::with_message(
+                    ErrorKind::DataConversion,
+                    format!("batch response has no response for operation {index}"),
+                )
+            })?;
+        let response = part.to_response()?;
+        results.push(
+            check_success(response, Some(CheckSuccessOptions { success_codes }))
+                .await
+                .map(Into::into),
+        );
+    }
+    Ok(results)
+}
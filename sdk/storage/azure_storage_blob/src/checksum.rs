@@ -0,0 +1,160 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Computes the transactional checksums of requests and validates those of responses.
+
+use crate::models::StorageErrorCode;
+use azure_core::{
+    error::{Error, ErrorKind},
+    http::{request::Body, Context, StatusCode},
+    Result,
+};
+use azure_storage_common::{ChecksumAlgorithm, ChecksumMismatchError};
+use std::fmt::Display;
+
+const CRC64_MISMATCH: &str = "Crc64Mismatch";
+
+/// The algorithm with which a single request's content is validated, which the `with_validate_content_md5()` and
+/// `with_validate_content_crc64()` methods of its options add to their context.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ContentValidation(pub(crate) ChecksumAlgorithm);
+
+/// Returns the algorithm with which the request whose options have `context` is validated, if any.
+pub(crate) fn requested_algorithm(context: &Context<'_>) -> Option<ChecksumAlgorithm> {
+    context
+        .value::<ContentValidation>()
+        .map(|validation| validation.0)
+}
+
+/// Returns the checksum of a request's content, reading a stream from its start.
+pub(crate) async fn content_checksum(algorithm: ChecksumAlgorithm, body: &Body) -> Result<Vec<u8>> {
+    match body {
+        Body::Bytes(bytes) => Ok(algorithm.checksum(bytes)),
+        #[cfg(not(target_arch = "wasm32"))]
+        Body::SeekableStream(stream) => {
+            use futures::io::AsyncReadExt as _;
+
+            let mut stream = stream.clone();
+            stream.reset().await?;
+            let mut hasher = algorithm.hasher();
+            let mut buffer = vec![0; stream.buffer_size()];
+            loop {
+                let read = stream.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+            stream.reset().await?;
+            Ok(hasher.finish())
+        }
+    }
+}
+
+/// Returns the algorithm selected by a pair of `validate_content_md5` and `validate_content_crc64` options.
+pub(crate) fn checksum_algorithm(
+    validate_content_md5: Option<bool>,
+    validate_content_crc64: Option<bool>,
+) -> Result<Option<ChecksumAlgorithm>> {
+    match (
+        validate_content_md5.unwrap_or_default(),
+        validate_content_crc64.unwrap_or_default(),
+    ) {
+        (false, false) => Ok(None),
+        (true, false) => Ok(Some(ChecksumAlgorithm::Md5)),
+        (false, true) => Ok(Some(ChecksumAlgorithm::Crc64)),
+        (true, true) => Err(Error::with_message(
+            ErrorKind::Other,
+            "only one of validate_content_md5 and validate_content_crc64 may be set",
+        )),
+    }
+}
+
+/// The `transactional_content_md5` and `transactional_content_crc64` options of a request sending `data`.
+pub(crate) fn transactional_checksums(
+    algorithm: Option<ChecksumAlgorithm>,
+    data: &[u8],
+) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    match algorithm {
+        Some(ChecksumAlgorithm::Md5) => (Some(ChecksumAlgorithm::Md5.checksum(data)), None),
+        Some(ChecksumAlgorithm::Crc64) => (None, Some(ChecksumAlgorithm::Crc64.checksum(data))),
+        _ => (None, None),
+    }
+}
+
+/// The `transactional_content_md5` and `transactional_content_crc64` options of a request whose content has
+/// `checksum`.
+pub(crate) fn transactional_options(
+    algorithm: ChecksumAlgorithm,
+    checksum: &[u8],
+) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    match algorithm {
+        ChecksumAlgorithm::Crc64 => (None, Some(checksum.to_vec())),
+        _ => (Some(checksum.to_vec()), None),
+    }
+}
+
+/// Validates `data` against `expected`, the checksum its response returned, which is an error if it's missing.
+pub(crate) fn validate(
+    algorithm: ChecksumAlgorithm,
+    expected: Option<Vec<u8>>,
+    data: &[u8],
+    description: impl Display,
+) -> Result<()> {
+    let Some(expected) = expected else {
+        return Err(Error::with_message(
+            ErrorKind::DataConversion,
+            format!("response has no {algorithm} for {description}"),
+        ));
+    };
+    algorithm.verify(&expected, data).map_err(|error| {
+        Error::with_error(
+            ErrorKind::DataConversion,
+            error,
+            format!("{algorithm} mismatch for {description}"),
+        )
+    })
+}
+
+/// Validates that the checksum a response returned for the content of its request, if any, is the one sent.
+pub(crate) fn validate_echo(
+    algorithm: ChecksumAlgorithm,
+    sent: &[u8],
+    returned: Option<Vec<u8>>,
+    description: impl Display,
+) -> Result<()> {
+    match returned {
+        Some(returned) if returned != sent => Err(Error::with_error(
+            ErrorKind::DataConversion,
+            ChecksumMismatchError::new(algorithm, sent.to_vec(), Some(returned)),
+            format!("{algorithm} mismatch for {description}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the error of a request the service rejected because its content didn't match the checksum sent, `sent`,
+/// with a [`ChecksumMismatchError`] as its source. Any other error is returned as is.
+pub(crate) fn map_rejection(
+    error: Error,
+    algorithm: Option<ChecksumAlgorithm>,
+    sent: &[u8],
+) -> Error {
+    let Some(algorithm) = algorithm else {
+        return error;
+    };
+    let mismatch = error.http_status() == Some(StatusCode::BadRequest)
+        && matches!(
+            error.kind(),
+            ErrorKind::HttpResponse { error_code: Some(error_code), .. }
+                if error_code == StorageErrorCode::Md5Mismatch.as_ref() || error_code == CRC64_MISMATCH
+        );
+    if !mismatch {
+        return error;
+    }
+    Error::with_error(
+        error.kind().clone(),
+        ChecksumMismatchError::new(algorithm, sent.to_vec(), None),
+        error.to_string(),
+    )
+}
@@ -2,15 +2,17 @@
 // Licensed under the MIT License.
 
 use crate::{
+    checksum,
     generated::clients::AppendBlobClient as GeneratedAppendBlobClient,
     models::{
         AppendBlobClientAppendBlockFromUrlOptions, AppendBlobClientAppendBlockFromUrlResult,
         AppendBlobClientAppendBlockOptions, AppendBlobClientAppendBlockResult,
-        AppendBlobClientCreateOptions, AppendBlobClientCreateResult, AppendBlobClientSealOptions,
-        AppendBlobClientSealResult,
+        AppendBlobClientAppendBlockResultHeaders, AppendBlobClientCreateOptions,
+        AppendBlobClientCreateResult, AppendBlobClientOpenWriteOptions,
+        AppendBlobClientSealOptions, AppendBlobClientSealResult,
     },
     pipeline::StorageHeadersPolicy,
-    AppendBlobClientOptions,
+    AppendBlobClientOptions, AppendBlobWriter,
 };
 use azure_core::{
     credentials::TokenCredential,
@@ -20,6 +22,7 @@ use azure_core::{
     },
     tracing, Bytes, Result,
 };
+use azure_storage_common::{ChecksumAlgorithm, SharedKeyAuthorizationPolicy, SharedKeyCredential};
 use std::sync::Arc;
 
 /// A client to interact with a specific Azure storage Append blob, although that blob may not yet exist.
@@ -80,6 +83,45 @@ impl GeneratedAppendBlobClient {
             pipeline,
         })
     }
+
+    /// Creates a new GeneratedAppendBlobClient from a blob URL, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_url` - The full URL of the Append blob, for example `https://myaccount.blob.core.windows.net/mycontainer/myblob`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.AppendBlob")]
+    pub fn from_url_with_shared_key(
+        blob_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<AppendBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            vec![auth_policy],
+            None,
+        );
+
+        Ok(Self {
+            endpoint: blob_url,
+            version: options.version,
+            pipeline,
+        })
+    }
 }
 
 impl AppendBlobClient {
@@ -115,6 +157,38 @@ impl AppendBlobClient {
         Ok(Self { client })
     }
 
+    /// Creates a new AppendBlobClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `container_name` - The name of the container containing this Append blob.
+    /// * `blob_name` - The name of the Append blob to interact with.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        container_name: &str,
+        blob_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<AppendBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut url = Url::parse(endpoint)?;
+
+        {
+            let mut path_segments = url.path_segments_mut().map_err(|_| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::Other,
+                    "Invalid endpoint URL: Failed to parse out path segments from provided endpoint URL.",
+                )
+            })?;
+            path_segments.extend([container_name, blob_name]);
+        }
+
+        let client = GeneratedAppendBlobClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
     /// Creates a new AppendBlobClient from a blob URL.
     ///
     /// # Arguments
@@ -156,16 +230,40 @@ impl AppendBlobClient {
     ///
     /// * `data` - The blob data to append.
     /// * `content_length` - Total length of the blob data to be appended.
-    /// * `options` - Optional configuration for the request.
+    /// * `options` - Optional configuration for the request. [`AppendBlobClientAppendBlockOptions::with_validate_content_md5()`]
+    ///   and [`with_validate_content_crc64()`](AppendBlobClientAppendBlockOptions::with_validate_content_crc64()) send
+    ///   the block's checksum.
     pub async fn append_block(
         &self,
         data: RequestContent<Bytes, NoFormat>,
         content_length: u64,
         options: Option<AppendBlobClientAppendBlockOptions<'_>>,
     ) -> Result<Response<AppendBlobClientAppendBlockResult, NoFormat>> {
-        self.client
-            .append_block(data, content_length, options)
+        let mut options = options.unwrap_or_default();
+        let algorithm = checksum::requested_algorithm(&options.method_options.context);
+        let sent = match algorithm {
+            Some(algorithm) => checksum::content_checksum(algorithm, data.body()).await?,
+            None => Vec::new(),
+        };
+        if let Some(algorithm) = algorithm {
+            (
+                options.transactional_content_md5,
+                options.transactional_content_crc64,
+            ) = checksum::transactional_options(algorithm, &sent);
+        }
+        let response = self
+            .client
+            .append_block(data, content_length, Some(options))
             .await
+            .map_err(|error| checksum::map_rejection(error, algorithm, &sent))?;
+        if let Some(algorithm) = algorithm {
+            let returned = match algorithm {
+                ChecksumAlgorithm::Crc64 => response.content_crc64()?,
+                _ => response.content_md5()?,
+            };
+            checksum::validate_echo(algorithm, &sent, returned, "the block")?;
+        }
+        Ok(response)
     }
 
     /// Creates a new block to be committed as part of an Append blob where the contents are
@@ -198,4 +296,27 @@ impl AppendBlobClient {
     ) -> Result<Response<AppendBlobClientSealResult, NoFormat>> {
         self.client.seal(options).await
     }
+
+    /// Opens the Append blob for writing, creating it if it doesn't exist, and returns an [`AppendBlobWriter`] that
+    /// implements [`AsyncWrite`](futures::io::AsyncWrite) and appends the data written in blocks.
+    ///
+    /// Each block is appended at the offset where the previous one ended, so writing fails if another client appends
+    /// to the blob meanwhile. Once the blob has 50,000 blocks, the writer continues with the blob named by
+    /// [`next_blob_name`](AppendBlobClientOpenWriteOptions::next_blob_name), if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the writer.
+    pub async fn open_write(
+        &self,
+        options: Option<AppendBlobClientOpenWriteOptions<'_>>,
+    ) -> Result<AppendBlobWriter> {
+        let client = GeneratedAppendBlobClient {
+            endpoint: self.client.endpoint.clone(),
+            pipeline: self.client.pipeline.clone(),
+            version: self.client.version.clone(),
+            tracer: self.client.tracer.clone(),
+        };
+        AppendBlobWriter::open(client, options.unwrap_or_default().into_owned()).await
+    }
 }
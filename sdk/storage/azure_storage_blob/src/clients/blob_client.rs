@@ -1,7 +1,10 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+#[cfg(feature = "client_side_encryption")]
+use crate::EncryptedBlobClient;
 use crate::{
+    checksum, copy, download,
     generated::clients::AppendBlobClient as GeneratedAppendBlobClient,
     generated::clients::BlobClient as GeneratedBlobClient,
     generated::clients::BlockBlobClient as GeneratedBlockBlobClient,
@@ -9,28 +12,43 @@ use crate::{
     generated::models::{
         BlobClientAcquireLeaseResult, BlobClientBreakLeaseResult, BlobClientChangeLeaseResult,
         BlobClientDownloadResult, BlobClientGetAccountInfoResult, BlobClientGetPropertiesResult,
-        BlobClientReleaseLeaseResult, BlobClientRenewLeaseResult, BlockBlobClientUploadResult,
+        BlobClientReleaseLeaseResult, BlobClientRenewLeaseResult,
+        BlockBlobClientCommitBlockListResult, BlockBlobClientUploadResult, QueryRequest,
+        QuerySerialization,
     },
     models::{
-        AccessTier, BlobClientAcquireLeaseOptions, BlobClientBreakLeaseOptions,
-        BlobClientChangeLeaseOptions, BlobClientDeleteOptions, BlobClientDownloadOptions,
-        BlobClientGetAccountInfoOptions, BlobClientGetPropertiesOptions, BlobClientGetTagsOptions,
+        AccessTier, BlobClientAbortCopyFromUrlOptions, BlobClientAbortCopyFromUrlResult,
+        BlobClientAcquireLeaseOptions, BlobClientBeginCopyFromUrlOptions,
+        BlobClientBreakLeaseOptions, BlobClientChangeLeaseOptions, BlobClientCreateSnapshotOptions,
+        BlobClientCreateSnapshotResult, BlobClientDeleteOptions, BlobClientDownloadOptions,
+        BlobClientDownloadToOptions, BlobClientGetAccountInfoOptions,
+        BlobClientGetPropertiesOptions, BlobClientGetTagsOptions, BlobClientOpenReadOptions,
         BlobClientReleaseLeaseOptions, BlobClientRenewLeaseOptions, BlobClientSetMetadataOptions,
         BlobClientSetPropertiesOptions, BlobClientSetTagsOptions, BlobClientSetTierOptions,
-        BlobTags, BlockBlobClientUploadOptions, StorageErrorCode,
+        BlobClientUndeleteOptions, BlobClientUndeleteResult, BlobClientUploadFromReaderOptions,
+        BlobCopyOperation, BlobTags, BlockBlobClientQueryOptions, BlockBlobClientUploadOptions,
+        BlockBlobClientUploadResultHeaders, QueryFormat, QueryRequestType, StorageErrorCode,
     },
     pipeline::StorageHeadersPolicy,
-    AppendBlobClient, BlobClientOptions, BlockBlobClient, PageBlobClient,
+    upload, AppendBlobClient, BlobClientOptions, BlobQueryReader, BlobReader, BlockBlobClient,
+    PageBlobClient,
 };
 use azure_core::{
     credentials::TokenCredential,
     error::ErrorKind,
     http::{
         policies::{BearerTokenAuthorizationPolicy, Policy},
+        poller::Poller,
         AsyncResponse, NoFormat, Pipeline, RequestContent, Response, StatusCode, Url, XmlFormat,
     },
     tracing, Bytes, Result,
 };
+use azure_storage_common::{
+    parse_account_name, parse_resource_path,
+    sas::{append_sas, BlobSasBuilder, UserDelegationKey},
+    ChecksumAlgorithm, SharedKeyAuthorizationPolicy, SharedKeyCredential,
+};
+use futures::io::{AsyncRead, AsyncWrite};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -92,6 +110,45 @@ impl GeneratedBlobClient {
             pipeline,
         })
     }
+
+    /// Creates a new GeneratedBlobClient from a blob URL, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_url` - The full URL of the blob, for example `https://myaccount.blob.core.windows.net/mycontainer/myblob`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.Blob")]
+    pub fn from_url_with_shared_key(
+        blob_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            vec![auth_policy],
+            None,
+        );
+
+        Ok(Self {
+            endpoint: blob_url,
+            version: options.version,
+            pipeline,
+        })
+    }
 }
 impl BlobClient {
     /// Creates a new BlobClient, using Entra ID authentication.
@@ -126,6 +183,38 @@ impl BlobClient {
         Ok(Self { client })
     }
 
+    /// Creates a new BlobClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `container_name` - The name of the container containing this blob.
+    /// * `blob_name` - The name of the blob to interact with.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        container_name: &str,
+        blob_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobClientOptions>,
+    ) -> Result<Self> {
+        let mut url = Url::parse(endpoint)?;
+
+        {
+            let mut path_segments = url.path_segments_mut().map_err(|_| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::Other,
+                    "Invalid endpoint URL: Failed to parse out path segments from provided endpoint URL.",
+                )
+            })?;
+            path_segments.extend([container_name, blob_name]);
+        }
+
+        let client = GeneratedBlobClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
     /// Creates a new BlobClient from a blob URL.
     ///
     /// # Arguments
@@ -184,6 +273,139 @@ impl BlobClient {
         &self.client.endpoint
     }
 
+    /// Returns a client for a snapshot of this blob, which reads its content, properties and metadata, and deletes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The timestamp of the snapshot, as returned by [`BlobClient::create_snapshot()`].
+    pub fn with_snapshot(&self, snapshot: &str) -> Self {
+        self.with_selector(Some(("snapshot", snapshot)))
+    }
+
+    /// Returns a client for a version of this blob, which reads its content, properties and metadata, and deletes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `version_id` - The ID of the version, as returned by operations that modify the blob when versioning is enabled.
+    pub fn with_version_id(&self, version_id: &str) -> Self {
+        self.with_selector(Some(("versionid", version_id)))
+    }
+
+    /// Returns a client that encrypts the blob's data before uploading it and decrypts it after downloading it, with
+    /// the keys of `options`.
+    ///
+    /// Requires the `client_side_encryption` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The keys with which to encrypt and decrypt the blob.
+    #[cfg(feature = "client_side_encryption")]
+    pub fn with_client_side_encryption(
+        &self,
+        options: crate::encryption::ClientSideEncryptionOptions,
+    ) -> EncryptedBlobClient {
+        EncryptedBlobClient {
+            blob_client: BlobClient {
+                client: GeneratedBlobClient {
+                    endpoint: self.client.endpoint.clone(),
+                    pipeline: self.client.pipeline.clone(),
+                    version: self.client.version.clone(),
+                    tracer: self.client.tracer.clone(),
+                },
+            },
+            options,
+        }
+    }
+
+    /// Returns a client whose URL selects the given snapshot or version of the blob, or the base blob if `None`.
+    fn with_selector(&self, selector: Option<(&str, &str)>) -> Self {
+        let mut url = self.client.endpoint.clone();
+        let query: Vec<(String, String)> = url
+            .query_pairs()
+            .into_owned()
+            .filter(|(name, _)| name != "snapshot" && name != "versionid")
+            .collect();
+        url.set_query(None);
+        if !query.is_empty() || selector.is_some() {
+            let mut pairs = url.query_pairs_mut();
+            pairs.extend_pairs(query);
+            if let Some((name, value)) = selector {
+                pairs.append_pair(name, value);
+            }
+        }
+        Self {
+            client: GeneratedBlobClient {
+                endpoint: url,
+                pipeline: self.client.pipeline.clone(),
+                version: self.client.version.clone(),
+                tracer: self.client.tracer.clone(),
+            },
+        }
+    }
+
+    /// Returns the URL of the blob with a service SAS appended, signed with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the SAS. Its container and blob names are set to this blob's, and
+    ///   its snapshot or version to the one this client's URL selects, if any.
+    /// * `credential` - The name and key of the storage account.
+    pub fn generate_sas_url(
+        &self,
+        sas: BlobSasBuilder,
+        credential: &SharedKeyCredential,
+    ) -> Result<Url> {
+        let sas = self.sas_builder(sas)?.sign(credential)?;
+        Ok(append_sas(self.url(), &sas))
+    }
+
+    /// Returns the URL of the blob with a user delegation SAS appended.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the SAS. Its container and blob names are set to this blob's, and
+    ///   its snapshot or version to the one this client's URL selects, if any.
+    /// * `user_delegation_key` - A key from [`BlobServiceClient::get_user_delegation_key()`](crate::BlobServiceClient::get_user_delegation_key).
+    pub fn generate_user_delegation_sas_url(
+        &self,
+        sas: BlobSasBuilder,
+        user_delegation_key: &UserDelegationKey,
+    ) -> Result<Url> {
+        let account_name = parse_account_name(self.url()).ok_or_else(|| {
+            azure_core::Error::with_message(
+                ErrorKind::Other,
+                format!("{} doesn't name a storage account", self.url()),
+            )
+        })?;
+        let sas = self
+            .sas_builder(sas)?
+            .sign_with_user_delegation_key(&account_name, user_delegation_key)?;
+        Ok(append_sas(self.url(), &sas))
+    }
+
+    fn sas_builder(&self, sas: BlobSasBuilder) -> Result<BlobSasBuilder> {
+        let path = parse_resource_path(self.url());
+        let mut sas = match path.as_slice() {
+            [container_name, blob_name @ ..] if !blob_name.is_empty() => sas
+                .with_container_name(container_name)
+                .with_blob_name(blob_name.join("/")),
+            _ => {
+                return Err(azure_core::Error::with_message(
+                    ErrorKind::Other,
+                    format!("{} doesn't name a blob", self.url()),
+                ))
+            }
+        };
+        for (name, value) in self.url().query_pairs() {
+            match name.as_ref() {
+                "snapshot" => sas = sas.with_snapshot(value),
+                "versionid" => sas = sas.with_version_id(value),
+                _ => {}
+            }
+        }
+        Ok(sas)
+    }
+
     /// Returns all user-defined metadata, standard HTTP properties, and system properties for the blob.
     /// The data returned does not include the content of the blob.
     ///
@@ -211,12 +433,126 @@ impl BlobClient {
 
     /// Downloads a blob from the service, including its metadata and properties.
     ///
-    /// * `options` - Optional configuration for the request.
+    /// * `options` - Optional configuration for the request. [`BlobClientDownloadOptions::with_validate_content_md5()`]
+    ///   and [`with_validate_content_crc64()`](BlobClientDownloadOptions::with_validate_content_crc64()) validate a range
+    ///   of at most 4 MiB, whose body is then read before it's returned.
     pub async fn download(
         &self,
         options: Option<BlobClientDownloadOptions<'_>>,
     ) -> Result<AsyncResponse<BlobClientDownloadResult>> {
-        self.client.download(options).await
+        let algorithm = options
+            .as_ref()
+            .and_then(|options| checksum::requested_algorithm(&options.method_options.context));
+        let response = self.client.download(options).await?;
+        match algorithm {
+            Some(algorithm) => download::validate_range(algorithm, response).await,
+            None => Ok(response),
+        }
+    }
+
+    /// Downloads the blob into `writer` in ranges, which are requested concurrently and written in order, and returns
+    /// the blob's size.
+    ///
+    /// Every range after the first is requested with the ETag of the first response, so the download fails rather than
+    /// returning a mix of versions if the blob changes meanwhile. If a response body breaks, the rest of its range is
+    /// requested again.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The [`AsyncWrite`] to write the blob data to.
+    /// * `options` - Optional configuration for the download.
+    pub async fn download_to_writer<W>(
+        &self,
+        writer: W,
+        options: Option<BlobClientDownloadToOptions<'_>>,
+    ) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        download::download_to_writer(&self.client, writer, options.unwrap_or_default()).await
+    }
+
+    /// Downloads the blob into a file, as [`download_to_writer()`](BlobClient::download_to_writer) does, creating or
+    /// truncating the file and writing each range at its offset as soon as it's received.
+    ///
+    /// Requires the `tokio` feature and a Tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to write.
+    /// * `options` - Optional configuration for the download.
+    #[cfg(feature = "tokio")]
+    pub async fn download_to_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        options: Option<BlobClientDownloadToOptions<'_>>,
+    ) -> Result<u64> {
+        use azure_core::error::ResultExt as _;
+
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .with_context_fn(ErrorKind::Io, || {
+                format!("failed to create {}", path.display())
+            })?;
+        download::download_to_file(&self.client, &mut file, options.unwrap_or_default()).await
+    }
+
+    /// Opens the blob for reading at random offsets, returning a [`BlobReader`] that implements
+    /// [`AsyncRead`] and [`AsyncSeek`](futures::io::AsyncSeek).
+    ///
+    /// The reader requests the blob in blocks, which it caches and reads ahead of the position being read. Every block
+    /// is requested with the ETag the blob has when it's opened, so reading fails if the blob changes meanwhile.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the reader.
+    pub async fn open_read(
+        &self,
+        options: Option<BlobClientOpenReadOptions<'_>>,
+    ) -> Result<BlobReader> {
+        let client = GeneratedBlobClient {
+            endpoint: self.client.endpoint.clone(),
+            pipeline: self.client.pipeline.clone(),
+            version: self.client.version.clone(),
+            tracer: self.client.tracer.clone(),
+        };
+        BlobReader::open(Arc::new(client), options.unwrap_or_default().into_owned()).await
+    }
+
+    /// Queries the blob's data with SQL, returning a [`BlobQueryReader`] that streams the results, progress and errors
+    /// as the service returns them.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - The query, such as `SELECT * FROM BlobStorage WHERE _2 > 100`.
+    /// * `input_format` - The format of the blob: delimited text, JSON or Parquet.
+    /// * `output_format` - The format of the results: delimited text, JSON or Apache Arrow.
+    /// * `options` - Optional configuration for the request.
+    pub async fn query(
+        &self,
+        expression: &str,
+        input_format: QueryFormat,
+        output_format: QueryFormat,
+        options: Option<BlockBlobClientQueryOptions<'_>>,
+    ) -> Result<BlobQueryReader> {
+        let reader_format = output_format.clone();
+        let query_request = QueryRequest {
+            expression: Some(expression.to_string()),
+            input_serialization: Some(QuerySerialization {
+                format: Some(input_format),
+            }),
+            output_serialization: Some(QuerySerialization {
+                format: Some(output_format),
+            }),
+            query_type: Some(QueryRequestType::Sql),
+        };
+        let response = self
+            .block_blob_client()
+            .client
+            .query(query_request.try_into()?, options)
+            .await?;
+        Ok(BlobQueryReader::new(response.into_body(), &reader_format))
     }
 
     /// Creates a new blob from a data source.
@@ -227,7 +563,9 @@ impl BlobClient {
     /// * `overwrite` - Whether the blob to be uploaded should overwrite the current data. If True, `upload()` will overwrite the existing data.
     ///   If False, the operation will fail with ResourceExistsError.
     /// * `content_length` - Total length of the blob data to be uploaded.
-    /// * `options` - Optional configuration for the request.
+    /// * `options` - Optional configuration for the request. [`BlockBlobClientUploadOptions::with_validate_content_md5()`]
+    ///   and [`with_validate_content_crc64()`](BlockBlobClientUploadOptions::with_validate_content_crc64()) send the
+    ///   blob's checksum.
     pub async fn upload(
         &self,
         data: RequestContent<Bytes, NoFormat>,
@@ -241,10 +579,91 @@ impl BlobClient {
             options.if_none_match = Some(String::from("*"));
         }
 
-        self.block_blob_client()
+        let algorithm = checksum::requested_algorithm(&options.method_options.context);
+        let sent = match algorithm {
+            Some(algorithm) => checksum::content_checksum(algorithm, data.body()).await?,
+            None => Vec::new(),
+        };
+        if let Some(algorithm) = algorithm {
+            (
+                options.transactional_content_md5,
+                options.transactional_content_crc64,
+            ) = checksum::transactional_options(algorithm, &sent);
+        }
+        let response = self
+            .block_blob_client()
             .client
             .upload(data, content_length, Some(options))
             .await
+            .map_err(|error| checksum::map_rejection(error, algorithm, &sent))?;
+        // `Put Blob` only returns the MD5 hash of the content.
+        if algorithm == Some(ChecksumAlgorithm::Md5) {
+            checksum::validate_echo(
+                ChecksumAlgorithm::Md5,
+                &sent,
+                response.content_md5()?,
+                "the blob",
+            )?;
+        }
+        Ok(response)
+    }
+
+    /// Creates a new block blob from data of any length, which needn't be known in advance.
+    ///
+    /// Data of up to `max_single_put_size` bytes is uploaded with a single request. Larger data is split into blocks,
+    /// which are staged concurrently and then committed with the blob's properties, metadata, tags and tier.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The blob data to upload, such as a [`SeekableStream`](azure_core::stream::SeekableStream) or any other [`AsyncRead`].
+    /// * `overwrite` - Whether the blob to be uploaded should overwrite the current data. If True, `upload_from_reader()` will overwrite the existing data.
+    ///   If False, the operation will fail with ResourceExistsError.
+    /// * `options` - Optional configuration for the upload.
+    pub async fn upload_from_reader<R>(
+        &self,
+        data: R,
+        overwrite: bool,
+        options: Option<BlobClientUploadFromReaderOptions<'_>>,
+    ) -> Result<Response<BlockBlobClientCommitBlockListResult, NoFormat>>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let mut options = options.unwrap_or_default();
+
+        if !overwrite {
+            options.upload_options.if_none_match = Some(String::from("*"));
+        }
+
+        upload::upload(&self.block_blob_client().client, data, options).await
+    }
+
+    /// Creates a new block blob from a file, as [`upload_from_reader()`](BlobClient::upload_from_reader) does.
+    ///
+    /// Requires the `tokio` feature and a Tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to upload.
+    /// * `overwrite` - Whether the blob to be uploaded should overwrite the current data. If True, `upload_file()` will overwrite the existing data.
+    ///   If False, the operation will fail with ResourceExistsError.
+    /// * `options` - Optional configuration for the upload.
+    #[cfg(feature = "tokio")]
+    pub async fn upload_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        overwrite: bool,
+        options: Option<BlobClientUploadFromReaderOptions<'_>>,
+    ) -> Result<Response<BlockBlobClientCommitBlockListResult, NoFormat>> {
+        use azure_core::error::ResultExt as _;
+
+        let path = path.as_ref();
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context_fn(ErrorKind::Io, || {
+                format!("failed to open {}", path.display())
+            })?;
+        self.upload_from_reader(upload::TokioReader(file), overwrite, options)
+            .await
     }
 
     /// Sets user-defined metadata for the specified blob as one or more name-value pairs. Each call to this operation
@@ -290,6 +709,91 @@ impl BlobClient {
         self.client.set_tier(tier, options).await
     }
 
+    /// Starts copying a blob or file from a URL to this blob, and returns a [`Poller`] that reports the progress of the
+    /// copy and completes with the properties of this blob once it succeeds.
+    ///
+    /// The copy runs asynchronously in the service, which may complete it before responding. Each status yielded by
+    /// the poller has the copy ID, which [`BlobClient::abort_copy_from_url()`] takes to cancel the copy. The poller
+    /// ends without output if the copy fails or is aborted.
+    ///
+    /// # Arguments
+    ///
+    /// * `copy_source` - The URL of the source, which must be public or authorized by a shared access signature.
+    /// * `options` - Optional configuration for the request.
+    pub fn begin_copy_from_url(
+        &self,
+        copy_source: Url,
+        options: Option<BlobClientBeginCopyFromUrlOptions<'_>>,
+    ) -> Result<Poller<BlobCopyOperation>> {
+        let options = options.unwrap_or_default().into_owned();
+        let client = GeneratedBlobClient {
+            endpoint: self.client.endpoint.clone(),
+            pipeline: self.client.pipeline.clone(),
+            version: self.client.version.clone(),
+            tracer: self.client.tracer.clone(),
+        };
+        Ok(copy::begin_copy_from_url(client, copy_source, options))
+    }
+
+    /// Aborts a pending copy to this blob, leaving it with zero length and full metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `copy_id` - The ID of the copy, returned by [`BlobClient::begin_copy_from_url()`].
+    /// * `options` - Optional configuration for the request.
+    pub async fn abort_copy_from_url(
+        &self,
+        copy_id: &str,
+        options: Option<BlobClientAbortCopyFromUrlOptions<'_>>,
+    ) -> Result<Response<BlobClientAbortCopyFromUrlResult, NoFormat>> {
+        self.client.abort_copy_from_url(copy_id, options).await
+    }
+
+    /// Creates a read-only snapshot of the blob, whose timestamp is returned in the `x-ms-snapshot` header. Use
+    /// [`BlobClient::with_snapshot()`] to access the snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the request.
+    pub async fn create_snapshot(
+        &self,
+        options: Option<BlobClientCreateSnapshotOptions<'_>>,
+    ) -> Result<Response<BlobClientCreateSnapshotResult, NoFormat>> {
+        self.client.create_snapshot(options).await
+    }
+
+    /// Restores the contents and metadata of a soft-deleted blob and its soft-deleted snapshots.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the request.
+    pub async fn undelete(
+        &self,
+        options: Option<BlobClientUndeleteOptions<'_>>,
+    ) -> Result<Response<BlobClientUndeleteResult, NoFormat>> {
+        self.client.undelete(options).await
+    }
+
+    /// Restores a previous version of the blob by copying it over the base blob, which makes the copy the current
+    /// version. Returns a [`Poller`] as [`BlobClient::begin_copy_from_url()`] does.
+    ///
+    /// The copy is authorized as this client is; a snapshot or version selected by this client's URL is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `version_id` - The ID of the version to restore, as listed by [`BlobContainerClient::list_blobs()`](crate::BlobContainerClient::list_blobs)
+    ///   when it includes versions.
+    /// * `options` - Optional configuration for the copy.
+    pub fn restore_version(
+        &self,
+        version_id: &str,
+        options: Option<BlobClientBeginCopyFromUrlOptions<'_>>,
+    ) -> Result<Poller<BlobCopyOperation>> {
+        let source = self.with_version_id(version_id);
+        self.with_selector(None)
+            .begin_copy_from_url(source.url().clone(), options)
+    }
+
     /// Requests a new lease on a blob. The lease lock duration can be 15 to 60 seconds, or can be infinite.
     ///
     /// # Arguments
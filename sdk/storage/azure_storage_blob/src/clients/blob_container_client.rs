@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 use crate::{
+    batch,
     generated::clients::BlobClient as GeneratedBlobClient,
     generated::clients::BlobContainerClient as GeneratedBlobContainerClient,
     generated::models::{
@@ -12,13 +13,17 @@ use crate::{
         BlobContainerClientFindBlobsByTagsOptions, BlobContainerClientGetAccountInfoOptions,
         BlobContainerClientGetAccountInfoResult, BlobContainerClientGetPropertiesOptions,
         BlobContainerClientGetPropertiesResult, BlobContainerClientListBlobFlatSegmentOptions,
-        BlobContainerClientReleaseLeaseOptions, BlobContainerClientReleaseLeaseResult,
-        BlobContainerClientRenewLeaseOptions, BlobContainerClientRenewLeaseResult,
-        BlobContainerClientSetMetadataOptions,
+        BlobContainerClientListBlobHierarchySegmentOptions, BlobContainerClientReleaseLeaseOptions,
+        BlobContainerClientReleaseLeaseResult, BlobContainerClientRenewLeaseOptions,
+        BlobContainerClientRenewLeaseResult, BlobContainerClientSetMetadataOptions,
     },
-    models::{FilterBlobSegment, ListBlobsFlatSegmentResponse, StorageErrorCode},
-    pipeline::StorageHeadersPolicy,
-    BlobClient, BlobContainerClientOptions,
+    hierarchy,
+    models::{BlobContainerClientSubmitBatchOptions, BlobContainerClientWalkBlobsOptions},
+    models::{
+        FilterBlobSegment, ListBlobsFlatSegmentResponse, ListBlobsHierarchyPage, StorageErrorCode,
+    },
+    pipeline::{batch_policies, StorageHeadersPolicy},
+    BlobBatch, BlobClient, BlobContainerClientOptions,
 };
 use azure_core::{
     credentials::TokenCredential,
@@ -29,6 +34,11 @@ use azure_core::{
     },
     tracing, Result,
 };
+use azure_storage_common::{
+    parse_account_name, parse_resource_path,
+    sas::{append_sas, BlobSasBuilder, UserDelegationKey},
+    SharedKeyAuthorizationPolicy, SharedKeyCredential,
+};
 use std::{collections::HashMap, sync::Arc};
 
 /// A client to interact with a specified Azure storage container, although that container may not yet exist.
@@ -58,7 +68,7 @@ impl GeneratedBlobContainerClient {
             .per_call_policies
             .push(storage_headers_policy);
 
-        let per_retry_policies = if let Some(token_credential) = credential {
+        let auth_policy = if let Some(token_credential) = credential {
             if !container_url.scheme().starts_with("https") {
                 return Err(azure_core::Error::with_message(
                     azure_core::error::ErrorKind::Other,
@@ -69,9 +79,9 @@ impl GeneratedBlobContainerClient {
                 token_credential,
                 vec!["https://storage.azure.com/.default"],
             ));
-            vec![auth_policy]
+            Some(auth_policy)
         } else {
-            Vec::default()
+            None
         };
 
         let pipeline = Pipeline::new(
@@ -79,7 +89,46 @@ impl GeneratedBlobContainerClient {
             option_env!("CARGO_PKG_VERSION"),
             options.client_options.clone(),
             Vec::default(),
-            per_retry_policies,
+            batch_policies(auth_policy),
+            None,
+        );
+
+        Ok(Self {
+            endpoint: container_url,
+            version: options.version,
+            pipeline,
+        })
+    }
+
+    /// Creates a new GeneratedBlobContainerClient from a container URL, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `container_url` - The full URL of the container, for example `https://myaccount.blob.core.windows.net/mycontainer`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.Container")]
+    pub fn from_url_with_shared_key(
+        container_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobContainerClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            batch_policies(Some(auth_policy)),
             None,
         );
 
@@ -122,6 +171,37 @@ impl BlobContainerClient {
         Ok(Self { client })
     }
 
+    /// Creates a new BlobContainerClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `container_name` - The name of the container.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        container_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobContainerClientOptions>,
+    ) -> Result<Self> {
+        let mut url = Url::parse(endpoint)?;
+
+        {
+            let mut path_segments = url.path_segments_mut().map_err(|_| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::Other,
+                    "Invalid endpoint URL: Failed to parse out path segments from provided endpoint URL.",
+                )
+            })?;
+            path_segments.extend([container_name]);
+        }
+
+        let client =
+            GeneratedBlobContainerClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
     /// Creates a new BlobContainerClient from a container URL.
     ///
     /// # Arguments
@@ -167,6 +247,64 @@ impl BlobContainerClient {
         &self.client.endpoint
     }
 
+    /// Returns a copy of the generated client that can be moved into a `'static` pager.
+    fn generated_client(&self) -> Arc<GeneratedBlobContainerClient> {
+        Arc::new(GeneratedBlobContainerClient {
+            endpoint: self.client.endpoint.clone(),
+            pipeline: self.client.pipeline.clone(),
+            version: self.client.version.clone(),
+            tracer: self.client.tracer.clone(),
+        })
+    }
+
+    /// Returns the URL of the container with a service SAS appended, signed with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the SAS. Its container name is set to this container's.
+    /// * `credential` - The name and key of the storage account.
+    pub fn generate_sas_url(
+        &self,
+        sas: BlobSasBuilder,
+        credential: &SharedKeyCredential,
+    ) -> Result<Url> {
+        let sas = self.sas_builder(sas)?.sign(credential)?;
+        Ok(append_sas(self.url(), &sas))
+    }
+
+    /// Returns the URL of the container with a user delegation SAS appended.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the SAS. Its container name is set to this container's.
+    /// * `user_delegation_key` - A key from [`BlobServiceClient::get_user_delegation_key()`](crate::BlobServiceClient::get_user_delegation_key).
+    pub fn generate_user_delegation_sas_url(
+        &self,
+        sas: BlobSasBuilder,
+        user_delegation_key: &UserDelegationKey,
+    ) -> Result<Url> {
+        let account_name = parse_account_name(self.url()).ok_or_else(|| {
+            azure_core::Error::with_message(
+                ErrorKind::Other,
+                format!("{} doesn't name a storage account", self.url()),
+            )
+        })?;
+        let sas = self
+            .sas_builder(sas)?
+            .sign_with_user_delegation_key(&account_name, user_delegation_key)?;
+        Ok(append_sas(self.url(), &sas))
+    }
+
+    fn sas_builder(&self, sas: BlobSasBuilder) -> Result<BlobSasBuilder> {
+        let Some(container_name) = parse_resource_path(self.url()).into_iter().next() else {
+            return Err(azure_core::Error::with_message(
+                ErrorKind::Other,
+                format!("{} doesn't name a container", self.url()),
+            ));
+        };
+        Ok(sas.with_container_name(container_name))
+    }
+
     /// Creates a new container under the specified account. If the container with the same name already exists, the operation fails.
     ///
     /// # Arguments
@@ -232,6 +370,48 @@ impl BlobContainerClient {
         self.client.list_blob_flat_segment(options)
     }
 
+    /// Returns a list of the blobs and virtual directories directly under a prefix of the container, as
+    /// [`BlobHierarchyItem`](crate::models::BlobHierarchyItem)s in name order. A virtual directory is the prefix, up to
+    /// and including the next `delimiter`, of the names of the blobs it contains.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - The delimiter between the virtual directories of blob names, usually `/`.
+    /// * `options` - Optional configuration for the request, including the prefix whose contents to list.
+    pub fn list_blobs_by_hierarchy(
+        &self,
+        delimiter: &str,
+        options: Option<BlobContainerClientListBlobHierarchySegmentOptions<'_>>,
+    ) -> Result<Pager<ListBlobsHierarchyPage, XmlFormat>> {
+        Ok(hierarchy::list_blobs_by_hierarchy(
+            self.generated_client(),
+            delimiter.to_string(),
+            options.unwrap_or_default().into_owned(),
+        ))
+    }
+
+    /// Returns a list of the blobs and virtual directories under a prefix of the container, descending into every
+    /// virtual directory, as [`BlobHierarchyItem`](crate::models::BlobHierarchyItem)s.
+    ///
+    /// Pages are listed concurrently, so the items of each page are in name order but pages of different virtual
+    /// directories are interleaved. A virtual directory is listed before its contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - The delimiter between the virtual directories of blob names, usually `/`.
+    /// * `options` - Optional configuration for the request, including the prefix to walk from.
+    pub fn walk_blobs(
+        &self,
+        delimiter: &str,
+        options: Option<BlobContainerClientWalkBlobsOptions<'_>>,
+    ) -> Result<Pager<ListBlobsHierarchyPage, XmlFormat>> {
+        hierarchy::walk_blobs(
+            self.generated_client(),
+            delimiter.to_string(),
+            options.unwrap_or_default(),
+        )
+    }
+
     /// Returns a list of blobs in the container whose tags match a given search expression.
     ///
     /// # Arguments
@@ -365,4 +545,56 @@ impl BlobContainerClient {
             Err(e) => Err(e),
         }
     }
+
+    /// Submits a batch of up to 256 operations on blobs in this container with a single request.
+    ///
+    /// Each operation is authorized as if it were sent by itself. Returns the result of each operation, in the order
+    /// they were added to the batch, unless the batch request itself fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The operations, all on blobs in this container.
+    /// * `options` - Optional configuration for the request.
+    pub async fn submit_batch(
+        &self,
+        batch: BlobBatch<'_>,
+        options: Option<BlobContainerClientSubmitBatchOptions<'_>>,
+    ) -> Result<Vec<Result<Response<(), NoFormat>>>> {
+        let options = options.unwrap_or_default();
+        let container_name = parse_resource_path(self.url())
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if let Some(operation) = batch
+            .operations()
+            .iter()
+            .find(|operation| operation.container_name() != container_name)
+        {
+            return Err(azure_core::Error::with_message(
+                ErrorKind::Other,
+                format!(
+                    "the batch contains an operation in container {}, not {container_name}",
+                    operation.container_name()
+                ),
+            ));
+        }
+
+        let mut url = self.url().clone();
+        url.query_pairs_mut()
+            .append_pair("restype", "container")
+            .append_pair("comp", "batch");
+        if let Some(timeout) = options.timeout {
+            url.query_pairs_mut()
+                .append_pair("timeout", &timeout.to_string());
+        }
+        batch::submit_batch(
+            &self.client.pipeline,
+            &self.client.version,
+            url,
+            batch,
+            |_, blob_name| self.blob_client(blob_name).client,
+            &options.method_options.context,
+        )
+        .await
+    }
 }
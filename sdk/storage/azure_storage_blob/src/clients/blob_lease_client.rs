@@ -0,0 +1,227 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    generated::clients::BlobClient as GeneratedBlobClient,
+    models::{BlobClientAcquireLeaseResultHeaders, BlobLeaseClientAcquireOptions},
+    BlobClient,
+};
+use azure_core::{
+    async_runtime::get_async_runtime,
+    error::{Error, ErrorKind},
+    http::StatusCode,
+    time::{Duration, OffsetDateTime},
+    Result,
+};
+use futures::{
+    channel::oneshot,
+    future::{self, Either, FutureExt, Shared},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// The request to stop renewing the lease, which carries the channel for the result of releasing it.
+type StopRequest = oneshot::Sender<Result<()>>;
+
+/// A lease on a blob that is renewed in the background until it's released, for use as a distributed lock or to elect
+/// a leader.
+///
+/// The lease is acquired for a finite duration by [`BlobLeaseClient::acquire()`] and renewed by a task on the async
+/// runtime of `azure_core` well before it expires. If the lease can't be renewed, because another client has broken or
+/// taken it, or because it expired while the service couldn't be reached, [`BlobLeaseClient::lost()`] completes and
+/// the holder must stop acting on the lease.
+///
+/// The lease is released by [`BlobLeaseClient::release()`], or in the background when the client is dropped.
+pub struct BlobLeaseClient {
+    lease_id: String,
+    lost: Arc<AtomicBool>,
+    lost_signal: Shared<oneshot::Receiver<()>>,
+    stop: Option<oneshot::Sender<StopRequest>>,
+}
+
+impl BlobLeaseClient {
+    /// Acquires a lease on the blob of `blob_client` and starts renewing it in the background.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_client` - The client of the blob to lease.
+    /// * `duration` - The duration of the lease, in seconds, between 15 and 60. The lease is lost if it isn't renewed
+    ///   within this duration.
+    /// * `options` - Optional configuration for the request.
+    pub async fn acquire(
+        blob_client: &BlobClient,
+        duration: i32,
+        options: Option<BlobLeaseClientAcquireOptions<'_>>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        if !(15..=60).contains(&duration) {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "duration must be between 15 and 60 seconds",
+            ));
+        }
+        let duration = Duration::seconds(duration.into());
+        let renewal_interval = options.renewal_interval.unwrap_or(duration / 3);
+        if renewal_interval <= Duration::ZERO || renewal_interval >= duration {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "renewal_interval must be positive and shorter than the duration of the lease",
+            ));
+        }
+
+        // The lease expires no earlier than a full duration after the request was sent.
+        let requested_on = OffsetDateTime::now_utc();
+        let response = blob_client
+            .acquire_lease(
+                duration.whole_seconds() as i32,
+                Some(options.acquire_options),
+            )
+            .await?;
+        let lease_id = response.lease_id()?.ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, "response has no lease ID")
+        })?;
+
+        let lost = Arc::new(AtomicBool::new(false));
+        let (lost_sender, lost_signal) = oneshot::channel();
+        let (stop, stop_signal) = oneshot::channel();
+        let renewal = Renewal {
+            client: GeneratedBlobClient {
+                endpoint: blob_client.client.endpoint.clone(),
+                pipeline: blob_client.client.pipeline.clone(),
+                version: blob_client.client.version.clone(),
+                tracer: blob_client.client.tracer.clone(),
+            },
+            lease_id: lease_id.clone(),
+            duration,
+            renewal_interval,
+            expires_on: requested_on + duration,
+        };
+        // The task holds the lease until it's stopped or lost; it needn't be joined.
+        drop(get_async_runtime().spawn(Box::pin(renewal.run(
+            lost.clone(),
+            lost_sender,
+            stop_signal,
+        ))));
+
+        Ok(Self {
+            lease_id,
+            lost,
+            lost_signal: lost_signal.shared(),
+            stop: Some(stop),
+        })
+    }
+
+    /// Returns the ID of the lease, which requests that modify the blob must specify while it's leased.
+    pub fn lease_id(&self) -> &str {
+        &self.lease_id
+    }
+
+    /// Returns whether the lease has been lost.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    /// Completes when the lease is lost, after which the blob may be leased by another client.
+    pub async fn lost(&self) {
+        // The signal is also canceled if the async runtime drops the renewal task, which leaves the lease to expire.
+        let _ = self.lost_signal.clone().await;
+    }
+
+    /// Stops renewing the lease and releases it, so that another client can immediately acquire a lease on the blob.
+    ///
+    /// Returns an error if the lease was lost.
+    pub async fn release(mut self) -> Result<()> {
+        let (result_sender, result) = oneshot::channel();
+        let released = match self.stop.take() {
+            Some(stop) => stop.send(result_sender).is_ok(),
+            None => false,
+        };
+        if !released {
+            return Err(self.lost_error());
+        }
+        result.await.map_err(|_| self.lost_error())?
+    }
+
+    fn lost_error(&self) -> Error {
+        Error::with_message(
+            ErrorKind::Other,
+            format!("lease {} was lost", self.lease_id),
+        )
+    }
+}
+
+impl Drop for BlobLeaseClient {
+    fn drop(&mut self) {
+        // Dropping the stop channel has the renewal task release the lease.
+        self.stop.take();
+    }
+}
+
+impl std::fmt::Debug for BlobLeaseClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobLeaseClient")
+            .field("lease_id", &self.lease_id)
+            .field("lost", &self.is_lost())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of the background task that renews a lease.
+struct Renewal {
+    client: GeneratedBlobClient,
+    lease_id: String,
+    duration: Duration,
+    renewal_interval: Duration,
+    expires_on: OffsetDateTime,
+}
+
+impl Renewal {
+    /// Renews the lease every `renewal_interval` until it's lost, or until `stop_signal` is sent or dropped, at which
+    /// point the lease is released.
+    ///
+    /// A renewal that fails for any reason other than the lease having been taken is retried at the next interval, or
+    /// halfway to the expiry of the lease if that's sooner, until the lease expires.
+    async fn run(
+        mut self,
+        lost: Arc<AtomicBool>,
+        lost_sender: oneshot::Sender<()>,
+        mut stop_signal: oneshot::Receiver<StopRequest>,
+    ) {
+        let runtime = get_async_runtime();
+        loop {
+            let remaining = self.expires_on - OffsetDateTime::now_utc();
+            let delay = self.renewal_interval.min(remaining / 2).max(Duration::ZERO);
+            if let Either::Right((stop, _)) =
+                future::select(runtime.sleep(delay), &mut stop_signal).await
+            {
+                let result = self
+                    .client
+                    .release_lease(self.lease_id.clone(), None)
+                    .await
+                    .map(|_| ());
+                if let Ok(result_sender) = stop {
+                    let _ = result_sender.send(result);
+                }
+                return;
+            }
+
+            let requested_on = OffsetDateTime::now_utc();
+            match self.client.renew_lease(self.lease_id.clone(), None).await {
+                Ok(_) => self.expires_on = requested_on + self.duration,
+                Err(error)
+                    if matches!(
+                        error.http_status(),
+                        Some(StatusCode::Conflict | StatusCode::NotFound)
+                    ) || OffsetDateTime::now_utc() >= self.expires_on =>
+                {
+                    lost.store(true, Ordering::Release);
+                    let _ = lost_sender.send(());
+                    return;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
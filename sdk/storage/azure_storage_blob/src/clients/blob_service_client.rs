@@ -2,26 +2,32 @@
 // Licensed under the MIT License.
 
 use crate::{
+    batch,
     generated::clients::BlobContainerClient as GeneratedBlobContainerClient,
     generated::clients::BlobServiceClient as GeneratedBlobServiceClient,
     generated::models::BlobServiceClientGetAccountInfoResult,
     models::{
         BlobServiceClientFindBlobsByTagsOptions, BlobServiceClientGetAccountInfoOptions,
-        BlobServiceClientGetPropertiesOptions, BlobServiceClientListContainersSegmentOptions,
-        BlobServiceClientSetPropertiesOptions, BlobServiceProperties, FilterBlobSegment,
-        ListContainersSegmentResponse,
+        BlobServiceClientGetPropertiesOptions, BlobServiceClientGetUserDelegationKeyOptions,
+        BlobServiceClientListContainersSegmentOptions, BlobServiceClientSetPropertiesOptions,
+        BlobServiceClientSubmitBatchOptions, BlobServiceProperties, FilterBlobSegment, KeyInfo,
+        ListContainersSegmentResponse, UserDelegationKey,
     },
-    pipeline::StorageHeadersPolicy,
-    BlobContainerClient, BlobServiceClientOptions,
+    pipeline::{batch_policies, StorageHeadersPolicy},
+    BlobBatch, BlobContainerClient, BlobServiceClientOptions,
 };
 use azure_core::{
-    credentials::TokenCredential,
+    credentials::{Secret, TokenCredential},
     http::{
         policies::{BearerTokenAuthorizationPolicy, Policy},
         NoFormat, Pager, Pipeline, RequestContent, Response, Url, XmlFormat,
     },
     tracing, Result,
 };
+use azure_storage_common::{
+    sas::{append_sas, AccountSasBuilder},
+    ConnectionString, SharedKeyAuthorizationPolicy, SharedKeyCredential,
+};
 use std::sync::Arc;
 
 /// A client to interact with an Azure storage account.
@@ -51,7 +57,7 @@ impl GeneratedBlobServiceClient {
             .per_call_policies
             .push(storage_headers_policy);
 
-        let per_retry_policies = if let Some(token_credential) = credential {
+        let auth_policy = if let Some(token_credential) = credential {
             if !blob_service_url.scheme().starts_with("https") {
                 return Err(azure_core::Error::with_message(
                     azure_core::error::ErrorKind::Other,
@@ -62,9 +68,9 @@ impl GeneratedBlobServiceClient {
                 token_credential,
                 vec!["https://storage.azure.com/.default"],
             ));
-            vec![auth_policy]
+            Some(auth_policy)
         } else {
-            Vec::default()
+            None
         };
 
         let pipeline = Pipeline::new(
@@ -72,7 +78,46 @@ impl GeneratedBlobServiceClient {
             option_env!("CARGO_PKG_VERSION"),
             options.client_options.clone(),
             Vec::default(),
-            per_retry_policies,
+            batch_policies(auth_policy),
+            None,
+        );
+
+        Ok(Self {
+            endpoint: blob_service_url,
+            version: options.version,
+            pipeline,
+        })
+    }
+
+    /// Creates a new GeneratedBlobServiceClient from the URL of the Azure storage account, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_service_url` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.Service")]
+    pub fn from_url_with_shared_key(
+        blob_service_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobServiceClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            batch_policies(Some(auth_policy)),
             None,
         );
 
@@ -103,6 +148,75 @@ impl BlobServiceClient {
         Ok(Self { client })
     }
 
+    /// Creates a new BlobServiceClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlobServiceClientOptions>,
+    ) -> Result<Self> {
+        let url = Url::parse(endpoint)?;
+
+        let client =
+            GeneratedBlobServiceClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
+    /// Creates a new BlobServiceClient from a storage connection string, authorizing requests with
+    /// its account key or shared access signature, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_string` - The connection string, for example `DefaultEndpointsProtocol=https;AccountName=myaccount;AccountKey=...`,
+    ///   or `UseDevelopmentStorage=true` to connect to Azurite.
+    /// * `options` - Optional configuration for the client.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use azure_core::credentials::Secret;
+    /// use azure_storage_blob::BlobServiceClient;
+    ///
+    /// let client =
+    ///     BlobServiceClient::from_connection_string(Secret::from("UseDevelopmentStorage=true"), None)
+    ///         .unwrap();
+    /// assert_eq!("http://127.0.0.1:10000/devstoreaccount1", client.url().as_str());
+    /// ```
+    pub fn from_connection_string(
+        connection_string: Secret,
+        options: Option<BlobServiceClientOptions>,
+    ) -> Result<Self> {
+        let connection_string = ConnectionString::try_from(&connection_string)?;
+        let endpoint = connection_string.blob_endpoint()?.clone();
+
+        let client = match connection_string {
+            ConnectionString {
+                account_name: Some(account_name),
+                account_key: Some(account_key),
+                ..
+            } => GeneratedBlobServiceClient::from_url_with_shared_key(
+                endpoint,
+                SharedKeyCredential::new(account_name, account_key)?,
+                options,
+            )?,
+            ConnectionString {
+                shared_access_signature: Some(sas),
+                ..
+            } => GeneratedBlobServiceClient::from_url(
+                append_sas(&endpoint, sas.secret()),
+                None,
+                options,
+            )?,
+            _ => GeneratedBlobServiceClient::from_url(endpoint, None, options)?,
+        };
+        Ok(Self { client })
+    }
+
     /// Returns a new instance of BlobContainerClient.
     ///
     /// # Arguments
@@ -208,4 +322,73 @@ impl BlobServiceClient {
     ) -> Result<Response<BlobServiceClientGetAccountInfoResult, NoFormat>> {
         self.client.get_account_info(options).await
     }
+
+    /// Gets a user delegation key, with which to sign user delegation SAS for the account's blobs and containers.
+    /// The client must be authorized with Entra ID.
+    ///
+    /// Convert the returned key into a [`sas::UserDelegationKey`](crate::sas::UserDelegationKey) with `try_into()`
+    /// to pass it to [`BlobClient::generate_user_delegation_sas_url()`](crate::BlobClient::generate_user_delegation_sas_url)
+    /// or [`BlobContainerClient::generate_user_delegation_sas_url()`](crate::BlobContainerClient::generate_user_delegation_sas_url).
+    ///
+    /// # Arguments
+    ///
+    /// * `key_info` - The start and expiry times of the key, in ISO 8601 UTC format.
+    /// * `options` - Optional configuration for the request.
+    pub async fn get_user_delegation_key(
+        &self,
+        key_info: RequestContent<KeyInfo, XmlFormat>,
+        options: Option<BlobServiceClientGetUserDelegationKeyOptions<'_>>,
+    ) -> Result<Response<UserDelegationKey, XmlFormat>> {
+        self.client.get_user_delegation_key(key_info, options).await
+    }
+
+    /// Returns the URL of the Blob service with an account SAS appended, signed with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the account SAS.
+    /// * `credential` - The name and key of the storage account.
+    pub fn generate_account_sas_url(
+        &self,
+        sas: &AccountSasBuilder,
+        credential: &SharedKeyCredential,
+    ) -> Result<Url> {
+        Ok(append_sas(self.url(), &sas.sign(credential)?))
+    }
+
+    /// Submits a batch of up to 256 operations on blobs in any containers of the account with a single request.
+    ///
+    /// Each operation is authorized as if it were sent by itself. Returns the result of each operation, in the order
+    /// they were added to the batch, unless the batch request itself fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The operations.
+    /// * `options` - Optional configuration for the request.
+    pub async fn submit_batch(
+        &self,
+        batch: BlobBatch<'_>,
+        options: Option<BlobServiceClientSubmitBatchOptions<'_>>,
+    ) -> Result<Vec<Result<Response<(), NoFormat>>>> {
+        let options = options.unwrap_or_default();
+        let mut url = self.url().clone();
+        url.query_pairs_mut().append_pair("comp", "batch");
+        if let Some(timeout) = options.timeout {
+            url.query_pairs_mut()
+                .append_pair("timeout", &timeout.to_string());
+        }
+        batch::submit_batch(
+            &self.client.pipeline,
+            &self.client.version,
+            url,
+            batch,
+            |container_name, blob_name| {
+                self.blob_container_client(container_name)
+                    .blob_client(blob_name)
+                    .client
+            },
+            &options.method_options.context,
+        )
+        .await
+    }
 }
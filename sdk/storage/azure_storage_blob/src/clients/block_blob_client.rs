@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 use crate::{
+    checksum,
     generated::clients::BlockBlobClient as GeneratedBlockBlobClient,
     generated::models::{
         BlockBlobClientCommitBlockListResult, BlockBlobClientStageBlockResult,
@@ -9,8 +10,8 @@ use crate::{
     },
     models::{
         BlockBlobClientCommitBlockListOptions, BlockBlobClientGetBlockListOptions,
-        BlockBlobClientStageBlockOptions, BlockBlobClientUploadBlobFromUrlOptions, BlockList,
-        BlockListType, BlockLookupList,
+        BlockBlobClientStageBlockOptions, BlockBlobClientStageBlockResultHeaders,
+        BlockBlobClientUploadBlobFromUrlOptions, BlockList, BlockListType, BlockLookupList,
     },
     pipeline::StorageHeadersPolicy,
     BlockBlobClientOptions,
@@ -23,6 +24,7 @@ use azure_core::{
     },
     tracing, Bytes, Result,
 };
+use azure_storage_common::{ChecksumAlgorithm, SharedKeyAuthorizationPolicy, SharedKeyCredential};
 use std::sync::Arc;
 
 /// A client to interact with a specific Azure storage Block blob, although that blob may not yet exist.
@@ -83,6 +85,45 @@ impl GeneratedBlockBlobClient {
             pipeline,
         })
     }
+
+    /// Creates a new GeneratedBlockBlobClient from a block blob URL, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_url` - The full URL of the block blob, for example `https://myaccount.blob.core.windows.net/mycontainer/myblob`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.BlockBlob")]
+    pub fn from_url_with_shared_key(
+        blob_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlockBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            vec![auth_policy],
+            None,
+        );
+
+        Ok(Self {
+            endpoint: blob_url,
+            version: options.version,
+            pipeline,
+        })
+    }
 }
 
 impl BlockBlobClient {
@@ -118,6 +159,38 @@ impl BlockBlobClient {
         Ok(Self { client })
     }
 
+    /// Creates a new BlockBlobClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `container_name` - The name of the container containing this Block blob.
+    /// * `blob_name` - The name of the Block blob to interact with.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        container_name: &str,
+        blob_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<BlockBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut url = Url::parse(endpoint)?;
+
+        {
+            let mut path_segments = url.path_segments_mut().map_err(|_| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::Other,
+                    "Invalid endpoint URL: Failed to parse out path segments from provided endpoint URL.",
+                )
+            })?;
+            path_segments.extend([container_name, blob_name]);
+        }
+
+        let client = GeneratedBlockBlobClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
     /// Creates a new BlockBlobClient from a Block blob URL.
     ///
     /// # Arguments
@@ -162,7 +235,9 @@ impl BlockBlobClient {
     ///   For a given blob, the `block_id` must be the same size for each block.
     /// * `content_length` - Total length of the blob data to be staged.
     /// * `data` - The content of the block.
-    /// * `options` - Optional configuration for the request.
+    /// * `options` - Optional configuration for the request. [`BlockBlobClientStageBlockOptions::with_validate_content_md5()`]
+    ///   and [`with_validate_content_crc64()`](BlockBlobClientStageBlockOptions::with_validate_content_crc64()) send the
+    ///   block's checksum.
     pub async fn stage_block(
         &self,
         block_id: &[u8],
@@ -170,9 +245,31 @@ impl BlockBlobClient {
         body: RequestContent<Bytes, NoFormat>,
         options: Option<BlockBlobClientStageBlockOptions<'_>>,
     ) -> Result<Response<BlockBlobClientStageBlockResult, NoFormat>> {
-        self.client
-            .stage_block(block_id, content_length, body, options)
+        let mut options = options.unwrap_or_default();
+        let algorithm = checksum::requested_algorithm(&options.method_options.context);
+        let sent = match algorithm {
+            Some(algorithm) => checksum::content_checksum(algorithm, body.body()).await?,
+            None => Vec::new(),
+        };
+        if let Some(algorithm) = algorithm {
+            (
+                options.transactional_content_md5,
+                options.transactional_content_crc64,
+            ) = checksum::transactional_options(algorithm, &sent);
+        }
+        let response = self
+            .client
+            .stage_block(block_id, content_length, body, Some(options))
             .await
+            .map_err(|error| checksum::map_rejection(error, algorithm, &sent))?;
+        if let Some(algorithm) = algorithm {
+            let returned = match algorithm {
+                ChecksumAlgorithm::Crc64 => response.content_crc64()?,
+                _ => response.content_md5()?,
+            };
+            checksum::validate_echo(algorithm, &sent, returned, "the block")?;
+        }
+        Ok(response)
     }
 
     /// Retrieves the list of blocks that have been uploaded as part of a block blob.
@@ -0,0 +1,362 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    download,
+    generated::models::BlockBlobClientCommitBlockListResult,
+    models::{
+        BlobClientDownloadOptions, BlobClientGetPropertiesOptions,
+        BlobClientGetPropertiesResultHeaders, BlobClientUploadFromReaderOptions,
+    },
+    upload, BlobClient,
+};
+use azure_core::{
+    error::{Error, ErrorKind, ResultExt},
+    http::{NoFormat, Response},
+    Bytes, Result,
+};
+use azure_storage_common::encryption::{
+    ClientSideEncryptionOptions, ContentDecryptor, ContentEncryptor, EncryptionData,
+};
+use futures::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    TryStreamExt,
+};
+use std::{
+    io,
+    ops::Range,
+    pin::{pin, Pin},
+    task::{ready, Context, Poll},
+};
+
+/// The metadata in which the envelope of an encrypted blob is stored.
+const ENCRYPTION_DATA_METADATA: &str = "encryptiondata";
+
+/// A client of a blob whose data is encrypted before it's uploaded and decrypted after it's downloaded, created by
+/// [`BlobClient::with_client_side_encryption()`].
+///
+/// Blobs are encrypted in the version 2.0 format of the other Azure Storage SDKs, which can read them, and vice
+/// versa. The envelope of the encryption is stored in the blob's `encryptiondata` metadata, which must be preserved
+/// when the metadata is changed.
+///
+/// Requires the `client_side_encryption` feature.
+pub struct EncryptedBlobClient {
+    pub(super) blob_client: BlobClient,
+    pub(super) options: ClientSideEncryptionOptions,
+}
+
+impl EncryptedBlobClient {
+    /// Returns the client of the blob, which reads and writes its encrypted data as is.
+    pub fn blob_client(&self) -> &BlobClient {
+        &self.blob_client
+    }
+
+    /// Encrypts data of any length, which needn't be known in advance, and uploads it to a new block blob, as
+    /// [`BlobClient::upload_from_reader()`] does.
+    ///
+    /// The data is encrypted with a new content-encryption key, which is wrapped by the key encryption key of the
+    /// client's [`ClientSideEncryptionOptions`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The blob data to encrypt and upload.
+    /// * `overwrite` - Whether the blob to be uploaded should overwrite the current data. If False, the operation will
+    ///   fail with ResourceExistsError.
+    /// * `options` - Optional configuration for the upload.
+    pub async fn upload_from_reader<R>(
+        &self,
+        data: R,
+        overwrite: bool,
+        options: Option<BlobClientUploadFromReaderOptions<'_>>,
+    ) -> Result<Response<BlockBlobClientCommitBlockListResult, NoFormat>>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let key_encryption_key = self.options.key_encryption_key.as_deref().ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Other,
+                "a key encryption key is required to encrypt data",
+            )
+        })?;
+        let encryptor = ContentEncryptor::new(key_encryption_key).await?;
+
+        let mut options = options.unwrap_or_default();
+        if !overwrite {
+            options.upload_options.if_none_match = Some(String::from("*"));
+        }
+        options
+            .upload_options
+            .metadata
+            .get_or_insert_with(Default::default)
+            .insert(
+                ENCRYPTION_DATA_METADATA.to_string(),
+                encryptor.encryption_data().to_json()?,
+            );
+
+        upload::upload(
+            &self.blob_client.block_blob_client().client,
+            EncryptingReader::new(data, encryptor),
+            options,
+        )
+        .await
+    }
+
+    /// Downloads and decrypts the blob, or a range of it, into memory, as
+    /// [`download_to_writer()`](EncryptedBlobClient::download_to_writer) does.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the request.
+    pub async fn download(&self, options: Option<BlobClientDownloadOptions<'_>>) -> Result<Bytes> {
+        let mut data = Vec::new();
+        self.download_to_writer(&mut data, options).await?;
+        Ok(data.into())
+    }
+
+    /// Downloads the blob, or a range of it, into `writer`, decrypting every region as soon as it's received, and
+    /// returns the length of the data written.
+    ///
+    /// The `range` of `options`, such as `bytes=0-1023`, is a range of the decrypted data, of which only the regions
+    /// holding the range are downloaded, in a single request that's resumed if its body breaks. Only one encrypted
+    /// region, of 4 MiB and 28 bytes, is held in memory at a time. The properties of the blob are read first, and the
+    /// data is requested with its ETag, so the download fails if the blob changes meanwhile. A blob without encryption
+    /// data is written as is, unless the client requires encryption.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The [`AsyncWrite`] to write the decrypted data to.
+    /// * `options` - Optional configuration for the request.
+    pub async fn download_to_writer<W>(
+        &self,
+        mut writer: W,
+        options: Option<BlobClientDownloadOptions<'_>>,
+    ) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let options = options.unwrap_or_default();
+        let properties = self
+            .blob_client
+            .get_properties(Some(BlobClientGetPropertiesOptions {
+                encryption_algorithm: options.encryption_algorithm,
+                encryption_key: options.encryption_key.clone(),
+                encryption_key_sha256: options.encryption_key_sha256.clone(),
+                if_match: options.if_match.clone(),
+                if_modified_since: options.if_modified_since,
+                if_none_match: options.if_none_match.clone(),
+                if_tags: options.if_tags.clone(),
+                if_unmodified_since: options.if_unmodified_since,
+                lease_id: options.lease_id.clone(),
+                method_options: options.method_options.clone(),
+                snapshot: options.snapshot.clone(),
+                timeout: options.timeout,
+                version_id: options.version_id.clone(),
+            }))
+            .await?;
+        let size = properties.content_length()?.unwrap_or_default();
+        let encryption_data = properties
+            .metadata()?
+            .remove(ENCRYPTION_DATA_METADATA)
+            .map(|json| EncryptionData::from_json(&json))
+            .transpose()?;
+        if encryption_data.is_none() && self.options.require_encryption.unwrap_or_default() {
+            return Err(Error::with_message(
+                ErrorKind::DataConversion,
+                "the blob isn't encrypted, but the client requires encryption",
+            ));
+        }
+
+        let plaintext_size = match &encryption_data {
+            Some(encryption_data) => encryption_data.plaintext_length(size)?,
+            None => size,
+        };
+        let range = match options.range.as_deref() {
+            Some(range) => parse_range(range, plaintext_size)?,
+            None => 0..plaintext_size,
+        };
+        if range.is_empty() {
+            flush(&mut writer).await?;
+            return Ok(0);
+        }
+        let options = BlobClientDownloadOptions {
+            if_match: properties.etag()?,
+            range: None,
+            range_get_content_crc64: None,
+            range_get_content_md5: None,
+            ..options
+        };
+
+        let Some(encryption_data) = encryption_data else {
+            let mut chunks = pin!(download::stream_range(
+                &self.blob_client.client,
+                options,
+                range.start,
+                range.end - range.start,
+            ));
+            while let Some(chunk) = chunks.try_next().await? {
+                write(&mut writer, &chunk).await?;
+            }
+            flush(&mut writer).await?;
+            return Ok(range.end - range.start);
+        };
+        let decryptor = ContentDecryptor::new(&self.options, &encryption_data).await?;
+        let encrypted_range = encryption_data.encrypted_range(range.clone())?;
+        let encrypted_end = encrypted_range.end.min(size);
+        let mut chunks = pin!(download::stream_range(
+            &self.blob_client.client,
+            options,
+            encrypted_range.start,
+            encrypted_end - encrypted_range.start,
+        ));
+
+        // The plaintext offset of the region being received.
+        let mut offset = encryption_data.plaintext_offset(encrypted_range.start)?;
+        let region_length = decryptor.encrypted_region_length() as usize;
+        let mut region = Vec::with_capacity(region_length);
+        while let Some(mut chunk) = chunks.try_next().await? {
+            while !chunk.is_empty() {
+                let len = chunk.len().min(region_length - region.len());
+                region.extend_from_slice(&chunk.split_to(len));
+                if region.len() == region_length {
+                    offset = write_region(&mut writer, &decryptor, &region, offset, &range).await?;
+                    region.clear();
+                }
+            }
+        }
+        if !region.is_empty() {
+            write_region(&mut writer, &decryptor, &region, offset, &range).await?;
+        }
+        flush(&mut writer).await?;
+        Ok(range.end - range.start)
+    }
+}
+
+impl std::fmt::Debug for EncryptedBlobClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedBlobClient")
+            .field("url", self.blob_client.url())
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+/// Parses a range such as `bytes=0-1023` or `bytes=1024-` of data of `size` bytes, returning the range clamped to the
+/// data.
+fn parse_range(range: &str, size: u64) -> Result<Range<u64>> {
+    let invalid = || Error::with_message(ErrorKind::Other, format!("invalid range {range}"));
+    let (start, end) = range
+        .strip_prefix("bytes=")
+        .and_then(|range| range.split_once('-'))
+        .ok_or_else(invalid)?;
+    let start: u64 = start.parse().map_err(|_| invalid())?;
+    let end = match end {
+        "" => size,
+        end => end
+            .parse::<u64>()
+            .map_err(|_| invalid())?
+            .saturating_add(1)
+            .min(size),
+    };
+    if start > size {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            format!("range {range} starts after the end of the blob"),
+        ));
+    }
+    if start > end {
+        return Err(invalid());
+    }
+    Ok(start..end)
+}
+
+/// Decrypts `region`, whose plaintext starts at `offset`, and writes the part of it in `range`, returning the offset
+/// of the next region.
+async fn write_region<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    decryptor: &ContentDecryptor,
+    region: &[u8],
+    offset: u64,
+    range: &Range<u64>,
+) -> Result<u64> {
+    let plaintext = decryptor.decrypt(region)?;
+    let end = offset + plaintext.len() as u64;
+    let start = range.start.clamp(offset, end) - offset;
+    let stop = range.end.clamp(offset, end) - offset;
+    write(writer, &plaintext[start as usize..stop as usize]).await?;
+    Ok(end)
+}
+
+async fn write<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<()> {
+    writer
+        .write_all(data)
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")
+}
+
+async fn flush<W: AsyncWrite + Unpin>(writer: &mut W) -> Result<()> {
+    writer
+        .flush()
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")
+}
+
+/// Encrypts the data of a reader region by region.
+struct EncryptingReader<R> {
+    inner: R,
+    encryptor: ContentEncryptor,
+    /// The plaintext of the region being read, of which `filled` bytes have been read.
+    plaintext: Vec<u8>,
+    filled: usize,
+    /// The encrypted region being returned, from `position`.
+    encrypted: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl<R> EncryptingReader<R> {
+    fn new(inner: R, encryptor: ContentEncryptor) -> Self {
+        Self {
+            inner,
+            plaintext: vec![0; encryptor.region_length() as usize],
+            encryptor,
+            filled: 0,
+            encrypted: Vec::new(),
+            position: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        while this.position == this.encrypted.len() {
+            if this.done {
+                return Poll::Ready(Ok(0));
+            }
+            let read = ready!(
+                Pin::new(&mut this.inner).poll_read(cx, &mut this.plaintext[this.filled..])
+            )?;
+            this.filled += read;
+            this.done = read == 0;
+            // Encrypt the region once it's full, or what's left of it once the data has ended.
+            if this.filled == this.plaintext.len() || (this.done && this.filled > 0) {
+                this.encrypted = this
+                    .encryptor
+                    .encrypt_region(&this.plaintext[..this.filled])
+                    .map_err(io::Error::other)?;
+                this.position = 0;
+                this.filled = 0;
+            }
+        }
+
+        let len = buf.len().min(this.encrypted.len() - this.position);
+        buf[..len].copy_from_slice(&this.encrypted[this.position..this.position + len]);
+        this.position += len;
+        Poll::Ready(Ok(len))
+    }
+}
@@ -6,15 +6,21 @@
 mod append_blob_client;
 mod blob_client;
 mod blob_container_client;
+mod blob_lease_client;
 mod blob_service_client;
 mod block_blob_client;
+#[cfg(feature = "client_side_encryption")]
+mod encrypted_blob_client;
 mod page_blob_client;
 
 pub use append_blob_client::AppendBlobClient;
 pub use blob_client::BlobClient;
 pub use blob_container_client::BlobContainerClient;
+pub use blob_lease_client::BlobLeaseClient;
 pub use blob_service_client::BlobServiceClient;
 pub use block_blob_client::BlockBlobClient;
+#[cfg(feature = "client_side_encryption")]
+pub use encrypted_blob_client::EncryptedBlobClient;
 pub use page_blob_client::PageBlobClient;
 
 pub use crate::generated::clients::{
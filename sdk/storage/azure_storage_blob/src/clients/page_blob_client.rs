@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 use crate::{
+    checksum,
     generated::clients::PageBlobClient as GeneratedPageBlobClient,
     models::{
         PageBlobClientClearPagesOptions, PageBlobClientClearPagesResult,
@@ -10,7 +11,8 @@ use crate::{
         PageBlobClientResizeResult, PageBlobClientSetSequenceNumberOptions,
         PageBlobClientSetSequenceNumberResult, PageBlobClientUploadPagesFromUrlOptions,
         PageBlobClientUploadPagesFromUrlResult, PageBlobClientUploadPagesOptions,
-        PageBlobClientUploadPagesResult, PageList, SequenceNumberActionType,
+        PageBlobClientUploadPagesResult, PageBlobClientUploadPagesResultHeaders, PageList,
+        SequenceNumberActionType,
     },
     pipeline::StorageHeadersPolicy,
     PageBlobClientOptions,
@@ -23,6 +25,7 @@ use azure_core::{
     },
     tracing, Bytes, Result,
 };
+use azure_storage_common::{ChecksumAlgorithm, SharedKeyAuthorizationPolicy, SharedKeyCredential};
 use std::sync::Arc;
 
 /// A client to interact with a specific Azure storage Page blob, although that blob may not yet exist.
@@ -83,6 +86,45 @@ impl GeneratedPageBlobClient {
             pipeline,
         })
     }
+
+    /// Creates a new GeneratedPageBlobClient from a blob URL, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_url` - The full URL of the Page blob, for example `https://myaccount.blob.core.windows.net/mycontainer/myblob`.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Blob.PageBlob")]
+    pub fn from_url_with_shared_key(
+        blob_url: Url,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<PageBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+
+        let storage_headers_policy = Arc::new(StorageHeadersPolicy);
+        options
+            .client_options
+            .per_call_policies
+            .push(storage_headers_policy);
+
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+
+        let pipeline = Pipeline::new(
+            option_env!("CARGO_PKG_NAME"),
+            option_env!("CARGO_PKG_VERSION"),
+            options.client_options.clone(),
+            Vec::default(),
+            vec![auth_policy],
+            None,
+        );
+
+        Ok(Self {
+            endpoint: blob_url,
+            version: options.version,
+            pipeline,
+        })
+    }
 }
 
 impl PageBlobClient {
@@ -118,6 +160,38 @@ impl PageBlobClient {
         Ok(Self { client })
     }
 
+    /// Creates a new PageBlobClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.blob.core.windows.net/`
+    /// * `container_name` - The name of the container containing this Page blob.
+    /// * `blob_name` - The name of the Page blob to interact with.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        container_name: &str,
+        blob_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<PageBlobClientOptions>,
+    ) -> Result<Self> {
+        let mut url = Url::parse(endpoint)?;
+
+        {
+            let mut path_segments = url.path_segments_mut().map_err(|_| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::Other,
+                    "Invalid endpoint URL: Failed to parse out path segments from provided endpoint URL.",
+                )
+            })?;
+            path_segments.extend([container_name, blob_name]);
+        }
+
+        let client = GeneratedPageBlobClient::from_url_with_shared_key(url, credential, options)?;
+        Ok(Self { client })
+    }
+
     /// Creates a new PageBlobClient from a blob URL.
     ///
     /// # Arguments
@@ -193,7 +267,9 @@ impl PageBlobClient {
     /// * `content_length` - Number of bytes to use for writing to a section of the blob. The
     ///   content_length specified must be a modulus of 512.
     /// * `range` - The range of the bytes to write. See [`format_page_range()`](crate::format_page_range) for help with the expected String format.
-    /// * `options` - Optional configuration for the request.
+    /// * `options` - Optional configuration for the request. [`PageBlobClientUploadPagesOptions::with_validate_content_md5()`]
+    ///   and [`with_validate_content_crc64()`](PageBlobClientUploadPagesOptions::with_validate_content_crc64()) send the
+    ///   pages' checksum.
     pub async fn upload_page(
         &self,
         data: RequestContent<Bytes, NoFormat>,
@@ -201,9 +277,31 @@ impl PageBlobClient {
         range: String,
         options: Option<PageBlobClientUploadPagesOptions<'_>>,
     ) -> Result<Response<PageBlobClientUploadPagesResult, NoFormat>> {
-        self.client
-            .upload_pages(data, content_length, range, options)
+        let mut options = options.unwrap_or_default();
+        let algorithm = checksum::requested_algorithm(&options.method_options.context);
+        let sent = match algorithm {
+            Some(algorithm) => checksum::content_checksum(algorithm, data.body()).await?,
+            None => Vec::new(),
+        };
+        if let Some(algorithm) = algorithm {
+            (
+                options.transactional_content_md5,
+                options.transactional_content_crc64,
+            ) = checksum::transactional_options(algorithm, &sent);
+        }
+        let response = self
+            .client
+            .upload_pages(data, content_length, range, Some(options))
             .await
+            .map_err(|error| checksum::map_rejection(error, algorithm, &sent))?;
+        if let Some(algorithm) = algorithm {
+            let returned = match algorithm {
+                ChecksumAlgorithm::Crc64 => response.content_crc64()?,
+                _ => response.content_md5()?,
+            };
+            checksum::validate_echo(algorithm, &sent, returned, "the pages")?;
+        }
+        Ok(response)
     }
 
     /// Sets the blob's sequence number. The operation will fail if the specified sequence
@@ -0,0 +1,135 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Copies a blob from a URL asynchronously, polling the properties of the destination until the copy completes.
+
+use crate::{
+    generated::clients::BlobClient as GeneratedBlobClient,
+    models::{
+        BlobClientBeginCopyFromUrlOptions, BlobClientGetPropertiesOptions,
+        BlobClientGetPropertiesResultHeaders, BlobClientStartCopyFromUrlOptions,
+        BlobClientStartCopyFromUrlResultHeaders, BlobCopyOperation,
+    },
+};
+use azure_core::{
+    error::{Error, ErrorKind},
+    http::{
+        headers::RETRY_AFTER,
+        poller::{
+            get_retry_after, Poller, PollerResult, PollerState, PollerStatus, StatusMonitor as _,
+        },
+        ClientMethodOptions, RawResponse, Url,
+    },
+    json,
+};
+use std::sync::Arc;
+
+/// Starts copying `copy_source` to the blob of `client` and returns a [`Poller`] that completes with the properties of
+/// the blob once the copy succeeds.
+///
+/// The first status is that returned by the `Copy Blob` request. Each following status is read from the
+/// `x-ms-copy-*` properties of the blob, and is an error if another copy has since replaced this one.
+pub(crate) fn begin_copy_from_url(
+    client: GeneratedBlobClient,
+    copy_source: Url,
+    options: BlobClientBeginCopyFromUrlOptions<'static>,
+) -> Poller<BlobCopyOperation> {
+    let client = Arc::new(client);
+    let copy_options = options.copy_options;
+    Poller::from_callback(
+        move |state: PollerState<String>, poller_options| {
+            let client = client.clone();
+            let copy_source = copy_source.clone();
+            let copy_options = copy_options.clone();
+            async move {
+                let method_options = ClientMethodOptions {
+                    context: poller_options.context.clone(),
+                };
+                // The final properties, if they were just read, are the output of a successful copy.
+                let (operation, response, properties) = match state {
+                    PollerState::Initial => {
+                        let response = client
+                            .start_copy_from_url(
+                                copy_source.to_string(),
+                                Some(BlobClientStartCopyFromUrlOptions {
+                                    method_options,
+                                    ..copy_options
+                                }),
+                            )
+                            .await?;
+                        let operation = BlobCopyOperation {
+                            copy_id: response.copy_id()?,
+                            copy_status: response.copy_status()?,
+                            ..Default::default()
+                        };
+                        (operation, RawResponse::from(response), None)
+                    }
+                    PollerState::More(copy_id) => {
+                        let response = client
+                            .get_properties(Some(BlobClientGetPropertiesOptions {
+                                method_options,
+                                ..Default::default()
+                            }))
+                            .await?;
+                        let operation = BlobCopyOperation {
+                            copy_id: response.copy_id()?,
+                            copy_status: response.copy_status()?,
+                            copy_status_description: response.copy_status_description()?,
+                            ..Default::default()
+                        }
+                        .with_copy_progress(response.copy_progress()?.as_deref())?;
+                        if operation.copy_id.as_deref() != Some(copy_id.as_str()) {
+                            return Err(Error::with_message(
+                                ErrorKind::Other,
+                                format!(
+                                    "copy {copy_id} was replaced by copy {}",
+                                    operation.copy_id.as_deref().unwrap_or("<none>")
+                                ),
+                            ));
+                        }
+                        (operation, response.to_raw_response(), Some(response))
+                    }
+                };
+
+                let (status, headers, _) = response.deconstruct();
+                let retry_after = get_retry_after(&headers, &[RETRY_AFTER], &poller_options);
+                let rsp =
+                    RawResponse::from_bytes(status, headers, json::to_json(&operation)?).into();
+                Ok(match operation.status() {
+                    PollerStatus::InProgress => PollerResult::InProgress {
+                        response: rsp,
+                        retry_after,
+                        next: operation.copy_id.ok_or_else(|| {
+                            Error::with_message(
+                                ErrorKind::DataConversion,
+                                "response has no copy ID",
+                            )
+                        })?,
+                    },
+                    PollerStatus::Succeeded => PollerResult::Succeeded {
+                        response: rsp,
+                        target: Box::new(move || {
+                            Box::pin(async move {
+                                match properties {
+                                    Some(properties) => Ok(properties),
+                                    None => {
+                                        client
+                                            .get_properties(Some(BlobClientGetPropertiesOptions {
+                                                method_options: ClientMethodOptions {
+                                                    context: poller_options.context.clone(),
+                                                },
+                                                ..Default::default()
+                                            }))
+                                            .await
+                                    }
+                                }
+                            })
+                        }),
+                    },
+                    _ => PollerResult::Done { response: rsp },
+                })
+            }
+        },
+        Some(options.method_options),
+    )
+}
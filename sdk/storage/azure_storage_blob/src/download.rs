@@ -0,0 +1,384 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Downloads a blob in ranges, concurrently, resuming any range whose response body breaks.
+
+use crate::{
+    checksum,
+    generated::clients::BlobClient as GeneratedBlobClient,
+    models::{
+        BlobClientDownloadOptions, BlobClientDownloadResult, BlobClientDownloadResultHeaders,
+        BlobClientDownloadToOptions,
+    },
+};
+use azure_core::{
+    error::{Error, ErrorKind, ResultExt},
+    http::{response::AsyncResponseBody, AsyncRawResponse, AsyncResponse, StatusCode},
+    Bytes, Result,
+};
+use azure_storage_common::ChecksumAlgorithm;
+use futures::{
+    io::{AsyncWrite, AsyncWriteExt},
+    Future, Stream, StreamExt, TryStreamExt,
+};
+
+const MIB: u64 = 1024 * 1024;
+const DEFAULT_RANGE_SIZE: u64 = 4 * MIB;
+/// The largest range whose checksum the service returns.
+pub(crate) const MAX_VALIDATED_RANGE_SIZE: u64 = 4 * MIB;
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+const MAX_RESUMES: usize = 5;
+
+/// Returns the checksum the service returned for a range.
+fn expected_checksum(
+    algorithm: ChecksumAlgorithm,
+    response: &AsyncResponse<BlobClientDownloadResult>,
+) -> Result<Option<Vec<u8>>> {
+    match algorithm {
+        ChecksumAlgorithm::Crc64 => response.content_crc64(),
+        _ => response.content_md5(),
+    }
+}
+
+/// Reads the body of `response`, the response to a request for a range and its checksum, and validates it.
+pub(crate) async fn validate_range(
+    algorithm: ChecksumAlgorithm,
+    response: AsyncResponse<BlobClientDownloadResult>,
+) -> Result<AsyncResponse<BlobClientDownloadResult>> {
+    let expected = expected_checksum(algorithm, &response)?;
+    let (status, headers, body) = response.deconstruct();
+    let data = body.collect().await?;
+    checksum::validate(algorithm, expected, &data, "the range")?;
+    Ok(AsyncRawResponse::from_bytes(status, headers, data).into())
+}
+
+/// A download whose first range has been received, pinned to the ETag of its response.
+struct Download<'a> {
+    client: &'a GeneratedBlobClient,
+    options: BlobClientDownloadOptions<'a>,
+    range_size: u64,
+    max_concurrency: usize,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    size: u64,
+    first: Bytes,
+}
+
+impl<'a> Download<'a> {
+    /// Requests the first range, which returns the blob's size and ETag.
+    async fn start(
+        client: &'a GeneratedBlobClient,
+        options: BlobClientDownloadToOptions<'a>,
+    ) -> Result<Self> {
+        let range_size = options.range_size.unwrap_or(DEFAULT_RANGE_SIZE);
+        if range_size == 0 {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "range_size must be at least 1",
+            ));
+        }
+        let max_concurrency = options.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY);
+        if max_concurrency == 0 {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "max_concurrency must be at least 1",
+            ));
+        }
+        let checksum_algorithm = checksum::checksum_algorithm(
+            options.validate_content_md5,
+            options.validate_content_crc64,
+        )?;
+        if checksum_algorithm.is_some() && range_size > MAX_VALIDATED_RANGE_SIZE {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!("range_size must be at most {MAX_VALIDATED_RANGE_SIZE} bytes to validate ranges"),
+            ));
+        }
+
+        let mut download = Self {
+            client,
+            options: BlobClientDownloadOptions {
+                range: None,
+                range_get_content_crc64: None,
+                range_get_content_md5: None,
+                ..options.download_options
+            },
+            range_size,
+            max_concurrency,
+            checksum_algorithm,
+            size: 0,
+            first: Bytes::new(),
+        };
+
+        let response = match client
+            .download(Some(download.range_options(0, range_size)))
+            .await
+        {
+            Ok(response) => response,
+            // The service can't satisfy any range of an empty blob.
+            Err(error) if error.http_status() == Some(StatusCode::RequestedRangeNotSatisfiable) => {
+                return Ok(download);
+            }
+            Err(error) => return Err(error),
+        };
+        download.size = match response.content_range()? {
+            Some(content_range) => content_range
+                .rsplit_once('/')
+                .and_then(|(_, size)| size.parse().ok())
+                .ok_or_else(|| {
+                    Error::with_message(
+                        ErrorKind::DataConversion,
+                        format!("invalid Content-Range {content_range}"),
+                    )
+                })?,
+            None => response.content_length()?.unwrap_or_default(),
+        };
+        // Pin the remaining ranges to this version of the blob so that a concurrent change fails the download.
+        download.options.if_match = Some(response.etag()?.ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, "response has no ETag")
+        })?);
+        download.first = download
+            .read_range(0, range_size.min(download.size), Some(response))
+            .await?;
+        Ok(download)
+    }
+
+    /// Returns the requests for the ranges following the first, in order.
+    fn ranges(&self) -> impl Iterator<Item = impl Future<Output = Result<(u64, Bytes)>> + '_> {
+        (self.first.len() as u64..self.size)
+            .step_by(self.range_size as usize)
+            .map(move |offset| async move {
+                let len = self.range_size.min(self.size - offset);
+                Ok((offset, self.read_range(offset, len, None).await?))
+            })
+    }
+
+    fn range_options(&self, offset: u64, len: u64) -> BlobClientDownloadOptions<'a> {
+        BlobClientDownloadOptions {
+            range: Some(format!("bytes={}-{}", offset, offset + len - 1)),
+            range_get_content_crc64: (self.checksum_algorithm == Some(ChecksumAlgorithm::Crc64))
+                .then_some(true),
+            range_get_content_md5: (self.checksum_algorithm == Some(ChecksumAlgorithm::Md5))
+                .then_some(true),
+            ..self.options.clone()
+        }
+    }
+
+    /// Reads `len` bytes at `offset`, starting with `response` if it has already been requested.
+    ///
+    /// If a response body breaks, the rest of the range is requested again from the last byte received. A partial
+    /// response can't be validated, however, so when validating, the whole response is requested again.
+    async fn read_range(
+        &self,
+        offset: u64,
+        len: u64,
+        mut response: Option<AsyncResponse<BlobClientDownloadResult>>,
+    ) -> Result<Bytes> {
+        let mut data = Vec::with_capacity(len as usize);
+        let mut resumes = 0;
+        loop {
+            let received = data.len();
+            let response = match response.take() {
+                Some(response) => response,
+                None => {
+                    let start = offset + received as u64;
+                    self.client
+                        .download(Some(self.range_options(start, len - received as u64)))
+                        .await?
+                }
+            };
+            let expected = match self.checksum_algorithm {
+                Some(algorithm) => expected_checksum(algorithm, &response)?,
+                None => None,
+            };
+
+            let mut body = response.into_body();
+            let error = loop {
+                match body.try_next().await {
+                    Ok(Some(chunk)) => data.extend_from_slice(&chunk),
+                    Ok(None) => break None,
+                    Err(error) => break Some(error),
+                }
+            };
+            if data.len() as u64 > len {
+                return Err(Error::with_message(
+                    ErrorKind::DataConversion,
+                    format!("received more than the {len} bytes requested at offset {offset}"),
+                ));
+            }
+            let error = match error {
+                None if data.len() as u64 == len => {
+                    if let Some(algorithm) = self.checksum_algorithm {
+                        checksum::validate(
+                            algorithm,
+                            expected,
+                            &data[received..],
+                            format_args!("the range at offset {offset}"),
+                        )?;
+                    }
+                    return Ok(data.into());
+                }
+                None => Error::with_message(
+                    ErrorKind::Io,
+                    format!("response ended before the {len} bytes requested at offset {offset}"),
+                ),
+                Some(error) => error,
+            };
+
+            resumes += 1;
+            if resumes > MAX_RESUMES {
+                return Err(error);
+            }
+            if self.checksum_algorithm.is_some() {
+                data.truncate(received);
+            }
+        }
+    }
+}
+
+/// Reads `len` bytes at `offset` with `options`, resuming the response if its body breaks.
+pub(crate) async fn download_range(
+    client: &GeneratedBlobClient,
+    options: BlobClientDownloadOptions<'_>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    offset: u64,
+    len: u64,
+) -> Result<Bytes> {
+    let download = Download {
+        client,
+        options,
+        range_size: len,
+        max_concurrency: 1,
+        checksum_algorithm,
+        size: offset + len,
+        first: Bytes::new(),
+    };
+    download.read_range(offset, len, None).await
+}
+
+/// Streams the `len` bytes at `offset` with `options` as they're received, resuming the response if its body breaks.
+pub(crate) fn stream_range<'a>(
+    client: &'a GeneratedBlobClient,
+    options: BlobClientDownloadOptions<'a>,
+    offset: u64,
+    len: u64,
+) -> impl Stream<Item = Result<Bytes>> + 'a {
+    struct State<'a> {
+        client: &'a GeneratedBlobClient,
+        options: BlobClientDownloadOptions<'a>,
+        body: Option<AsyncResponseBody>,
+        received: u64,
+        resumes: usize,
+    }
+
+    let state = State {
+        client,
+        options,
+        body: None,
+        received: 0,
+        resumes: 0,
+    };
+    futures::stream::try_unfold(state, move |mut state| async move {
+        loop {
+            if state.received == len {
+                return Ok(None);
+            }
+            let body = match &mut state.body {
+                Some(body) => body,
+                None => {
+                    let start = offset + state.received;
+                    let response = state
+                        .client
+                        .download(Some(BlobClientDownloadOptions {
+                            range: Some(format!("bytes={}-{}", start, offset + len - 1)),
+                            ..state.options.clone()
+                        }))
+                        .await?;
+                    state.body.insert(response.into_body())
+                }
+            };
+            let error = match body.try_next().await {
+                Ok(Some(chunk)) if state.received + chunk.len() as u64 > len => {
+                    return Err(Error::with_message(
+                        ErrorKind::DataConversion,
+                        format!("received more than the {len} bytes requested at offset {offset}"),
+                    ));
+                }
+                Ok(Some(chunk)) => {
+                    state.received += chunk.len() as u64;
+                    return Ok(Some((chunk, state)));
+                }
+                Ok(None) => Error::with_message(
+                    ErrorKind::Io,
+                    format!("response ended before the {len} bytes requested at offset {offset}"),
+                ),
+                Err(error) => error,
+            };
+
+            state.body = None;
+            state.resumes += 1;
+            if state.resumes > MAX_RESUMES {
+                return Err(error);
+            }
+        }
+    })
+}
+
+/// Downloads the blob into `writer` in order, requesting up to `max_concurrency` ranges at a time, and returns its
+/// size.
+pub(crate) async fn download_to_writer<W>(
+    client: &GeneratedBlobClient,
+    mut writer: W,
+    options: BlobClientDownloadToOptions<'_>,
+) -> Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    let download = Download::start(client, options).await?;
+    write(&mut writer, &download.first).await?;
+    let mut ranges = futures::stream::iter(download.ranges()).buffered(download.max_concurrency);
+    while let Some((_, data)) = ranges.try_next().await? {
+        write(&mut writer, &data).await?;
+    }
+    writer
+        .flush()
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")?;
+    Ok(download.size)
+}
+
+async fn write<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<()> {
+    writer
+        .write_all(data)
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")
+}
+
+/// Downloads the blob into `file`, writing each range at its offset as soon as it's received, and returns its size.
+#[cfg(feature = "tokio")]
+pub(crate) async fn download_to_file(
+    client: &GeneratedBlobClient,
+    file: &mut tokio::fs::File,
+    options: BlobClientDownloadToOptions<'_>,
+) -> Result<u64> {
+    use std::io::SeekFrom;
+    use tokio::io::{AsyncSeekExt as _, AsyncWriteExt as _};
+
+    let download = Download::start(client, options).await?;
+    file.write_all(&download.first)
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")?;
+    let mut ranges =
+        futures::stream::iter(download.ranges()).buffer_unordered(download.max_concurrency);
+    while let Some((offset, data)) = ranges.try_next().await? {
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .with_context(ErrorKind::Io, "failed to write download data")?;
+        file.write_all(&data)
+            .await
+            .with_context(ErrorKind::Io, "failed to write download data")?;
+    }
+    file.flush()
+        .await
+        .with_context(ErrorKind::Io, "failed to write download data")?;
+    Ok(download.size)
+}
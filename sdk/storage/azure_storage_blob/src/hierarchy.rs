@@ -0,0 +1,191 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Lists the blobs of a container by virtual directory, one directory or a whole tree at a time.
+
+use crate::{
+    generated::clients::BlobContainerClient as GeneratedBlobContainerClient,
+    models::{
+        BlobContainerClientListBlobHierarchySegmentOptions, BlobContainerClientWalkBlobsOptions,
+        ListBlobsHierarchyPage, ListBlobsHierarchySegmentResponse,
+    },
+};
+use azure_core::{
+    error::{CheckSuccessOptions, Error, ErrorKind},
+    http::{
+        headers::VERSION,
+        pager::{PagerResult, PagerState},
+        Method, Pager, PipelineSendOptions, RawResponse, Request, Response, XmlFormat,
+    },
+    xml, Result,
+};
+use futures::{future::BoxFuture, stream::FuturesUnordered, TryStreamExt};
+use std::{collections::VecDeque, sync::Arc};
+
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+type Listing = (
+    Response<ListBlobsHierarchyPage, XmlFormat>,
+    ListBlobsHierarchySegmentResponse,
+);
+
+/// The request for a page of a virtual directory, which returns the directory's prefix with the page.
+type ListingFuture = BoxFuture<'static, Result<(Option<String>, Listing)>>;
+
+/// Lists the blobs and virtual directories directly under the prefix of `options`, continuing from its marker.
+pub(crate) fn list_blobs_by_hierarchy(
+    client: Arc<GeneratedBlobContainerClient>,
+    delimiter: String,
+    options: BlobContainerClientListBlobHierarchySegmentOptions<'static>,
+) -> Pager<ListBlobsHierarchyPage, XmlFormat> {
+    let pager_options = options.method_options.clone();
+    Pager::from_callback(
+        move |marker: PagerState<String>, ctx| {
+            let client = client.clone();
+            let delimiter = delimiter.clone();
+            let mut options = options.clone();
+            if let PagerState::More(marker) = marker {
+                options.marker = Some(marker);
+            }
+            options.method_options.context = ctx;
+            async move {
+                let (response, listing) = list_page(&client, &delimiter, options).await?;
+                Ok(match listing.next_marker {
+                    Some(next_marker) if !next_marker.is_empty() => PagerResult::More {
+                        response,
+                        continuation: next_marker,
+                    },
+                    _ => PagerResult::Done { response },
+                })
+            }
+        },
+        Some(pager_options),
+    )
+}
+
+/// Lists the blobs and virtual directories under the prefix of `options`, descending into each virtual directory.
+///
+/// Up to `max_concurrency` pages are requested at a time, from any of the directories found so far, and each page is
+/// yielded as soon as it's received.
+pub(crate) fn walk_blobs(
+    client: Arc<GeneratedBlobContainerClient>,
+    delimiter: String,
+    options: BlobContainerClientWalkBlobsOptions<'_>,
+) -> Result<Pager<ListBlobsHierarchyPage, XmlFormat>> {
+    let max_concurrency = options.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY);
+    if max_concurrency == 0 {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            "max_concurrency must be at least 1",
+        ));
+    }
+    let options = BlobContainerClientListBlobHierarchySegmentOptions {
+        marker: None,
+        ..options.list_options
+    }
+    .into_owned();
+
+    // The directories, with the marker of their next page, that are yet to be requested.
+    let pending = VecDeque::from([(options.prefix.clone(), None)]);
+    let listing: FuturesUnordered<ListingFuture> = FuturesUnordered::new();
+    let stream =
+        futures::stream::try_unfold((pending, listing), move |(mut pending, mut listing)| {
+            while listing.len() < max_concurrency {
+                let Some((prefix, marker)) = pending.pop_front() else {
+                    break;
+                };
+                let client = client.clone();
+                let delimiter = delimiter.clone();
+                let options = BlobContainerClientListBlobHierarchySegmentOptions {
+                    prefix: prefix.clone(),
+                    marker,
+                    ..options.clone()
+                };
+                listing.push(Box::pin(async move {
+                    Ok((prefix, list_page(&client, &delimiter, options).await?))
+                }));
+            }
+            async move {
+                let Some((prefix, (response, page))) = listing.try_next().await? else {
+                    return Ok(None);
+                };
+                if let Some(next_marker) = page.next_marker.filter(|marker| !marker.is_empty()) {
+                    pending.push_front((prefix, Some(next_marker)));
+                }
+                pending.extend(
+                    page.segment
+                        .blob_prefixes
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|prefix| prefix.name?.content)
+                        .map(|prefix| (Some(prefix), None)),
+                );
+                Ok(Some((response, (pending, listing))))
+            }
+        });
+    Ok(Pager::from_stream(stream))
+}
+
+/// Requests a single page of the hierarchical listing described by `options`.
+///
+/// The generated pager can't deserialize a page in which blobs and virtual directories are interleaved, as the
+/// service returns them, so this sends the request itself.
+async fn list_page(
+    client: &GeneratedBlobContainerClient,
+    delimiter: &str,
+    options: BlobContainerClientListBlobHierarchySegmentOptions<'static>,
+) -> Result<Listing> {
+    let mut url = client.endpoint.clone();
+    url.query_pairs_mut()
+        .append_pair("comp", "list")
+        .append_key_only("hierarchy")
+        .append_pair("restype", "container")
+        .append_pair("delimiter", delimiter);
+    if let Some(include) = &options.include {
+        url.query_pairs_mut().append_pair(
+            "include",
+            &include
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+    }
+    if let Some(marker) = &options.marker {
+        url.query_pairs_mut().append_pair("marker", marker);
+    }
+    if let Some(maxresults) = options.maxresults {
+        url.query_pairs_mut()
+            .append_pair("maxresults", &maxresults.to_string());
+    }
+    if let Some(prefix) = &options.prefix {
+        url.query_pairs_mut().append_pair("prefix", prefix);
+    }
+    if let Some(timeout) = options.timeout {
+        url.query_pairs_mut()
+            .append_pair("timeout", &timeout.to_string());
+    }
+    let mut request = Request::new(url, Method::Get);
+    request.insert_header("accept", "application/xml");
+    request.insert_header("content-type", "application/xml");
+    request.insert_header(VERSION, client.version.clone());
+    let response = client
+        .pipeline
+        .send(
+            &options.method_options.context,
+            &mut request,
+            Some(PipelineSendOptions {
+                check_success: CheckSuccessOptions {
+                    success_codes: &[200],
+                },
+                ..Default::default()
+            }),
+        )
+        .await?;
+    let (status, headers, body) = response.deconstruct();
+    let page: ListBlobsHierarchyPage = xml::from_xml(&body)?;
+    Ok((
+        RawResponse::from_bytes(status, headers, body).into(),
+        page.response,
+    ))
+}
@@ -7,11 +7,29 @@
 #![allow(dead_code)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+mod avro;
+mod batch;
+mod checksum;
 pub mod clients;
+mod copy;
+mod download;
 #[allow(unused_imports)]
 mod generated;
+mod hierarchy;
 mod parsers;
 mod pipeline;
+mod query;
+mod reader;
+mod upload;
+mod writer;
+#[cfg(feature = "client_side_encryption")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client_side_encryption")))]
+pub use azure_storage_common::encryption;
+pub use azure_storage_common::{sas, SharedKeyCredential};
+pub use batch::BlobBatch;
 pub use clients::*;
 pub use parsers::*;
+pub use query::BlobQueryReader;
+pub use reader::BlobReader;
+pub use writer::AppendBlobWriter;
 pub mod models;
@@ -0,0 +1,67 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::models::{BlobClientGetPropertiesResult, CopyStatus};
+use azure_core::{
+    error::{Error, ErrorKind},
+    fmt::SafeDebug,
+    http::{
+        poller::{PollerStatus, StatusMonitor},
+        NoFormat,
+    },
+    Result,
+};
+use serde::{Deserialize, Serialize};
+
+/// The status of a copy started by `BlobClient::begin_copy_from_url()`, as reported by the destination blob.
+#[derive(Clone, Default, Deserialize, SafeDebug, Serialize)]
+#[non_exhaustive]
+pub struct BlobCopyOperation {
+    /// The number of bytes copied so far.
+    pub bytes_copied: Option<u64>,
+
+    /// The ID of the copy, which `BlobClient::abort_copy_from_url()` takes to cancel it.
+    pub copy_id: Option<String>,
+
+    /// The state of the copy.
+    pub copy_status: Option<CopyStatus>,
+
+    /// The cause of the last fatal or non-fatal failure of the copy.
+    pub copy_status_description: Option<String>,
+
+    /// The number of bytes to copy, which is the size of the source blob.
+    pub total_bytes: Option<u64>,
+}
+
+impl BlobCopyOperation {
+    /// Parses the `x-ms-copy-progress` header, which has the form `<bytes copied>/<total bytes>`.
+    pub(crate) fn with_copy_progress(mut self, copy_progress: Option<&str>) -> Result<Self> {
+        if let Some(copy_progress) = copy_progress {
+            let (copied, total) = copy_progress
+                .split_once('/')
+                .and_then(|(copied, total)| Some((copied.parse().ok()?, total.parse().ok()?)))
+                .ok_or_else(|| {
+                    Error::with_message(
+                        ErrorKind::DataConversion,
+                        format!("invalid x-ms-copy-progress {copy_progress}"),
+                    )
+                })?;
+            self.bytes_copied = Some(copied);
+            self.total_bytes = Some(total);
+        }
+        Ok(self)
+    }
+}
+
+impl StatusMonitor for BlobCopyOperation {
+    type Output = BlobClientGetPropertiesResult;
+    type Format = NoFormat;
+    fn status(&self) -> PollerStatus {
+        match self.copy_status {
+            Some(CopyStatus::Success) => PollerStatus::Succeeded,
+            Some(CopyStatus::Failed) => PollerStatus::Failed,
+            Some(CopyStatus::Aborted) => PollerStatus::Canceled,
+            _ => PollerStatus::InProgress,
+        }
+    }
+}
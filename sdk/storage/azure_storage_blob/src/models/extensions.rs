@@ -1,10 +1,20 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use crate::models::{
-    AppendBlobClientCreateOptions, BlobTag, BlobTags, BlockBlobClientUploadBlobFromUrlOptions,
-    BlockBlobClientUploadOptions, PageBlobClientCreateOptions,
+use crate::{
+    checksum::ContentValidation,
+    models::{
+        AppendBlobClientAppendBlockOptions, AppendBlobClientCreateOptions, ArrowConfiguration,
+        ArrowField, BlobClientDownloadOptions, BlobContainerClientListBlobFlatSegmentOptions,
+        BlobContainerClientListBlobHierarchySegmentOptions, BlobTag, BlobTags,
+        BlockBlobClientStageBlockOptions, BlockBlobClientUploadBlobFromUrlOptions,
+        BlockBlobClientUploadOptions, DelimitedTextConfiguration, JsonTextConfiguration,
+        ListBlobsIncludeItem, PageBlobClientCreateOptions, PageBlobClientUploadPagesOptions,
+        ParquetConfiguration, QueryFormat, QueryType, UserDelegationKey,
+    },
 };
+use azure_core::{base64, credentials::Secret, http::ClientMethodOptions};
+use azure_storage_common::{sas, ChecksumAlgorithm};
 use std::collections::HashMap;
 
 /// Augments the current options bag to only create if the Page blob does not already exist.
@@ -66,6 +76,181 @@ impl BlockBlobClientUploadOptions<'_> {
     }
 }
 
+/// Implements `with_validate_content_md5()` and `with_validate_content_crc64()` for the options of a request that
+/// sends content, which is sent with its transactional checksum.
+macro_rules! impl_validate_content {
+    ($($options:ident: $method:literal),* $(,)?) => {$(
+        impl $options<'_> {
+            #[doc = concat!("Augments the current options bag to send the MD5 hash of the content, computed by `", $method, "()`, which the")]
+            /// service validates. A mismatch returns an error whose source is an
+            /// [`azure_storage_common::ChecksumMismatchError`]. A stream is read twice to compute the hash.
+            ///
+            /// This replaces `transactional_content_md5` and `transactional_content_crc64`.
+            pub fn with_validate_content_md5(self) -> Self {
+                Self {
+                    method_options: validate_content(self.method_options, ChecksumAlgorithm::Md5),
+                    ..self
+                }
+            }
+
+            #[doc = concat!("Augments the current options bag to send the CRC-64 of the content, computed by `", $method, "()`, which the service")]
+            /// validates. A mismatch returns an error whose source is an
+            /// [`azure_storage_common::ChecksumMismatchError`]. A stream is read twice to compute the CRC-64.
+            ///
+            /// This replaces `transactional_content_md5` and `transactional_content_crc64`.
+            pub fn with_validate_content_crc64(self) -> Self {
+                Self {
+                    method_options: validate_content(self.method_options, ChecksumAlgorithm::Crc64),
+                    ..self
+                }
+            }
+        }
+    )*};
+}
+
+impl_validate_content!(
+    AppendBlobClientAppendBlockOptions: "AppendBlobClient::append_block",
+    BlockBlobClientStageBlockOptions: "BlockBlobClient::stage_block",
+    BlockBlobClientUploadOptions: "BlobClient::upload",
+    PageBlobClientUploadPagesOptions: "PageBlobClient::upload_page",
+);
+
+impl BlobClientDownloadOptions<'_> {
+    /// Augments the current options bag to request the MD5 hash of the range, which `BlobClient::download()` validates
+    /// once it has read the body. The range must be at most 4 MiB.
+    ///
+    /// A mismatch returns an error whose source is an [`azure_storage_common::ChecksumMismatchError`].
+    pub fn with_validate_content_md5(self) -> Self {
+        Self {
+            method_options: validate_content(self.method_options, ChecksumAlgorithm::Md5),
+            range_get_content_crc64: None,
+            range_get_content_md5: Some(true),
+            ..self
+        }
+    }
+
+    /// Augments the current options bag to request the CRC-64 of the range, which `BlobClient::download()` validates
+    /// once it has read the body. The range must be at most 4 MiB.
+    ///
+    /// A mismatch returns an error whose source is an [`azure_storage_common::ChecksumMismatchError`].
+    pub fn with_validate_content_crc64(self) -> Self {
+        Self {
+            method_options: validate_content(self.method_options, ChecksumAlgorithm::Crc64),
+            range_get_content_crc64: Some(true),
+            range_get_content_md5: None,
+            ..self
+        }
+    }
+}
+
+/// Marks `method_options` so that the content of their request is validated with `algorithm`.
+fn validate_content(
+    method_options: ClientMethodOptions<'_>,
+    algorithm: ChecksumAlgorithm,
+) -> ClientMethodOptions<'_> {
+    ClientMethodOptions {
+        context: method_options
+            .context
+            .with_value(ContentValidation(algorithm)),
+    }
+}
+
+/// Augments the current options bag to also list the given items, such as versions, snapshots or soft-deleted blobs.
+/// # Arguments
+///
+/// * `self` - The options bag to be modified.
+/// * `include` - The items to include, in addition to those already included.
+impl BlobContainerClientListBlobFlatSegmentOptions<'_> {
+    pub fn with_include(self, include: impl IntoIterator<Item = ListBlobsIncludeItem>) -> Self {
+        Self {
+            include: Some(merge_include(self.include, include)),
+            ..self
+        }
+    }
+}
+
+/// Augments the current options bag to also list the given items, such as versions, snapshots or soft-deleted blobs.
+/// # Arguments
+///
+/// * `self` - The options bag to be modified.
+/// * `include` - The items to include, in addition to those already included.
+impl BlobContainerClientListBlobHierarchySegmentOptions<'_> {
+    pub fn with_include(self, include: impl IntoIterator<Item = ListBlobsIncludeItem>) -> Self {
+        Self {
+            include: Some(merge_include(self.include, include)),
+            ..self
+        }
+    }
+}
+
+fn merge_include(
+    included: Option<Vec<ListBlobsIncludeItem>>,
+    include: impl IntoIterator<Item = ListBlobsIncludeItem>,
+) -> Vec<ListBlobsIncludeItem> {
+    let mut included = included.unwrap_or_default();
+    for item in include {
+        if !included.contains(&item) {
+            included.push(item);
+        }
+    }
+    included
+}
+
+/// Constructors of the input and output formats of `BlobClient::query()`.
+impl QueryFormat {
+    /// Returns the format of comma-separated values with records separated by newlines, fields optionally quoted with
+    /// `"` and, if `headers_present`, a first record holding the column names.
+    pub fn csv(headers_present: bool) -> Self {
+        Self::delimited(DelimitedTextConfiguration {
+            column_separator: Some(",".into()),
+            escape_char: Some(String::new()),
+            field_quote: Some("\"".into()),
+            headers_present: Some(headers_present),
+            record_separator: Some("\n".into()),
+        })
+    }
+
+    /// Returns the format of delimited text, such as tab-separated values.
+    pub fn delimited(configuration: DelimitedTextConfiguration) -> Self {
+        Self {
+            delimited_text_configuration: Some(configuration),
+            type_prop: Some(QueryType::Delimited),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the format of JSON objects separated by `record_separator`, such as `\n`.
+    pub fn json(record_separator: &str) -> Self {
+        Self {
+            json_text_configuration: Some(JsonTextConfiguration {
+                record_separator: Some(record_separator.into()),
+            }),
+            type_prop: Some(QueryType::Json),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the Parquet format, which is only supported as the input of a query.
+    pub fn parquet() -> Self {
+        Self {
+            parquet_text_configuration: Some(ParquetConfiguration::default()),
+            type_prop: Some(QueryType::Parquet),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the Apache Arrow format with the given schema, which is only supported as the output of a query.
+    pub fn arrow(schema: Vec<ArrowField>) -> Self {
+        Self {
+            arrow_configuration: Some(ArrowConfiguration {
+                schema: Some(schema),
+            }),
+            type_prop: Some(QueryType::Arrow),
+            ..Default::default()
+        }
+    }
+}
+
 /// Converts a `BlobTags` struct into `HashMap<String, String>`.
 impl TryFrom<BlobTags> for HashMap<String, String> {
     type Error = azure_core::Error;
@@ -108,3 +293,30 @@ impl From<HashMap<String, String>> for BlobTags {
         }
     }
 }
+
+/// Converts a `UserDelegationKey` returned by [`BlobServiceClient::get_user_delegation_key()`](crate::BlobServiceClient::get_user_delegation_key)
+/// into a key for signing SAS with [`BlobSasBuilder::sign_with_user_delegation_key()`](azure_storage_common::sas::BlobSasBuilder::sign_with_user_delegation_key).
+impl TryFrom<UserDelegationKey> for sas::UserDelegationKey {
+    type Error = azure_core::Error;
+
+    fn try_from(key: UserDelegationKey) -> Result<Self, azure_core::Error> {
+        fn required<T>(value: Option<T>, name: &str) -> Result<T, azure_core::Error> {
+            value.ok_or_else(|| {
+                azure_core::Error::with_message(
+                    azure_core::error::ErrorKind::DataConversion,
+                    format!("UserDelegationKey missing {name}"),
+                )
+            })
+        }
+
+        Ok(Self {
+            signed_oid: required(key.signed_oid, "SignedOid")?,
+            signed_tid: required(key.signed_tid, "SignedTid")?,
+            signed_start: required(key.signed_start, "SignedStart")?,
+            signed_expiry: required(key.signed_expiry, "SignedExpiry")?,
+            signed_service: required(key.signed_service, "SignedService")?,
+            signed_version: required(key.signed_version, "SignedVersion")?,
+            value: Secret::new(base64::encode(required(key.value, "Value")?)),
+        })
+    }
+}
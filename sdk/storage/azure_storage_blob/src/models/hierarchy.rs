@@ -0,0 +1,130 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::models::{
+    BlobHierarchyListSegment, BlobItemInternal, BlobPrefix, ListBlobsHierarchySegmentResponse,
+};
+use async_trait::async_trait;
+use azure_core::{fmt::SafeDebug, http::pager::Page, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A blob or virtual directory listed by `BlobContainerClient::list_blobs_by_hierarchy()` or
+/// `BlobContainerClient::walk_blobs()`.
+#[derive(Clone, Debug)]
+pub enum BlobHierarchyItem {
+    /// A blob.
+    Blob(Box<BlobItemInternal>),
+
+    /// A virtual directory: the prefix, up to and including the delimiter, shared by the blobs it contains.
+    Prefix(BlobPrefix),
+}
+
+impl BlobHierarchyItem {
+    /// Returns the name of the blob, or the prefix of the virtual directory.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            BlobHierarchyItem::Blob(blob) => blob.name.as_ref(),
+            BlobHierarchyItem::Prefix(prefix) => prefix.name.as_ref(),
+        }
+        .and_then(|name| name.content.as_deref())
+    }
+}
+
+/// A page of blobs and virtual directories, whose items are in name order.
+#[derive(Clone, Default, SafeDebug, Serialize)]
+#[serde(transparent)]
+pub struct ListBlobsHierarchyPage {
+    /// The response to the `List Blobs` request.
+    pub response: ListBlobsHierarchySegmentResponse,
+}
+
+impl<'de> Deserialize<'de> for ListBlobsHierarchyPage {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The service interleaves `Blob` and `BlobPrefix` elements, which the generated segment
+        // can't deserialize, so this reads them in document order and then separates them.
+        #[derive(Deserialize)]
+        #[serde(rename = "EnumerationResults")]
+        struct Response {
+            #[serde(rename = "@ContainerName")]
+            container_name: Option<String>,
+            #[serde(rename = "Delimiter")]
+            delimiter: Option<String>,
+            #[serde(rename = "Marker")]
+            marker: Option<String>,
+            #[serde(rename = "MaxResults")]
+            max_results: Option<i32>,
+            #[serde(rename = "NextMarker")]
+            next_marker: Option<String>,
+            #[serde(rename = "Prefix")]
+            prefix: Option<String>,
+            #[serde(default, rename = "Blobs")]
+            segment: Segment,
+            #[serde(rename = "@ServiceEndpoint")]
+            service_endpoint: Option<String>,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Segment {
+            #[serde(default, rename = "$value")]
+            items: Vec<SegmentItem>,
+        }
+
+        #[derive(Deserialize)]
+        enum SegmentItem {
+            Blob(Box<BlobItemInternal>),
+            BlobPrefix(BlobPrefix),
+        }
+
+        let response = Response::deserialize(deserializer)?;
+        let mut segment = BlobHierarchyListSegment::default();
+        for item in response.segment.items {
+            match item {
+                SegmentItem::Blob(blob) => segment.blob_items.push(*blob),
+                SegmentItem::BlobPrefix(prefix) => segment
+                    .blob_prefixes
+                    .get_or_insert_with(Vec::new)
+                    .push(prefix),
+            }
+        }
+        Ok(Self {
+            response: ListBlobsHierarchySegmentResponse {
+                container_name: response.container_name,
+                delimiter: response.delimiter,
+                marker: response.marker,
+                max_results: response.max_results,
+                next_marker: response.next_marker,
+                prefix: response.prefix,
+                segment,
+                service_endpoint: response.service_endpoint,
+            },
+        })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Page for ListBlobsHierarchyPage {
+    type Item = BlobHierarchyItem;
+    type IntoIter = <Vec<BlobHierarchyItem> as IntoIterator>::IntoIter;
+    async fn into_items(self) -> Result<Self::IntoIter> {
+        let segment = self.response.segment;
+        let mut items: Vec<BlobHierarchyItem> = segment
+            .blob_prefixes
+            .unwrap_or_default()
+            .into_iter()
+            .map(BlobHierarchyItem::Prefix)
+            .chain(
+                segment
+                    .blob_items
+                    .into_iter()
+                    .map(|blob| BlobHierarchyItem::Blob(Box::new(blob))),
+            )
+            .collect();
+        // The service lists blobs and prefixes together in name order, but the segment holds them separately.
+        items.sort_by(|a, b| a.name().cmp(&b.name()));
+        Ok(items.into_iter())
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::models::{
+    AppendBlobClientAppendBlockOptions, AppendBlobClientCreateOptions,
+    BlobClientAcquireLeaseOptions, BlobClientDownloadOptions, BlobClientStartCopyFromUrlOptions,
+    BlobContainerClientListBlobHierarchySegmentOptions, BlockBlobClientUploadOptions,
+};
+use azure_core::{
+    fmt::SafeDebug,
+    http::{poller::PollerOptions, ClientMethodOptions},
+    time::Duration,
+};
+use std::{fmt, sync::Arc};
+
+/// A callback that returns the name, within the same container, of the blob that `AppendBlobWriter` continues
+/// writing to once the current blob has 50,000 blocks. It's called with the number of blobs filled so far.
+pub type AppendBlobNamer = Arc<dyn Fn(usize) -> String + Send + Sync>;
+
+/// Options to be passed to `AppendBlobClient::open_write()`
+#[derive(Clone, Default)]
+pub struct AppendBlobClientOpenWriteOptions<'a> {
+    /// The lease, access conditions and encryption settings of every `Append Block` request. The append position is
+    /// set for each block.
+    pub append_options: AppendBlobClientAppendBlockOptions<'a>,
+
+    /// The size up to which data is buffered before it's appended as a single block. Defaults to and may be at most
+    /// 4 MiB.
+    pub block_size: Option<u64>,
+
+    /// The properties, metadata, tags and encryption settings of each blob created, which is only created if it doesn't
+    /// exist.
+    pub create_options: AppendBlobClientCreateOptions<'a>,
+
+    /// How long data may be buffered before it's appended, even if it's less than `block_size`. By default, data is
+    /// only appended once `block_size` is buffered, or when the writer is flushed or closed.
+    pub flush_interval: Option<Duration>,
+
+    /// Returns the name of the blob to continue writing to once the current blob is full. By default, writing to a
+    /// full blob fails.
+    pub next_blob_name: Option<AppendBlobNamer>,
+
+    /// Whether to seal each blob once it's full or the writer is closed, which makes it read-only.
+    pub seal: Option<bool>,
+
+    /// Whether to send the CRC-64 of each block, which the service validates.
+    pub validate_content_crc64: Option<bool>,
+
+    /// Whether to send the MD5 hash of each block, which the service validates.
+    pub validate_content_md5: Option<bool>,
+}
+
+impl AppendBlobClientOpenWriteOptions<'_> {
+    /// Converts these options into an owned form so they can be used in `'static` contexts.
+    #[must_use]
+    pub fn into_owned(self) -> AppendBlobClientOpenWriteOptions<'static> {
+        let append_options = self.append_options;
+        let create_options = self.create_options;
+        AppendBlobClientOpenWriteOptions {
+            append_options: AppendBlobClientAppendBlockOptions {
+                append_position: append_options.append_position,
+                encryption_algorithm: append_options.encryption_algorithm,
+                encryption_key: append_options.encryption_key,
+                encryption_key_sha256: append_options.encryption_key_sha256,
+                encryption_scope: append_options.encryption_scope,
+                if_match: append_options.if_match,
+                if_modified_since: append_options.if_modified_since,
+                if_none_match: append_options.if_none_match,
+                if_tags: append_options.if_tags,
+                if_unmodified_since: append_options.if_unmodified_since,
+                lease_id: append_options.lease_id,
+                max_size: append_options.max_size,
+                method_options: ClientMethodOptions {
+                    context: append_options.method_options.context.into_owned(),
+                },
+                structured_body_type: append_options.structured_body_type,
+                structured_content_length: append_options.structured_content_length,
+                timeout: append_options.timeout,
+                transactional_content_crc64: append_options.transactional_content_crc64,
+                transactional_content_md5: append_options.transactional_content_md5,
+            },
+            block_size: self.block_size,
+            create_options: AppendBlobClientCreateOptions {
+                blob_cache_control: create_options.blob_cache_control,
+                blob_content_disposition: create_options.blob_content_disposition,
+                blob_content_encoding: create_options.blob_content_encoding,
+                blob_content_language: create_options.blob_content_language,
+                blob_content_md5: create_options.blob_content_md5,
+                blob_content_type: create_options.blob_content_type,
+                blob_tags_string: create_options.blob_tags_string,
+                encryption_algorithm: create_options.encryption_algorithm,
+                encryption_key: create_options.encryption_key,
+                encryption_key_sha256: create_options.encryption_key_sha256,
+                encryption_scope: create_options.encryption_scope,
+                if_match: create_options.if_match,
+                if_modified_since: create_options.if_modified_since,
+                if_none_match: create_options.if_none_match,
+                if_tags: create_options.if_tags,
+                if_unmodified_since: create_options.if_unmodified_since,
+                immutability_policy_expiry: create_options.immutability_policy_expiry,
+                immutability_policy_mode: create_options.immutability_policy_mode,
+                lease_id: create_options.lease_id,
+                legal_hold: create_options.legal_hold,
+                metadata: create_options.metadata,
+                method_options: ClientMethodOptions {
+                    context: create_options.method_options.context.into_owned(),
+                },
+                timeout: create_options.timeout,
+            },
+            flush_interval: self.flush_interval,
+            next_blob_name: self.next_blob_name,
+            seal: self.seal,
+            validate_content_crc64: self.validate_content_crc64,
+            validate_content_md5: self.validate_content_md5,
+        }
+    }
+}
+
+impl fmt::Debug for AppendBlobClientOpenWriteOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppendBlobClientOpenWriteOptions")
+            .field("append_options", &self.append_options)
+            .field("block_size", &self.block_size)
+            .field("create_options", &self.create_options)
+            .field("flush_interval", &self.flush_interval)
+            .field("seal", &self.seal)
+            .field("validate_content_crc64", &self.validate_content_crc64)
+            .field("validate_content_md5", &self.validate_content_md5)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options to be passed to `BlobClient::begin_copy_from_url()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobClientBeginCopyFromUrlOptions<'a> {
+    /// The properties, metadata, tags and tier of the destination blob, and the access conditions of the source and
+    /// destination. Its context is replaced by that of `method_options`.
+    pub copy_options: BlobClientStartCopyFromUrlOptions<'a>,
+
+    /// Allows customization of the [`Poller`](azure_core::http::poller::Poller).
+    pub method_options: PollerOptions<'a>,
+}
+
+impl BlobClientBeginCopyFromUrlOptions<'_> {
+    /// Converts these options into an owned form so they can be used in `'static` contexts.
+    #[must_use]
+    pub fn into_owned(self) -> BlobClientBeginCopyFromUrlOptions<'static> {
+        let copy_options = self.copy_options;
+        BlobClientBeginCopyFromUrlOptions {
+            copy_options: BlobClientStartCopyFromUrlOptions {
+                blob_tags_string: copy_options.blob_tags_string,
+                if_match: copy_options.if_match,
+                if_modified_since: copy_options.if_modified_since,
+                if_none_match: copy_options.if_none_match,
+                if_tags: copy_options.if_tags,
+                if_unmodified_since: copy_options.if_unmodified_since,
+                immutability_policy_expiry: copy_options.immutability_policy_expiry,
+                immutability_policy_mode: copy_options.immutability_policy_mode,
+                lease_id: copy_options.lease_id,
+                legal_hold: copy_options.legal_hold,
+                metadata: copy_options.metadata,
+                method_options: ClientMethodOptions {
+                    context: copy_options.method_options.context.into_owned(),
+                },
+                rehydrate_priority: copy_options.rehydrate_priority,
+                seal_blob: copy_options.seal_blob,
+                source_if_match: copy_options.source_if_match,
+                source_if_modified_since: copy_options.source_if_modified_since,
+                source_if_none_match: copy_options.source_if_none_match,
+                source_if_tags: copy_options.source_if_tags,
+                source_if_unmodified_since: copy_options.source_if_unmodified_since,
+                tier: copy_options.tier,
+                timeout: copy_options.timeout,
+            },
+            method_options: self.method_options.into_owned(),
+        }
+    }
+}
+
+/// Options to be passed to `BlobClient::download_to_writer()` and `BlobClient::download_to_file()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobClientDownloadToOptions<'a> {
+    /// The access conditions, lease, snapshot, version and encryption settings of every request. The range and
+    /// transactional hash settings are set for each range.
+    pub download_options: BlobClientDownloadOptions<'a>,
+
+    /// The maximum number of ranges downloaded concurrently, each of which is buffered in memory. Defaults to 8.
+    pub max_concurrency: Option<usize>,
+
+    /// The size of each range downloaded with a single request. Defaults to 4 MiB, which is also the largest range for
+    /// which the service returns a transactional hash.
+    pub range_size: Option<u64>,
+
+    /// Whether to request and validate the CRC-64 of each range.
+    pub validate_content_crc64: Option<bool>,
+
+    /// Whether to request and validate the MD5 hash of each range.
+    pub validate_content_md5: Option<bool>,
+}
+
+/// Options to be passed to `BlobClient::open_read()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobClientOpenReadOptions<'a> {
+    /// The size of each block requested with a single `Get Blob` request, which is the unit of the cache. Defaults to
+    /// 4 MiB.
+    pub block_size: Option<u64>,
+
+    /// The maximum number of blocks kept in memory, including those being read ahead. Defaults to 4.
+    pub cache_blocks: Option<usize>,
+
+    /// The access conditions, lease and encryption settings of every request. The range and transactional hash
+    /// settings are set for each block.
+    pub download_options: BlobClientDownloadOptions<'a>,
+
+    /// The number of blocks following the one being read that are requested ahead of time, which must be fewer than
+    /// `cache_blocks`. Defaults to 1.
+    pub read_ahead: Option<usize>,
+
+    /// Whether to request and validate the CRC-64 of each block, which must be at most 4 MiB.
+    pub validate_content_crc64: Option<bool>,
+
+    /// Whether to request and validate the MD5 hash of each block, which must be at most 4 MiB.
+    pub validate_content_md5: Option<bool>,
+}
+
+impl BlobClientOpenReadOptions<'_> {
+    /// Converts these options into an owned form so they can be used in `'static` contexts.
+    #[must_use]
+    pub fn into_owned(self) -> BlobClientOpenReadOptions<'static> {
+        let download_options = self.download_options;
+        BlobClientOpenReadOptions {
+            block_size: self.block_size,
+            cache_blocks: self.cache_blocks,
+            download_options: BlobClientDownloadOptions {
+                encryption_algorithm: download_options.encryption_algorithm,
+                encryption_key: download_options.encryption_key,
+                encryption_key_sha256: download_options.encryption_key_sha256,
+                if_match: download_options.if_match,
+                if_modified_since: download_options.if_modified_since,
+                if_none_match: download_options.if_none_match,
+                if_tags: download_options.if_tags,
+                if_unmodified_since: download_options.if_unmodified_since,
+                lease_id: download_options.lease_id,
+                method_options: ClientMethodOptions {
+                    context: download_options.method_options.context.into_owned(),
+                },
+                range: download_options.range,
+                range_get_content_crc64: download_options.range_get_content_crc64,
+                range_get_content_md5: download_options.range_get_content_md5,
+                snapshot: download_options.snapshot,
+                structured_body_type: download_options.structured_body_type,
+                timeout: download_options.timeout,
+                version_id: download_options.version_id,
+            },
+            read_ahead: self.read_ahead,
+            validate_content_crc64: self.validate_content_crc64,
+            validate_content_md5: self.validate_content_md5,
+        }
+    }
+}
+
+/// Options to be passed to `BlobClient::upload_from_reader()` and `BlobClient::upload_file()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobClientUploadFromReaderOptions<'a> {
+    /// The size of each block staged when the data is larger than `max_single_put_size`. Defaults to 4 MiB and may be up
+    /// to 4000 MiB. A block blob has at most 50,000 blocks, so this limits the size of the blob.
+    pub block_size: Option<u64>,
+
+    /// The maximum number of blocks staged concurrently, each of which is buffered in memory. Defaults to 8.
+    pub max_concurrency: Option<usize>,
+
+    /// The size up to which data is uploaded with a single `Put Blob` request rather than in blocks. Defaults to 64 MiB
+    /// and may be up to 5000 MiB.
+    pub max_single_put_size: Option<u64>,
+
+    /// The blob's properties, metadata, tags, tier, access conditions and encryption settings. The transactional hashes
+    /// and structured message settings only apply to data uploaded with a single request, and the transactional hashes
+    /// are replaced by those computed for `validate_content_md5` or `validate_content_crc64`.
+    pub upload_options: BlockBlobClientUploadOptions<'a>,
+
+    /// Whether to send the CRC-64 of each request's data, which the service validates.
+    pub validate_content_crc64: Option<bool>,
+
+    /// Whether to send the MD5 hash of each request's data, which the service validates.
+    pub validate_content_md5: Option<bool>,
+}
+
+/// Options to be passed to `BlobContainerClient::submit_batch()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobContainerClientSubmitBatchOptions<'a> {
+    /// Allows customization of the method call.
+    pub method_options: ClientMethodOptions<'a>,
+
+    /// The timeout parameter is expressed in seconds. For more information, see [Setting Timeouts for Blob Service Operations.](https://docs.microsoft.com/en-us/rest/api/storageservices/fileservices/setting-timeouts-for-blob-service-operations)
+    pub timeout: Option<i32>,
+}
+
+/// Options to be passed to `BlobContainerClient::walk_blobs()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobContainerClientWalkBlobsOptions<'a> {
+    /// The settings of every listing request. The prefix is the virtual directory to walk, and the marker is ignored.
+    pub list_options: BlobContainerClientListBlobHierarchySegmentOptions<'a>,
+
+    /// The maximum number of pages requested concurrently, from any of the virtual directories found so far. Defaults
+    /// to 8.
+    pub max_concurrency: Option<usize>,
+}
+
+/// Options to be passed to `BlobLeaseClient::acquire()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobLeaseClientAcquireOptions<'a> {
+    /// The access conditions and proposed ID of the lease. Only the `Acquire Lease` request uses these options.
+    pub acquire_options: BlobClientAcquireLeaseOptions<'a>,
+
+    /// How often the lease is renewed. Defaults to a third of its duration, so that a renewal may fail and be retried
+    /// before the lease expires.
+    pub renewal_interval: Option<Duration>,
+}
+
+/// Options to be passed to `BlobServiceClient::submit_batch()`
+#[derive(Clone, Default, SafeDebug)]
+pub struct BlobServiceClientSubmitBatchOptions<'a> {
+    /// Allows customization of the method call.
+    pub method_options: ClientMethodOptions<'a>,
+
+    /// The timeout parameter is expressed in seconds. For more information, see [Setting Timeouts for Blob Service Operations.](https://docs.microsoft.com/en-us/rest/api/storageservices/fileservices/setting-timeouts-for-blob-service-operations)
+    pub timeout: Option<i32>,
+}
@@ -1,7 +1,21 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+mod copy;
 mod extensions;
+mod hierarchy;
+mod method_options;
+mod query;
+
+pub use copy::BlobCopyOperation;
+pub use hierarchy::{BlobHierarchyItem, ListBlobsHierarchyPage};
+pub use method_options::{
+    AppendBlobClientOpenWriteOptions, AppendBlobNamer, BlobClientBeginCopyFromUrlOptions,
+    BlobClientDownloadToOptions, BlobClientOpenReadOptions, BlobClientUploadFromReaderOptions,
+    BlobContainerClientSubmitBatchOptions, BlobContainerClientWalkBlobsOptions,
+    BlobLeaseClientAcquireOptions, BlobServiceClientSubmitBatchOptions,
+};
+pub use query::{BlobQueryError, BlobQueryEvent};
 
 pub use crate::generated::models::{
     AccessTier, AccountKind, AppendBlobClientAppendBlockFromUrlOptions,
@@ -10,26 +24,28 @@ pub use crate::generated::models::{
     AppendBlobClientAppendBlockResultHeaders, AppendBlobClientCreateOptions,
     AppendBlobClientCreateResult, AppendBlobClientCreateResultHeaders, AppendBlobClientSealOptions,
     AppendBlobClientSealResult, AppendBlobClientSealResultHeaders, ArchiveStatus,
+    ArrowConfiguration, ArrowField, BlobClientAbortCopyFromUrlOptions,
     BlobClientAbortCopyFromUrlResult, BlobClientAbortCopyFromUrlResultHeaders,
     BlobClientAcquireLeaseOptions, BlobClientAcquireLeaseResult,
     BlobClientAcquireLeaseResultHeaders, BlobClientBreakLeaseOptions, BlobClientBreakLeaseResult,
     BlobClientBreakLeaseResultHeaders, BlobClientChangeLeaseOptions, BlobClientChangeLeaseResult,
     BlobClientChangeLeaseResultHeaders, BlobClientCopyFromUrlResult,
-    BlobClientCopyFromUrlResultHeaders, BlobClientCreateSnapshotResult,
-    BlobClientCreateSnapshotResultHeaders, BlobClientDeleteImmutabilityPolicyResult,
-    BlobClientDeleteImmutabilityPolicyResultHeaders, BlobClientDeleteOptions,
-    BlobClientDownloadOptions, BlobClientDownloadResult, BlobClientDownloadResultHeaders,
-    BlobClientGetAccountInfoOptions, BlobClientGetAccountInfoResult,
-    BlobClientGetAccountInfoResultHeaders, BlobClientGetPropertiesOptions,
-    BlobClientGetPropertiesResult, BlobClientGetPropertiesResultHeaders, BlobClientGetTagsOptions,
-    BlobClientReleaseLeaseOptions, BlobClientReleaseLeaseResult,
-    BlobClientReleaseLeaseResultHeaders, BlobClientRenewLeaseOptions, BlobClientRenewLeaseResult,
-    BlobClientRenewLeaseResultHeaders, BlobClientSetExpiryResult, BlobClientSetExpiryResultHeaders,
-    BlobClientSetImmutabilityPolicyResult, BlobClientSetImmutabilityPolicyResultHeaders,
-    BlobClientSetLegalHoldResult, BlobClientSetLegalHoldResultHeaders,
-    BlobClientSetMetadataOptions, BlobClientSetPropertiesOptions, BlobClientSetTagsOptions,
-    BlobClientSetTierOptions, BlobClientStartCopyFromUrlResult,
-    BlobClientStartCopyFromUrlResultHeaders, BlobClientUndeleteResult,
+    BlobClientCopyFromUrlResultHeaders, BlobClientCreateSnapshotOptions,
+    BlobClientCreateSnapshotResult, BlobClientCreateSnapshotResultHeaders,
+    BlobClientDeleteImmutabilityPolicyResult, BlobClientDeleteImmutabilityPolicyResultHeaders,
+    BlobClientDeleteOptions, BlobClientDownloadOptions, BlobClientDownloadResult,
+    BlobClientDownloadResultHeaders, BlobClientGetAccountInfoOptions,
+    BlobClientGetAccountInfoResult, BlobClientGetAccountInfoResultHeaders,
+    BlobClientGetPropertiesOptions, BlobClientGetPropertiesResult,
+    BlobClientGetPropertiesResultHeaders, BlobClientGetTagsOptions, BlobClientReleaseLeaseOptions,
+    BlobClientReleaseLeaseResult, BlobClientReleaseLeaseResultHeaders, BlobClientRenewLeaseOptions,
+    BlobClientRenewLeaseResult, BlobClientRenewLeaseResultHeaders, BlobClientSetExpiryResult,
+    BlobClientSetExpiryResultHeaders, BlobClientSetImmutabilityPolicyResult,
+    BlobClientSetImmutabilityPolicyResultHeaders, BlobClientSetLegalHoldResult,
+    BlobClientSetLegalHoldResultHeaders, BlobClientSetMetadataOptions,
+    BlobClientSetPropertiesOptions, BlobClientSetTagsOptions, BlobClientSetTierOptions,
+    BlobClientStartCopyFromUrlOptions, BlobClientStartCopyFromUrlResult,
+    BlobClientStartCopyFromUrlResultHeaders, BlobClientUndeleteOptions, BlobClientUndeleteResult,
     BlobClientUndeleteResultHeaders, BlobContainerClientAcquireLeaseOptions,
     BlobContainerClientAcquireLeaseResult, BlobContainerClientAcquireLeaseResultHeaders,
     BlobContainerClientBreakLeaseOptions, BlobContainerClientBreakLeaseResult,
@@ -40,46 +56,49 @@ pub use crate::generated::models::{
     BlobContainerClientGetAccountInfoResult, BlobContainerClientGetAccountInfoResultHeaders,
     BlobContainerClientGetPropertiesOptions, BlobContainerClientGetPropertiesResult,
     BlobContainerClientGetPropertiesResultHeaders, BlobContainerClientListBlobFlatSegmentOptions,
-    BlobContainerClientReleaseLeaseOptions, BlobContainerClientReleaseLeaseResult,
-    BlobContainerClientReleaseLeaseResultHeaders, BlobContainerClientRenameResult,
-    BlobContainerClientRenameResultHeaders, BlobContainerClientRenewLeaseOptions,
-    BlobContainerClientRenewLeaseResult, BlobContainerClientRenewLeaseResultHeaders,
-    BlobContainerClientRestoreResult, BlobContainerClientRestoreResultHeaders,
-    BlobContainerClientSetAccessPolicyResult, BlobContainerClientSetAccessPolicyResultHeaders,
-    BlobContainerClientSetMetadataOptions, BlobCopySourceTags, BlobDeleteType, BlobExpiryOptions,
-    BlobFlatListSegment, BlobImmutabilityPolicyMode, BlobItemInternal, BlobMetadata, BlobName,
-    BlobPropertiesInternal, BlobServiceClientFindBlobsByTagsOptions,
+    BlobContainerClientListBlobHierarchySegmentOptions, BlobContainerClientReleaseLeaseOptions,
+    BlobContainerClientReleaseLeaseResult, BlobContainerClientReleaseLeaseResultHeaders,
+    BlobContainerClientRenameResult, BlobContainerClientRenameResultHeaders,
+    BlobContainerClientRenewLeaseOptions, BlobContainerClientRenewLeaseResult,
+    BlobContainerClientRenewLeaseResultHeaders, BlobContainerClientRestoreResult,
+    BlobContainerClientRestoreResultHeaders, BlobContainerClientSetAccessPolicyResult,
+    BlobContainerClientSetAccessPolicyResultHeaders, BlobContainerClientSetMetadataOptions,
+    BlobCopySourceTags, BlobDeleteType, BlobExpiryOptions, BlobFlatListSegment,
+    BlobHierarchyListSegment, BlobImmutabilityPolicyMode, BlobItemInternal, BlobMetadata, BlobName,
+    BlobPrefix, BlobPropertiesInternal, BlobServiceClientFindBlobsByTagsOptions,
     BlobServiceClientGetAccountInfoOptions, BlobServiceClientGetAccountInfoResult,
     BlobServiceClientGetAccountInfoResultHeaders, BlobServiceClientGetPropertiesOptions,
-    BlobServiceClientListContainersSegmentOptions, BlobServiceClientSetPropertiesOptions,
-    BlobServiceProperties, BlobTag, BlobTags, BlobTagsHeaders, BlobType, Block,
-    BlockBlobClientCommitBlockListOptions, BlockBlobClientCommitBlockListResult,
-    BlockBlobClientCommitBlockListResultHeaders, BlockBlobClientGetBlockListOptions,
-    BlockBlobClientQueryResult, BlockBlobClientQueryResultHeaders,
-    BlockBlobClientStageBlockFromUrlResult, BlockBlobClientStageBlockFromUrlResultHeaders,
-    BlockBlobClientStageBlockOptions, BlockBlobClientStageBlockResult,
-    BlockBlobClientStageBlockResultHeaders, BlockBlobClientUploadBlobFromUrlOptions,
-    BlockBlobClientUploadBlobFromUrlResult, BlockBlobClientUploadBlobFromUrlResultHeaders,
-    BlockBlobClientUploadOptions, BlockBlobClientUploadResult, BlockBlobClientUploadResultHeaders,
-    BlockList, BlockListHeaders, BlockListType, BlockLookupList, ContainerItem, CopyStatus,
-    CorsRule, DeleteSnapshotsOptionType, EncryptionAlgorithmType, FileShareTokenIntent,
-    FilterBlobItem, FilterBlobSegment, FilterBlobsIncludeItem, GeoReplicationStatusType,
-    ImmutabilityPolicyMode, LeaseDuration, LeaseState, LeaseStatus, ListBlobsFlatSegmentResponse,
-    ListBlobsFlatSegmentResponseHeaders, ListBlobsHierarchySegmentResponse,
-    ListBlobsHierarchySegmentResponseHeaders, ListBlobsIncludeItem, ListContainersIncludeType,
-    ListContainersSegmentResponse, Logging, Metrics, ObjectReplicationMetadata,
-    PageBlobClientClearPagesOptions, PageBlobClientClearPagesResult,
-    PageBlobClientClearPagesResultHeaders, PageBlobClientCopyIncrementalResult,
-    PageBlobClientCopyIncrementalResultHeaders, PageBlobClientCreateOptions,
-    PageBlobClientCreateResult, PageBlobClientCreateResultHeaders,
+    BlobServiceClientGetUserDelegationKeyOptions, BlobServiceClientListContainersSegmentOptions,
+    BlobServiceClientSetPropertiesOptions, BlobServiceProperties, BlobTag, BlobTags,
+    BlobTagsHeaders, BlobType, Block, BlockBlobClientCommitBlockListOptions,
+    BlockBlobClientCommitBlockListResult, BlockBlobClientCommitBlockListResultHeaders,
+    BlockBlobClientGetBlockListOptions, BlockBlobClientQueryOptions, BlockBlobClientQueryResult,
+    BlockBlobClientQueryResultHeaders, BlockBlobClientStageBlockFromUrlResult,
+    BlockBlobClientStageBlockFromUrlResultHeaders, BlockBlobClientStageBlockOptions,
+    BlockBlobClientStageBlockResult, BlockBlobClientStageBlockResultHeaders,
+    BlockBlobClientUploadBlobFromUrlOptions, BlockBlobClientUploadBlobFromUrlResult,
+    BlockBlobClientUploadBlobFromUrlResultHeaders, BlockBlobClientUploadOptions,
+    BlockBlobClientUploadResult, BlockBlobClientUploadResultHeaders, BlockList, BlockListHeaders,
+    BlockListType, BlockLookupList, ContainerItem, CopyStatus, CorsRule, DeleteSnapshotsOptionType,
+    DelimitedTextConfiguration, EncryptionAlgorithmType, FileShareTokenIntent, FilterBlobItem,
+    FilterBlobSegment, FilterBlobsIncludeItem, GeoReplicationStatusType, ImmutabilityPolicyMode,
+    JsonTextConfiguration, KeyInfo, LeaseDuration, LeaseState, LeaseStatus,
+    ListBlobsFlatSegmentResponse, ListBlobsFlatSegmentResponseHeaders,
+    ListBlobsHierarchySegmentResponse, ListBlobsHierarchySegmentResponseHeaders,
+    ListBlobsIncludeItem, ListContainersIncludeType, ListContainersSegmentResponse, Logging,
+    Metrics, ObjectReplicationMetadata, PageBlobClientClearPagesOptions,
+    PageBlobClientClearPagesResult, PageBlobClientClearPagesResultHeaders,
+    PageBlobClientCopyIncrementalResult, PageBlobClientCopyIncrementalResultHeaders,
+    PageBlobClientCreateOptions, PageBlobClientCreateResult, PageBlobClientCreateResultHeaders,
     PageBlobClientGetPageRangesOptions, PageBlobClientResizeOptions, PageBlobClientResizeResult,
     PageBlobClientResizeResultHeaders, PageBlobClientSetSequenceNumberOptions,
     PageBlobClientSetSequenceNumberResult, PageBlobClientSetSequenceNumberResultHeaders,
     PageBlobClientUploadPagesFromUrlOptions, PageBlobClientUploadPagesFromUrlResult,
     PageBlobClientUploadPagesFromUrlResultHeaders, PageBlobClientUploadPagesOptions,
     PageBlobClientUploadPagesResult, PageBlobClientUploadPagesResultHeaders, PageList,
-    PageListHeaders, PremiumPageBlobAccessTier, PublicAccessType, QueryRequestType, QueryType,
-    RehydratePriority, RetentionPolicy, SequenceNumberActionType, SignedIdentifier, SkuName,
-    StaticWebsite, StorageErrorCode, StorageServiceStats, StorageServiceStatsHeaders,
-    UserDelegationKey, UserDelegationKeyHeaders, VecSignedIdentifierHeaders,
+    PageListHeaders, ParquetConfiguration, PremiumPageBlobAccessTier, PublicAccessType,
+    QueryFormat, QueryRequestType, QueryType, RehydratePriority, RetentionPolicy,
+    SequenceNumberActionType, SignedIdentifier, SkuName, StaticWebsite, StorageErrorCode,
+    StorageServiceStats, StorageServiceStatsHeaders, UserDelegationKey, UserDelegationKeyHeaders,
+    VecSignedIdentifierHeaders,
 };
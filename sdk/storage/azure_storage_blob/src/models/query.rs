@@ -0,0 +1,60 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use azure_core::Bytes;
+use std::fmt;
+
+/// An event of the response to `BlobClient::query()`.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum BlobQueryEvent {
+    /// Results of the query, in its output format. Delimited and JSON results may split a record across events.
+    Data(Bytes),
+
+    /// The progress of the query through the blob.
+    Progress {
+        /// The number of bytes of the blob scanned so far.
+        bytes_scanned: i64,
+
+        /// The size of the blob.
+        total_bytes: i64,
+    },
+
+    /// An error in the blob data. The query continues after an error that isn't fatal.
+    Error(BlobQueryError),
+
+    /// The end of the results, which is the last event.
+    End {
+        /// The size of the blob.
+        total_bytes: i64,
+    },
+}
+
+/// An error encountered by `BlobClient::query()`, such as a record that doesn't match the input format.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct BlobQueryError {
+    /// Whether the error ended the query.
+    pub fatal: bool,
+
+    /// The name of the error.
+    pub name: String,
+
+    /// The description of the error.
+    pub description: String,
+
+    /// The offset in the blob at which the error occurred.
+    pub position: i64,
+}
+
+impl fmt::Display for BlobQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "query error {} at position {}: {}",
+            self.name, self.position, self.description
+        )
+    }
+}
+
+impl std::error::Error for BlobQueryError {}
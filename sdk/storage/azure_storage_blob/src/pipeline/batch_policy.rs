@@ -0,0 +1,76 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use async_trait::async_trait;
+use azure_core::http::{
+    headers::{Headers, VERSION},
+    policies::{Policy, PolicyResult},
+    AsyncRawResponse, Context, Request, StatusCode,
+};
+use std::sync::{Arc, Mutex};
+
+/// Marks a request, in its [`Context`], as a sub-request of a blob batch and receives it once authorized.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BatchSubRequest(Arc<Mutex<Option<Request>>>);
+
+impl BatchSubRequest {
+    /// Takes the authorized sub-request.
+    pub(crate) fn take(&self) -> Option<Request> {
+        self.0.lock().expect("poisoned lock").take()
+    }
+}
+
+/// Removes the `x-ms-version` header, which only the batch request carries, from blob batch sub-requests before
+/// they're authorized.
+#[derive(Debug, Clone)]
+pub(crate) struct BatchSubRequestPolicy;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Policy for BatchSubRequestPolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        if ctx.value::<BatchSubRequest>().is_some() {
+            request.headers_mut().remove(VERSION);
+        }
+        next[0].send(ctx, request, &next[1..]).await
+    }
+}
+
+/// Captures authorized blob batch sub-requests instead of sending them, answering each with `202 Accepted`.
+#[derive(Debug, Clone)]
+pub(crate) struct BatchCapturePolicy;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Policy for BatchCapturePolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        let Some(sub_request) = ctx.value::<BatchSubRequest>() else {
+            return next[0].send(ctx, request, &next[1..]).await;
+        };
+        *sub_request.0.lock().expect("poisoned lock") = Some(request.clone());
+        Ok(AsyncRawResponse::from_bytes(
+            StatusCode::Accepted,
+            Headers::new(),
+            Vec::new(),
+        ))
+    }
+}
+
+/// Returns the per-try policies of a client that submits blob batches: `auth_policy` between the policies that
+/// prepare and capture sub-requests.
+pub(crate) fn batch_policies(auth_policy: Option<Arc<dyn Policy>>) -> Vec<Arc<dyn Policy>> {
+    let mut policies: Vec<Arc<dyn Policy>> = vec![Arc::new(BatchSubRequestPolicy)];
+    policies.extend(auth_policy);
+    policies.push(Arc::new(BatchCapturePolicy));
+    policies
+}
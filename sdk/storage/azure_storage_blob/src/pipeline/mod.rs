@@ -1,6 +1,8 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+mod batch_policy;
 mod storage_headers_policy;
 
+pub(crate) use batch_policy::{batch_policies, BatchSubRequest};
 pub use storage_headers_policy::StorageHeadersPolicy;
@@ -0,0 +1,411 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    avro::{Decoder, Value},
+    models::{BlobQueryError, BlobQueryEvent, QueryFormat, QueryType},
+};
+use azure_core::{
+    error::{Error, ErrorKind},
+    http::response::AsyncResponseBody,
+    Bytes, Result,
+};
+use futures::{future, stream, Stream, StreamExt as _, TryStreamExt as _};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+/// The separators and quoting of the output of a query, by which its results are split into records.
+#[derive(Clone, Debug)]
+enum OutputFormat {
+    Delimited {
+        record_separator: Vec<u8>,
+        column_separator: u8,
+        field_quote: Option<u8>,
+        escape_char: Option<u8>,
+    },
+    Json {
+        record_separator: Vec<u8>,
+    },
+    Arrow,
+}
+
+impl OutputFormat {
+    fn new(format: &QueryFormat) -> Self {
+        fn byte(value: Option<&String>) -> Option<u8> {
+            value.and_then(|value| value.bytes().next())
+        }
+        fn separator(value: Option<&String>) -> Vec<u8> {
+            value
+                .filter(|value| !value.is_empty())
+                .map_or_else(|| b"\n".to_vec(), |value| value.as_bytes().to_vec())
+        }
+
+        match format.type_prop {
+            Some(QueryType::Json) => OutputFormat::Json {
+                record_separator: separator(
+                    format
+                        .json_text_configuration
+                        .as_ref()
+                        .and_then(|configuration| configuration.record_separator.as_ref()),
+                ),
+            },
+            Some(QueryType::Arrow) | Some(QueryType::Parquet) => OutputFormat::Arrow,
+            _ => {
+                let configuration = format.delimited_text_configuration.as_ref();
+                OutputFormat::Delimited {
+                    record_separator: separator(
+                        configuration
+                            .and_then(|configuration| configuration.record_separator.as_ref()),
+                    ),
+                    column_separator: byte(
+                        configuration
+                            .and_then(|configuration| configuration.column_separator.as_ref()),
+                    )
+                    .unwrap_or(b','),
+                    field_quote: byte(
+                        configuration.and_then(|configuration| configuration.field_quote.as_ref()),
+                    ),
+                    escape_char: byte(
+                        configuration.and_then(|configuration| configuration.escape_char.as_ref()),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Deserializes a record: a JSON object, or the fields of delimited text as an array of strings.
+    fn parse_record<T: DeserializeOwned>(&self, row: &[u8]) -> Result<T> {
+        let result = match self {
+            OutputFormat::Json { .. } => serde_json::from_slice(row),
+            OutputFormat::Delimited {
+                column_separator,
+                field_quote,
+                escape_char,
+                ..
+            } => {
+                let fields = split_fields(row, *column_separator, *field_quote, *escape_char)?;
+                serde_json::from_value(serde_json::Value::Array(
+                    fields.into_iter().map(serde_json::Value::String).collect(),
+                ))
+            }
+            OutputFormat::Arrow => return Err(unsupported_records()),
+        };
+        result.map_err(|error| {
+            Error::with_error(
+                ErrorKind::DataConversion,
+                error,
+                "failed to deserialize a query record",
+            )
+        })
+    }
+}
+
+fn unsupported_records() -> Error {
+    Error::with_message(
+        ErrorKind::Other,
+        "records can only be read from delimited or JSON query output",
+    )
+}
+
+/// Splits a record of delimited text into its fields, removing their quotes and escapes.
+fn split_fields(
+    row: &[u8],
+    column_separator: u8,
+    field_quote: Option<u8>,
+    escape_char: Option<u8>,
+) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = Vec::new();
+    let mut quoted = false;
+    let mut bytes = row.iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        if Some(byte) == escape_char {
+            field.extend(bytes.next());
+        } else if Some(byte) == field_quote {
+            // A doubled quote within a quoted field is a literal quote.
+            if quoted && bytes.peek().copied() == field_quote {
+                field.push(byte);
+                bytes.next();
+            } else {
+                quoted = !quoted;
+            }
+        } else if byte == column_separator && !quoted {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(byte);
+        }
+    }
+    fields.push(field);
+    fields
+        .into_iter()
+        .map(|field| {
+            String::from_utf8(field).map_err(|error| {
+                Error::with_error(ErrorKind::DataConversion, error, "query field isn't UTF-8")
+            })
+        })
+        .collect()
+}
+
+/// Splits the results of a query into records as they arrive, ignoring record separators within quoted fields.
+struct RowSplitter {
+    record_separator: Vec<u8>,
+    field_quote: Option<u8>,
+    escape_char: Option<u8>,
+    buffer: Vec<u8>,
+    /// How much of `buffer` has been scanned for separators.
+    scanned: usize,
+    quoted: bool,
+    escaped: bool,
+}
+
+impl RowSplitter {
+    fn new(format: &OutputFormat) -> Option<Self> {
+        let (record_separator, field_quote, escape_char) = match format {
+            OutputFormat::Delimited {
+                record_separator,
+                field_quote,
+                escape_char,
+                ..
+            } => (record_separator.clone(), *field_quote, *escape_char),
+            OutputFormat::Json { record_separator } => (record_separator.clone(), None, None),
+            OutputFormat::Arrow => return None,
+        };
+        Some(Self {
+            record_separator,
+            field_quote,
+            escape_char,
+            buffer: Vec::new(),
+            scanned: 0,
+            quoted: false,
+            escaped: false,
+        })
+    }
+
+    /// Appends results, returning the records they complete.
+    fn push(&mut self, data: &[u8]) -> Vec<Bytes> {
+        self.buffer.extend_from_slice(data);
+        let mut rows = Vec::new();
+        let mut start = 0;
+        let mut i = self.scanned;
+        while i < self.buffer.len() {
+            let byte = self.buffer[i];
+            if self.escaped {
+                self.escaped = false;
+            } else if Some(byte) == self.escape_char {
+                self.escaped = true;
+            } else if Some(byte) == self.field_quote {
+                self.quoted = !self.quoted;
+            } else if !self.quoted && byte == self.record_separator[0] {
+                let end = i + self.record_separator.len();
+                if end > self.buffer.len() {
+                    // Wait for the rest of what may be a separator.
+                    break;
+                }
+                if self.buffer[i..end] == self.record_separator[..] {
+                    rows.push(Bytes::copy_from_slice(&self.buffer[start..i]));
+                    start = end;
+                    i = end;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        self.buffer.drain(..start);
+        self.scanned = i - start;
+        rows
+    }
+
+    /// Returns the last record, which needn't end with a separator.
+    fn finish(&mut self) -> Option<Bytes> {
+        self.scanned = 0;
+        (!self.buffer.is_empty()).then(|| Bytes::from(std::mem::take(&mut self.buffer)))
+    }
+}
+
+/// The results of a query started by [`BlobClient::query()`](crate::BlobClient::query), streamed as they're
+/// decoded from the response.
+///
+/// The reader is a [`Stream`] of every [`BlobQueryEvent`], including progress and errors. [`into_data()`](BlobQueryReader::into_data),
+/// [`rows()`](BlobQueryReader::rows) and [`records()`](BlobQueryReader::records) return only the results.
+pub struct BlobQueryReader {
+    body: AsyncResponseBody,
+    decoder: Decoder,
+    output_format: OutputFormat,
+    body_ended: bool,
+    ended: bool,
+    finished: bool,
+}
+
+impl BlobQueryReader {
+    pub(crate) fn new(body: AsyncResponseBody, output_format: &QueryFormat) -> Self {
+        Self {
+            body,
+            decoder: Decoder::default(),
+            output_format: OutputFormat::new(output_format),
+            body_ended: false,
+            ended: false,
+            finished: false,
+        }
+    }
+
+    /// Returns a stream of the results, in the output format of the query.
+    ///
+    /// A fatal [`BlobQueryError`] is returned as the source of an error, ending the stream. Errors that aren't fatal
+    /// and progress are skipped.
+    pub fn into_data(self) -> impl Stream<Item = Result<Bytes>> {
+        self.try_filter_map(|event| {
+            future::ready(match event {
+                BlobQueryEvent::Data(data) => Ok(Some(data)),
+                BlobQueryEvent::Error(error) if error.fatal => {
+                    Err(Error::new(ErrorKind::Other, error))
+                }
+                _ => Ok(None),
+            })
+        })
+    }
+
+    /// Returns a stream of the records of delimited or JSON results, without their record separators.
+    ///
+    /// Separators within quoted fields of delimited results don't end a record. Errors are returned as
+    /// [`into_data()`](BlobQueryReader::into_data) returns them.
+    pub fn rows(self) -> impl Stream<Item = Result<Bytes>> {
+        let splitter = RowSplitter::new(&self.output_format);
+        let data = self.into_data().boxed().fuse();
+        stream::try_unfold(
+            (data, splitter, VecDeque::new()),
+            |(mut data, splitter, mut rows)| async move {
+                let Some(mut splitter) = splitter else {
+                    return Err(unsupported_records());
+                };
+                loop {
+                    if let Some(row) = rows.pop_front() {
+                        return Ok(Some((row, (data, Some(splitter), rows))));
+                    }
+                    match data.try_next().await? {
+                        Some(chunk) => rows.extend(splitter.push(&chunk)),
+                        None => {
+                            return Ok(splitter
+                                .finish()
+                                .map(|row| (row, (data, Some(splitter), rows))))
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// Returns a stream of the records of delimited or JSON results, deserialized as `T`.
+    ///
+    /// A JSON record is deserialized from its object. A delimited record is deserialized from its fields as a sequence
+    /// of strings, so `T` may be `Vec<String>` or a tuple of strings.
+    pub fn records<T: DeserializeOwned>(self) -> impl Stream<Item = Result<T>> {
+        let output_format = self.output_format.clone();
+        self.rows()
+            .and_then(move |row| future::ready(output_format.parse_record(&row)))
+    }
+
+    /// Returns the event a decoded object represents, or `None` if it's of a type that isn't known.
+    fn event(value: Value) -> Result<Option<BlobQueryEvent>> {
+        let invalid =
+            || Error::with_message(ErrorKind::DataConversion, "invalid query response event");
+        let Value::Record(name, mut fields) = value else {
+            return Err(invalid());
+        };
+        let long = |fields: &mut HashMap<String, Value>, name: &str| {
+            fields
+                .remove(name)
+                .and_then(Value::into_long)
+                .ok_or_else(invalid)
+        };
+        let event = match name.rsplit('.').next() {
+            Some("resultData") => match fields.remove("data") {
+                Some(Value::Bytes(data)) => BlobQueryEvent::Data(data.into()),
+                _ => return Err(invalid()),
+            },
+            Some("progress") => BlobQueryEvent::Progress {
+                bytes_scanned: long(&mut fields, "bytesScanned")?,
+                total_bytes: long(&mut fields, "totalBytes")?,
+            },
+            Some("error") => {
+                let mut string = |name: &str| match fields.remove(name) {
+                    Some(Value::String(value)) => Ok(value),
+                    _ => Err(invalid()),
+                };
+                let name = string("name")?;
+                let description = string("description")?;
+                let fatal = matches!(fields.remove("fatal"), Some(Value::Boolean(true)));
+                BlobQueryEvent::Error(BlobQueryError {
+                    fatal,
+                    name,
+                    description,
+                    position: long(&mut fields, "position")?,
+                })
+            }
+            Some("end") => BlobQueryEvent::End {
+                total_bytes: long(&mut fields, "totalBytes")?,
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
+
+impl Stream for BlobQueryReader {
+    type Item = Result<BlobQueryEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+        loop {
+            match this
+                .decoder
+                .next()
+                .and_then(|value| value.map(Self::event).transpose())
+            {
+                Ok(Some(Some(event))) => {
+                    this.ended |= matches!(event, BlobQueryEvent::End { .. });
+                    return Poll::Ready(Some(Ok(event)));
+                }
+                Ok(Some(None)) => continue,
+                Ok(None) => {}
+                Err(error) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+            }
+            if this.body_ended {
+                this.finished = true;
+                if !this.ended || !this.decoder.is_empty() {
+                    return Poll::Ready(Some(Err(Error::with_message(
+                        ErrorKind::Io,
+                        "query response ended before its end event",
+                    ))));
+                }
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.decoder.push(&chunk),
+                Some(Err(error)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => this.body_ended = true,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for BlobQueryReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobQueryReader")
+            .field("output_format", &self.output_format)
+            .field("ended", &self.ended)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Reads a blob at random offsets through a cache of blocks, each requested with a single ranged download.
+
+use crate::{
+    checksum,
+    download::{self, MAX_VALIDATED_RANGE_SIZE},
+    generated::clients::BlobClient as GeneratedBlobClient,
+    models::{
+        BlobClientDownloadOptions, BlobClientGetPropertiesOptions,
+        BlobClientGetPropertiesResultHeaders, BlobClientOpenReadOptions,
+    },
+};
+use azure_core::{
+    error::{Error, ErrorKind},
+    Bytes, Result,
+};
+use azure_storage_common::ChecksumAlgorithm;
+use futures::{
+    future::BoxFuture,
+    io::{AsyncRead, AsyncSeek},
+    FutureExt as _,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+const DEFAULT_BLOCK_SIZE: u64 = 4 * 1024 * 1024;
+const DEFAULT_CACHE_BLOCKS: usize = 4;
+const DEFAULT_READ_AHEAD: usize = 1;
+
+/// A reader of a blob, created by [`BlobClient::open_read()`](crate::BlobClient::open_read), that implements
+/// [`AsyncRead`] and [`AsyncSeek`].
+///
+/// The blob is read in blocks of a fixed size, which are cached, least recently used first out, and requested ahead of
+/// the position being read. Every block is requested with the ETag the blob had when it was opened, so reading fails
+/// rather than returning a mix of versions if the blob changes meanwhile.
+pub struct BlobReader {
+    client: Arc<GeneratedBlobClient>,
+    options: BlobClientDownloadOptions<'static>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    etag: String,
+    size: u64,
+    block_size: u64,
+    cache_blocks: usize,
+    read_ahead: usize,
+    position: u64,
+    /// The blocks received, by index, with the most recently read last.
+    cache: VecDeque<(u64, Bytes)>,
+    /// The blocks being requested, by index.
+    pending: HashMap<u64, BoxFuture<'static, Result<Bytes>>>,
+}
+
+impl BlobReader {
+    /// Reads the size and ETag of the blob, which pins every following request.
+    pub(crate) async fn open(
+        client: Arc<GeneratedBlobClient>,
+        options: BlobClientOpenReadOptions<'static>,
+    ) -> Result<Self> {
+        let block_size = options.block_size.unwrap_or(DEFAULT_BLOCK_SIZE);
+        if block_size == 0 {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "block_size must be at least 1",
+            ));
+        }
+        let checksum_algorithm = checksum::checksum_algorithm(
+            options.validate_content_md5,
+            options.validate_content_crc64,
+        )?;
+        if checksum_algorithm.is_some() && block_size > MAX_VALIDATED_RANGE_SIZE {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!("block_size must be at most {MAX_VALIDATED_RANGE_SIZE} bytes to validate blocks"),
+            ));
+        }
+        let cache_blocks = options.cache_blocks.unwrap_or(DEFAULT_CACHE_BLOCKS);
+        let read_ahead = options.read_ahead.unwrap_or(DEFAULT_READ_AHEAD);
+        if read_ahead >= cache_blocks {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "read_ahead must be less than cache_blocks",
+            ));
+        }
+
+        let download_options = options.download_options;
+        let properties = client
+            .get_properties(Some(BlobClientGetPropertiesOptions {
+                encryption_algorithm: download_options.encryption_algorithm,
+                encryption_key: download_options.encryption_key.clone(),
+                encryption_key_sha256: download_options.encryption_key_sha256.clone(),
+                if_match: download_options.if_match.clone(),
+                if_modified_since: download_options.if_modified_since,
+                if_none_match: download_options.if_none_match.clone(),
+                if_tags: download_options.if_tags.clone(),
+                if_unmodified_since: download_options.if_unmodified_since,
+                lease_id: download_options.lease_id.clone(),
+                method_options: download_options.method_options.clone(),
+                snapshot: download_options.snapshot.clone(),
+                timeout: download_options.timeout,
+                version_id: download_options.version_id.clone(),
+            }))
+            .await?;
+        let etag = properties.etag()?.ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, "response has no ETag")
+        })?;
+
+        Ok(Self {
+            client,
+            options: BlobClientDownloadOptions {
+                if_match: Some(etag.clone()),
+                range: None,
+                range_get_content_crc64: None,
+                range_get_content_md5: None,
+                ..download_options
+            },
+            checksum_algorithm,
+            etag,
+            size: properties.content_length()?.unwrap_or_default(),
+            block_size,
+            cache_blocks,
+            read_ahead,
+            position: 0,
+            cache: VecDeque::new(),
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Returns the ETag of the blob when it was opened, which every read is pinned to.
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Returns the size of the blob, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Requests the block at `index` and those read ahead of it that aren't cached or being requested, and cancels the
+    /// requests for any other blocks.
+    fn request(&mut self, index: u64) {
+        let blocks = self.size.div_ceil(self.block_size);
+        let window = index..(index + 1 + self.read_ahead as u64).min(blocks);
+        self.pending.retain(|pending, _| window.contains(pending));
+        for index in window {
+            if self.pending.contains_key(&index) || self.cache.iter().any(|(i, _)| *i == index) {
+                continue;
+            }
+            let client = self.client.clone();
+            let options = self.options.clone();
+            let checksum_algorithm = self.checksum_algorithm;
+            let offset = index * self.block_size;
+            let len = self.block_size.min(self.size - offset);
+            self.pending.insert(
+                index,
+                async move {
+                    download::download_range(&client, options, checksum_algorithm, offset, len)
+                        .await
+                }
+                .boxed(),
+            );
+        }
+    }
+
+    /// Polls every pending request, caching the blocks received, and returns the block at `index` if it was received
+    /// or its request failed.
+    fn poll_pending(&mut self, cx: &mut Context<'_>, index: u64) -> Option<Result<Bytes>> {
+        let mut block = None;
+        let mut received = Vec::new();
+        self.pending
+            .retain(|i, request| match request.poll_unpin(cx) {
+                Poll::Ready(Ok(data)) => {
+                    if *i == index {
+                        block = Some(Ok(data.clone()));
+                    }
+                    received.push((*i, data));
+                    false
+                }
+                // A block read ahead whose request failed is requested again when it's read.
+                Poll::Ready(Err(error)) => {
+                    if *i == index {
+                        block = Some(Err(error));
+                    }
+                    false
+                }
+                Poll::Pending => true,
+            });
+        // Cache the block being read last, so that it's evicted last.
+        received.sort_by_key(|(i, _)| *i == index);
+        for (i, data) in received {
+            self.cache(i, data);
+        }
+        block
+    }
+
+    /// Returns the block at `index` if it's cached, marking it most recently read.
+    fn cached(&mut self, index: u64) -> Option<Bytes> {
+        let position = self.cache.iter().position(|(i, _)| *i == index)?;
+        let entry = self.cache.remove(position)?;
+        let data = entry.1.clone();
+        self.cache.push_back(entry);
+        Some(data)
+    }
+
+    fn cache(&mut self, index: u64, data: Bytes) {
+        self.cache.push_back((index, data));
+        while self.cache.len() > self.cache_blocks {
+            self.cache.pop_front();
+        }
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() || this.position >= this.size {
+            return Poll::Ready(Ok(0));
+        }
+        let index = this.position / this.block_size;
+        this.request(index);
+        let block = match this.poll_pending(cx, index) {
+            Some(Ok(data)) => data,
+            Some(Err(error)) => return Poll::Ready(Err(io::Error::other(error))),
+            None => match this.cached(index) {
+                Some(data) => data,
+                None => return Poll::Pending,
+            },
+        };
+
+        let start = (this.position - index * this.block_size) as usize;
+        let len = buf.len().min(block.len() - start);
+        buf[..len].copy_from_slice(&block[start..start + len]);
+        this.position += len as u64;
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => this.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => this.position.checked_add_signed(offset),
+        };
+        let Some(position) = position else {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )));
+        };
+        this.position = position;
+        Poll::Ready(Ok(position))
+    }
+}
+
+impl std::fmt::Debug for BlobReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobReader")
+            .field("etag", &self.etag)
+            .field("size", &self.size)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
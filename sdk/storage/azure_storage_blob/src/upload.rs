@@ -0,0 +1,268 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Uploads data of any length to a block blob, staging blocks concurrently when it's too large for a single request.
+
+use crate::{
+    checksum,
+    generated::clients::BlockBlobClient as GeneratedBlockBlobClient,
+    models::{
+        BlobClientUploadFromReaderOptions, BlockBlobClientCommitBlockListOptions,
+        BlockBlobClientCommitBlockListResult, BlockBlobClientStageBlockOptions,
+        BlockBlobClientStageBlockResultHeaders, BlockBlobClientUploadOptions,
+        BlockBlobClientUploadResultHeaders, BlockLookupList,
+    },
+};
+use azure_core::{
+    error::{Error, ErrorKind, ResultExt},
+    http::{NoFormat, RawResponse, Response},
+    Bytes, Result,
+};
+use azure_storage_common::ChecksumAlgorithm;
+use futures::{
+    io::{AsyncRead, AsyncReadExt, Cursor},
+    stream, TryStreamExt,
+};
+use uuid::Uuid;
+
+const MIB: u64 = 1024 * 1024;
+const DEFAULT_BLOCK_SIZE: u64 = 4 * MIB;
+const MAX_BLOCK_SIZE: u64 = 4000 * MIB;
+const DEFAULT_MAX_SINGLE_PUT_SIZE: u64 = 64 * MIB;
+const MAX_SINGLE_PUT_SIZE: u64 = 5000 * MIB;
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+const MAX_BLOCKS: usize = 50_000;
+
+/// Uploads `data` with a single `Put Blob` request if it's at most `max_single_put_size` bytes;
+/// otherwise, stages it in blocks of `block_size` bytes, up to `max_concurrency` at a time, and
+/// commits them.
+///
+/// Both requests return the same headers, so the response of a single `Put Blob` is returned as a
+/// `Put Block List` response.
+pub(crate) async fn upload<R>(
+    client: &GeneratedBlockBlobClient,
+    mut data: R,
+    options: BlobClientUploadFromReaderOptions<'_>,
+) -> Result<Response<BlockBlobClientCommitBlockListResult, NoFormat>>
+where
+    R: AsyncRead + Send + Unpin,
+{
+    let block_size = options.block_size.unwrap_or(DEFAULT_BLOCK_SIZE);
+    if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            format!("block_size must be between 1 and {MAX_BLOCK_SIZE} bytes"),
+        ));
+    }
+    let max_single_put_size = options
+        .max_single_put_size
+        .unwrap_or(DEFAULT_MAX_SINGLE_PUT_SIZE);
+    if max_single_put_size > MAX_SINGLE_PUT_SIZE {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            format!("max_single_put_size must be at most {MAX_SINGLE_PUT_SIZE} bytes"),
+        ));
+    }
+    let max_concurrency = options.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY);
+    if max_concurrency == 0 {
+        return Err(Error::with_message(
+            ErrorKind::Other,
+            "max_concurrency must be at least 1",
+        ));
+    }
+    let checksum_algorithm =
+        checksum::checksum_algorithm(options.validate_content_md5, options.validate_content_crc64)?;
+    let upload_options = options.upload_options;
+
+    // Reading one byte past max_single_put_size tells whether the data fits in a single request.
+    let head = read_block(&mut data, max_single_put_size + 1).await?;
+    if head.len() as u64 <= max_single_put_size {
+        let content_length = head.len() as u64;
+        let mut upload_options = upload_options;
+        let sent = checksum_algorithm.map(|algorithm| algorithm.checksum(&head));
+        if checksum_algorithm.is_some() {
+            (
+                upload_options.transactional_content_md5,
+                upload_options.transactional_content_crc64,
+            ) = checksum::transactional_checksums(checksum_algorithm, &head);
+        }
+        let response = client
+            .upload(
+                Bytes::from(head).into(),
+                content_length,
+                Some(upload_options),
+            )
+            .await
+            .map_err(|error| {
+                checksum::map_rejection(
+                    error,
+                    checksum_algorithm,
+                    sent.as_deref().unwrap_or_default(),
+                )
+            })?;
+        if let (Some(ChecksumAlgorithm::Md5), Some(sent)) = (checksum_algorithm, &sent) {
+            checksum::validate_echo(
+                ChecksumAlgorithm::Md5,
+                sent,
+                response.content_md5()?,
+                "the blob",
+            )?;
+        }
+        return Ok(RawResponse::from(response).into());
+    }
+
+    let stage_options = stage_block_options(&upload_options);
+    let upload_id = Uuid::new_v4();
+    let blocks = stream::try_unfold(
+        (Cursor::new(head).chain(data), 0),
+        move |(mut reader, index)| async move {
+            let block = read_block(&mut reader, block_size).await?;
+            if block.is_empty() {
+                return Ok(None);
+            }
+            if index == MAX_BLOCKS {
+                return Err(Error::with_message(
+                    ErrorKind::Other,
+                    format!("data exceeds {MAX_BLOCKS} blocks of {block_size} bytes; increase block_size"),
+                ));
+            }
+            Ok(Some(((index, block), (reader, index + 1))))
+        },
+    );
+    let block_ids: Vec<Vec<u8>> = blocks
+        .map_ok(|(index, block)| {
+            // Block IDs within a blob must all have the same length.
+            let block_id = format!("{upload_id}-{index:05}").into_bytes();
+            let options = stage_options.clone();
+            async move {
+                let content_length = block.len() as u64;
+                let (transactional_content_md5, transactional_content_crc64) =
+                    checksum::transactional_checksums(checksum_algorithm, &block);
+                let sent = transactional_content_md5
+                    .clone()
+                    .or_else(|| transactional_content_crc64.clone());
+                let response = client
+                    .stage_block(
+                        &block_id,
+                        content_length,
+                        Bytes::from(block).into(),
+                        Some(BlockBlobClientStageBlockOptions {
+                            transactional_content_crc64,
+                            transactional_content_md5,
+                            ..options
+                        }),
+                    )
+                    .await
+                    .map_err(|error| {
+                        checksum::map_rejection(
+                            error,
+                            checksum_algorithm,
+                            sent.as_deref().unwrap_or_default(),
+                        )
+                    })?;
+                if let (Some(algorithm), Some(sent)) = (checksum_algorithm, sent) {
+                    let returned = match algorithm {
+                        ChecksumAlgorithm::Crc64 => response.content_crc64()?,
+                        _ => response.content_md5()?,
+                    };
+                    checksum::validate_echo(
+                        algorithm,
+                        &sent,
+                        returned,
+                        format_args!("block {index}"),
+                    )?;
+                }
+                Ok(block_id)
+            }
+        })
+        .try_buffered(max_concurrency)
+        .try_collect()
+        .await?;
+
+    let blocks = BlockLookupList {
+        latest: Some(block_ids),
+        ..Default::default()
+    };
+    client
+        .commit_block_list(
+            blocks.try_into()?,
+            Some(commit_block_list_options(upload_options)),
+        )
+        .await
+}
+
+/// Reads up to `limit` bytes from `reader`, returning fewer only at the end of the data.
+async fn read_block<R: AsyncRead + Unpin>(reader: &mut R, limit: u64) -> Result<Vec<u8>> {
+    let mut block = Vec::new();
+    reader
+        .take(limit)
+        .read_to_end(&mut block)
+        .await
+        .with_context(ErrorKind::Io, "failed to read upload data")?;
+    Ok(block)
+}
+
+fn stage_block_options<'a>(
+    options: &BlockBlobClientUploadOptions<'a>,
+) -> BlockBlobClientStageBlockOptions<'a> {
+    BlockBlobClientStageBlockOptions {
+        encryption_algorithm: options.encryption_algorithm,
+        encryption_key: options.encryption_key.clone(),
+        encryption_key_sha256: options.encryption_key_sha256.clone(),
+        encryption_scope: options.encryption_scope.clone(),
+        lease_id: options.lease_id.clone(),
+        method_options: options.method_options.clone(),
+        timeout: options.timeout,
+        ..Default::default()
+    }
+}
+
+fn commit_block_list_options(
+    options: BlockBlobClientUploadOptions<'_>,
+) -> BlockBlobClientCommitBlockListOptions<'_> {
+    BlockBlobClientCommitBlockListOptions {
+        blob_cache_control: options.blob_cache_control,
+        blob_content_disposition: options.blob_content_disposition,
+        blob_content_encoding: options.blob_content_encoding,
+        blob_content_language: options.blob_content_language,
+        blob_content_md5: options.blob_content_md5,
+        blob_content_type: options.blob_content_type,
+        blob_tags_string: options.blob_tags_string,
+        encryption_algorithm: options.encryption_algorithm,
+        encryption_key: options.encryption_key,
+        encryption_key_sha256: options.encryption_key_sha256,
+        encryption_scope: options.encryption_scope,
+        if_match: options.if_match,
+        if_modified_since: options.if_modified_since,
+        if_none_match: options.if_none_match,
+        if_tags: options.if_tags,
+        if_unmodified_since: options.if_unmodified_since,
+        immutability_policy_expiry: options.immutability_policy_expiry,
+        immutability_policy_mode: options.immutability_policy_mode,
+        lease_id: options.lease_id,
+        legal_hold: options.legal_hold,
+        metadata: options.metadata,
+        method_options: options.method_options,
+        tier: options.tier,
+        timeout: options.timeout,
+        ..Default::default()
+    }
+}
+
+/// Adapts a Tokio reader, such as a file, to [`futures::io::AsyncRead`].
+#[cfg(feature = "tokio")]
+pub(crate) struct TokioReader<R>(pub(crate) R);
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> AsyncRead for TokioReader<R> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        std::pin::Pin::new(&mut self.0)
+            .poll_read(cx, &mut buf)
+            .map_ok(|()| buf.filled().len())
+    }
+}
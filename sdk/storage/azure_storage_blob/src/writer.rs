@@ -0,0 +1,455 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Writes a stream to append blobs in blocks, from a task that appends buffered data by size or age.
+
+use crate::{
+    checksum,
+    generated::clients::{
+        AppendBlobClient as GeneratedAppendBlobClient, BlobClient as GeneratedBlobClient,
+    },
+    models::{
+        AppendBlobClientAppendBlockOptions, AppendBlobClientAppendBlockResultHeaders,
+        AppendBlobClientCreateOptions, AppendBlobClientOpenWriteOptions, AppendBlobNamer,
+        BlobClientGetPropertiesOptions, BlobClientGetPropertiesResultHeaders,
+    },
+};
+use azure_core::{
+    async_runtime::get_async_runtime,
+    error::{Error, ErrorKind},
+    http::{StatusCode, Url},
+    time::{Duration, OffsetDateTime},
+    Bytes, Result,
+};
+use azure_storage_common::{parse_resource_path, ChecksumAlgorithm};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, Either},
+    io::AsyncWrite,
+    FutureExt as _, StreamExt as _,
+};
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+const MAX_BLOCK_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_BLOCKS: u64 = 50_000;
+const COMMAND_CAPACITY: usize = 4;
+
+enum Command {
+    Write(Bytes),
+    Flush(oneshot::Sender<Result<()>>),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// A writer to an append blob, created by [`AppendBlobClient::open_write()`](crate::AppendBlobClient::open_write),
+/// that implements [`AsyncWrite`].
+///
+/// Data is appended by a task on the async runtime of `azure_core`, in blocks of up to `block_size` bytes, once a
+/// block is full, the oldest data buffered is older than `flush_interval`, or the writer is flushed. Each block is
+/// appended at the offset where the previous one ended, so if another client appends to the blob meanwhile, writing
+/// fails with status `412 Precondition Failed` rather than interleaving data.
+///
+/// Closing the writer appends the remaining data and, if configured, seals the blob. Dropping the writer without
+/// closing it still appends the remaining data in the background, but any error is lost.
+pub struct AppendBlobWriter {
+    commands: mpsc::Sender<Command>,
+    block_size: usize,
+    /// The reply to the flush or close being awaited, and whether it's a close.
+    reply: Option<(oneshot::Receiver<Result<()>>, bool)>,
+    failure: Arc<Mutex<Option<Error>>>,
+}
+
+impl AppendBlobWriter {
+    /// Creates the blob if it doesn't exist and starts the task that appends to it.
+    pub(crate) async fn open(
+        client: GeneratedAppendBlobClient,
+        options: AppendBlobClientOpenWriteOptions<'static>,
+    ) -> Result<Self> {
+        let block_size = options.block_size.unwrap_or(MAX_BLOCK_SIZE);
+        if !(1..=MAX_BLOCK_SIZE).contains(&block_size) {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!("block_size must be between 1 and {MAX_BLOCK_SIZE} bytes"),
+            ));
+        }
+        if options
+            .flush_interval
+            .is_some_and(|interval| interval <= Duration::ZERO)
+        {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "flush_interval must be positive",
+            ));
+        }
+
+        let checksum_algorithm = checksum::checksum_algorithm(
+            options.validate_content_md5,
+            options.validate_content_crc64,
+        )?;
+
+        let mut appender = Appender {
+            client,
+            append_options: options.append_options,
+            create_options: options.create_options,
+            block_size: block_size as usize,
+            flush_interval: options.flush_interval,
+            next_blob_name: options.next_blob_name,
+            seal: options.seal.unwrap_or_default(),
+            checksum_algorithm,
+            position: 0,
+            blocks: 0,
+            blobs_filled: 0,
+            buffer: Vec::new(),
+            buffered_since: None,
+        };
+        appender.create().await?;
+
+        let (commands, receiver) = mpsc::channel(COMMAND_CAPACITY);
+        let failure = Arc::new(Mutex::new(None));
+        drop(get_async_runtime().spawn(Box::pin(appender.run(receiver, failure.clone()))));
+        Ok(Self {
+            commands,
+            block_size: block_size as usize,
+            reply: None,
+            failure,
+        })
+    }
+
+    /// Returns the error that stopped the task, or that the writer was closed if it stopped without one.
+    fn stopped(&self) -> io::Error {
+        match self.failure.lock().unwrap().take() {
+            Some(error) => io::Error::other(error),
+            None => io::Error::new(io::ErrorKind::BrokenPipe, "append blob writer has stopped"),
+        }
+    }
+
+    /// Sends a flush or close and waits for its reply, after any flush or close already being awaited.
+    fn poll_command(&mut self, cx: &mut Context<'_>, close: bool) -> Poll<io::Result<()>> {
+        loop {
+            if let Some((reply, closing)) = &mut self.reply {
+                let closing = *closing;
+                let result = match reply.poll_unpin(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(result)) => result.map_err(io::Error::other),
+                    Poll::Ready(Err(_)) => Err(self.stopped()),
+                };
+                self.reply = None;
+                if closing == close || result.is_err() {
+                    return Poll::Ready(result);
+                }
+            }
+            if futures::ready!(self.commands.poll_ready(cx)).is_err() {
+                // Closing a writer that's already closed succeeds, unless it stopped because appending failed.
+                let failed = self.failure.lock().unwrap().is_some();
+                return Poll::Ready(if close && !failed {
+                    Ok(())
+                } else {
+                    Err(self.stopped())
+                });
+            }
+            let (sender, reply) = oneshot::channel();
+            let command = if close {
+                Command::Close(sender)
+            } else {
+                Command::Flush(sender)
+            };
+            if self.commands.start_send(command).is_err() {
+                return Poll::Ready(Err(self.stopped()));
+            }
+            self.reply = Some((reply, close));
+        }
+    }
+}
+
+impl AsyncWrite for AppendBlobWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if futures::ready!(this.commands.poll_ready(cx)).is_err() {
+            return Poll::Ready(Err(this.stopped()));
+        }
+        let len = buf.len().min(this.block_size);
+        if this
+            .commands
+            .start_send(Command::Write(Bytes::copy_from_slice(&buf[..len])))
+            .is_err()
+        {
+            return Poll::Ready(Err(this.stopped()));
+        }
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_command(cx, false)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_command(cx, true)
+    }
+}
+
+impl std::fmt::Debug for AppendBlobWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppendBlobWriter")
+            .field("block_size", &self.block_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of the task that appends to the blob.
+struct Appender {
+    client: GeneratedAppendBlobClient,
+    append_options: AppendBlobClientAppendBlockOptions<'static>,
+    create_options: AppendBlobClientCreateOptions<'static>,
+    block_size: usize,
+    flush_interval: Option<Duration>,
+    next_blob_name: Option<AppendBlobNamer>,
+    seal: bool,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// The offset at which the next block is appended.
+    position: u64,
+    /// The number of blocks committed to the blob.
+    blocks: u64,
+    blobs_filled: usize,
+    buffer: Vec<u8>,
+    buffered_since: Option<OffsetDateTime>,
+}
+
+impl Appender {
+    /// Handles commands until the writer is closed or dropped, or appending fails, in which case the error is stored
+    /// in `failure` for the writer to return.
+    async fn run(
+        mut self,
+        mut commands: mpsc::Receiver<Command>,
+        failure: Arc<Mutex<Option<Error>>>,
+    ) {
+        let runtime = get_async_runtime();
+        loop {
+            let deadline = self
+                .flush_interval
+                .zip(self.buffered_since)
+                .map(|(interval, since)| since + interval - OffsetDateTime::now_utc());
+            let command = match deadline {
+                Some(delay) => {
+                    match future::select(runtime.sleep(delay.max(Duration::ZERO)), commands.next())
+                        .await
+                    {
+                        Either::Left(_) => None,
+                        Either::Right((command, _)) => Some(command),
+                    }
+                }
+                None => Some(commands.next().await),
+            };
+
+            let result = match command {
+                // The oldest data buffered is due.
+                None => self.flush().await,
+                Some(Some(Command::Write(data))) => self.write(&data).await,
+                Some(Some(Command::Flush(reply))) => {
+                    let result = self.flush().await;
+                    let failed = result.is_err();
+                    // Refuse further writes before replying, so that none succeeds after the failure is returned.
+                    if failed {
+                        commands.close();
+                    }
+                    let _ = reply.send(result);
+                    if failed {
+                        return;
+                    }
+                    Ok(())
+                }
+                Some(Some(Command::Close(reply))) => {
+                    let result = self.close().await;
+                    commands.close();
+                    let _ = reply.send(result);
+                    return;
+                }
+                // The writer was dropped without being closed.
+                Some(None) => {
+                    let _ = self.flush().await;
+                    return;
+                }
+            };
+            if let Err(error) = result {
+                *failure.lock().unwrap() = Some(error);
+                commands.close();
+                return;
+            }
+        }
+    }
+
+    /// Buffers `data`, appending each block that's filled.
+    async fn write(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            // Data left over after a full block is appended is due a full interval after it was buffered.
+            if self.buffer.is_empty() {
+                self.buffered_since = Some(OffsetDateTime::now_utc());
+            }
+            let len = data.len().min(self.block_size - self.buffer.len());
+            self.buffer.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.buffer.len() == self.block_size {
+                self.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the data buffered as a single block, continuing with the next blob if the current one is full.
+    async fn flush(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        if self.blocks >= MAX_BLOCKS {
+            self.roll_over().await?;
+        }
+        let data = Bytes::from(std::mem::take(&mut self.buffer));
+        let len = data.len() as u64;
+        let (transactional_content_md5, transactional_content_crc64) =
+            checksum::transactional_checksums(self.checksum_algorithm, &data);
+        let sent = transactional_content_md5
+            .clone()
+            .or_else(|| transactional_content_crc64.clone());
+        let response = self
+            .client
+            .append_block(
+                data.into(),
+                len,
+                Some(AppendBlobClientAppendBlockOptions {
+                    append_position: Some(self.position as i64),
+                    transactional_content_crc64,
+                    transactional_content_md5,
+                    ..self.append_options.clone()
+                }),
+            )
+            .await
+            .map_err(|error| {
+                checksum::map_rejection(
+                    error,
+                    self.checksum_algorithm,
+                    sent.as_deref().unwrap_or_default(),
+                )
+            })?;
+        if let (Some(algorithm), Some(sent)) = (self.checksum_algorithm, sent) {
+            let returned = match algorithm {
+                ChecksumAlgorithm::Crc64 => response.content_crc64()?,
+                _ => response.content_md5()?,
+            };
+            checksum::validate_echo(
+                algorithm,
+                &sent,
+                returned,
+                format_args!("the block at offset {}", self.position),
+            )?;
+        }
+        self.position += len;
+        self.blocks = match response.blob_committed_block_count()? {
+            Some(blocks) => blocks as u64,
+            None => self.blocks + 1,
+        };
+        self.buffered_since = None;
+        Ok(())
+    }
+
+    /// Appends the data buffered and seals the blob if configured.
+    async fn close(&mut self) -> Result<()> {
+        self.flush().await?;
+        if self.seal {
+            self.client.seal(None).await?;
+        }
+        Ok(())
+    }
+
+    /// Seals the current blob if configured, and continues with the blob named by `next_blob_name`.
+    async fn roll_over(&mut self) -> Result<()> {
+        let Some(next_blob_name) = self.next_blob_name.clone() else {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!(
+                    "append blob {} has {MAX_BLOCKS} blocks",
+                    self.client.endpoint
+                ),
+            ));
+        };
+        if self.seal {
+            self.client.seal(None).await?;
+        }
+        self.blobs_filled += 1;
+        let endpoint = sibling_url(&self.client.endpoint, &next_blob_name(self.blobs_filled))?;
+        self.client = GeneratedAppendBlobClient {
+            endpoint,
+            pipeline: self.client.pipeline.clone(),
+            version: self.client.version.clone(),
+            tracer: self.client.tracer.clone(),
+        };
+        self.create().await
+    }
+
+    /// Creates the current blob if it doesn't exist, or reads where to append to it if it does.
+    async fn create(&mut self) -> Result<()> {
+        let created = self
+            .client
+            .create(Some(AppendBlobClientCreateOptions {
+                if_none_match: Some("*".into()),
+                ..self.create_options.clone()
+            }))
+            .await;
+        match created {
+            Ok(_) => {
+                self.position = 0;
+                self.blocks = 0;
+                Ok(())
+            }
+            Err(error) if error.http_status() == Some(StatusCode::Conflict) => {
+                let blob_client = GeneratedBlobClient {
+                    endpoint: self.client.endpoint.clone(),
+                    pipeline: self.client.pipeline.clone(),
+                    version: self.client.version.clone(),
+                    tracer: self.client.tracer.clone(),
+                };
+                let properties = blob_client
+                    .get_properties(Some(BlobClientGetPropertiesOptions {
+                        encryption_algorithm: self.append_options.encryption_algorithm,
+                        encryption_key: self.append_options.encryption_key.clone(),
+                        encryption_key_sha256: self.append_options.encryption_key_sha256.clone(),
+                        lease_id: self.append_options.lease_id.clone(),
+                        ..Default::default()
+                    }))
+                    .await?;
+                self.position = properties.content_length()?.unwrap_or_default();
+                self.blocks = properties.blob_committed_block_count()?.unwrap_or_default() as u64;
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Returns the URL of the blob named `blob_name` in the same container as the blob at `url`.
+fn sibling_url(url: &Url, blob_name: &str) -> Result<Url> {
+    let invalid =
+        || Error::with_message(ErrorKind::Other, format!("{url} isn't the URL of a blob"));
+    // The blob name may span several segments, following the storage account, if any, and the container.
+    let blob_segments = parse_resource_path(url)
+        .len()
+        .checked_sub(1)
+        .filter(|segments| *segments > 0)
+        .ok_or_else(invalid)?;
+    let mut sibling = url.clone();
+    {
+        let mut segments = sibling.path_segments_mut().map_err(|_| invalid())?;
+        segments.pop_if_empty();
+        for _ in 0..blob_segments {
+            segments.pop();
+        }
+        segments.push(blob_name);
+    }
+    Ok(sibling)
+}
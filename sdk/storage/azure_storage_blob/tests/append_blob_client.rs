@@ -1,13 +1,28 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use azure_core::http::{RequestContent, StatusCode};
+use azure_core::{
+    base64,
+    http::{Method, RequestContent, StatusCode},
+};
 use azure_core_test::{recorded, TestContext};
 use azure_storage_blob::models::{
+    AppendBlobClientAppendBlockOptions, AppendBlobClientOpenWriteOptions,
     BlobClientDownloadResultHeaders, BlobClientGetPropertiesResultHeaders, BlobType,
 };
-use azure_storage_blob_test::{create_test_blob, get_blob_name, get_container_client};
-use std::error::Error;
+use azure_storage_blob_test::{
+    create_test_blob, get_blob_name, get_container_client, FakeStorage, FakeStorageExt,
+};
+use azure_storage_common::{content_crc64, content_md5, ChecksumAlgorithm, ChecksumMismatchError};
+use futures::io::AsyncWriteExt as _;
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 #[recorded::test]
 async fn test_create_append_blob(ctx: TestContext) -> Result<(), Box<dyn Error>> {
@@ -139,3 +154,313 @@ async fn test_seal_append_blob(ctx: TestContext) -> Result<(), Box<dyn Error>> {
     container_client.delete_container(None).await?;
     Ok(())
 }
+
+/// The path of the append blob the fake storage clients address.
+const APPEND_BLOB: &str = "container/logs/app.log";
+
+/// Adds an append blob holding `data` in `block_count` blocks to `storage`.
+fn insert_append_blob(storage: &FakeStorage, data: &[u8], block_count: u64) {
+    let mut state = storage.state();
+    let blob = state.insert_blob(APPEND_BLOB, data);
+    blob.blob_type = "AppendBlob".to_string();
+    blob.blocks.clear();
+    blob.committed_block_count = block_count;
+}
+
+/// Describes each request `storage` received.
+fn requests(storage: &FakeStorage) -> Vec<String> {
+    storage
+        .requests()
+        .iter()
+        .map(|request| {
+            let path = request.path();
+            let path = path.trim_start_matches("container/");
+            match (request.method, request.query("comp").as_deref()) {
+                (Method::Put, None) => {
+                    assert_eq!(Some("*"), request.header("if-none-match"));
+                    format!("create {path}")
+                }
+                (Method::Head, None) => format!("get properties {path}"),
+                (Method::Put, Some("appendblock")) => {
+                    format!("append {path} {}", String::from_utf8_lossy(&request.body))
+                }
+                (Method::Put, Some("seal")) => format!("seal {path}"),
+                _ => panic!("unexpected request {} {}", request.method, request.url),
+            }
+        })
+        .collect()
+}
+
+fn appended(storage: &FakeStorage) -> Vec<u8> {
+    storage.blob(APPEND_BLOB).unwrap().data
+}
+
+#[tokio::test]
+async fn test_append_blob_writer() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            block_size: Some(4),
+            seal: Some(true),
+            ..Default::default()
+        }))
+        .await?;
+    writer.write_all(b"hello world").await?;
+    writer.close().await?;
+    writer.close().await?;
+    assert!(writer.write_all(b"!").await.is_err());
+
+    assert_eq!(b"hello world", appended(&storage).as_slice());
+    assert_eq!(
+        vec![
+            "create logs/app.log",
+            "append logs/app.log hell",
+            "append logs/app.log o wo",
+            "append logs/app.log rld",
+            "seal logs/app.log",
+        ],
+        requests(&storage)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_blob_writer_sends_checksums() -> Result<(), Box<dyn Error>> {
+    // Once `corrupt` is set, the first byte of every block is corrupted on its way to the service.
+    let corrupt = Arc::new(AtomicBool::new(false));
+    let corrupting = corrupt.clone();
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if !corrupting.load(Ordering::SeqCst)
+            || request.query("comp").as_deref() != Some("appendblock")
+        {
+            return None;
+        }
+        let mut corrupted = request.clone();
+        let mut body = corrupted.body.to_vec();
+        body[0] ^= 0xff;
+        corrupted.body = body.into();
+        Some(state.serve(&corrupted))
+    });
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            block_size: Some(4),
+            validate_content_crc64: Some(true),
+            ..Default::default()
+        }))
+        .await?;
+    writer.write_all(b"hello world").await?;
+    writer.close().await?;
+    assert_eq!(b"hello world", appended(&storage).as_slice());
+
+    // A block corrupted on its way to the service is rejected.
+    corrupt.store(true, Ordering::SeqCst);
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            validate_content_md5: Some(true),
+            ..Default::default()
+        }))
+        .await?;
+    writer.write_all(b"!").await?;
+    let error = writer.flush().await.unwrap_err();
+    let error = error
+        .into_inner()
+        .unwrap()
+        .downcast::<azure_core::Error>()
+        .unwrap();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(b"!").to_vec(), mismatch.expected);
+    assert_eq!(b"hello world", appended(&storage).as_slice());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_blob_writer_concurrent_writer() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    insert_append_blob(&storage, b"abc", 1);
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+
+    // An existing blob is appended to where it ends.
+    let mut writer = client.open_write(None).await?;
+    writer.write_all(b"def").await?;
+    writer.flush().await?;
+    assert_eq!(b"abcdef", appended(&storage).as_slice());
+
+    // Another writer appends meanwhile.
+    storage
+        .state()
+        .blobs
+        .get_mut(APPEND_BLOB)
+        .unwrap()
+        .data
+        .push(b'!');
+    writer.write_all(b"ghi").await?;
+    let error = writer.flush().await.unwrap_err();
+    let error = error
+        .into_inner()
+        .unwrap()
+        .downcast::<azure_core::Error>()
+        .unwrap();
+    assert_eq!(Some(StatusCode::PreconditionFailed), error.http_status());
+    assert!(writer.write_all(b"jkl").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_blob_writer_flush_interval() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            flush_interval: Some(azure_core::time::Duration::milliseconds(50)),
+            ..Default::default()
+        }))
+        .await?;
+    writer.write_all(b"line 1\n").await?;
+
+    for _ in 0..100 {
+        if requests(&storage).len() > 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        vec!["create logs/app.log", "append logs/app.log line 1\n"],
+        requests(&storage)
+    );
+    writer.close().await?;
+    assert_eq!(2, requests(&storage).len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_blob_writer_flush_interval_after_full_block() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            block_size: Some(4),
+            flush_interval: Some(azure_core::time::Duration::milliseconds(50)),
+            ..Default::default()
+        }))
+        .await?;
+    writer.write_all(b"ab").await?;
+    // Fills the first block, leaving "ef" buffered.
+    writer.write_all(b"cdef").await?;
+
+    for _ in 0..100 {
+        if requests(&storage).len() > 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        vec![
+            "create logs/app.log",
+            "append logs/app.log abcd",
+            "append logs/app.log ef"
+        ],
+        requests(&storage)
+    );
+    writer.close().await?;
+    assert_eq!(3, requests(&storage).len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_blob_writer_rolls_over() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    insert_append_blob(&storage, b"full", 50_000);
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+    let options = AppendBlobClientOpenWriteOptions {
+        seal: Some(true),
+        ..Default::default()
+    };
+
+    let mut writer = client.open_write(Some(options.clone())).await?;
+    writer.write_all(b"more").await?;
+    assert!(writer.close().await.is_err());
+
+    storage.state().requests.clear();
+    let mut writer = client
+        .open_write(Some(AppendBlobClientOpenWriteOptions {
+            next_blob_name: Some(Arc::new(|blobs_filled| {
+                format!("logs/app.{blobs_filled}.log")
+            })),
+            ..options
+        }))
+        .await?;
+    writer.write_all(b"more").await?;
+    writer.close().await?;
+    assert_eq!(
+        vec![
+            "create logs/app.log",
+            "get properties logs/app.log",
+            "seal logs/app.log",
+            "create logs/app.1.log",
+            "append logs/app.1.log more",
+            "seal logs/app.1.log",
+        ],
+        requests(&storage)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_append_block_validates_content() -> Result<(), Box<dyn Error>> {
+    // Once `corrupt` is set, the first byte of every block is corrupted on its way to the service.
+    let corrupt = Arc::new(AtomicBool::new(false));
+    let corrupting = corrupt.clone();
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if !corrupting.load(Ordering::SeqCst) {
+            return None;
+        }
+        let mut corrupted = request.clone();
+        let mut body = corrupted.body.to_vec();
+        body[0] ^= 0xff;
+        corrupted.body = body.into();
+        Some(state.serve(&corrupted))
+    });
+    insert_append_blob(&storage, b"", 0);
+    let client = storage.append_blob_client(APPEND_BLOB)?;
+
+    client
+        .append_block(
+            RequestContent::from(b"hello".to_vec()),
+            5,
+            Some(AppendBlobClientAppendBlockOptions::default().with_validate_content_crc64()),
+        )
+        .await?;
+    assert_eq!(
+        Some(base64::encode(content_crc64(b"hello")).as_str()),
+        storage.requests()[0].header("x-ms-content-crc64")
+    );
+
+    corrupt.store(true, Ordering::SeqCst);
+    let error = client
+        .append_block(
+            RequestContent::from(b"!".to_vec()),
+            1,
+            Some(AppendBlobClientAppendBlockOptions::default().with_validate_content_md5()),
+        )
+        .await
+        .unwrap_err();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(b"!").to_vec(), mismatch.expected);
+    assert_eq!(b"hello", appended(&storage).as_slice());
+
+    Ok(())
+}
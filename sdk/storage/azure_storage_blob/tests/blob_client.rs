@@ -2,24 +2,50 @@
 // Licensed under the MIT License.
 
 use azure_core::{
-    http::{ClientOptions, RequestContent, StatusCode},
+    base64,
+    credentials::Secret,
+    http::{
+        headers::Headers, poller::PollerOptions, ClientOptions, Method, RequestContent, StatusCode,
+    },
+    time::OffsetDateTime,
     Bytes,
 };
 use azure_core_test::{recorded, Matcher, TestContext};
 use azure_storage_blob::{
     models::{
         AccessTier, AccountKind, BlobClientAcquireLeaseResultHeaders,
-        BlobClientChangeLeaseResultHeaders, BlobClientDownloadOptions,
-        BlobClientDownloadResultHeaders, BlobClientGetAccountInfoResultHeaders,
-        BlobClientGetPropertiesOptions, BlobClientGetPropertiesResultHeaders,
+        BlobClientBeginCopyFromUrlOptions, BlobClientChangeLeaseResultHeaders,
+        BlobClientCreateSnapshotResultHeaders, BlobClientDownloadOptions,
+        BlobClientDownloadResultHeaders, BlobClientDownloadToOptions,
+        BlobClientGetAccountInfoResultHeaders, BlobClientGetPropertiesOptions,
+        BlobClientGetPropertiesResultHeaders, BlobClientOpenReadOptions,
         BlobClientSetMetadataOptions, BlobClientSetPropertiesOptions, BlobClientSetTierOptions,
-        BlockBlobClientUploadOptions, LeaseState,
+        BlobClientUploadFromReaderOptions, BlobLeaseClientAcquireOptions, BlobQueryError,
+        BlobQueryEvent, BlockBlobClientUploadOptions, CopyStatus, LeaseState, QueryFormat,
     },
+    sas::{BlobSasBuilder, BlobSasPermissions},
     BlobClient, BlobClientOptions, BlobContainerClient, BlobContainerClientOptions,
+    BlobLeaseClient, SharedKeyCredential,
+};
+use azure_storage_blob_test::{
+    create_test_blob, get_blob_name, get_container_client, FakeResponse, FakeStorage,
+    FakeStorageExt,
+};
+use azure_storage_common::{content_crc64, content_md5, ChecksumAlgorithm, ChecksumMismatchError};
+use futures::{
+    io::{AsyncReadExt as _, AsyncSeekExt as _},
+    TryStreamExt,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::SeekFrom,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
-use azure_storage_blob_test::{create_test_blob, get_blob_name, get_container_client};
-use futures::TryStreamExt;
-use std::{collections::HashMap, error::Error, time::Duration};
 use tokio::time;
 use typespec_client_core::http::Url;
 
@@ -644,3 +670,1142 @@ async fn test_encoding_edge_cases(ctx: TestContext) -> Result<(), Box<dyn Error>
 
     Ok(())
 }
+
+#[test]
+fn test_generate_sas_url() -> Result<(), Box<dyn Error>> {
+    let credential = SharedKeyCredential::new("devstoreaccount1", Secret::new("a2V5"))?;
+    let permissions = BlobSasPermissions {
+        read: true,
+        ..Default::default()
+    };
+    let expiry = OffsetDateTime::now_utc() + azure_core::time::Duration::hours(1);
+    let blob_client = BlobClient::with_shared_key(
+        "http://127.0.0.1:10000/devstoreaccount1",
+        "container",
+        "dir/blob",
+        credential.clone(),
+        None,
+    )?;
+
+    let url = blob_client.generate_sas_url(
+        BlobSasBuilder::new()
+            .with_permissions(permissions)
+            .with_expiry(expiry),
+        &credential,
+    )?;
+    let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!(blob_client.url().path(), url.path());
+    assert_eq!("b", params["sr"]);
+    assert_eq!("r", params["sp"]);
+    assert!(params.contains_key("sig"));
+
+    // The SAS covers the snapshot the URL selects.
+    let mut snapshot_url = blob_client.url().clone();
+    snapshot_url
+        .query_pairs_mut()
+        .append_pair("snapshot", "2026-10-19T00:00:00.0000000Z");
+    let snapshot_client = BlobClient::from_url(snapshot_url, None, None)?;
+    let url = snapshot_client.generate_sas_url(
+        BlobSasBuilder::new()
+            .with_permissions(permissions)
+            .with_expiry(expiry),
+        &credential,
+    )?;
+    let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!("bs", params["sr"]);
+    assert_eq!("2026-10-19T00:00:00.0000000Z", params["snapshot"]);
+
+    Ok(())
+}
+
+/// The path of the blob the fake storage clients address.
+const BLOB: &str = "container/blob";
+
+/// Creates a `BlobClient` for a blob in an empty fake storage account.
+fn mock_blob_client() -> Result<(BlobClient, FakeStorage), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    Ok((storage.blob_client(BLOB)?, storage))
+}
+
+#[tokio::test]
+async fn test_upload_from_reader_in_blocks() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_blob_client()?;
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    let options = BlobClientUploadFromReaderOptions {
+        block_size: Some(1024),
+        max_concurrency: Some(2),
+        max_single_put_size: Some(2048),
+        upload_options: BlockBlobClientUploadOptions {
+            blob_content_type: Some("application/octet-stream".to_string()),
+            metadata: Some(HashMap::from([(
+                "origin".to_string(),
+                "backup".to_string(),
+            )])),
+            tier: Some(AccessTier::Cool),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    blob_client
+        .upload_from_reader(futures::io::Cursor::new(data.clone()), false, Some(options))
+        .await?;
+
+    assert_eq!(data, storage.blob(BLOB).unwrap().data);
+    let requests = storage.requests();
+    let (commit, stages) = requests.split_last().unwrap();
+    assert_eq!(5, stages.len());
+    let mut staged: Vec<(Vec<u8>, Bytes)> = stages
+        .iter()
+        .map(|request| {
+            assert_eq!(Some("block"), request.query("comp").as_deref());
+            assert!(request.header("x-ms-meta-origin").is_none());
+            let block_id = base64::decode(request.query("blockid").unwrap()).unwrap();
+            (block_id, request.body.clone())
+        })
+        .collect();
+    // Block IDs end with the block's index.
+    staged.sort();
+    let staged_data: Vec<u8> = staged.iter().flat_map(|(_, body)| body.to_vec()).collect();
+    assert_eq!(data, staged_data);
+
+    assert_eq!(Some("blocklist"), commit.query("comp").as_deref());
+    assert_eq!(Some("*"), commit.header("if-none-match"));
+    assert_eq!(Some("Cool"), commit.header("x-ms-access-tier"));
+    assert_eq!(Some("backup"), commit.header("x-ms-meta-origin"));
+    assert_eq!(
+        Some("application/octet-stream"),
+        commit.header("x-ms-blob-content-type")
+    );
+    let committed: Vec<Vec<u8>> = storage
+        .blob(BLOB)
+        .unwrap()
+        .blocks
+        .iter()
+        .map(|(block_id, _)| base64::decode(block_id).unwrap())
+        .collect();
+    let staged_ids: Vec<Vec<u8>> = staged.into_iter().map(|(block_id, _)| block_id).collect();
+    assert_eq!(staged_ids, committed);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_from_reader_single_put() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_blob_client()?;
+    let options = BlobClientUploadFromReaderOptions {
+        max_single_put_size: Some(2048),
+        ..Default::default()
+    };
+
+    blob_client
+        .upload_from_reader(
+            futures::io::Cursor::new(vec![1u8; 2048]),
+            true,
+            Some(options),
+        )
+        .await?;
+
+    let requests = storage.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!(None, requests[0].url.query());
+    assert_eq!(Some("BlockBlob"), requests[0].header("x-ms-blob-type"));
+    assert!(requests[0].header("if-none-match").is_none());
+    assert_eq!(vec![1u8; 2048], storage.blob(BLOB).unwrap().data);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_from_reader_sends_checksums() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_blob_client()?;
+    let data: Vec<u8> = (0..3000).map(|i| (i % 251) as u8).collect();
+    let options = BlobClientUploadFromReaderOptions {
+        block_size: Some(1024),
+        max_single_put_size: Some(1024),
+        validate_content_crc64: Some(true),
+        ..Default::default()
+    };
+
+    blob_client
+        .upload_from_reader(futures::io::Cursor::new(data), true, Some(options))
+        .await?;
+
+    let requests = storage.requests();
+    let (_, stages) = requests.split_last().unwrap();
+    assert_eq!(3, stages.len());
+    for request in stages {
+        assert_eq!(
+            Some(base64::encode(content_crc64(&request.body)).as_str()),
+            request.header("x-ms-content-crc64")
+        );
+        assert!(request.header("content-md5").is_none());
+    }
+
+    let (blob_client, storage) = mock_blob_client()?;
+    let options = BlobClientUploadFromReaderOptions {
+        validate_content_md5: Some(true),
+        ..Default::default()
+    };
+    blob_client
+        .upload_from_reader(
+            futures::io::Cursor::new(vec![1u8; 100]),
+            true,
+            Some(options),
+        )
+        .await?;
+    let request = &storage.requests()[0];
+    assert_eq!(
+        Some(base64::encode(content_md5(&request.body)).as_str()),
+        request.header("content-md5")
+    );
+
+    let options = BlobClientUploadFromReaderOptions {
+        validate_content_md5: Some(true),
+        validate_content_crc64: Some(true),
+        ..Default::default()
+    };
+    assert!(blob_client
+        .upload_from_reader(
+            futures::io::Cursor::new(vec![1u8; 100]),
+            true,
+            Some(options)
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_upload_file() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_blob_client()?;
+    let path = std::env::temp_dir().join(format!("upload-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, vec![7u8; 3000])?;
+    let options = BlobClientUploadFromReaderOptions {
+        block_size: Some(1024),
+        max_single_put_size: Some(1024),
+        ..Default::default()
+    };
+
+    let result = blob_client.upload_file(&path, true, Some(options)).await;
+    std::fs::remove_file(&path)?;
+    result?;
+
+    let requests = storage.requests();
+    assert_eq!(4, requests.len());
+    let staged: usize = requests[..3].iter().map(|request| request.body.len()).sum();
+    assert_eq!(3000, staged);
+    assert_eq!(vec![7u8; 3000], storage.blob(BLOB).unwrap().data);
+
+    Ok(())
+}
+
+/// Creates a `BlobClient` for a blob holding `data` with ETag `"etag"`, breaking the first response for the range
+/// starting at `break_at` after 100 bytes, and corrupting the first byte of every range starting at `corrupt_at` after
+/// computing its checksum.
+fn mock_download_client(
+    data: Vec<u8>,
+    break_at: Option<u64>,
+    corrupt_at: Option<u64>,
+) -> Result<(BlobClient, FakeStorage), Box<dyn Error>> {
+    let broken = AtomicBool::new(false);
+    let storage = FakeStorage::with_handler(move |request, state| {
+        let (start, _) = request
+            .header("range")?
+            .trim_start_matches("bytes=")
+            .split_once('-')?;
+        let start: u64 = start.parse().ok()?;
+        let mut response = state.serve(request);
+        if corrupt_at == Some(start) && !response.body.is_empty() {
+            let mut corrupted = response.body.to_vec();
+            corrupted[0] ^= 0xff;
+            response.body = corrupted.into();
+        }
+        if break_at == Some(start) && !broken.swap(true, Ordering::SeqCst) {
+            response.fail_after = Some(100);
+        }
+        Some(response)
+    });
+    storage.state().insert_blob(BLOB, data).etag = "\"etag\"".to_string();
+    Ok((storage.blob_client(BLOB)?, storage))
+}
+
+/// Returns the headers of the ranged requests `storage` received.
+fn range_requests(storage: &FakeStorage) -> Vec<Headers> {
+    storage
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == Method::Get)
+        .map(|request| request.headers)
+        .collect()
+}
+
+fn ranges(storage: &FakeStorage) -> Vec<String> {
+    let mut ranges: Vec<String> = range_requests(storage)
+        .iter()
+        .map(|headers| headers.get_str(&"range".into()).unwrap().to_string())
+        .collect();
+    ranges.sort_by_key(|range| {
+        range
+            .trim_start_matches("bytes=")
+            .split_once('-')
+            .map(|(start, _)| start.parse::<u64>().unwrap())
+    });
+    ranges
+}
+
+#[tokio::test]
+async fn test_download_to_writer_resumes() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    let (blob_client, storage) = mock_download_client(data.clone(), Some(2048), None)?;
+    let options = BlobClientDownloadToOptions {
+        max_concurrency: Some(3),
+        range_size: Some(1024),
+        ..Default::default()
+    };
+
+    let mut downloaded = Vec::new();
+    let size = blob_client
+        .download_to_writer(&mut downloaded, Some(options))
+        .await?;
+
+    assert_eq!(5000, size);
+    assert_eq!(data, downloaded);
+    assert_eq!(
+        vec![
+            "bytes=0-1023",
+            "bytes=1024-2047",
+            "bytes=2048-3071",
+            "bytes=2148-3071",
+            "bytes=3072-4095",
+            "bytes=4096-4999",
+        ],
+        ranges(&storage)
+    );
+    let requests = range_requests(&storage);
+    assert!(requests[0].get_optional_str(&"if-match".into()).is_none());
+    for headers in &requests[1..] {
+        assert_eq!("\"etag\"", headers.get_str(&"if-match".into())?);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_to_writer_validates_md5() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    let (blob_client, storage) = mock_download_client(data.clone(), Some(2048), None)?;
+    let options = BlobClientDownloadToOptions {
+        range_size: Some(1024),
+        validate_content_md5: Some(true),
+        ..Default::default()
+    };
+
+    let mut downloaded = Vec::new();
+    blob_client
+        .download_to_writer(&mut downloaded, Some(options))
+        .await?;
+
+    assert_eq!(data, downloaded);
+    // A partial response can't be validated, so its whole range is requested again.
+    assert_eq!(
+        vec![
+            "bytes=0-1023",
+            "bytes=1024-2047",
+            "bytes=2048-3071",
+            "bytes=2048-3071",
+            "bytes=3072-4095",
+            "bytes=4096-4999",
+        ],
+        ranges(&storage)
+    );
+
+    let options = BlobClientDownloadToOptions {
+        validate_content_md5: Some(true),
+        validate_content_crc64: Some(true),
+        ..Default::default()
+    };
+    assert!(blob_client
+        .download_to_writer(Vec::new(), Some(options))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_to_writer_checksum_mismatch() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    let (blob_client, _) = mock_download_client(data, None, Some(2048))?;
+    let options = BlobClientDownloadToOptions {
+        range_size: Some(1024),
+        validate_content_crc64: Some(true),
+        ..Default::default()
+    };
+
+    let error = blob_client
+        .download_to_writer(Vec::new(), Some(options))
+        .await
+        .unwrap_err();
+
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Crc64, mismatch.algorithm);
+    assert!(mismatch.actual.is_some());
+    assert_ne!(Some(&mismatch.expected), mismatch.actual.as_ref());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_to_writer_empty_blob() -> Result<(), Box<dyn Error>> {
+    let (blob_client, _) = mock_download_client(Vec::new(), None, None)?;
+
+    let mut downloaded = Vec::new();
+    let size = blob_client
+        .download_to_writer(&mut downloaded, None)
+        .await?;
+
+    assert_eq!(0, size);
+    assert!(downloaded.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_read() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..100).collect();
+    let (blob_client, storage) = mock_download_client(data.clone(), None, None)?;
+    let options = BlobClientOpenReadOptions {
+        block_size: Some(16),
+        cache_blocks: Some(3),
+        read_ahead: Some(1),
+        ..Default::default()
+    };
+    let mut reader = blob_client.open_read(Some(options)).await?;
+    assert_eq!(100, reader.size());
+    assert_eq!("\"etag\"", reader.etag());
+
+    // Read a footer, as a Parquet reader does, which requests only the blocks at the end.
+    let mut footer = Vec::new();
+    assert_eq!(90, reader.seek(SeekFrom::End(-10)).await?);
+    reader.read_to_end(&mut footer).await?;
+    assert_eq!(&data[90..], footer);
+    assert_eq!(vec!["bytes=80-95", "bytes=96-99"], ranges(&storage));
+
+    // Reading across blocks requests the next block ahead of time.
+    let mut buf = [0; 20];
+    reader.seek(SeekFrom::Start(10)).await?;
+    reader.read_exact(&mut buf).await?;
+    assert_eq!(&data[10..30], buf);
+    let requested = ranges(&storage);
+    assert!(requested.contains(&"bytes=0-15".to_string()));
+    assert!(requested.contains(&"bytes=16-31".to_string()));
+    assert!(range_requests(&storage)
+        .iter()
+        .all(|headers| headers.get_optional_str(&"if-match".into()) == Some("\"etag\"")));
+
+    // Cached blocks are read again without requests.
+    let requested = range_requests(&storage).len();
+    reader.seek(SeekFrom::Current(-25)).await?;
+    reader.read_exact(&mut buf[..10]).await?;
+    assert_eq!(&data[5..15], &buf[..10]);
+    assert_eq!(requested, range_requests(&storage).len());
+
+    assert_eq!(200, reader.seek(SeekFrom::Start(200)).await?);
+    assert_eq!(0, reader.read(&mut buf).await?);
+    assert!(reader.seek(SeekFrom::Current(-300)).await.is_err());
+
+    assert!(blob_client
+        .open_read(Some(BlobClientOpenReadOptions {
+            cache_blocks: Some(1),
+            read_ahead: Some(1),
+            ..Default::default()
+        }))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_read_validates_md5() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..100).collect();
+    let (blob_client, storage) = mock_download_client(data.clone(), None, Some(32))?;
+    let options = BlobClientOpenReadOptions {
+        block_size: Some(16),
+        read_ahead: Some(0),
+        validate_content_md5: Some(true),
+        ..Default::default()
+    };
+    let mut reader = blob_client.open_read(Some(options)).await?;
+
+    let mut buf = [0; 32];
+    reader.read_exact(&mut buf).await?;
+    assert_eq!(&data[..32], buf);
+    assert!(range_requests(&storage).iter().all(|headers| {
+        headers.get_optional_str(&"x-ms-range-get-content-md5".into()) == Some("true")
+    }));
+
+    let error = reader.read(&mut buf).await.unwrap_err();
+    let error = error
+        .into_inner()
+        .unwrap()
+        .downcast::<azure_core::Error>()
+        .unwrap();
+    assert!(error.downcast_ref::<ChecksumMismatchError>().is_some());
+
+    assert!(blob_client
+        .open_read(Some(BlobClientOpenReadOptions {
+            block_size: Some(8 * 1024 * 1024),
+            validate_content_crc64: Some(true),
+            ..Default::default()
+        }))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_validates_content() -> Result<(), Box<dyn Error>> {
+    // Once `corrupt` is set, the first byte of every upload is corrupted on its way to the service.
+    let corrupt = Arc::new(AtomicBool::new(false));
+    let corrupting = corrupt.clone();
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if !corrupting.load(Ordering::SeqCst) || request.method != Method::Put {
+            return None;
+        }
+        let mut corrupted = request.clone();
+        let mut body = corrupted.body.to_vec();
+        body[0] ^= 0xff;
+        corrupted.body = body.into();
+        Some(state.serve(&corrupted))
+    });
+    let blob_client = storage.blob_client(BLOB)?;
+    let data: Vec<u8> = (0..100).collect();
+
+    blob_client
+        .upload(
+            RequestContent::from(data.clone()),
+            true,
+            data.len() as u64,
+            Some(BlockBlobClientUploadOptions::default().with_validate_content_crc64()),
+        )
+        .await?;
+    let request = &storage.requests()[0];
+    assert_eq!(
+        Some(base64::encode(content_crc64(&data)).as_str()),
+        request.header("x-ms-content-crc64")
+    );
+    assert!(request.header("content-md5").is_none());
+    assert_eq!(data, storage.blob(BLOB).unwrap().data);
+
+    corrupt.store(true, Ordering::SeqCst);
+    let error = blob_client
+        .upload(
+            RequestContent::from(vec![1u8; 100]),
+            true,
+            100,
+            Some(BlockBlobClientUploadOptions::default().with_validate_content_md5()),
+        )
+        .await
+        .unwrap_err();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(&[1u8; 100]).to_vec(), mismatch.expected);
+    assert_eq!(data, storage.blob(BLOB).unwrap().data);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_validates_content() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..100).collect();
+    let (blob_client, storage) = mock_download_client(data.clone(), None, Some(50))?;
+
+    let options = BlobClientDownloadOptions {
+        range: Some("bytes=0-49".to_string()),
+        ..Default::default()
+    };
+    let response = blob_client
+        .download(Some(options.with_validate_content_crc64()))
+        .await?;
+    assert_eq!(&data[..50], response.into_body().collect().await?.as_ref());
+    let headers = &range_requests(&storage)[0];
+    assert_eq!(
+        Some("true"),
+        headers.get_optional_str(&"x-ms-range-get-content-crc64".into())
+    );
+
+    let options = BlobClientDownloadOptions {
+        range: Some("bytes=50-99".to_string()),
+        ..Default::default()
+    };
+    let error = blob_client
+        .download(Some(options.with_validate_content_md5()))
+        .await
+        .unwrap_err();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(&data[50..]).to_vec(), mismatch.expected);
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_download_to_file() -> Result<(), Box<dyn Error>> {
+    let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    let (blob_client, _) = mock_download_client(data.clone(), Some(1024), None)?;
+    let path = std::env::temp_dir().join(format!("download-{}", uuid::Uuid::new_v4()));
+    let options = BlobClientDownloadToOptions {
+        max_concurrency: Some(4),
+        range_size: Some(1024),
+        validate_content_crc64: Some(true),
+        ..Default::default()
+    };
+
+    let size = blob_client.download_to_file(&path, Some(options)).await;
+    let downloaded = std::fs::read(&path);
+    std::fs::remove_file(&path)?;
+
+    assert_eq!(5000, size?);
+    assert_eq!(data, downloaded?);
+
+    Ok(())
+}
+
+/// Creates a `BlobClient` whose copy `copy1` reports each of `statuses`, with its progress, in turn, starting with the
+/// response to `Copy Blob`. An abort request makes the copy report `aborted`.
+fn mock_copy_client(
+    statuses: &'static [(&'static str, Option<&'static str>)],
+) -> Result<(BlobClient, FakeStorage), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if request.header("x-ms-copy-action").is_some() {
+            return Some(FakeResponse::new(StatusCode::NoContent));
+        }
+        let aborted = state
+            .requests
+            .iter()
+            .any(|request| request.header("x-ms-copy-action") == Some("abort"));
+        let (copy_status, copy_progress) = if aborted {
+            ("aborted", None)
+        } else {
+            statuses[(state.requests.len() - 1).min(statuses.len() - 1)]
+        };
+        let status = if state.requests.len() == 1 {
+            assert_eq!(
+                Some("https://source.blob.core.windows.net/container/blob?sig=signature"),
+                request.header("x-ms-copy-source")
+            );
+            StatusCode::Accepted
+        } else {
+            StatusCode::Ok
+        };
+        let mut response = FakeResponse::new(status)
+            .with_header("x-ms-copy-id", "copy1")
+            .with_header("x-ms-copy-status", copy_status);
+        if let Some(copy_progress) = copy_progress {
+            response = response
+                .with_header("x-ms-copy-progress", copy_progress)
+                .with_header("content-length", "1024");
+        }
+        Some(response)
+    });
+    Ok((storage.blob_client(BLOB)?, storage))
+}
+
+/// Describes each copy request `storage` received by its method and, if set, copy action and ID.
+fn copy_requests(storage: &FakeStorage) -> Vec<String> {
+    storage
+        .requests()
+        .iter()
+        .map(|request| {
+            let copy_action = request
+                .header("x-ms-copy-action")
+                .map(|copy_action| {
+                    let copy_id = request
+                        .url
+                        .query_pairs()
+                        .find(|(name, value)| name == "copyid" && !value.is_empty())
+                        .map(|(_, value)| value.into_owned())
+                        .unwrap_or_default();
+                    format!("{copy_action} {copy_id}")
+                })
+                .unwrap_or_default();
+            format!("{} {copy_action}", request.method)
+        })
+        .collect()
+}
+
+fn copy_options() -> Option<BlobClientBeginCopyFromUrlOptions<'static>> {
+    Some(BlobClientBeginCopyFromUrlOptions {
+        method_options: PollerOptions {
+            frequency: azure_core::time::Duration::seconds(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_begin_copy_from_url() -> Result<(), Box<dyn Error>> {
+    let source = Url::parse("https://source.blob.core.windows.net/container/blob?sig=signature")?;
+
+    // The service may complete the copy before responding.
+    let (blob_client, storage) =
+        mock_copy_client(&[("success", None), ("success", Some("1024/1024"))])?;
+    let properties = blob_client
+        .begin_copy_from_url(source.clone(), copy_options())?
+        .await?;
+    assert_eq!(Some(1024), properties.content_length()?);
+    assert_eq!(vec!["PUT ", "HEAD "], copy_requests(&storage));
+
+    let (blob_client, storage) = mock_copy_client(&[
+        ("pending", None),
+        ("pending", Some("512/1024")),
+        ("success", Some("1024/1024")),
+    ])?;
+    let mut poller = blob_client.begin_copy_from_url(source.clone(), copy_options())?;
+    let operation = poller.try_next().await?.unwrap().into_model()?;
+    assert_eq!(Some("copy1".into()), operation.copy_id);
+    assert_eq!(Some(CopyStatus::Pending), operation.copy_status);
+    let operation = poller.try_next().await?.unwrap().into_model()?;
+    assert_eq!(Some(512), operation.bytes_copied);
+    assert_eq!(Some(1024), operation.total_bytes);
+    let properties = poller.await?;
+    assert_eq!(Some(CopyStatus::Success), properties.copy_status()?);
+    assert_eq!(Some("1024/1024".into()), properties.copy_progress()?);
+    assert_eq!(3, storage.requests().len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_begin_copy_from_url_abort() -> Result<(), Box<dyn Error>> {
+    let source = Url::parse("https://source.blob.core.windows.net/container/blob?sig=signature")?;
+    let (blob_client, storage) = mock_copy_client(&[("pending", Some("0/1024"))])?;
+
+    let mut poller = blob_client.begin_copy_from_url(source, copy_options())?;
+    let operation = poller.try_next().await?.unwrap().into_model()?;
+    blob_client
+        .abort_copy_from_url(operation.copy_id.as_deref().unwrap(), None)
+        .await?;
+    let operation = poller.try_next().await?.unwrap().into_model()?;
+    assert_eq!(Some(CopyStatus::Aborted), operation.copy_status);
+    assert!(poller.try_next().await?.is_none());
+    assert_eq!(
+        vec!["PUT ", "PUT abort copy1", "HEAD "],
+        copy_requests(&storage)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshots_and_versions() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(|request, _| {
+        let response = match request.query("comp").as_deref() {
+            Some("snapshot") => FakeResponse::new(StatusCode::Created)
+                .with_header("x-ms-snapshot", "2025-01-01T00:00:00.0000000Z"),
+            _ if request.header("x-ms-copy-source").is_some() => {
+                FakeResponse::new(StatusCode::Accepted)
+                    .with_header("x-ms-copy-id", "copy1")
+                    .with_header("x-ms-copy-status", "success")
+            }
+            _ => FakeResponse::new(StatusCode::Ok),
+        };
+        Some(response)
+    });
+    let options = BlobClientOptions {
+        client_options: storage.client_options(),
+        ..Default::default()
+    };
+    let blob_client = BlobClient::from_url(
+        Url::parse("https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb")?,
+        None,
+        Some(options),
+    )?;
+
+    let snapshot = blob_client
+        .create_snapshot(None)
+        .await?
+        .snapshot()?
+        .unwrap();
+    let snapshot_client = blob_client.with_snapshot(&snapshot);
+    assert_eq!(
+        "https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&snapshot=2025-01-01T00%3A00%3A00.0000000Z",
+        snapshot_client.url().as_str()
+    );
+    snapshot_client.get_properties(None).await?;
+
+    // A client for a version replaces the snapshot its URL selects.
+    let version_client = snapshot_client.with_version_id("v1");
+    assert_eq!(
+        "https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&versionid=v1",
+        version_client.url().as_str()
+    );
+
+    blob_client.undelete(None).await?;
+    let properties = version_client.restore_version("v2", None)?.await?;
+    assert_eq!(StatusCode::Ok, properties.status());
+
+    assert_eq!(
+        vec![
+            "PUT https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&comp=snapshot",
+            "HEAD https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&snapshot=2025-01-01T00%3A00%3A00.0000000Z",
+            "PUT https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&comp=undelete",
+            "PUT https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb from https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb&versionid=v2",
+            "HEAD https://account.blob.core.windows.net/container/blob?sv=2025-01-05&sig=a%2Bb",
+        ],
+        storage
+            .requests()
+            .iter()
+            .map(|request| match request.header("x-ms-copy-source") {
+                Some(copy_source) => format!("{} {} from {copy_source}", request.method, request.url),
+                None => format!("{} {}", request.method, request.url),
+            })
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+fn mock_lease_client(
+    renew_status: StatusCode,
+) -> Result<(BlobClient, FakeStorage), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(move |request, _| {
+        let lease_action = request.header("x-ms-lease-action").unwrap();
+        if lease_action != "acquire" {
+            assert_eq!(Some("lease1"), request.header("x-ms-lease-id"));
+        }
+        let response = match lease_action {
+            "acquire" => {
+                assert_eq!(Some("15"), request.header("x-ms-lease-duration"));
+                FakeResponse::new(StatusCode::Created).with_header("x-ms-lease-id", "lease1")
+            }
+            "renew" => FakeResponse::new(renew_status),
+            _ => FakeResponse::new(StatusCode::Ok),
+        };
+        Some(response)
+    });
+    Ok((storage.blob_client(BLOB)?, storage))
+}
+
+/// Returns the lease action of each request `storage` received.
+fn lease_actions(storage: &FakeStorage) -> Vec<String> {
+    storage
+        .requests()
+        .iter()
+        .filter_map(|request| request.header("x-ms-lease-action").map(String::from))
+        .collect()
+}
+
+fn lease_options() -> Option<BlobLeaseClientAcquireOptions<'static>> {
+    Some(BlobLeaseClientAcquireOptions {
+        renewal_interval: Some(azure_core::time::Duration::milliseconds(50)),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_lease_client_renews_and_releases() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_lease_client(StatusCode::Ok)?;
+    assert!(BlobLeaseClient::acquire(&blob_client, 10, None)
+        .await
+        .is_err());
+    assert!(storage.requests().is_empty());
+
+    let lease = BlobLeaseClient::acquire(&blob_client, 15, lease_options()).await?;
+    assert_eq!("lease1", lease.lease_id());
+    time::sleep(Duration::from_millis(180)).await;
+    assert!(!lease.is_lost());
+    lease.release().await?;
+
+    let requests = lease_actions(&storage);
+    assert_eq!(Some("acquire"), requests.first().map(String::as_str));
+    assert_eq!(Some("release"), requests.last().map(String::as_str));
+    let renewals = requests.iter().filter(|action| *action == "renew").count();
+    assert!(renewals >= 2, "{renewals} renewals");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lease_client_releases_on_drop() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_lease_client(StatusCode::Ok)?;
+    let lease = BlobLeaseClient::acquire(&blob_client, 15, lease_options()).await?;
+    drop(lease);
+
+    for _ in 0..100 {
+        if lease_actions(&storage)
+            .iter()
+            .any(|action| action == "release")
+        {
+            return Ok(());
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    Err("lease wasn't released".into())
+}
+
+#[tokio::test]
+async fn test_lease_client_lost() -> Result<(), Box<dyn Error>> {
+    let (blob_client, storage) = mock_lease_client(StatusCode::Conflict)?;
+    let lease = BlobLeaseClient::acquire(&blob_client, 15, lease_options()).await?;
+
+    time::timeout(Duration::from_secs(5), lease.lost()).await?;
+    assert!(lease.is_lost());
+    assert!(lease.release().await.is_err());
+    assert_eq!(vec!["acquire", "renew"], lease_actions(&storage));
+
+    Ok(())
+}
+
+/// The schema of the events of a query response, as the service sends it.
+const QUERY_SCHEMA: &str = r#"[
+    {"type": "record", "name": "com.microsoft.azure.storage.queryBlobContents.resultData",
+     "fields": [{"name": "data", "type": "bytes"}]},
+    {"type": "record", "name": "com.microsoft.azure.storage.queryBlobContents.error",
+     "fields": [{"name": "fatal", "type": "boolean"}, {"name": "name", "type": "string"},
+                {"name": "description", "type": "string"}, {"name": "position", "type": "long"}]},
+    {"type": "record", "name": "com.microsoft.azure.storage.queryBlobContents.progress",
+     "fields": [{"name": "bytesScanned", "type": "long"}, {"name": "totalBytes", "type": "long"}]},
+    {"type": "record", "name": "com.microsoft.azure.storage.queryBlobContents.end",
+     "fields": [{"name": "totalBytes", "type": "long"}]}
+]"#;
+
+fn avro_long(out: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn avro_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    avro_long(out, bytes.len() as i64);
+    out.extend_from_slice(bytes);
+}
+
+fn result_data_event(data: &[u8]) -> Vec<u8> {
+    let mut event = Vec::new();
+    avro_long(&mut event, 0);
+    avro_bytes(&mut event, data);
+    event
+}
+
+fn error_event(fatal: bool, name: &str, position: i64) -> Vec<u8> {
+    let mut event = Vec::new();
+    avro_long(&mut event, 1);
+    event.push(fatal.into());
+    avro_bytes(&mut event, name.as_bytes());
+    avro_bytes(&mut event, b"description");
+    avro_long(&mut event, position);
+    event
+}
+
+fn progress_event(bytes_scanned: i64, total_bytes: i64) -> Vec<u8> {
+    let mut event = Vec::new();
+    avro_long(&mut event, 2);
+    avro_long(&mut event, bytes_scanned);
+    avro_long(&mut event, total_bytes);
+    event
+}
+
+fn end_event(total_bytes: i64) -> Vec<u8> {
+    let mut event = Vec::new();
+    avro_long(&mut event, 3);
+    avro_long(&mut event, total_bytes);
+    event
+}
+
+/// Encodes an Avro object container file of events, with the first two events in one block.
+fn query_response(events: &[Vec<u8>]) -> Vec<u8> {
+    let sync = [7u8; 16];
+    let mut file = b"Obj\x01".to_vec();
+    avro_long(&mut file, 2);
+    avro_bytes(&mut file, b"avro.schema");
+    avro_bytes(&mut file, QUERY_SCHEMA.as_bytes());
+    avro_bytes(&mut file, b"avro.codec");
+    avro_bytes(&mut file, b"null");
+    avro_long(&mut file, 0);
+    file.extend_from_slice(&sync);
+    let split = events.len().min(2);
+    for block in [&events[..split]]
+        .into_iter()
+        .chain(events[split..].chunks(1))
+    {
+        let data = block.concat();
+        avro_long(&mut file, block.len() as i64);
+        avro_long(&mut file, data.len() as i64);
+        file.extend_from_slice(&data);
+        file.extend_from_slice(&sync);
+    }
+    file
+}
+
+/// Returns a client whose query responses are `response`, streamed in chunks of 5 bytes.
+fn mock_query_client(response: Vec<u8>) -> Result<(BlobClient, FakeStorage), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(move |request, _| {
+        assert_eq!(Method::Post, request.method);
+        assert_eq!(Some("query"), request.query("comp").as_deref());
+        let mut response = FakeResponse::new(StatusCode::Ok).with_body(response.clone());
+        response.chunk_size = Some(5);
+        Some(response)
+    });
+    Ok((storage.blob_client(BLOB)?, storage))
+}
+
+/// Returns the body of the last query request `storage` received.
+fn query_request_body(storage: &FakeStorage) -> String {
+    let requests = storage.requests();
+    String::from_utf8(requests.last().unwrap().body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_query_csv() -> Result<(), Box<dyn Error>> {
+    // The first record has a quoted field holding a separator, and is split across events.
+    let response = query_response(&[
+        progress_event(0, 100),
+        result_data_event(b"1,\"a,\nb"),
+        error_event(false, "InvalidRecord", 40),
+        result_data_event(b"\"\n2,c\n"),
+        progress_event(100, 100),
+        end_event(100),
+    ]);
+    let (blob_client, storage) = mock_query_client(response)?;
+    let query = || {
+        blob_client.query(
+            "SELECT * FROM BlobStorage",
+            QueryFormat::csv(true),
+            QueryFormat::csv(false),
+            None,
+        )
+    };
+
+    let events: Vec<BlobQueryEvent> = query().await?.try_collect().await?;
+    assert_eq!(6, events.len());
+    assert!(matches!(
+        events[0],
+        BlobQueryEvent::Progress {
+            bytes_scanned: 0,
+            total_bytes: 100
+        }
+    ));
+    assert!(matches!(&events[1], BlobQueryEvent::Data(data) if data.as_ref() == b"1,\"a,\nb"));
+    assert!(matches!(
+        &events[2],
+        BlobQueryEvent::Error(error) if !error.fatal && error.name == "InvalidRecord" && error.position == 40
+    ));
+    assert!(matches!(
+        events[5],
+        BlobQueryEvent::End { total_bytes: 100 }
+    ));
+
+    let body = query_request_body(&storage);
+    for element in [
+        "<Expression>SELECT * FROM BlobStorage</Expression>",
+        "<QueryType>SQL</QueryType>",
+        "<Type>delimited</Type>",
+        "<HasHeaders>true</HasHeaders>",
+        "<ColumnSeparator>,</ColumnSeparator>",
+    ] {
+        assert!(body.contains(element), "{element} not in {body}");
+    }
+
+    let rows: Vec<Bytes> = query().await?.rows().try_collect().await?;
+    assert_eq!(
+        vec![
+            Bytes::from_static(b"1,\"a,\nb\""),
+            Bytes::from_static(b"2,c")
+        ],
+        rows
+    );
+    let records: Vec<(String, String)> = query().await?.records().try_collect().await?;
+    assert_eq!(
+        vec![
+            ("1".to_string(), "a,\nb".to_string()),
+            ("2".to_string(), "c".to_string())
+        ],
+        records
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_query_json_and_errors() -> Result<(), Box<dyn Error>> {
+    let response = query_response(&[
+        result_data_event(b"{\"n\":1}\n{\"n\""),
+        result_data_event(b":2}\n"),
+        end_event(10),
+    ]);
+    let (blob_client, storage) = mock_query_client(response)?;
+    let records: Vec<HashMap<String, i64>> = blob_client
+        .query(
+            "SELECT n FROM BlobStorage",
+            QueryFormat::parquet(),
+            QueryFormat::json("\n"),
+            None,
+        )
+        .await?
+        .records()
+        .try_collect()
+        .await?;
+    assert_eq!(
+        vec![1, 2],
+        records.iter().map(|r| r["n"]).collect::<Vec<_>>()
+    );
+    assert!(query_request_body(&storage).contains("<Type>parquet</Type>"));
+
+    // A fatal error fails the stream of data.
+    let response = query_response(&[
+        result_data_event(b"1\n"),
+        error_event(true, "ParseError", 2),
+        end_event(10),
+    ]);
+    let (blob_client, _) = mock_query_client(response)?;
+    let error = blob_client
+        .query(
+            "SELECT * FROM BlobStorage",
+            QueryFormat::csv(false),
+            QueryFormat::csv(false),
+            None,
+        )
+        .await?
+        .into_data()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    let source = error.into_inner()?;
+    let error = source.downcast_ref::<BlobQueryError>().unwrap();
+    assert_eq!("ParseError", error.name);
+
+    // A response that ends before its end event is an error.
+    let mut response = query_response(&[result_data_event(b"1\n"), end_event(10)]);
+    response.truncate(response.len() - 20);
+    let (blob_client, _) = mock_query_client(response)?;
+    let events = blob_client
+        .query(
+            "SELECT * FROM BlobStorage",
+            QueryFormat::csv(false),
+            QueryFormat::csv(false),
+            None,
+        )
+        .await?
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(events.is_err());
+
+    Ok(())
+}
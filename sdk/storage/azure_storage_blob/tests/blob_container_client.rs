@@ -1,18 +1,26 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use azure_core::http::{RequestContent, StatusCode};
+use azure_core::{
+    credentials::Secret,
+    http::{multipart, RequestContent, StatusCode},
+};
 use azure_core_test::{recorded, Matcher, TestContext, TestMode};
-use azure_storage_blob::format_filter_expression;
 use azure_storage_blob::models::{
-    AccountKind, BlobContainerClientAcquireLeaseResultHeaders,
+    AccessTier, AccountKind, BlobContainerClientAcquireLeaseResultHeaders,
     BlobContainerClientChangeLeaseResultHeaders, BlobContainerClientGetAccountInfoResultHeaders,
     BlobContainerClientGetPropertiesResultHeaders, BlobContainerClientListBlobFlatSegmentOptions,
-    BlobContainerClientSetMetadataOptions, BlobType, BlockBlobClientUploadOptions, LeaseState,
+    BlobContainerClientListBlobHierarchySegmentOptions, BlobContainerClientSetMetadataOptions,
+    BlobContainerClientWalkBlobsOptions, BlobHierarchyItem, BlobType, BlockBlobClientUploadOptions,
+    LeaseState, ListBlobsIncludeItem,
+};
+use azure_storage_blob::{
+    format_filter_expression, BlobBatch, BlobContainerClient, BlobContainerClientOptions,
+    SharedKeyCredential,
 };
 use azure_storage_blob_test::{
     create_test_blob, get_blob_name, get_blob_service_client, get_container_client,
-    get_container_name,
+    get_container_name, FakeResponse, FakeStorage, FakeStorageExt,
 };
 use futures::{StreamExt, TryStreamExt};
 use std::{collections::HashMap, error::Error, time::Duration};
@@ -400,3 +408,237 @@ async fn test_find_blobs_by_tags_container(ctx: TestContext) -> Result<(), Box<d
     container_client.delete_container(None).await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_submit_batch() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(|request, _| {
+        assert_eq!("/container", request.url.path());
+        assert_eq!(Some("restype=container&comp=batch"), request.url.query());
+        let parts =
+            multipart::decode(request.header("content-type").unwrap(), &request.body).unwrap();
+        let mut response = String::new();
+        for part in &parts {
+            let message = std::str::from_utf8(&part.body).unwrap();
+            assert!(message.starts_with("PUT /container/blob"));
+            assert!(message.contains("x-ms-access-tier: Cool\r\n"));
+            response.push_str(&format!(
+                "--batchresponse_1\r\nContent-Type: application/http\r\nContent-ID: {}\r\n\r\nHTTP/1.1 200 OK\r\n\r\n\r\n",
+                part.headers.get_str(&"content-id".into()).unwrap()
+            ));
+        }
+        response.push_str("--batchresponse_1--\r\n");
+        Some(
+            FakeResponse::new(StatusCode::Accepted)
+                .with_header("content-type", "multipart/mixed; boundary=batchresponse_1")
+                .with_body(response),
+        )
+    });
+    let options = BlobContainerClientOptions {
+        client_options: storage.client_options(),
+        ..Default::default()
+    };
+    let container_client = BlobContainerClient::with_shared_key(
+        "https://myaccount.blob.core.windows.net/",
+        "container",
+        SharedKeyCredential::new("myaccount", Secret::from("a2V5"))?,
+        Some(options),
+    )?;
+
+    let mut batch = BlobBatch::new();
+    for i in 0..3 {
+        batch.set_blob_tier("container", &format!("blob{i}"), AccessTier::Cool, None)?;
+    }
+    let results = container_client.submit_batch(batch, None).await?;
+    assert_eq!(3, results.len());
+    assert!(results.iter().all(Result::is_ok));
+
+    // Operations on other containers can only be submitted at service scope.
+    let mut batch = BlobBatch::new();
+    batch.set_blob_tier("other", "blob", AccessTier::Cool, None)?;
+    assert!(container_client.submit_batch(batch, None).await.is_err());
+    assert!(container_client
+        .submit_batch(BlobBatch::new(), None)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+/// Creates a `BlobContainerClient` whose container has blobs named `names`, listing at most `page_size` blobs and
+/// virtual directories in each page.
+fn mock_hierarchy_client(
+    names: &[&str],
+    page_size: usize,
+) -> Result<(BlobContainerClient, FakeStorage), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    {
+        let mut state = storage.state();
+        state.page_size = Some(page_size);
+        for name in names {
+            state.insert_blob(&format!("container/{name}"), Vec::new());
+        }
+    }
+    Ok((storage.container_client("container")?, storage))
+}
+
+/// Returns the prefix and marker of each listing `storage` received.
+fn listings(storage: &FakeStorage) -> Vec<(String, usize)> {
+    storage
+        .requests()
+        .iter()
+        .map(|request| {
+            assert_eq!(Some("/"), request.query("delimiter").as_deref());
+            (
+                request.query("prefix").unwrap_or_default(),
+                request
+                    .query("marker")
+                    .map_or(0, |marker| marker.parse().unwrap()),
+            )
+        })
+        .collect()
+}
+
+fn hierarchy_names(items: &[BlobHierarchyItem]) -> Vec<String> {
+    items
+        .iter()
+        .map(|item| match item {
+            BlobHierarchyItem::Blob(_) => item.name().unwrap().to_string(),
+            BlobHierarchyItem::Prefix(_) => format!("{} (prefix)", item.name().unwrap()),
+        })
+        .collect()
+}
+
+const HIERARCHY: &[&str] = &["a", "dir1/b", "dir1/sub/c", "dir1/sub/d", "dir2/e", "z"];
+
+#[tokio::test]
+async fn test_list_blobs_by_hierarchy() -> Result<(), Box<dyn Error>> {
+    let (container_client, storage) = mock_hierarchy_client(HIERARCHY, 2)?;
+
+    let items: Vec<BlobHierarchyItem> = container_client
+        .list_blobs_by_hierarchy("/", None)?
+        .try_collect()
+        .await?;
+    assert_eq!(
+        vec!["a", "dir1/ (prefix)", "dir2/ (prefix)", "z"],
+        hierarchy_names(&items)
+    );
+    assert_eq!(
+        vec![(String::new(), 0), (String::new(), 2)],
+        listings(&storage)
+    );
+
+    let options = BlobContainerClientListBlobHierarchySegmentOptions {
+        prefix: Some("dir1/".into()),
+        ..Default::default()
+    };
+    let items: Vec<BlobHierarchyItem> = container_client
+        .list_blobs_by_hierarchy("/", Some(options))?
+        .try_collect()
+        .await?;
+    assert_eq!(
+        vec!["dir1/b", "dir1/sub/ (prefix)"],
+        hierarchy_names(&items)
+    );
+
+    // Blobs and virtual directories are interleaved in a single page.
+    let (container_client, _) = mock_hierarchy_client(HIERARCHY, 10)?;
+    let items: Vec<BlobHierarchyItem> = container_client
+        .list_blobs_by_hierarchy("/", None)?
+        .try_collect()
+        .await?;
+    assert_eq!(
+        vec!["a", "dir1/ (prefix)", "dir2/ (prefix)", "z"],
+        hierarchy_names(&items)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_blobs() -> Result<(), Box<dyn Error>> {
+    let (container_client, storage) = mock_hierarchy_client(HIERARCHY, 2)?;
+
+    let options = BlobContainerClientWalkBlobsOptions {
+        max_concurrency: Some(2),
+        ..Default::default()
+    };
+    let mut names = hierarchy_names(
+        &container_client
+            .walk_blobs("/", Some(options))?
+            .try_collect::<Vec<_>>()
+            .await?,
+    );
+    names.sort();
+    assert_eq!(
+        vec![
+            "a",
+            "dir1/ (prefix)",
+            "dir1/b",
+            "dir1/sub/ (prefix)",
+            "dir1/sub/c",
+            "dir1/sub/d",
+            "dir2/ (prefix)",
+            "dir2/e",
+            "z"
+        ],
+        names
+    );
+    let mut requests = listings(&storage);
+    requests.sort();
+    assert_eq!(
+        vec![
+            (String::new(), 0),
+            (String::new(), 2),
+            ("dir1/".to_string(), 0),
+            ("dir1/sub/".to_string(), 0),
+            ("dir2/".to_string(), 0),
+        ],
+        requests
+    );
+
+    let options = BlobContainerClientWalkBlobsOptions {
+        list_options: BlobContainerClientListBlobHierarchySegmentOptions {
+            prefix: Some("dir1/".into()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let items: Vec<BlobHierarchyItem> = container_client
+        .walk_blobs("/", Some(options))?
+        .try_collect()
+        .await?;
+    assert_eq!(4, items.len());
+
+    let options = BlobContainerClientWalkBlobsOptions {
+        max_concurrency: Some(0),
+        ..Default::default()
+    };
+    assert!(container_client.walk_blobs("/", Some(options)).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_list_blobs_with_include() {
+    let options = BlobContainerClientListBlobFlatSegmentOptions {
+        include: Some(vec![ListBlobsIncludeItem::Metadata]),
+        ..Default::default()
+    }
+    .with_include([
+        ListBlobsIncludeItem::Versions,
+        ListBlobsIncludeItem::Metadata,
+    ])
+    .with_include([
+        ListBlobsIncludeItem::Snapshots,
+        ListBlobsIncludeItem::Deleted,
+    ]);
+    assert_eq!(
+        Some(vec![
+            ListBlobsIncludeItem::Metadata,
+            ListBlobsIncludeItem::Versions,
+            ListBlobsIncludeItem::Snapshots,
+            ListBlobsIncludeItem::Deleted,
+        ]),
+        options.include
+    );
+}
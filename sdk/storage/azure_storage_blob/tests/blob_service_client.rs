@@ -1,17 +1,24 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use azure_core::http::{RequestContent, XmlFormat};
+use azure_core::{
+    credentials::Secret,
+    error::ErrorKind,
+    http::{multipart, RequestContent, StatusCode, XmlFormat},
+};
 use azure_core_test::{recorded, TestContext, TestMode};
-use azure_storage_blob::format_filter_expression;
 use azure_storage_blob::models::{
-    AccountKind, BlobServiceClientGetAccountInfoResultHeaders,
+    AccessTier, AccountKind, BlobServiceClientGetAccountInfoResultHeaders,
     BlobServiceClientGetPropertiesOptions, BlobServiceClientListContainersSegmentOptions,
-    BlobServiceProperties, BlockBlobClientUploadOptions,
+    BlobServiceClientSubmitBatchOptions, BlobServiceProperties, BlockBlobClientUploadOptions,
+};
+use azure_storage_blob::{
+    format_filter_expression, BlobBatch, BlobServiceClient, BlobServiceClientOptions,
+    SharedKeyCredential,
 };
 use azure_storage_blob_test::{
     create_test_blob, get_blob_name, get_blob_service_client, get_container_client,
-    get_container_name,
+    get_container_name, FakeResponse, FakeStorage,
 };
 use futures::StreamExt;
 use std::{collections::HashMap, error::Error, time::Duration};
@@ -261,3 +268,140 @@ async fn test_find_blobs_by_tags_service(ctx: TestContext) -> Result<(), Box<dyn
     container_client_2.delete_container(None).await?;
     Ok(())
 }
+
+#[test]
+fn test_from_connection_string() -> Result<(), Box<dyn Error>> {
+    let service_client = BlobServiceClient::from_connection_string(
+        Secret::from("DefaultEndpointsProtocol=https;AccountName=myaccount;AccountKey=a2V5;EndpointSuffix=core.windows.net"),
+        None,
+    )?;
+    assert_eq!(
+        "https://myaccount.blob.core.windows.net/",
+        service_client.url().as_str()
+    );
+
+    let service_client = BlobServiceClient::from_connection_string(
+        Secret::from("UseDevelopmentStorage=true"),
+        None,
+    )?;
+    assert_eq!(
+        "http://127.0.0.1:10000/devstoreaccount1/container",
+        service_client
+            .blob_container_client("container")
+            .url()
+            .as_str()
+    );
+
+    let service_client = BlobServiceClient::from_connection_string(
+        Secret::from(
+            "BlobEndpoint=https://blob.contoso.com/;SharedAccessSignature=?sv=2022-11-02&sig=abc",
+        ),
+        None,
+    )?;
+    assert_eq!(
+        "https://blob.contoso.com/container?sv=2022-11-02&sig=abc",
+        service_client
+            .blob_container_client("container")
+            .url()
+            .as_str()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submit_batch() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::with_handler(|request, _| {
+        assert_eq!(Some("comp=batch&timeout=30"), request.url.query());
+        assert!(request
+            .header("content-type")
+            .unwrap()
+            .starts_with("multipart/mixed; boundary=batch_"));
+
+        // The service may return responses in any order.
+        let body = "--batchresponse_1\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: 1\r\n\
+            \r\n\
+            HTTP/1.1 404 The specified blob does not exist.\r\n\
+            x-ms-error-code: BlobNotFound\r\n\
+            \r\n\
+            \r\n\
+            --batchresponse_1\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: 0\r\n\
+            \r\n\
+            HTTP/1.1 202 Accepted\r\n\
+            x-ms-delete-type-permanent: true\r\n\
+            \r\n\
+            \r\n\
+            --batchresponse_1--\r\n";
+        Some(
+            FakeResponse::new(StatusCode::Accepted)
+                .with_header("content-type", "multipart/mixed; boundary=batchresponse_1")
+                .with_body(body),
+        )
+    });
+    let options = BlobServiceClientOptions {
+        client_options: storage.client_options(),
+        ..Default::default()
+    };
+    let service_client = BlobServiceClient::with_shared_key(
+        "https://myaccount.blob.core.windows.net/",
+        SharedKeyCredential::new("myaccount", Secret::from("a2V5"))?,
+        Some(options),
+    )?;
+
+    let mut batch = BlobBatch::new();
+    batch.delete_blob("container1", "blob1", None)?;
+    batch.delete_blob("container2", "dir/blob2", None)?;
+    let results = service_client
+        .submit_batch(
+            batch,
+            Some(BlobServiceClientSubmitBatchOptions {
+                timeout: Some(30),
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    let request = &storage.requests()[0];
+    let sub_requests = multipart::decode(request.header("content-type").unwrap(), &request.body)?;
+    assert_eq!(2, sub_requests.len());
+    for (index, (part, path)) in sub_requests
+        .iter()
+        .zip(["/container1/blob1", "/container2/dir%2Fblob2"])
+        .enumerate()
+    {
+        assert_eq!(
+            index.to_string(),
+            part.headers.get_str(&"content-id".into())?
+        );
+        let message = std::str::from_utf8(&part.body)?;
+        assert!(message.starts_with(&format!("DELETE {path} HTTP/1.1\r\n")));
+        assert!(message.contains("authorization: SharedKey myaccount:"));
+        assert!(message.contains("x-ms-date: "));
+        assert!(!message.contains("x-ms-version"));
+    }
+
+    assert_eq!(2, results.len());
+    assert!(results[0].is_ok());
+    let error = results[1].as_ref().unwrap_err();
+    assert_eq!(Some(StatusCode::NotFound), error.http_status());
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::HttpResponse { error_code: Some(error_code), .. } if error_code == "BlobNotFound"
+    ));
+
+    let mut batch = BlobBatch::new();
+    batch.delete_blob("container", "blob", None)?;
+    assert!(batch
+        .set_blob_tier("container", "blob", AccessTier::Cool, None)
+        .is_err());
+    for i in 1..256 {
+        batch.delete_blob("container", &format!("blob{i}"), None)?;
+    }
+    assert!(batch.delete_blob("container", "blob256", None).is_err());
+
+    Ok(())
+}
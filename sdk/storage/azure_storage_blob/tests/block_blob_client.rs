@@ -2,15 +2,19 @@
 // Licensed under the MIT License.
 
 use azure_core::{
+    base64,
     http::{RequestContent, StatusCode},
     Bytes,
 };
 use azure_core_test::{recorded, TestContext};
 use azure_storage_blob::models::{
-    BlobClientDownloadResultHeaders, BlockBlobClientUploadBlobFromUrlOptions, BlockListType,
-    BlockLookupList,
+    BlobClientDownloadResultHeaders, BlockBlobClientStageBlockOptions,
+    BlockBlobClientUploadBlobFromUrlOptions, BlockListType, BlockLookupList,
 };
-use azure_storage_blob_test::{create_test_blob, get_blob_name, get_container_client};
+use azure_storage_blob_test::{
+    create_test_blob, get_blob_name, get_container_client, FakeStorage, FakeStorageExt,
+};
+use azure_storage_common::{content_crc64, content_md5, ChecksumAlgorithm, ChecksumMismatchError};
 use std::error::Error;
 
 #[recorded::test]
@@ -197,3 +201,48 @@ async fn test_upload_blob_from_url(ctx: TestContext) -> Result<(), Box<dyn Error
     container_client.delete_container(None).await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_stage_block_validates_content() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let block_blob_client = storage.block_blob_client("container/blob")?;
+    block_blob_client
+        .stage_block(
+            b"block",
+            5,
+            RequestContent::from(b"hello".to_vec()),
+            Some(BlockBlobClientStageBlockOptions::default().with_validate_content_crc64()),
+        )
+        .await?;
+    let request = &storage.requests()[0];
+    assert_eq!(
+        Some(base64::encode(content_crc64(b"hello")).as_str()),
+        request.header("x-ms-content-crc64")
+    );
+
+    // The first byte of every block is corrupted on its way to the service.
+    let storage = FakeStorage::with_handler(|request, state| {
+        let mut corrupted = request.clone();
+        let mut body = corrupted.body.to_vec();
+        body[0] ^= 0xff;
+        corrupted.body = body.into();
+        Some(state.serve(&corrupted))
+    });
+    let error = storage
+        .block_blob_client("container/blob")?
+        .stage_block(
+            b"block",
+            5,
+            RequestContent::from(b"hello".to_vec()),
+            Some(BlockBlobClientStageBlockOptions::default().with_validate_content_md5()),
+        )
+        .await
+        .unwrap_err();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(b"hello").to_vec(), mismatch.expected);
+
+    Ok(())
+}
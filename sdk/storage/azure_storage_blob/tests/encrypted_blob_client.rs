@@ -0,0 +1,212 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+#![cfg(feature = "client_side_encryption")]
+
+use azure_core::http::Method;
+use azure_storage_blob::{
+    encryption::{
+        ClientSideEncryptionOptions, EncryptionData, KeyEncryptionKey, KeyEncryptionKeyResolver,
+        LocalKeyEncryptionKey,
+    },
+    models::{BlobClientDownloadOptions, BlobClientUploadFromReaderOptions},
+};
+use azure_storage_blob_test::{FakeStorage, FakeStorageExt};
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// The path of the blob the fake storage clients address.
+const BLOB: &str = "container/blob";
+
+/// Returns the ranges of the downloads `storage` received.
+fn ranges(storage: &FakeStorage) -> Vec<String> {
+    storage
+        .requests()
+        .iter()
+        .filter(|request| request.method == Method::Get)
+        .map(|request| request.header("range").unwrap().to_string())
+        .collect()
+}
+
+fn encryption_options(key: LocalKeyEncryptionKey) -> ClientSideEncryptionOptions {
+    ClientSideEncryptionOptions {
+        key_encryption_key: Some(Arc::new(key)),
+        ..Default::default()
+    }
+}
+
+/// Resolves every key ID to the same key.
+#[derive(Debug)]
+struct MockResolver(Arc<LocalKeyEncryptionKey>);
+
+#[async_trait::async_trait]
+impl KeyEncryptionKeyResolver for MockResolver {
+    async fn resolve(&self, key_id: &str) -> azure_core::Result<Arc<dyn KeyEncryptionKey>> {
+        assert_eq!(self.0.key_id(), key_id);
+        Ok(self.0.clone())
+    }
+}
+
+const REGION: usize = 4 * 1024 * 1024;
+
+#[tokio::test]
+async fn test_encrypted_upload_and_download() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let blob_client = storage.blob_client(BLOB)?;
+    let client = blob_client.with_client_side_encryption(encryption_options(
+        LocalKeyEncryptionKey::new("kek", [1; 32]),
+    ));
+    let data: Vec<u8> = (0..REGION + 1000).map(|i| (i % 251) as u8).collect();
+
+    client
+        .upload_from_reader(
+            futures::io::Cursor::new(data.clone()),
+            true,
+            Some(BlobClientUploadFromReaderOptions {
+                block_size: Some(1024 * 1024),
+                max_single_put_size: Some(1024 * 1024),
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    {
+        let blob = storage.blob(BLOB).unwrap();
+        // Two regions, each with a nonce and a tag.
+        assert_eq!(data.len() + 2 * 28, blob.data.len());
+        assert_ne!(&data[..100], &blob.data[12..112]);
+        let (_, json) = blob
+            .metadata
+            .iter()
+            .find(|(name, _)| name == "x-ms-meta-encryptiondata")
+            .expect("expected encryption data");
+        let encryption_data = EncryptionData::from_json(json)?;
+        assert_eq!("2.0", encryption_data.encryption_agent.protocol);
+        assert_eq!(
+            "AES_GCM_256",
+            encryption_data.encryption_agent.encryption_algorithm
+        );
+        assert_eq!("kek", encryption_data.wrapped_content_key.key_id);
+        assert_eq!("A256KW", encryption_data.wrapped_content_key.algorithm);
+    }
+
+    assert_eq!(data, client.download(None).await?.to_vec());
+
+    // A range requests only the regions that hold it.
+    storage.state().requests.clear();
+    let range = client
+        .download(Some(BlobClientDownloadOptions {
+            range: Some(format!("bytes={}-{}", REGION - 10, REGION + 9)),
+            ..Default::default()
+        }))
+        .await?;
+    assert_eq!(&data[REGION - 10..REGION + 10], range.to_vec());
+    let range = client
+        .download(Some(BlobClientDownloadOptions {
+            range: Some(format!("bytes={}-", REGION + 900)),
+            ..Default::default()
+        }))
+        .await?;
+    assert_eq!(&data[REGION + 900..], range.to_vec());
+    assert_eq!(
+        vec![
+            format!("bytes=0-{}", data.len() + 2 * 28 - 1),
+            format!("bytes={}-{}", REGION + 28, data.len() + 2 * 28 - 1),
+        ],
+        ranges(&storage)
+    );
+
+    // A client without the key decrypts with the key its resolver returns.
+    let resolving_client = blob_client.with_client_side_encryption(ClientSideEncryptionOptions {
+        key_resolver: Some(Arc::new(MockResolver(Arc::new(
+            LocalKeyEncryptionKey::new("kek", [1; 32]),
+        )))),
+        ..Default::default()
+    });
+    assert_eq!(data, resolving_client.download(None).await?.to_vec());
+
+    // A different key can't decrypt the blob.
+    let wrong_client = blob_client.with_client_side_encryption(encryption_options(
+        LocalKeyEncryptionKey::new("kek", [2; 32]),
+    ));
+    assert!(wrong_client.download(None).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypted_download_of_unencrypted_blob() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    storage.state().insert_blob(BLOB, b"plaintext");
+    let blob_client = storage.blob_client(BLOB)?;
+
+    let client = blob_client.with_client_side_encryption(encryption_options(
+        LocalKeyEncryptionKey::new("kek", [1; 32]),
+    ));
+    assert_eq!(b"plaintext", client.download(None).await?.as_ref());
+
+    let client = blob_client.with_client_side_encryption(ClientSideEncryptionOptions {
+        require_encryption: Some(true),
+        ..encryption_options(LocalKeyEncryptionKey::new("kek", [1; 32]))
+    });
+    assert!(client.download(None).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypted_download_to_writer_resumes() -> Result<(), Box<dyn Error>> {
+    // The first download breaks in the middle of the second region, and every body arrives in chunks that straddle
+    // the regions.
+    let broken = AtomicBool::new(false);
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if request.method != Method::Get {
+            return None;
+        }
+        let mut response = state.serve(request);
+        response.chunk_size = Some(1024 * 1024 + 7);
+        if !broken.swap(true, Ordering::SeqCst) {
+            response.fail_after = Some(REGION + 100);
+        }
+        Some(response)
+    });
+    let client = storage
+        .blob_client(BLOB)?
+        .with_client_side_encryption(encryption_options(LocalKeyEncryptionKey::new(
+            "kek", [1; 32],
+        )));
+    let data: Vec<u8> = (0..2 * REGION + 1000).map(|i| (i % 251) as u8).collect();
+    client
+        .upload_from_reader(futures::io::Cursor::new(data.clone()), true, None)
+        .await?;
+    storage.state().requests.clear();
+
+    let mut downloaded = Vec::new();
+    let len = client
+        .download_to_writer(
+            &mut downloaded,
+            Some(BlobClientDownloadOptions {
+                range: Some(format!("bytes=10-{}", 2 * REGION + 499)),
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    assert_eq!(2 * REGION as u64 + 490, len);
+    assert_eq!(&data[10..2 * REGION + 500], downloaded);
+    let last = data.len() + 3 * 28 - 1;
+    assert_eq!(
+        vec![
+            format!("bytes=0-{last}"),
+            format!("bytes={}-{last}", REGION + 100),
+        ],
+        ranges(&storage)
+    );
+
+    Ok(())
+}
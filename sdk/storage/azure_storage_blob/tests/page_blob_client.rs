@@ -1,18 +1,29 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
-use azure_core::http::{RequestContent, StatusCode};
+use azure_core::{
+    base64,
+    http::{RequestContent, StatusCode},
+};
 use azure_core_test::{recorded, TestContext};
 use azure_storage_blob::{
     format_page_range,
     models::{
         BlobClientDownloadResultHeaders, BlobClientGetPropertiesResultHeaders, BlobType,
         PageBlobClientCreateOptions, PageBlobClientSetSequenceNumberOptions,
-        PageBlobClientSetSequenceNumberResultHeaders, SequenceNumberActionType,
+        PageBlobClientSetSequenceNumberResultHeaders, PageBlobClientUploadPagesOptions,
+        SequenceNumberActionType,
+    },
+};
+use azure_storage_blob_test::{get_blob_name, get_container_client, FakeStorage, FakeStorageExt};
+use azure_storage_common::{content_crc64, content_md5, ChecksumAlgorithm, ChecksumMismatchError};
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
     },
 };
-use azure_storage_blob_test::{get_blob_name, get_container_client};
-use std::error::Error;
 
 #[recorded::test]
 async fn test_create_page_blob(ctx: TestContext) -> Result<(), Box<dyn Error>> {
@@ -304,3 +315,62 @@ async fn test_get_page_ranges(ctx: TestContext) -> Result<(), Box<dyn Error>> {
     container_client.delete_container(None).await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_upload_page_validates_content() -> Result<(), Box<dyn Error>> {
+    const PAGE_BLOB: &str = "container/disk.vhd";
+    // Once `corrupt` is set, the first byte of every page is corrupted on its way to the service.
+    let corrupt = Arc::new(AtomicBool::new(false));
+    let corrupting = corrupt.clone();
+    let storage = FakeStorage::with_handler(move |request, state| {
+        if !corrupting.load(Ordering::SeqCst) {
+            return None;
+        }
+        let mut corrupted = request.clone();
+        let mut body = corrupted.body.to_vec();
+        body[0] ^= 0xff;
+        corrupted.body = body.into();
+        Some(state.serve(&corrupted))
+    });
+    storage
+        .state()
+        .insert_blob(PAGE_BLOB, vec![0; 1024])
+        .blob_type = "PageBlob".to_string();
+    let page_blob_client = storage.page_blob_client(PAGE_BLOB)?;
+
+    page_blob_client
+        .upload_page(
+            RequestContent::from(vec![1; 512]),
+            512,
+            format_page_range(0, 512)?,
+            Some(PageBlobClientUploadPagesOptions::default().with_validate_content_crc64()),
+        )
+        .await?;
+    let request = &storage.requests()[0];
+    assert_eq!(
+        Some(base64::encode(content_crc64(&[1; 512])).as_str()),
+        request.header("x-ms-content-crc64")
+    );
+
+    corrupt.store(true, Ordering::SeqCst);
+    let error = page_blob_client
+        .upload_page(
+            RequestContent::from(vec![2; 512]),
+            512,
+            format_page_range(512, 512)?,
+            Some(PageBlobClientUploadPagesOptions::default().with_validate_content_md5()),
+        )
+        .await
+        .unwrap_err();
+    let mismatch = error
+        .downcast_ref::<ChecksumMismatchError>()
+        .expect("expected a ChecksumMismatchError");
+    assert_eq!(ChecksumAlgorithm::Md5, mismatch.algorithm);
+    assert_eq!(content_md5(&[2; 512]).to_vec(), mismatch.expected);
+    assert_eq!(
+        [vec![1; 512], vec![0; 512]].concat(),
+        storage.blob(PAGE_BLOB).unwrap().data
+    );
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use azure_core::{http::Url, Result};
+use azure_storage_blob::{
+    AppendBlobClient, AppendBlobClientOptions, BlobClient, BlobClientOptions, BlobContainerClient,
+    BlobContainerClientOptions, BlockBlobClient, BlockBlobClientOptions, PageBlobClient,
+    PageBlobClientOptions,
+};
+use azure_storage_test::FakeStorage;
+
+/// The endpoint of the Blob service the clients a [`FakeStorageExt`] returns address.
+const ENDPOINT: &str = "https://account.blob.core.windows.net/";
+
+/// Returns Blob clients whose requests a [`FakeStorage`] answers.
+pub trait FakeStorageExt {
+    /// Returns a [`BlobContainerClient`] for `container`.
+    fn container_client(&self, container: &str) -> Result<BlobContainerClient>;
+
+    /// Returns a [`BlobClient`] for the blob at `path`, such as `container/dir/blob`.
+    fn blob_client(&self, path: &str) -> Result<BlobClient>;
+
+    /// Returns an [`AppendBlobClient`] for the blob at `path`, such as `container/dir/blob`.
+    fn append_blob_client(&self, path: &str) -> Result<AppendBlobClient>;
+
+    /// Returns a [`BlockBlobClient`] for the blob at `path`, such as `container/dir/blob`.
+    fn block_blob_client(&self, path: &str) -> Result<BlockBlobClient>;
+
+    /// Returns a [`PageBlobClient`] for the blob at `path`, such as `container/dir/blob`.
+    fn page_blob_client(&self, path: &str) -> Result<PageBlobClient>;
+}
+
+impl FakeStorageExt for FakeStorage {
+    fn container_client(&self, container: &str) -> Result<BlobContainerClient> {
+        BlobContainerClient::from_url(
+            blob_url(container)?,
+            None,
+            Some(BlobContainerClientOptions {
+                client_options: self.client_options(),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn blob_client(&self, path: &str) -> Result<BlobClient> {
+        BlobClient::from_url(
+            blob_url(path)?,
+            None,
+            Some(BlobClientOptions {
+                client_options: self.client_options(),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn append_blob_client(&self, path: &str) -> Result<AppendBlobClient> {
+        AppendBlobClient::from_url(
+            blob_url(path)?,
+            None,
+            Some(AppendBlobClientOptions {
+                client_options: self.client_options(),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn block_blob_client(&self, path: &str) -> Result<BlockBlobClient> {
+        BlockBlobClient::from_url(
+            blob_url(path)?,
+            None,
+            Some(BlockBlobClientOptions {
+                client_options: self.client_options(),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn page_blob_client(&self, path: &str) -> Result<PageBlobClient> {
+        PageBlobClient::from_url(
+            blob_url(path)?,
+            None,
+            Some(PageBlobClientOptions {
+                client_options: self.client_options(),
+                ..Default::default()
+            }),
+        )
+    }
+}
+
+/// Returns the URL of `path`, such as `container/dir/blob`, in the fake's Blob service.
+fn blob_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(ENDPOINT)?.join(path)?)
+}
@@ -1,6 +1,11 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+mod fake_storage;
+
+pub use azure_storage_test::{FakeBlob, FakeResponse, FakeState, FakeStorage, RecordedRequest};
+pub use fake_storage::FakeStorageExt;
+
 use azure_core::{
     http::{ClientOptions, NoFormat, RequestContent, Response},
     Bytes, Result,
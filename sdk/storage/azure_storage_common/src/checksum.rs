@@ -0,0 +1,230 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use azure_core::{base64, error::ErrorKind};
+use md5::{Digest, Md5};
+use std::fmt;
+
+/// The reflected polynomial of CRC-64/NVME, the variant Azure Storage uses for `x-ms-content-crc64`.
+const CRC64_POLYNOMIAL: u64 = 0x9A6C_9329_AC4B_C9B5;
+
+const CRC64_TABLE: [u64; 256] = crc64_table();
+
+const fn crc64_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Returns the MD5 hash of `data` as sent in the `Content-MD5` header.
+pub fn content_md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data).into()
+}
+
+/// Returns the Azure Storage CRC-64 of `data` as sent in the `x-ms-content-crc64` header: little-endian.
+pub fn content_crc64(data: &[u8]) -> [u8; 8] {
+    (!crc64_update(!0, data)).to_le_bytes()
+}
+
+fn crc64_update(crc: u64, data: &[u8]) -> u64 {
+    data.iter().fold(crc, |crc, &b| {
+        CRC64_TABLE[((crc ^ u64::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// A transactional hash of the content of a request or response, which Azure Storage computes and validates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChecksumAlgorithm {
+    /// The MD5 hash sent in `Content-MD5`.
+    Md5,
+
+    /// The Azure Storage CRC-64 sent in `x-ms-content-crc64`.
+    Crc64,
+}
+
+impl ChecksumAlgorithm {
+    /// Returns the checksum of `data`.
+    pub fn checksum(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            ChecksumAlgorithm::Md5 => content_md5(data).to_vec(),
+            ChecksumAlgorithm::Crc64 => content_crc64(data).to_vec(),
+        }
+    }
+
+    /// Returns a [`Checksum`] that computes the checksum of content given in parts.
+    pub fn hasher(self) -> Checksum {
+        Checksum(match self {
+            ChecksumAlgorithm::Md5 => ChecksumState::Md5(Md5::new()),
+            ChecksumAlgorithm::Crc64 => ChecksumState::Crc64(!0),
+        })
+    }
+
+    /// Returns a [`ChecksumMismatchError`] if the checksum of `data` isn't `expected`.
+    pub fn verify(self, expected: &[u8], data: &[u8]) -> Result<(), ChecksumMismatchError> {
+        let actual = self.checksum(data);
+        if actual != expected {
+            return Err(ChecksumMismatchError {
+                algorithm: self,
+                expected: expected.to_vec(),
+                actual: Some(actual),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChecksumAlgorithm::Md5 => "MD5",
+            ChecksumAlgorithm::Crc64 => "CRC-64",
+        })
+    }
+}
+
+/// The checksum of content given in parts, so that a stream can be hashed without reading it into memory.
+///
+/// Created by [`ChecksumAlgorithm::hasher()`].
+#[derive(Clone, Debug)]
+pub struct Checksum(ChecksumState);
+
+#[derive(Clone, Debug)]
+enum ChecksumState {
+    Md5(Md5),
+    Crc64(u64),
+}
+
+impl Checksum {
+    /// Adds `data`, the next part of the content, to the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.0 {
+            ChecksumState::Md5(md5) => md5.update(data),
+            ChecksumState::Crc64(crc) => *crc = crc64_update(*crc, data),
+        }
+    }
+
+    /// Returns the checksum of the content given so far.
+    pub fn finish(self) -> Vec<u8> {
+        match self.0 {
+            ChecksumState::Md5(md5) => md5.finalize().to_vec(),
+            ChecksumState::Crc64(crc) => (!crc).to_le_bytes().to_vec(),
+        }
+    }
+}
+
+/// The content of a request or response doesn't match its transactional checksum, so it was corrupted in transit.
+///
+/// Operations that validate checksums return this as the source of an [`azure_core::Error`], which
+/// [`downcast_ref()`](azure_core::Error::downcast_ref) returns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ChecksumMismatchError {
+    /// The algorithm of the checksum.
+    pub algorithm: ChecksumAlgorithm,
+
+    /// The checksum sent with the content.
+    pub expected: Vec<u8>,
+
+    /// The checksum of the content as received, which the service doesn't return when it rejects a request.
+    pub actual: Option<Vec<u8>>,
+}
+
+impl ChecksumMismatchError {
+    /// Creates an error for content whose checksum, as received, isn't `expected`, or is unknown if `actual` is `None`.
+    pub fn new(algorithm: ChecksumAlgorithm, expected: Vec<u8>, actual: Option<Vec<u8>>) -> Self {
+        Self {
+            algorithm,
+            expected,
+            actual,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} mismatch: expected {}",
+            self.algorithm,
+            base64::encode(&self.expected)
+        )?;
+        if let Some(actual) = &self.actual {
+            write!(f, ", received content with {}", base64::encode(actual))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ChecksumMismatchError {}
+
+impl From<ChecksumMismatchError> for azure_core::Error {
+    fn from(error: ChecksumMismatchError) -> Self {
+        azure_core::Error::new(ErrorKind::DataConversion, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn md5() {
+        assert_eq!(
+            [
+                0x5e, 0xb6, 0x3b, 0xbb, 0xe0, 0x1e, 0xee, 0xd0, 0x93, 0xcb, 0x22, 0xbb, 0x8f, 0x5a,
+                0xcd, 0xc3
+            ],
+            content_md5(b"hello world")
+        );
+    }
+
+    #[test]
+    fn crc64() {
+        assert_eq!([0; 8], content_crc64(b""));
+        // The check value of CRC-64/NVME, which this variant is.
+        assert_eq!(
+            0xae8b_1486_0a79_9888u64.to_le_bytes(),
+            content_crc64(b"123456789")
+        );
+    }
+
+    #[test]
+    fn hasher() {
+        let data = b"hello world";
+        for algorithm in [ChecksumAlgorithm::Md5, ChecksumAlgorithm::Crc64] {
+            let mut hasher = algorithm.hasher();
+            for part in data.chunks(3) {
+                hasher.update(part);
+            }
+            assert_eq!(algorithm.checksum(data), hasher.finish());
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let data = b"hello world";
+        let md5 = ChecksumAlgorithm::Md5.checksum(data);
+        assert!(ChecksumAlgorithm::Md5.verify(&md5, data).is_ok());
+
+        let error = ChecksumAlgorithm::Crc64.verify(&md5, data).unwrap_err();
+        assert_eq!(ChecksumAlgorithm::Crc64, error.algorithm);
+        assert_eq!(Some(content_crc64(data).to_vec()), error.actual);
+        let error = azure_core::Error::from(error);
+        assert!(error.downcast_ref::<ChecksumMismatchError>().is_some());
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use azure_core::{
+    credentials::Secret,
+    error::{Error, ErrorKind, ResultExt},
+    fmt::SafeDebug,
+    http::Url,
+};
+use std::str::FromStr;
+
+/// The account name of the Azurite storage emulator.
+pub const DEVELOPMENT_ACCOUNT_NAME: &str = "devstoreaccount1";
+
+/// The well-known account key of the Azurite storage emulator.
+pub const DEVELOPMENT_ACCOUNT_KEY: &str =
+    "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+
+const DEFAULT_ENDPOINT_SUFFIX: &str = "core.windows.net";
+const DEVELOPMENT_HOST: &str = "http://127.0.0.1";
+const DEVELOPMENT_BLOB_PORT: u16 = 10000;
+const DEVELOPMENT_QUEUE_PORT: u16 = 10001;
+
+/// Represents an Azure Storage connection string.
+///
+/// Besides account connection strings like
+/// `DefaultEndpointsProtocol=https;AccountName=myaccount;AccountKey=...;EndpointSuffix=core.windows.net`,
+/// this parses connection strings with explicit `BlobEndpoint` or `QueueEndpoint`, with a
+/// `SharedAccessSignature` instead of an account key, and `UseDevelopmentStorage=true`, which
+/// connects to Azurite at its default endpoints with its well-known account.
+#[derive(Clone, SafeDebug)]
+pub struct ConnectionString {
+    /// The name of the storage account.
+    pub account_name: Option<String>,
+    /// One of the storage account's base64-encoded access keys.
+    pub account_key: Option<Secret>,
+    /// A shared access signature query string, without a leading `?`.
+    pub shared_access_signature: Option<Secret>,
+    /// The Blob service endpoint.
+    pub blob_endpoint: Option<Url>,
+    /// The Queue service endpoint.
+    pub queue_endpoint: Option<Url>,
+}
+
+impl ConnectionString {
+    /// Returns the Blob service endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection string has neither a `BlobEndpoint` nor an `AccountName`.
+    pub fn blob_endpoint(&self) -> azure_core::Result<&Url> {
+        self.blob_endpoint
+            .as_ref()
+            .ok_or_else(|| missing("'BlobEndpoint' or 'AccountName'"))
+    }
+
+    /// Returns the Queue service endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection string has neither a `QueueEndpoint` nor an `AccountName`.
+    pub fn queue_endpoint(&self) -> azure_core::Result<&Url> {
+        self.queue_endpoint
+            .as_ref()
+            .ok_or_else(|| missing("'QueueEndpoint' or 'AccountName'"))
+    }
+}
+
+fn missing(keys: &str) -> Error {
+    Error::with_message(
+        ErrorKind::Other,
+        format!("invalid connection string, missing {keys}"),
+    )
+}
+
+fn parse_endpoint(key: &str, value: &str) -> azure_core::Result<Url> {
+    Url::parse(value).with_context_fn(ErrorKind::Other, || {
+        format!("invalid connection string, '{key}' is not a URL")
+    })
+}
+
+/// Returns the endpoint of the service named `service` in the account, for example
+/// `https://myaccount.blob.core.windows.net/`.
+fn account_endpoint(
+    protocol: &str,
+    account_name: &str,
+    service: &str,
+    suffix: &str,
+) -> azure_core::Result<Url> {
+    parse_endpoint(
+        "AccountName",
+        &format!("{protocol}://{account_name}.{service}.{suffix}/"),
+    )
+}
+
+fn development_endpoint(proxy: &Url, port: u16) -> Url {
+    let mut url = proxy.clone();
+    // Only a URL without a host can't have a port, and the proxy URL has one.
+    let _ = url.set_port(Some(port));
+    url.set_path(DEVELOPMENT_ACCOUNT_NAME);
+    url.set_query(None);
+    url
+}
+
+impl TryFrom<&Secret> for ConnectionString {
+    type Error = azure_core::Error;
+    fn try_from(secret: &Secret) -> Result<Self, Self::Error> {
+        secret.secret().parse()
+    }
+}
+
+impl FromStr for ConnectionString {
+    type Err = azure_core::Error;
+    fn from_str(connection_string: &str) -> Result<Self, Self::Err> {
+        if connection_string.trim().is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "connection string cannot be empty",
+            ));
+        }
+
+        let mut account_name = None;
+        let mut account_key = None;
+        let mut shared_access_signature = None;
+        let mut blob_endpoint = None;
+        let mut queue_endpoint = None;
+        let mut protocol = None;
+        let mut suffix = None;
+        let mut development_storage = false;
+        let mut development_proxy = None;
+        for part in connection_string.split(';') {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+
+            let (key, value) = part.split_once('=').ok_or_else(|| {
+                Error::with_message(ErrorKind::Other, "invalid connection string")
+            })?;
+            let (key, value) = (key.trim(), value.trim());
+
+            if key.eq_ignore_ascii_case("AccountName") {
+                account_name = Some(value.to_string());
+            } else if key.eq_ignore_ascii_case("AccountKey") {
+                account_key = Some(Secret::new(value.to_string()));
+            } else if key.eq_ignore_ascii_case("SharedAccessSignature") {
+                shared_access_signature =
+                    Some(Secret::new(value.trim_start_matches('?').to_string()));
+            } else if key.eq_ignore_ascii_case("BlobEndpoint") {
+                blob_endpoint = Some(parse_endpoint(key, value)?);
+            } else if key.eq_ignore_ascii_case("QueueEndpoint") {
+                queue_endpoint = Some(parse_endpoint(key, value)?);
+            } else if key.eq_ignore_ascii_case("DefaultEndpointsProtocol") {
+                if !value.eq_ignore_ascii_case("https") && !value.eq_ignore_ascii_case("http") {
+                    return Err(Error::with_message(
+                        ErrorKind::Other,
+                        format!("invalid connection string, unsupported 'DefaultEndpointsProtocol' '{value}'"),
+                    ));
+                }
+                protocol = Some(value.to_ascii_lowercase());
+            } else if key.eq_ignore_ascii_case("EndpointSuffix") {
+                suffix = Some(value.to_string());
+            } else if key.eq_ignore_ascii_case("UseDevelopmentStorage") {
+                development_storage = value.eq_ignore_ascii_case("true");
+            } else if key.eq_ignore_ascii_case("DevelopmentStorageProxyUri") {
+                development_proxy = Some(parse_endpoint(key, value)?);
+            }
+        }
+
+        if development_storage {
+            let proxy = match development_proxy {
+                Some(proxy) => proxy,
+                None => parse_endpoint("UseDevelopmentStorage", DEVELOPMENT_HOST)?,
+            };
+            return Ok(Self {
+                account_name: Some(DEVELOPMENT_ACCOUNT_NAME.to_string()),
+                account_key: Some(Secret::new(DEVELOPMENT_ACCOUNT_KEY)),
+                shared_access_signature: None,
+                blob_endpoint: Some(development_endpoint(&proxy, DEVELOPMENT_BLOB_PORT)),
+                queue_endpoint: Some(development_endpoint(&proxy, DEVELOPMENT_QUEUE_PORT)),
+            });
+        }
+
+        if account_key.is_some() && account_name.is_none() {
+            return Err(missing("'AccountName'"));
+        }
+
+        if let Some(account_name) = &account_name {
+            let protocol = protocol.as_deref().unwrap_or("https");
+            let suffix = suffix.as_deref().unwrap_or(DEFAULT_ENDPOINT_SUFFIX);
+            if blob_endpoint.is_none() {
+                blob_endpoint = Some(account_endpoint(protocol, account_name, "blob", suffix)?);
+            }
+            if queue_endpoint.is_none() {
+                queue_endpoint = Some(account_endpoint(protocol, account_name, "queue", suffix)?);
+            }
+        }
+
+        if blob_endpoint.is_none() && queue_endpoint.is_none() {
+            return Err(missing("'AccountName', 'BlobEndpoint' or 'QueueEndpoint'"));
+        }
+
+        Ok(Self {
+            account_name,
+            account_key,
+            shared_access_signature,
+            blob_endpoint,
+            queue_endpoint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConnectionString;
+    use azure_core::credentials::Secret;
+
+    fn parse(connection_string: &str) -> ConnectionString {
+        ConnectionString::try_from(&Secret::new(connection_string.to_owned())).unwrap()
+    }
+
+    #[test]
+    pub fn test_account_connection_string() {
+        let connection_string = parse(
+            "DefaultEndpointsProtocol=https;AccountName=myaccount;AccountKey=a2V5;EndpointSuffix=core.chinacloudapi.cn",
+        );
+
+        assert_eq!(Some("myaccount"), connection_string.account_name.as_deref());
+        assert_eq!(
+            Some("a2V5"),
+            connection_string.account_key.as_ref().map(Secret::secret)
+        );
+        assert!(connection_string.shared_access_signature.is_none());
+        assert_eq!(
+            "https://myaccount.blob.core.chinacloudapi.cn/",
+            connection_string.blob_endpoint().unwrap().as_str()
+        );
+        assert_eq!(
+            "https://myaccount.queue.core.chinacloudapi.cn/",
+            connection_string.queue_endpoint().unwrap().as_str()
+        );
+    }
+
+    #[test]
+    pub fn test_default_protocol_and_suffix() {
+        let connection_string = parse("accountname=myaccount;accountkey=a2V5;");
+
+        assert_eq!(
+            "https://myaccount.blob.core.windows.net/",
+            connection_string.blob_endpoint().unwrap().as_str()
+        );
+    }
+
+    #[test]
+    pub fn test_endpoint_overrides() {
+        let connection_string = parse(
+            "BlobEndpoint=https://blob.contoso.com/;SharedAccessSignature=?sv=2022-11-02&sig=abc",
+        );
+
+        assert_eq!(
+            "https://blob.contoso.com/",
+            connection_string.blob_endpoint().unwrap().as_str()
+        );
+        assert_eq!(
+            "invalid connection string, missing 'QueueEndpoint' or 'AccountName'",
+            connection_string.queue_endpoint().unwrap_err().to_string()
+        );
+        assert_eq!(
+            Some("sv=2022-11-02&sig=abc"),
+            connection_string
+                .shared_access_signature
+                .as_ref()
+                .map(Secret::secret)
+        );
+        assert!(connection_string.account_key.is_none());
+    }
+
+    #[test]
+    pub fn test_development_storage() {
+        let connection_string = parse("UseDevelopmentStorage=true");
+
+        assert_eq!(
+            Some(super::DEVELOPMENT_ACCOUNT_NAME),
+            connection_string.account_name.as_deref()
+        );
+        assert_eq!(
+            Some(super::DEVELOPMENT_ACCOUNT_KEY),
+            connection_string.account_key.as_ref().map(Secret::secret)
+        );
+        assert_eq!(
+            "http://127.0.0.1:10000/devstoreaccount1",
+            connection_string.blob_endpoint().unwrap().as_str()
+        );
+        assert_eq!(
+            "http://127.0.0.1:10001/devstoreaccount1",
+            connection_string.queue_endpoint().unwrap().as_str()
+        );
+
+        let connection_string =
+            parse("UseDevelopmentStorage=true;DevelopmentStorageProxyUri=http://azurite");
+        assert_eq!(
+            "http://azurite:10000/devstoreaccount1",
+            connection_string.blob_endpoint().unwrap().as_str()
+        );
+    }
+
+    #[test]
+    pub fn test_empty_connection_string() {
+        test_bad_connection_string("", "connection string cannot be empty")
+    }
+
+    #[test]
+    pub fn test_malformed_connection_string() {
+        test_bad_connection_string("AccountNamemyaccount", "invalid connection string");
+    }
+
+    #[test]
+    pub fn test_connection_string_missing_account_name() {
+        test_bad_connection_string(
+            "AccountKey=a2V5",
+            "invalid connection string, missing 'AccountName'",
+        );
+    }
+
+    #[test]
+    pub fn test_connection_string_missing_endpoints() {
+        test_bad_connection_string(
+            "SharedAccessSignature=sv=2022-11-02",
+            "invalid connection string, missing 'AccountName', 'BlobEndpoint' or 'QueueEndpoint'",
+        );
+    }
+
+    #[test]
+    pub fn test_connection_string_invalid_endpoint() {
+        test_bad_connection_string(
+            "BlobEndpoint=blob.contoso.com",
+            "invalid connection string, 'BlobEndpoint' is not a URL",
+        );
+    }
+
+    fn test_bad_connection_string(connection_string: &str, expected_error_message: &str) {
+        let secret = Secret::new(connection_string.to_owned());
+        let err = ConnectionString::try_from(&secret).unwrap_err();
+        assert_eq!(expected_error_message, err.to_string());
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use super::KeyEncryptionKey;
+use async_trait::async_trait;
+use azure_core::{
+    error::{Error, ErrorKind},
+    Result,
+};
+use azure_security_keyvault_keys::{
+    models::{
+        EncryptionAlgorithm, KeyClientUnwrapKeyOptions, KeyClientWrapKeyOptions,
+        KeyOperationParameters,
+    },
+    KeyClient, ResourceId,
+};
+use std::{fmt, sync::Arc};
+
+/// A key in Key Vault or Managed HSM that wraps content-encryption keys, by default with `RSA-OAEP-256`, without the
+/// key leaving the vault.
+///
+/// Requires the `key_vault` feature.
+pub struct KeyVaultKeyEncryptionKey {
+    client: Arc<KeyClient>,
+    key_id: String,
+    name: String,
+    version: String,
+    algorithm: EncryptionAlgorithm,
+}
+
+impl KeyVaultKeyEncryptionKey {
+    /// Creates a key encryption key from a client of its vault and its ID, for example
+    /// `https://my-vault.vault.azure.net/keys/my-key/0123456789abcdef0123456789abcdef`.
+    ///
+    /// The ID must include the version of the key, which is stored with every key it wraps, so that data remains
+    /// readable after the key is rotated.
+    pub fn new(client: Arc<KeyClient>, key_id: &str) -> Result<Self> {
+        let resource: ResourceId = key_id.parse()?;
+        let version = resource.version.ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Other,
+                format!("key ID {key_id} must include a version"),
+            )
+        })?;
+        Ok(Self {
+            client,
+            key_id: format!("{}/keys/{}/{version}", resource.vault_url, resource.name),
+            name: resource.name,
+            version,
+            algorithm: EncryptionAlgorithm::RsaOaep256,
+        })
+    }
+
+    /// Sets the algorithm that wraps keys, which must be supported by the type of the key.
+    pub fn with_algorithm(mut self, algorithm: EncryptionAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl KeyEncryptionKey for KeyVaultKeyEncryptionKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn wrap_key(&self, key: &[u8]) -> Result<(Vec<u8>, String)> {
+        let parameters = KeyOperationParameters {
+            algorithm: Some(self.algorithm.clone()),
+            value: Some(key.to_vec()),
+            ..Default::default()
+        };
+        let result = self
+            .client
+            .wrap_key(
+                &self.name,
+                parameters.try_into()?,
+                Some(KeyClientWrapKeyOptions {
+                    key_version: Some(self.version.clone()),
+                    ..Default::default()
+                }),
+            )
+            .await?
+            .into_model()?;
+        let wrapped = result.result.ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, "response has no wrapped key")
+        })?;
+        Ok((wrapped, self.algorithm.to_string()))
+    }
+
+    async fn unwrap_key(&self, wrapped_key: &[u8], algorithm: &str) -> Result<Vec<u8>> {
+        let parameters = KeyOperationParameters {
+            algorithm: Some(algorithm.parse()?),
+            value: Some(wrapped_key.to_vec()),
+            ..Default::default()
+        };
+        let result = self
+            .client
+            .unwrap_key(
+                &self.name,
+                parameters.try_into()?,
+                Some(KeyClientUnwrapKeyOptions {
+                    key_version: Some(self.version.clone()),
+                    ..Default::default()
+                }),
+            )
+            .await?
+            .into_model()?;
+        result.result.ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, "response has no unwrapped key")
+        })
+    }
+}
+
+impl fmt::Debug for KeyVaultKeyEncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyVaultKeyEncryptionKey")
+            .field("key_id", &self.key_id)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,492 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Client-side encryption, compatible with version 2.0 of the format the other Azure Storage SDKs use.
+//!
+//! Data is encrypted with AES-256-GCM in regions of 4 MiB, each stored as a random 12-byte nonce followed by the
+//! ciphertext and the 16-byte authentication tag, so that any range can be decrypted without the rest of the data.
+//! The content-encryption key is random for every blob or message and is wrapped by a [`KeyEncryptionKey`], such as a
+//! [`LocalKeyEncryptionKey`] or a key in Key Vault. The wrapped key and the parameters of the encryption are stored
+//! with the data as [`EncryptionData`].
+//!
+//! Requires the `client_side_encryption` feature.
+
+#[cfg(feature = "key_vault")]
+mod key_vault;
+mod local_key;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use async_trait::async_trait;
+use azure_core::{
+    base64,
+    error::{Error, ErrorKind},
+    json, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, ops::Range, sync::Arc};
+
+#[cfg(feature = "key_vault")]
+pub use key_vault::KeyVaultKeyEncryptionKey;
+pub use local_key::LocalKeyEncryptionKey;
+
+const PROTOCOL_V2: &str = "2.0";
+const AES_GCM_256: &str = "AES_GCM_256";
+const FULL_BLOB: &str = "FullBlob";
+const REGION_LENGTH: u64 = 4 * 1024 * 1024;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const CONTENT_KEY_LENGTH: usize = 32;
+/// The length of the protocol version prepended to the content-encryption key before it's wrapped.
+const WRAPPED_VERSION_LENGTH: usize = 8;
+
+/// A key that wraps and unwraps content-encryption keys, such as a [`LocalKeyEncryptionKey`] or a key in Key Vault.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait KeyEncryptionKey: Send + Sync + fmt::Debug {
+    /// Returns the ID of the key, which is stored with the wrapped key to find this key again to unwrap it.
+    fn key_id(&self) -> &str;
+
+    /// Wraps `key`, returning the wrapped key and the name of the algorithm that wrapped it.
+    async fn wrap_key(&self, key: &[u8]) -> Result<(Vec<u8>, String)>;
+
+    /// Unwraps `wrapped_key`, which `algorithm` wrapped.
+    async fn unwrap_key(&self, wrapped_key: &[u8], algorithm: &str) -> Result<Vec<u8>>;
+}
+
+/// Finds the key that wrapped a content-encryption key by its ID, for data encrypted with a key other than
+/// [`ClientSideEncryptionOptions::key_encryption_key`], such as a previous version of a rotated key.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait KeyEncryptionKeyResolver: Send + Sync + fmt::Debug {
+    /// Returns the key whose ID is `key_id`.
+    async fn resolve(&self, key_id: &str) -> Result<Arc<dyn KeyEncryptionKey>>;
+}
+
+/// The keys with which a client encrypts and decrypts data.
+#[derive(Clone, Debug, Default)]
+pub struct ClientSideEncryptionOptions {
+    /// The key that wraps the content-encryption key of the data a client encrypts, and unwraps it for any data whose
+    /// key it wrapped. Required to encrypt data.
+    pub key_encryption_key: Option<Arc<dyn KeyEncryptionKey>>,
+
+    /// Finds the key that wrapped the content-encryption key of data being decrypted if it isn't
+    /// `key_encryption_key`.
+    pub key_resolver: Option<Arc<dyn KeyEncryptionKeyResolver>>,
+
+    /// Whether reading data that isn't encrypted is an error, rather than returning the data as is.
+    pub require_encryption: Option<bool>,
+}
+
+impl ClientSideEncryptionOptions {
+    /// Returns the key that wrapped a content-encryption key, by its ID.
+    async fn resolve(&self, key_id: &str) -> Result<Arc<dyn KeyEncryptionKey>> {
+        if let Some(key) = &self.key_encryption_key {
+            if key.key_id() == key_id {
+                return Ok(key.clone());
+            }
+        }
+        match &self.key_resolver {
+            Some(resolver) => resolver.resolve(key_id).await,
+            None => Err(Error::with_message(
+                ErrorKind::Credential,
+                format!("no key encryption key or resolver for key {key_id}"),
+            )),
+        }
+    }
+}
+
+/// The envelope of encrypted data, which holds its wrapped content-encryption key and the parameters of its
+/// encryption, as stored in the `encryptiondata` metadata of a blob or alongside an encrypted queue message.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct EncryptionData {
+    /// The scope of the encryption, which is always `FullBlob`.
+    pub encryption_mode: String,
+
+    /// The wrapped content-encryption key.
+    pub wrapped_content_key: WrappedContentKey,
+
+    /// The protocol and algorithm of the encryption.
+    pub encryption_agent: EncryptionAgent,
+
+    /// The layout of the encrypted regions, which version 1.0 of the protocol doesn't have.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_region_info: Option<EncryptedRegionInfo>,
+
+    /// Information about the library that encrypted the data.
+    #[serde(default)]
+    pub key_wrapping_metadata: HashMap<String, String>,
+}
+
+/// A wrapped content-encryption key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct WrappedContentKey {
+    /// The ID of the [`KeyEncryptionKey`] that wrapped the key.
+    pub key_id: String,
+
+    /// The wrapped key.
+    #[serde(
+        deserialize_with = "base64::deserialize",
+        serialize_with = "base64::serialize"
+    )]
+    pub encrypted_key: Vec<u8>,
+
+    /// The algorithm that wrapped the key.
+    pub algorithm: String,
+}
+
+/// The protocol and algorithm of an encryption.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct EncryptionAgent {
+    /// The version of the protocol, `2.0`.
+    pub protocol: String,
+
+    /// The algorithm that encrypted the content, `AES_GCM_256`.
+    pub encryption_algorithm: String,
+}
+
+/// The layout of encrypted regions.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct EncryptedRegionInfo {
+    /// The length of the plaintext of every region but the last.
+    pub data_length: u64,
+
+    /// The length of the nonce preceding the ciphertext of every region.
+    pub nonce_length: usize,
+}
+
+impl EncryptionData {
+    /// Parses the JSON of encryption data.
+    pub fn from_json(json: &str) -> Result<Self> {
+        json::from_json(json)
+    }
+
+    /// Serializes the encryption data as JSON.
+    pub fn to_json(&self) -> Result<String> {
+        let json = json::to_json(self)?;
+        String::from_utf8(json.to_vec()).map_err(|error| {
+            Error::with_error(ErrorKind::DataConversion, error, "invalid encryption data")
+        })
+    }
+
+    /// Returns the length of the plaintext of every region but the last, after checking that the data was encrypted
+    /// with a supported version of the protocol.
+    fn region_length(&self) -> Result<u64> {
+        if self.encryption_agent.protocol != PROTOCOL_V2
+            || self.encryption_agent.encryption_algorithm != AES_GCM_256
+        {
+            return Err(Error::with_message(
+                ErrorKind::DataConversion,
+                format!(
+                    "unsupported client-side encryption protocol {} with {}",
+                    self.encryption_agent.protocol, self.encryption_agent.encryption_algorithm
+                ),
+            ));
+        }
+        match &self.encrypted_region_info {
+            Some(info) if info.nonce_length == NONCE_LENGTH && info.data_length > 0 => {
+                Ok(info.data_length)
+            }
+            _ => Err(Error::with_message(
+                ErrorKind::DataConversion,
+                "invalid encrypted region info",
+            )),
+        }
+    }
+
+    /// Returns the length of the plaintext of data whose encrypted length is `encrypted_length`.
+    pub fn plaintext_length(&self, encrypted_length: u64) -> Result<u64> {
+        let encrypted_region_length = self.region_length()? + REGION_OVERHEAD;
+        let regions = encrypted_length.div_ceil(encrypted_region_length);
+        encrypted_length
+            .checked_sub(regions * REGION_OVERHEAD)
+            .ok_or_else(|| {
+                Error::with_message(ErrorKind::DataConversion, "invalid encrypted length")
+            })
+    }
+
+    /// Returns the range of the encrypted data that holds the whole regions of the plaintext in `range`. The range may
+    /// extend past the end of the data.
+    pub fn encrypted_range(&self, range: Range<u64>) -> Result<Range<u64>> {
+        let region_length = self.region_length()?;
+        let encrypted_region_length = region_length + REGION_OVERHEAD;
+        let first = range.start / region_length;
+        let end = range.end.max(range.start + 1).div_ceil(region_length);
+        Ok(first * encrypted_region_length..end * encrypted_region_length)
+    }
+
+    /// Returns the offset in the plaintext of the region at `encrypted_offset`, the start of an encrypted range.
+    pub fn plaintext_offset(&self, encrypted_offset: u64) -> Result<u64> {
+        let region_length = self.region_length()?;
+        Ok(encrypted_offset / (region_length + REGION_OVERHEAD) * region_length)
+    }
+}
+
+/// The length that encryption adds to every region.
+const REGION_OVERHEAD: u64 = (NONCE_LENGTH + TAG_LENGTH) as u64;
+
+/// Encrypts data in regions with a new content-encryption key.
+pub struct ContentEncryptor {
+    cipher: Aes256Gcm,
+    encryption_data: EncryptionData,
+}
+
+impl ContentEncryptor {
+    /// Generates a content-encryption key and wraps it with `key_encryption_key`.
+    pub async fn new(key_encryption_key: &dyn KeyEncryptionKey) -> Result<Self> {
+        let content_key = Aes256Gcm::generate_key(OsRng);
+        let mut to_wrap = Vec::with_capacity(WRAPPED_VERSION_LENGTH + CONTENT_KEY_LENGTH);
+        to_wrap.extend_from_slice(PROTOCOL_V2.as_bytes());
+        to_wrap.resize(WRAPPED_VERSION_LENGTH, 0);
+        to_wrap.extend_from_slice(&content_key);
+        let (encrypted_key, algorithm) = key_encryption_key.wrap_key(&to_wrap).await?;
+
+        Ok(Self {
+            cipher: Aes256Gcm::new(&content_key),
+            encryption_data: EncryptionData {
+                encryption_mode: FULL_BLOB.to_string(),
+                wrapped_content_key: WrappedContentKey {
+                    key_id: key_encryption_key.key_id().to_string(),
+                    encrypted_key,
+                    algorithm,
+                },
+                encryption_agent: EncryptionAgent {
+                    protocol: PROTOCOL_V2.to_string(),
+                    encryption_algorithm: AES_GCM_256.to_string(),
+                },
+                encrypted_region_info: Some(EncryptedRegionInfo {
+                    data_length: REGION_LENGTH,
+                    nonce_length: NONCE_LENGTH,
+                }),
+                key_wrapping_metadata: HashMap::from([(
+                    "EncryptionLibrary".to_string(),
+                    format!("Rust {}", env!("CARGO_PKG_VERSION")),
+                )]),
+            },
+        })
+    }
+
+    /// Returns the envelope to store with the encrypted data.
+    pub fn encryption_data(&self) -> &EncryptionData {
+        &self.encryption_data
+    }
+
+    /// Returns the length of the plaintext of every region but the last.
+    pub fn region_length(&self) -> u64 {
+        REGION_LENGTH
+    }
+
+    /// Encrypts a region of up to [`region_length()`](ContentEncryptor::region_length) bytes with a new nonce.
+    pub fn encrypt_region(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        if plaintext.len() as u64 > REGION_LENGTH {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                format!("a region must be at most {REGION_LENGTH} bytes"),
+            ));
+        }
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| Error::with_message(ErrorKind::Other, "failed to encrypt region"))?;
+        let mut region = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
+        region.extend_from_slice(&nonce);
+        region.extend_from_slice(&ciphertext);
+        Ok(region)
+    }
+
+    /// Encrypts `plaintext` of any length, region by region.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut encrypted = Vec::new();
+        for region in plaintext.chunks(REGION_LENGTH as usize) {
+            encrypted.extend(self.encrypt_region(region)?);
+        }
+        Ok(encrypted)
+    }
+}
+
+impl fmt::Debug for ContentEncryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentEncryptor")
+            .field("encryption_data", &self.encryption_data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decrypts data in regions with the content-encryption key of its [`EncryptionData`].
+pub struct ContentDecryptor {
+    cipher: Aes256Gcm,
+    region_length: u64,
+}
+
+impl ContentDecryptor {
+    /// Unwraps the content-encryption key of `encryption_data` with the key that wrapped it, which is found in
+    /// `options`.
+    pub async fn new(
+        options: &ClientSideEncryptionOptions,
+        encryption_data: &EncryptionData,
+    ) -> Result<Self> {
+        let region_length = encryption_data.region_length()?;
+        let wrapped = &encryption_data.wrapped_content_key;
+        let key_encryption_key = options.resolve(&wrapped.key_id).await?;
+        let unwrapped = key_encryption_key
+            .unwrap_key(&wrapped.encrypted_key, &wrapped.algorithm)
+            .await?;
+        // Version 2.0 wraps the protocol version with the key, so that it can't be downgraded.
+        let (version, content_key) = unwrapped
+            .split_at_checked(WRAPPED_VERSION_LENGTH)
+            .ok_or_else(|| Error::with_message(ErrorKind::DataConversion, "invalid wrapped key"))?;
+        let version_length = PROTOCOL_V2.len();
+        if &version[..version_length] != PROTOCOL_V2.as_bytes()
+            || version[version_length..].iter().any(|&b| b != 0)
+            || content_key.len() != CONTENT_KEY_LENGTH
+        {
+            return Err(Error::with_message(
+                ErrorKind::DataConversion,
+                "the wrapped key doesn't match the encryption protocol",
+            ));
+        }
+
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(content_key)),
+            region_length,
+        })
+    }
+
+    /// Returns the length of every encrypted region but the last, its plaintext preceded by a nonce and followed by a
+    /// tag.
+    pub fn encrypted_region_length(&self) -> u64 {
+        self.region_length + REGION_OVERHEAD
+    }
+
+    /// Decrypts whole regions, the last of which may be shorter than the others.
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>> {
+        let encrypted_region_length = self.encrypted_region_length() as usize;
+        let mut plaintext = Vec::with_capacity(encrypted.len());
+        for region in encrypted.chunks(encrypted_region_length) {
+            if region.len() < REGION_OVERHEAD as usize {
+                return Err(Error::with_message(
+                    ErrorKind::DataConversion,
+                    "truncated encrypted region",
+                ));
+            }
+            let (nonce, ciphertext) = region.split_at(NONCE_LENGTH);
+            let decrypted = self
+                .cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| {
+                    Error::with_message(
+                        ErrorKind::DataConversion,
+                        "failed to decrypt region: the data or its key has been tampered with",
+                    )
+                })?;
+            plaintext.extend(decrypted);
+        }
+        Ok(plaintext)
+    }
+}
+
+impl fmt::Debug for ContentDecryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentDecryptor")
+            .field("region_length", &self.region_length)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(key: LocalKeyEncryptionKey) -> ClientSideEncryptionOptions {
+        ClientSideEncryptionOptions {
+            key_encryption_key: Some(Arc::new(key)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let key = LocalKeyEncryptionKey::new("local-key", [7; 32]);
+        let encryptor = ContentEncryptor::new(&key).await.unwrap();
+        let data: Vec<u8> = (0..REGION_LENGTH as usize + 100).map(|i| i as u8).collect();
+        let encrypted = encryptor.encrypt(&data).unwrap();
+        assert_eq!(data.len() + 2 * REGION_OVERHEAD as usize, encrypted.len());
+
+        let encryption_data =
+            EncryptionData::from_json(&encryptor.encryption_data().to_json().unwrap()).unwrap();
+        assert_eq!(encryptor.encryption_data(), &encryption_data);
+        assert_eq!(
+            data.len() as u64,
+            encryption_data
+                .plaintext_length(encrypted.len() as u64)
+                .unwrap()
+        );
+        let decryptor = ContentDecryptor::new(&options(key), &encryption_data)
+            .await
+            .unwrap();
+        assert_eq!(data, decryptor.decrypt(&encrypted).unwrap());
+
+        // The last region alone decrypts to the end of the data.
+        let range = encryption_data
+            .encrypted_range(REGION_LENGTH + 10..REGION_LENGTH + 20)
+            .unwrap();
+        assert_eq!(REGION_LENGTH + REGION_OVERHEAD, range.start);
+        assert_eq!(
+            REGION_LENGTH,
+            encryption_data.plaintext_offset(range.start).unwrap()
+        );
+        assert_eq!(
+            &data[REGION_LENGTH as usize..],
+            decryptor
+                .decrypt(&encrypted[range.start as usize..])
+                .unwrap()
+        );
+
+        let mut tampered = encrypted.clone();
+        tampered[100] ^= 1;
+        assert!(decryptor.decrypt(&tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn wrong_key() {
+        let encryptor = ContentEncryptor::new(&LocalKeyEncryptionKey::new("local-key", [7; 32]))
+            .await
+            .unwrap();
+        let encryption_data = encryptor.encryption_data();
+        assert!(ContentDecryptor::new(
+            &options(LocalKeyEncryptionKey::new("other-key", [7; 32])),
+            encryption_data
+        )
+        .await
+        .is_err());
+        assert!(ContentDecryptor::new(
+            &options(LocalKeyEncryptionKey::new("local-key", [8; 32])),
+            encryption_data
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn parses_other_sdks_encryption_data() {
+        let json = r#"{"WrappedContentKey":{"KeyId":"local-key","EncryptedKey":"AAECAw==","Algorithm":"A256KW"},"EncryptionAgent":{"Protocol":"2.0","EncryptionAlgorithm":"AES_GCM_256"},"EncryptionMode":"FullBlob","KeyWrappingMetadata":{"EncryptionLibrary":"Python 12.19.0"},"EncryptedRegionInfo":{"DataLength":4194304,"NonceLength":12}}"#;
+        let encryption_data = EncryptionData::from_json(json).unwrap();
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            encryption_data.wrapped_content_key.encrypted_key
+        );
+        assert_eq!(0, encryption_data.plaintext_length(0).unwrap());
+        assert_eq!(100, encryption_data.plaintext_length(128).unwrap());
+    }
+}
@@ -1,2 +1,21 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
+
+#![doc = include_str!("../README.md")]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+mod checksum;
+mod connection_string;
+#[cfg(feature = "client_side_encryption")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client_side_encryption")))]
+pub mod encryption;
+mod parsers;
+pub mod sas;
+mod shared_key;
+
+pub use checksum::{
+    content_crc64, content_md5, Checksum, ChecksumAlgorithm, ChecksumMismatchError,
+};
+pub use connection_string::{ConnectionString, DEVELOPMENT_ACCOUNT_KEY, DEVELOPMENT_ACCOUNT_NAME};
+pub use parsers::{parse_account_name, parse_resource_path};
+pub use shared_key::{SharedKeyAuthorizationPolicy, SharedKeyCredential};
@@ -0,0 +1,97 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use azure_core::http::Url;
+use percent_encoding::percent_decode_str;
+use url::Host;
+
+/// Returns whether `url` is path-style, naming the storage account in its first path segment as
+/// Azurite's `http://127.0.0.1:10000/devstoreaccount1` does, rather than in its host name.
+fn is_path_style(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(_) | Host::Ipv6(_)) => true,
+        Some(Host::Domain(domain)) => !domain.contains('.'),
+        None => false,
+    }
+}
+
+/// Returns the name of the storage account `url` addresses, for example `myaccount` for
+/// `https://myaccount.blob.core.windows.net/mycontainer` or `devstoreaccount1` for
+/// `http://127.0.0.1:10000/devstoreaccount1/mycontainer`.
+pub fn parse_account_name(url: &Url) -> Option<String> {
+    if is_path_style(url) {
+        url.path_segments()?
+            .next()
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+    } else {
+        url.host_str()?.split('.').next().map(ToString::to_string)
+    }
+}
+
+/// Returns the decoded path segments of `url` following the storage account, for example
+/// `["mycontainer", "dir/myblob"]` for `https://myaccount.blob.core.windows.net/mycontainer/dir%2Fmyblob`
+/// or `http://127.0.0.1:10000/devstoreaccount1/mycontainer/dir%2Fmyblob`.
+pub fn parse_resource_path(url: &Url) -> Vec<String> {
+    let Some(segments) = url.path_segments() else {
+        return Vec::new();
+    };
+    segments
+        .skip(usize::from(is_path_style(url)))
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_and_path() {
+        for (url, account, path) in [
+            (
+                "https://myaccount.blob.core.windows.net/container/dir%2Fblob%20name",
+                Some("myaccount"),
+                vec!["container", "dir/blob name"],
+            ),
+            (
+                "https://myaccount.blob.core.windows.net/container/dir/blob",
+                Some("myaccount"),
+                vec!["container", "dir", "blob"],
+            ),
+            (
+                "https://myaccount.queue.core.windows.net/",
+                Some("myaccount"),
+                vec![],
+            ),
+            (
+                "http://127.0.0.1:10000/devstoreaccount1/container/blob",
+                Some("devstoreaccount1"),
+                vec!["container", "blob"],
+            ),
+            (
+                "http://azurite:10001/devstoreaccount1/queue",
+                Some("devstoreaccount1"),
+                vec!["queue"],
+            ),
+            ("http://127.0.0.1:10000/", None, vec![]),
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert_eq!(account.map(String::from), parse_account_name(&url), "{url}");
+            assert_eq!(path, parse_resource_path(&url), "{url}");
+        }
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!("a b/c%", percent_decode("a%20b%2Fc%"));
+        assert_eq!("%zz", percent_decode("%zz"));
+        assert_eq!("é", percent_decode("%C3%A9"));
+        assert_eq!("a+b", percent_decode("a+b"));
+    }
+}
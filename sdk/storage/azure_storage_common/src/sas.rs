@@ -0,0 +1,1200 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Shared access signatures (SAS), which grant time-limited access to storage resources.
+//!
+//! [`BlobSasBuilder`] and [`QueueSasBuilder`] create service SAS for a container, blob or queue,
+//! signed with the account key or, for Blob Storage, a [`UserDelegationKey`]. [`AccountSasBuilder`]
+//! creates account SAS. Each produces the SAS query string to append to the resource's URL.
+
+use crate::SharedKeyCredential;
+use azure_core::{
+    credentials::Secret,
+    error::{Error, ErrorKind},
+    hmac::hmac_sha256,
+    http::Url,
+    time::{to_rfc3339, Duration, OffsetDateTime},
+    Result,
+};
+use std::{fmt, net::IpAddr};
+use url::form_urlencoded;
+
+/// The service version of the SAS the builders create, which determines their string-to-sign.
+pub const SAS_VERSION: &str = "2022-11-02";
+
+/// Renders the permissions whose flags are set, in the order the service requires.
+fn write_flags(f: &mut fmt::Formatter<'_>, flags: &[(bool, char)]) -> fmt::Result {
+    for (set, c) in flags {
+        if *set {
+            write!(f, "{c}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Permissions a blob or container SAS grants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlobSasPermissions {
+    /// Read the content, properties, metadata and block list.
+    pub read: bool,
+    /// Add a block to an append blob.
+    pub add: bool,
+    /// Create a new blob.
+    pub create: bool,
+    /// Create or write content, properties, metadata or a block list.
+    pub write: bool,
+    /// Delete a blob.
+    pub delete: bool,
+    /// Delete a blob version.
+    pub delete_version: bool,
+    /// Permanently delete a soft-deleted blob snapshot or version.
+    pub permanent_delete: bool,
+    /// List the blobs in a container.
+    pub list: bool,
+    /// Read or write blob tags.
+    pub tags: bool,
+    /// Find blobs by tags.
+    pub filter_by_tags: bool,
+    /// Set or delete an immutability policy or legal hold.
+    pub set_immutability_policy: bool,
+}
+
+impl fmt::Display for BlobSasPermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                (self.read, 'r'),
+                (self.add, 'a'),
+                (self.create, 'c'),
+                (self.write, 'w'),
+                (self.delete, 'd'),
+                (self.delete_version, 'x'),
+                (self.permanent_delete, 'y'),
+                (self.list, 'l'),
+                (self.tags, 't'),
+                (self.filter_by_tags, 'f'),
+                (self.set_immutability_policy, 'i'),
+            ],
+        )
+    }
+}
+
+/// Permissions a queue SAS grants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueSasPermissions {
+    /// Read metadata and properties and peek messages.
+    pub read: bool,
+    /// Add messages.
+    pub add: bool,
+    /// Update messages.
+    pub update: bool,
+    /// Receive and delete messages.
+    pub process: bool,
+}
+
+impl fmt::Display for QueueSasPermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                (self.read, 'r'),
+                (self.add, 'a'),
+                (self.update, 'u'),
+                (self.process, 'p'),
+            ],
+        )
+    }
+}
+
+/// Permissions an account SAS grants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountSasPermissions {
+    /// Read resources.
+    pub read: bool,
+    /// Write resources.
+    pub write: bool,
+    /// Delete resources other than queue messages.
+    pub delete: bool,
+    /// Delete blob versions.
+    pub delete_version: bool,
+    /// Permanently delete soft-deleted blob snapshots and versions.
+    pub permanent_delete: bool,
+    /// List resources.
+    pub list: bool,
+    /// Add queue messages, table entities and append blob blocks.
+    pub add: bool,
+    /// Create resources.
+    pub create: bool,
+    /// Update queue messages and table entities.
+    pub update: bool,
+    /// Receive and delete queue messages.
+    pub process: bool,
+    /// Read and write blob tags.
+    pub tags: bool,
+    /// Find blobs by tags.
+    pub filter_by_tags: bool,
+    /// Set or delete immutability policies and legal holds.
+    pub set_immutability_policy: bool,
+}
+
+impl fmt::Display for AccountSasPermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                (self.read, 'r'),
+                (self.write, 'w'),
+                (self.delete, 'd'),
+                (self.delete_version, 'x'),
+                (self.permanent_delete, 'y'),
+                (self.list, 'l'),
+                (self.add, 'a'),
+                (self.create, 'c'),
+                (self.update, 'u'),
+                (self.process, 'p'),
+                (self.tags, 't'),
+                (self.filter_by_tags, 'f'),
+                (self.set_immutability_policy, 'i'),
+            ],
+        )
+    }
+}
+
+/// The services an account SAS grants access to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountSasServices {
+    /// Blob Storage.
+    pub blob: bool,
+    /// Queue Storage.
+    pub queue: bool,
+    /// Table Storage.
+    pub table: bool,
+    /// Azure Files.
+    pub file: bool,
+}
+
+impl fmt::Display for AccountSasServices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                (self.blob, 'b'),
+                (self.queue, 'q'),
+                (self.table, 't'),
+                (self.file, 'f'),
+            ],
+        )
+    }
+}
+
+/// The types of resources an account SAS grants access to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountSasResourceTypes {
+    /// Service-level operations such as getting service properties and listing containers or queues.
+    pub service: bool,
+    /// Container-level operations such as creating a container or queue.
+    pub container: bool,
+    /// Object-level operations such as reading a blob or adding a queue message.
+    pub object: bool,
+}
+
+impl fmt::Display for AccountSasResourceTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                (self.service, 's'),
+                (self.container, 'c'),
+                (self.object, 'o'),
+            ],
+        )
+    }
+}
+
+/// The protocols a SAS permits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SasProtocol {
+    /// HTTPS only.
+    Https,
+    /// HTTPS and HTTP.
+    HttpsAndHttp,
+}
+
+impl fmt::Display for SasProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Https => "https",
+            Self::HttpsAndHttp => "https,http",
+        })
+    }
+}
+
+/// The IP address or inclusive range of addresses from which a SAS permits requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SasIpRange {
+    /// The first address in the range.
+    pub start: IpAddr,
+    /// The last address in the range, if it has more than one.
+    pub end: Option<IpAddr>,
+}
+
+impl From<IpAddr> for SasIpRange {
+    fn from(address: IpAddr) -> Self {
+        Self {
+            start: address,
+            end: None,
+        }
+    }
+}
+
+impl fmt::Display for SasIpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}-{end}", self.start),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
+/// A key with which to sign a user delegation SAS, acquired from Blob Storage with Microsoft
+/// Entra ID credentials.
+#[derive(Clone, Debug)]
+pub struct UserDelegationKey {
+    /// The object ID of the Microsoft Entra identity that requested the key.
+    pub signed_oid: String,
+    /// The ID of the Microsoft Entra tenant of the identity that requested the key.
+    pub signed_tid: String,
+    /// When the key becomes valid, as returned by the service.
+    pub signed_start: String,
+    /// When the key expires, as returned by the service.
+    pub signed_expiry: String,
+    /// The service that issued the key.
+    pub signed_service: String,
+    /// The service version that issued the key.
+    pub signed_version: String,
+    /// The base64-encoded key.
+    pub value: Secret,
+}
+
+/// Returns `url` with the SAS query string `sas` appended to any query it already has.
+pub fn append_sas(url: &Url, sas: &str) -> Url {
+    let mut url = url.clone();
+    let query = match url.query() {
+        Some(query) if !query.is_empty() => format!("{query}&{sas}"),
+        _ => sas.to_string(),
+    };
+    url.set_query(Some(&query));
+    url
+}
+
+/// Formats a SAS time: UTC without fractional seconds.
+fn format_time(time: &OffsetDateTime) -> String {
+    to_rfc3339(&(OffsetDateTime::UNIX_EPOCH + Duration::seconds(time.unix_timestamp())))
+}
+
+fn option_time(time: &Option<OffsetDateTime>) -> String {
+    time.as_ref().map(format_time).unwrap_or_default()
+}
+
+fn option_string<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+/// Builds a SAS query string from `pairs`, skipping empty values.
+fn query<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs.into_iter().filter(|(_, v)| !v.is_empty()))
+        .finish()
+}
+
+/// The settings common to service SAS.
+#[derive(Clone, Debug, Default)]
+struct ServiceSas<P> {
+    permissions: Option<P>,
+    start: Option<OffsetDateTime>,
+    expiry: Option<OffsetDateTime>,
+    ip_range: Option<SasIpRange>,
+    protocol: Option<SasProtocol>,
+    identifier: Option<String>,
+}
+
+impl<P: fmt::Display> ServiceSas<P> {
+    /// Returns an error when the SAS neither refers to a stored access policy nor sets its own
+    /// permissions and expiry.
+    fn validate(&self) -> Result<()> {
+        if self.identifier.is_none() && (self.permissions.is_none() || self.expiry.is_none()) {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "a SAS without a stored access policy identifier requires permissions and an expiry time",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a service SAS for a blob or container.
+///
+/// Sign the SAS with the account key by [`BlobSasBuilder::sign`] or with a user delegation key by
+/// [`BlobSasBuilder::sign_with_user_delegation_key`].
+///
+/// # Examples
+///
+/// ```
+/// use azure_core::{credentials::Secret, time::{Duration, OffsetDateTime}};
+/// use azure_storage_common::{sas::{BlobSasBuilder, BlobSasPermissions}, SharedKeyCredential};
+///
+/// # fn main() -> azure_core::Result<()> {
+/// let credential = SharedKeyCredential::new("myaccount", Secret::new("a2V5"))?;
+/// let sas = BlobSasBuilder::new()
+///     .with_container_name("mycontainer")
+///     .with_blob_name("report.pdf")
+///     .with_permissions(BlobSasPermissions { read: true, ..Default::default() })
+///     .with_expiry(OffsetDateTime::now_utc() + Duration::hours(1))
+///     .with_content_disposition("attachment; filename=report.pdf")
+///     .sign(&credential)?;
+/// let url = format!("https://myaccount.blob.core.windows.net/mycontainer/report.pdf?{sas}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BlobSasBuilder {
+    sas: ServiceSas<BlobSasPermissions>,
+    container_name: String,
+    blob_name: Option<String>,
+    snapshot: Option<String>,
+    version_id: Option<String>,
+    encryption_scope: Option<String>,
+    cache_control: Option<String>,
+    content_disposition: Option<String>,
+    content_encoding: Option<String>,
+    content_language: Option<String>,
+    content_type: Option<String>,
+}
+
+impl BlobSasBuilder {
+    /// Creates a new `BlobSasBuilder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the container the SAS grants access to, or which contains the blob it grants access to.
+    pub fn with_container_name(mut self, container_name: impl Into<String>) -> Self {
+        self.container_name = container_name.into();
+        self
+    }
+
+    /// Sets the name of the blob the SAS grants access to. Without one, the SAS grants access to the container.
+    pub fn with_blob_name(mut self, blob_name: impl Into<String>) -> Self {
+        self.blob_name = Some(blob_name.into());
+        self
+    }
+
+    /// Sets the permissions the SAS grants. Required unless the SAS refers to a stored access policy setting them.
+    pub fn with_permissions(mut self, permissions: BlobSasPermissions) -> Self {
+        self.sas.permissions = Some(permissions);
+        self
+    }
+
+    /// Sets when the SAS becomes valid. By default, it's valid immediately.
+    pub fn with_start(mut self, start: OffsetDateTime) -> Self {
+        self.sas.start = Some(start);
+        self
+    }
+
+    /// Sets when the SAS expires. Required unless the SAS refers to a stored access policy setting it.
+    pub fn with_expiry(mut self, expiry: OffsetDateTime) -> Self {
+        self.sas.expiry = Some(expiry);
+        self
+    }
+
+    /// Sets the IP addresses from which the SAS permits requests.
+    pub fn with_ip_range(mut self, ip_range: impl Into<SasIpRange>) -> Self {
+        self.sas.ip_range = Some(ip_range.into());
+        self
+    }
+
+    /// Sets the protocols the SAS permits.
+    pub fn with_protocol(mut self, protocol: SasProtocol) -> Self {
+        self.sas.protocol = Some(protocol);
+        self
+    }
+
+    /// Sets the identifier of a stored access policy on the container, which the SAS inherits
+    /// settings from and which can revoke it. User delegation SAS can't refer to stored access policies.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.sas.identifier = Some(identifier.into());
+        self
+    }
+
+    /// Limits the SAS to a snapshot of the blob, which the request URL must also select with its `snapshot` parameter.
+    pub fn with_snapshot(mut self, snapshot: impl Into<String>) -> Self {
+        self.snapshot = Some(snapshot.into());
+        self
+    }
+
+    /// Limits the SAS to a version of the blob, which the request URL must also select with its `versionid` parameter.
+    pub fn with_version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+
+    /// Sets the encryption scope with which requests authorized by the SAS encrypt blob content.
+    pub fn with_encryption_scope(mut self, encryption_scope: impl Into<String>) -> Self {
+        self.encryption_scope = Some(encryption_scope.into());
+        self
+    }
+
+    /// Overrides the `Cache-Control` response header of requests authorized by the SAS.
+    pub fn with_cache_control(mut self, cache_control: impl Into<String>) -> Self {
+        self.cache_control = Some(cache_control.into());
+        self
+    }
+
+    /// Overrides the `Content-Disposition` response header of requests authorized by the SAS.
+    pub fn with_content_disposition(mut self, content_disposition: impl Into<String>) -> Self {
+        self.content_disposition = Some(content_disposition.into());
+        self
+    }
+
+    /// Overrides the `Content-Encoding` response header of requests authorized by the SAS.
+    pub fn with_content_encoding(mut self, content_encoding: impl Into<String>) -> Self {
+        self.content_encoding = Some(content_encoding.into());
+        self
+    }
+
+    /// Overrides the `Content-Language` response header of requests authorized by the SAS.
+    pub fn with_content_language(mut self, content_language: impl Into<String>) -> Self {
+        self.content_language = Some(content_language.into());
+        self
+    }
+
+    /// Overrides the `Content-Type` response header of requests authorized by the SAS.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Returns the SAS query string, signed with the account key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the builder's settings are incomplete or inconsistent.
+    pub fn sign(&self, credential: &SharedKeyCredential) -> Result<String> {
+        let fields = self.fields(credential.account_name())?;
+        let string_to_sign = [
+            fields.permissions.as_str(),
+            &fields.start,
+            &fields.expiry,
+            &fields.canonicalized_resource,
+            self.sas.identifier.as_deref().unwrap_or_default(),
+            &fields.ip_range,
+            &fields.protocol,
+            SAS_VERSION,
+            fields.resource,
+            fields.snapshot,
+            fields.encryption_scope,
+            fields.cache_control,
+            fields.content_disposition,
+            fields.content_encoding,
+            fields.content_language,
+            fields.content_type,
+        ]
+        .join("\n");
+        let signature = credential.sign(&string_to_sign)?;
+        Ok(fields.query(&[
+            ("si", self.sas.identifier.as_deref().unwrap_or_default()),
+            ("sig", &signature),
+        ]))
+    }
+
+    /// Returns the SAS query string, signed with a user delegation key.
+    ///
+    /// # Arguments
+    ///
+    /// * `account_name` - The name of the storage account.
+    /// * `key` - The user delegation key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the builder's settings are incomplete or inconsistent, or the SAS refers
+    /// to a stored access policy.
+    pub fn sign_with_user_delegation_key(
+        &self,
+        account_name: &str,
+        key: &UserDelegationKey,
+    ) -> Result<String> {
+        if self.sas.identifier.is_some() {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "a user delegation SAS can't refer to a stored access policy",
+            ));
+        }
+        let fields = self.fields(account_name)?;
+        let string_to_sign = [
+            fields.permissions.as_str(),
+            &fields.start,
+            &fields.expiry,
+            &fields.canonicalized_resource,
+            &key.signed_oid,
+            &key.signed_tid,
+            &key.signed_start,
+            &key.signed_expiry,
+            &key.signed_service,
+            &key.signed_version,
+            // signed authorized and unauthorized user object IDs and correlation ID
+            "",
+            "",
+            "",
+            &fields.ip_range,
+            &fields.protocol,
+            SAS_VERSION,
+            fields.resource,
+            fields.snapshot,
+            fields.encryption_scope,
+            fields.cache_control,
+            fields.content_disposition,
+            fields.content_encoding,
+            fields.content_language,
+            fields.content_type,
+        ]
+        .join("\n");
+        let signature = hmac_sha256(&string_to_sign, &key.value)?;
+        Ok(fields.query(&[
+            ("skoid", &key.signed_oid),
+            ("sktid", &key.signed_tid),
+            ("skt", &key.signed_start),
+            ("ske", &key.signed_expiry),
+            ("sks", &key.signed_service),
+            ("skv", &key.signed_version),
+            ("sig", &signature),
+        ]))
+    }
+
+    fn fields(&self, account_name: &str) -> Result<BlobSasFields<'_>> {
+        self.sas.validate()?;
+        if self.container_name.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "a blob SAS requires a container name",
+            ));
+        }
+        let (resource, snapshot) = match (&self.blob_name, &self.snapshot, &self.version_id) {
+            (None, None, None) => ("c", ""),
+            (Some(_), None, None) => ("b", ""),
+            (Some(_), Some(snapshot), None) => ("bs", snapshot.as_str()),
+            (Some(_), None, Some(version_id)) => ("bv", version_id.as_str()),
+            (None, ..) => {
+                return Err(Error::with_message(
+                    ErrorKind::Other,
+                    "a snapshot or version SAS requires a blob name",
+                ))
+            }
+            (Some(_), Some(_), Some(_)) => {
+                return Err(Error::with_message(
+                    ErrorKind::Other,
+                    "a SAS can't specify both a snapshot and a version",
+                ))
+            }
+        };
+        let mut canonicalized_resource = format!("/blob/{account_name}/{}", self.container_name);
+        if let Some(blob_name) = &self.blob_name {
+            canonicalized_resource.push('/');
+            canonicalized_resource.push_str(blob_name);
+        }
+        Ok(BlobSasFields {
+            permissions: option_string(&self.sas.permissions),
+            start: option_time(&self.sas.start),
+            expiry: option_time(&self.sas.expiry),
+            canonicalized_resource,
+            ip_range: option_string(&self.sas.ip_range),
+            protocol: option_string(&self.sas.protocol),
+            resource,
+            snapshot,
+            encryption_scope: self.encryption_scope.as_deref().unwrap_or_default(),
+            cache_control: self.cache_control.as_deref().unwrap_or_default(),
+            content_disposition: self.content_disposition.as_deref().unwrap_or_default(),
+            content_encoding: self.content_encoding.as_deref().unwrap_or_default(),
+            content_language: self.content_language.as_deref().unwrap_or_default(),
+            content_type: self.content_type.as_deref().unwrap_or_default(),
+        })
+    }
+}
+
+/// The values of a blob SAS's signed fields.
+struct BlobSasFields<'a> {
+    permissions: String,
+    start: String,
+    expiry: String,
+    canonicalized_resource: String,
+    ip_range: String,
+    protocol: String,
+    resource: &'static str,
+    snapshot: &'a str,
+    encryption_scope: &'a str,
+    cache_control: &'a str,
+    content_disposition: &'a str,
+    content_encoding: &'a str,
+    content_language: &'a str,
+    content_type: &'a str,
+}
+
+impl BlobSasFields<'_> {
+    fn query(&self, signature_fields: &[(&str, &str)]) -> String {
+        query(
+            [
+                ("sv", SAS_VERSION),
+                ("sp", &self.permissions),
+                ("st", &self.start),
+                ("se", &self.expiry),
+                ("sip", &self.ip_range),
+                ("spr", &self.protocol),
+                ("sr", self.resource),
+                ("ses", self.encryption_scope),
+                ("rscc", self.cache_control),
+                ("rscd", self.content_disposition),
+                ("rsce", self.content_encoding),
+                ("rscl", self.content_language),
+                ("rsct", self.content_type),
+            ]
+            .into_iter()
+            .chain(signature_fields.iter().copied()),
+        )
+    }
+}
+
+/// Builds a service SAS for a queue, signed with the account key.
+#[derive(Clone, Debug, Default)]
+pub struct QueueSasBuilder {
+    sas: ServiceSas<QueueSasPermissions>,
+    queue_name: String,
+}
+
+impl QueueSasBuilder {
+    /// Creates a new `QueueSasBuilder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the queue the SAS grants access to.
+    pub fn with_queue_name(mut self, queue_name: impl Into<String>) -> Self {
+        self.queue_name = queue_name.into();
+        self
+    }
+
+    /// Sets the permissions the SAS grants. Required unless the SAS refers to a stored access policy setting them.
+    pub fn with_permissions(mut self, permissions: QueueSasPermissions) -> Self {
+        self.sas.permissions = Some(permissions);
+        self
+    }
+
+    /// Sets when the SAS becomes valid. By default, it's valid immediately.
+    pub fn with_start(mut self, start: OffsetDateTime) -> Self {
+        self.sas.start = Some(start);
+        self
+    }
+
+    /// Sets when the SAS expires. Required unless the SAS refers to a stored access policy setting it.
+    pub fn with_expiry(mut self, expiry: OffsetDateTime) -> Self {
+        self.sas.expiry = Some(expiry);
+        self
+    }
+
+    /// Sets the IP addresses from which the SAS permits requests.
+    pub fn with_ip_range(mut self, ip_range: impl Into<SasIpRange>) -> Self {
+        self.sas.ip_range = Some(ip_range.into());
+        self
+    }
+
+    /// Sets the protocols the SAS permits.
+    pub fn with_protocol(mut self, protocol: SasProtocol) -> Self {
+        self.sas.protocol = Some(protocol);
+        self
+    }
+
+    /// Sets the identifier of a stored access policy on the queue, which the SAS inherits settings
+    /// from and which can revoke it.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.sas.identifier = Some(identifier.into());
+        self
+    }
+
+    /// Returns the SAS query string, signed with the account key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the builder's settings are incomplete.
+    pub fn sign(&self, credential: &SharedKeyCredential) -> Result<String> {
+        self.sas.validate()?;
+        if self.queue_name.is_empty() {
+            return Err(Error::with_message(
+                ErrorKind::Other,
+                "a queue SAS requires a queue name",
+            ));
+        }
+        let permissions = option_string(&self.sas.permissions);
+        let start = option_time(&self.sas.start);
+        let expiry = option_time(&self.sas.expiry);
+        let ip_range = option_string(&self.sas.ip_range);
+        let protocol = option_string(&self.sas.protocol);
+        let identifier = self.sas.identifier.as_deref().unwrap_or_default();
+        let string_to_sign = [
+            permissions.as_str(),
+            &start,
+            &expiry,
+            &format!("/queue/{}/{}", credential.account_name(), self.queue_name),
+            identifier,
+            &ip_range,
+            &protocol,
+            SAS_VERSION,
+        ]
+        .join("\n");
+        let signature = credential.sign(&string_to_sign)?;
+        Ok(query([
+            ("sv", SAS_VERSION),
+            ("sp", &permissions),
+            ("st", &start),
+            ("se", &expiry),
+            ("sip", &ip_range),
+            ("spr", &protocol),
+            ("si", identifier),
+            ("sig", &signature),
+        ]))
+    }
+}
+
+/// Builds an account SAS, which grants access to resources in one or more of a storage account's services.
+#[derive(Clone, Debug)]
+pub struct AccountSasBuilder {
+    services: AccountSasServices,
+    resource_types: AccountSasResourceTypes,
+    permissions: AccountSasPermissions,
+    start: Option<OffsetDateTime>,
+    expiry: OffsetDateTime,
+    ip_range: Option<SasIpRange>,
+    protocol: Option<SasProtocol>,
+    encryption_scope: Option<String>,
+}
+
+impl AccountSasBuilder {
+    /// Creates a new `AccountSasBuilder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `services` - The services the SAS grants access to.
+    /// * `resource_types` - The types of resources the SAS grants access to.
+    /// * `permissions` - The permissions the SAS grants.
+    /// * `expiry` - When the SAS expires.
+    pub fn new(
+        services: AccountSasServices,
+        resource_types: AccountSasResourceTypes,
+        permissions: AccountSasPermissions,
+        expiry: OffsetDateTime,
+    ) -> Self {
+        Self {
+            services,
+            resource_types,
+            permissions,
+            start: None,
+            expiry,
+            ip_range: None,
+            protocol: None,
+            encryption_scope: None,
+        }
+    }
+
+    /// Sets when the SAS becomes valid. By default, it's valid immediately.
+    pub fn with_start(mut self, start: OffsetDateTime) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Sets the IP addresses from which the SAS permits requests.
+    pub fn with_ip_range(mut self, ip_range: impl Into<SasIpRange>) -> Self {
+        self.ip_range = Some(ip_range.into());
+        self
+    }
+
+    /// Sets the protocols the SAS permits.
+    pub fn with_protocol(mut self, protocol: SasProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Sets the encryption scope with which requests authorized by the SAS encrypt blob content.
+    pub fn with_encryption_scope(mut self, encryption_scope: impl Into<String>) -> Self {
+        self.encryption_scope = Some(encryption_scope.into());
+        self
+    }
+
+    /// Returns the SAS query string, signed with the account key.
+    pub fn sign(&self, credential: &SharedKeyCredential) -> Result<String> {
+        let permissions = self.permissions.to_string();
+        let services = self.services.to_string();
+        let resource_types = self.resource_types.to_string();
+        let start = option_time(&self.start);
+        let expiry = format_time(&self.expiry);
+        let ip_range = option_string(&self.ip_range);
+        let protocol = option_string(&self.protocol);
+        let encryption_scope = self.encryption_scope.as_deref().unwrap_or_default();
+        let string_to_sign = [
+            credential.account_name(),
+            &permissions,
+            &services,
+            &resource_types,
+            &start,
+            &expiry,
+            &ip_range,
+            &protocol,
+            SAS_VERSION,
+            encryption_scope,
+            "",
+        ]
+        .join("\n");
+        let signature = credential.sign(&string_to_sign)?;
+        Ok(query([
+            ("sv", SAS_VERSION),
+            ("ss", &services),
+            ("srt", &resource_types),
+            ("sp", &permissions),
+            ("st", &start),
+            ("se", &expiry),
+            ("sip", &ip_range),
+            ("spr", &protocol),
+            ("ses", encryption_scope),
+            ("sig", &signature),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use azure_core::base64;
+    use std::{collections::HashMap, net::Ipv4Addr, sync::Arc};
+
+    // base64 of "key"
+    const ACCOUNT_KEY: &str = "a2V5";
+
+    fn credential() -> Arc<SharedKeyCredential> {
+        SharedKeyCredential::new("account", Secret::new(ACCOUNT_KEY)).unwrap()
+    }
+
+    fn time(s: &str) -> OffsetDateTime {
+        azure_core::time::parse_rfc3339(s).unwrap()
+    }
+
+    fn params(query: &str) -> HashMap<String, String> {
+        form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    #[test]
+    fn append_to_url() {
+        let url = Url::parse("https://account.blob.core.windows.net/container/blob").unwrap();
+        assert_eq!(
+            "https://account.blob.core.windows.net/container/blob?sv=1&sig=a%2Bb",
+            append_sas(&url, "sv=1&sig=a%2Bb").as_str()
+        );
+        let url =
+            Url::parse("https://account.blob.core.windows.net/container/blob?snapshot=s").unwrap();
+        assert_eq!(
+            "https://account.blob.core.windows.net/container/blob?snapshot=s&sv=1",
+            append_sas(&url, "sv=1").as_str()
+        );
+    }
+
+    #[test]
+    fn permissions() {
+        let all = BlobSasPermissions {
+            read: true,
+            add: true,
+            create: true,
+            write: true,
+            delete: true,
+            delete_version: true,
+            permanent_delete: true,
+            list: true,
+            tags: true,
+            filter_by_tags: true,
+            set_immutability_policy: true,
+        };
+        assert_eq!("racwdxyltfi", all.to_string());
+        assert_eq!(
+            "rl",
+            BlobSasPermissions {
+                list: true,
+                read: true,
+                ..Default::default()
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "raup",
+            QueueSasPermissions {
+                read: true,
+                add: true,
+                update: true,
+                process: true,
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "bq",
+            AccountSasServices {
+                queue: true,
+                blob: true,
+                ..Default::default()
+            }
+            .to_string()
+        );
+        assert_eq!("https,http", SasProtocol::HttpsAndHttp.to_string());
+        assert_eq!(
+            "10.0.0.1-10.0.0.9",
+            SasIpRange {
+                start: Ipv4Addr::new(10, 0, 0, 1).into(),
+                end: Some(Ipv4Addr::new(10, 0, 0, 9).into()),
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn blob_sas() {
+        let credential = credential();
+        let sas = BlobSasBuilder::new()
+            .with_container_name("container")
+            .with_blob_name("dir/blob")
+            .with_permissions(BlobSasPermissions {
+                read: true,
+                ..Default::default()
+            })
+            .with_start(time("2026-10-19T00:00:00.5Z"))
+            .with_expiry(time("2026-10-19T01:00:00Z"))
+            .with_ip_range(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)))
+            .with_protocol(SasProtocol::Https)
+            .with_content_disposition("attachment; filename=blob.txt")
+            .with_content_type("text/plain");
+        let params = params(&sas.sign(&credential).unwrap());
+
+        let string_to_sign = "r\n2026-10-19T00:00:00Z\n2026-10-19T01:00:00Z\n/blob/account/container/dir/blob\n\n10.0.0.1\nhttps\n2022-11-02\nb\n\n\n\nattachment; filename=blob.txt\n\n\ntext/plain";
+        assert_eq!(
+            Some(&credential.sign(string_to_sign).unwrap()),
+            params.get("sig")
+        );
+        for (name, value) in [
+            ("sv", "2022-11-02"),
+            ("sp", "r"),
+            ("st", "2026-10-19T00:00:00Z"),
+            ("se", "2026-10-19T01:00:00Z"),
+            ("sip", "10.0.0.1"),
+            ("spr", "https"),
+            ("sr", "b"),
+            ("rscd", "attachment; filename=blob.txt"),
+            ("rsct", "text/plain"),
+        ] {
+            assert_eq!(Some(value), params.get(name).map(String::as_str), "{name}");
+        }
+        assert!(!params.contains_key("si"));
+        assert!(!params.contains_key("rscc"));
+    }
+
+    #[test]
+    fn container_sas_with_identifier() {
+        let credential = credential();
+        let params = params(
+            &BlobSasBuilder::new()
+                .with_container_name("container")
+                .with_identifier("policy")
+                .sign(&credential)
+                .unwrap(),
+        );
+        let string_to_sign =
+            "\n\n\n/blob/account/container\npolicy\n\n\n2022-11-02\nc\n\n\n\n\n\n\n";
+        assert_eq!(
+            Some(&credential.sign(string_to_sign).unwrap()),
+            params.get("sig")
+        );
+        assert_eq!(Some("c"), params.get("sr").map(String::as_str));
+        assert_eq!(Some("policy"), params.get("si").map(String::as_str));
+        assert!(!params.contains_key("sp"));
+    }
+
+    #[test]
+    fn snapshot_and_version_sas() {
+        let builder = BlobSasBuilder::new()
+            .with_container_name("container")
+            .with_blob_name("blob")
+            .with_permissions(BlobSasPermissions {
+                read: true,
+                ..Default::default()
+            })
+            .with_expiry(OffsetDateTime::now_utc() + Duration::hours(1));
+        let sr =
+            |builder: BlobSasBuilder| params(&builder.sign(&credential()).unwrap())["sr"].clone();
+        assert_eq!(
+            "bs",
+            sr(builder
+                .clone()
+                .with_snapshot("2026-10-19T00:00:00.0000000Z"))
+        );
+        assert_eq!(
+            "bv",
+            sr(builder
+                .clone()
+                .with_version_id("2026-10-19T00:00:00.0000000Z"))
+        );
+        builder
+            .with_snapshot("snapshot")
+            .with_version_id("version")
+            .sign(&credential())
+            .expect_err("snapshot and version");
+    }
+
+    #[test]
+    fn invalid_blob_sas() {
+        let expiry = OffsetDateTime::now_utc() + Duration::hours(1);
+        let read = BlobSasPermissions {
+            read: true,
+            ..Default::default()
+        };
+        for builder in [
+            // no container
+            BlobSasBuilder::new()
+                .with_permissions(read)
+                .with_expiry(expiry),
+            // no expiry
+            BlobSasBuilder::new()
+                .with_container_name("container")
+                .with_permissions(read),
+            // no permissions
+            BlobSasBuilder::new()
+                .with_container_name("container")
+                .with_expiry(expiry),
+            // snapshot of a container
+            BlobSasBuilder::new()
+                .with_container_name("container")
+                .with_permissions(read)
+                .with_expiry(expiry)
+                .with_snapshot("snapshot"),
+        ] {
+            builder.sign(&credential()).expect_err("invalid SAS");
+        }
+    }
+
+    #[test]
+    fn user_delegation_sas() {
+        let key = UserDelegationKey {
+            signed_oid: "oid".to_string(),
+            signed_tid: "tid".to_string(),
+            signed_start: "2026-10-19T00:00:00Z".to_string(),
+            signed_expiry: "2026-10-20T00:00:00Z".to_string(),
+            signed_service: "b".to_string(),
+            signed_version: "2025-11-05".to_string(),
+            value: Secret::new(base64::encode("user delegation key")),
+        };
+        let builder = BlobSasBuilder::new()
+            .with_container_name("container")
+            .with_blob_name("blob")
+            .with_permissions(BlobSasPermissions {
+                read: true,
+                ..Default::default()
+            })
+            .with_expiry(time("2026-10-19T01:00:00Z"));
+        let params = params(
+            &builder
+                .sign_with_user_delegation_key("account", &key)
+                .unwrap(),
+        );
+
+        let string_to_sign = "r\n\n2026-10-19T01:00:00Z\n/blob/account/container/blob\noid\ntid\n2026-10-19T00:00:00Z\n2026-10-20T00:00:00Z\nb\n2025-11-05\n\n\n\n\n\n2022-11-02\nb\n\n\n\n\n\n\n";
+        assert_eq!(
+            Some(&hmac_sha256(string_to_sign, &key.value).unwrap()),
+            params.get("sig")
+        );
+        for (name, value) in [
+            ("skoid", "oid"),
+            ("sktid", "tid"),
+            ("skt", "2026-10-19T00:00:00Z"),
+            ("ske", "2026-10-20T00:00:00Z"),
+            ("sks", "b"),
+            ("skv", "2025-11-05"),
+        ] {
+            assert_eq!(Some(value), params.get(name).map(String::as_str), "{name}");
+        }
+
+        builder
+            .with_identifier("policy")
+            .sign_with_user_delegation_key("account", &key)
+            .expect_err("user delegation SAS with stored access policy");
+    }
+
+    #[test]
+    fn queue_sas() {
+        let credential = credential();
+        let params = params(
+            &QueueSasBuilder::new()
+                .with_queue_name("queue")
+                .with_permissions(QueueSasPermissions {
+                    process: true,
+                    ..Default::default()
+                })
+                .with_expiry(time("2026-10-19T01:00:00Z"))
+                .sign(&credential)
+                .unwrap(),
+        );
+        let string_to_sign = "p\n\n2026-10-19T01:00:00Z\n/queue/account/queue\n\n\n\n2022-11-02";
+        assert_eq!(
+            Some(&credential.sign(string_to_sign).unwrap()),
+            params.get("sig")
+        );
+        assert_eq!(Some("p"), params.get("sp").map(String::as_str));
+        assert!(!params.contains_key("sr"));
+
+        QueueSasBuilder::new()
+            .with_permissions(QueueSasPermissions {
+                read: true,
+                ..Default::default()
+            })
+            .with_expiry(time("2026-10-19T01:00:00Z"))
+            .sign(&credential)
+            .expect_err("no queue name");
+    }
+
+    #[test]
+    fn account_sas() {
+        let credential = credential();
+        let params = params(
+            &AccountSasBuilder::new(
+                AccountSasServices {
+                    blob: true,
+                    queue: true,
+                    ..Default::default()
+                },
+                AccountSasResourceTypes {
+                    container: true,
+                    object: true,
+                    ..Default::default()
+                },
+                AccountSasPermissions {
+                    read: true,
+                    list: true,
+                    ..Default::default()
+                },
+                time("2026-10-19T01:00:00Z"),
+            )
+            .with_protocol(SasProtocol::Https)
+            .sign(&credential)
+            .unwrap(),
+        );
+        let string_to_sign = "account\nrl\nbq\nco\n\n2026-10-19T01:00:00Z\n\nhttps\n2022-11-02\n\n";
+        assert_eq!(
+            Some(&credential.sign(string_to_sign).unwrap()),
+            params.get("sig")
+        );
+        for (name, value) in [("ss", "bq"), ("srt", "co"), ("sp", "rl"), ("spr", "https")] {
+            assert_eq!(Some(value), params.get(name).map(String::as_str), "{name}");
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+use crate::{
+    models::{
+        PeekedMessage, QueueClientPeekMessagesOptions, QueueClientReceiveMessagesOptions,
+        QueueClientSendMessageOptions, QueueMessage, ReceivedMessage, SentMessage,
+    },
+    QueueClient,
+};
+use azure_core::{
+    base64,
+    error::{Error, ErrorKind},
+    http::{Response, XmlFormat},
+    json, Result,
+};
+use azure_storage_common::encryption::{
+    ClientSideEncryptionOptions, ContentDecryptor, ContentEncryptor, EncryptionData,
+};
+use serde::{Deserialize, Serialize};
+
+/// The text of an encrypted message: its encrypted contents and the envelope of their encryption.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct EncryptedMessage {
+    #[serde(
+        deserialize_with = "base64::deserialize",
+        serialize_with = "base64::serialize"
+    )]
+    encrypted_message_contents: Vec<u8>,
+    encryption_data: EncryptionData,
+}
+
+/// A client of a queue whose messages are encrypted before they're sent and decrypted after they're received, created
+/// by [`QueueClient::with_client_side_encryption()`].
+///
+/// Messages are encrypted in the version 2.0 format of the other Azure Storage SDKs, which can read them, and vice
+/// versa. The text of an encrypted message is a JSON document holding the encrypted text and the envelope of its
+/// encryption.
+///
+/// Requires the `client_side_encryption` feature.
+pub struct EncryptedQueueClient {
+    pub(super) queue_client: QueueClient,
+    pub(super) options: ClientSideEncryptionOptions,
+}
+
+impl EncryptedQueueClient {
+    /// Returns the client of the queue, which sends and receives encrypted messages as is.
+    pub fn queue_client(&self) -> &QueueClient {
+        &self.queue_client
+    }
+
+    /// Encrypts a message and enqueues it to the queue.
+    ///
+    /// The message is encrypted with a new content-encryption key, which is wrapped by the key encryption key of the
+    /// client's [`ClientSideEncryptionOptions`].
+    ///
+    /// # Arguments
+    ///
+    /// * `message_text` - The text of the message to encrypt and send.
+    /// * `options` - Optional configuration for the request.
+    pub async fn send_message(
+        &self,
+        message_text: &str,
+        options: Option<QueueClientSendMessageOptions<'_>>,
+    ) -> Result<Response<SentMessage, XmlFormat>> {
+        let key_encryption_key = self.options.key_encryption_key.as_deref().ok_or_else(|| {
+            Error::with_message(
+                ErrorKind::Other,
+                "a key encryption key is required to encrypt messages",
+            )
+        })?;
+        let encryptor = ContentEncryptor::new(key_encryption_key).await?;
+        let message = EncryptedMessage {
+            encrypted_message_contents: encryptor.encrypt(message_text.as_bytes())?,
+            encryption_data: encryptor.encryption_data().clone(),
+        };
+        let json = json::to_json(&message)?;
+        let queue_message = QueueMessage {
+            message_text: Some(String::from_utf8(json.to_vec()).map_err(|error| {
+                Error::with_error(
+                    ErrorKind::DataConversion,
+                    error,
+                    "invalid encrypted message",
+                )
+            })?),
+        };
+
+        self.queue_client
+            .send_message(queue_message.try_into()?, options)
+            .await
+    }
+
+    /// Retrieves one or more messages from the front of the queue and decrypts their text.
+    ///
+    /// Messages that aren't encrypted are returned as is, unless the client requires encryption.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the request. Use `number_of_messages` to specify
+    ///   how many messages to retrieve (up to 32) and set the visibility timeout.
+    pub async fn receive_messages(
+        &self,
+        options: Option<QueueClientReceiveMessagesOptions<'_>>,
+    ) -> Result<Vec<ReceivedMessage>> {
+        let mut messages = self
+            .queue_client
+            .receive_messages(options)
+            .await?
+            .into_model()?
+            .items
+            .unwrap_or_default();
+        for message in &mut messages {
+            self.decrypt(&mut message.message_text).await?;
+        }
+        Ok(messages)
+    }
+
+    /// Peeks one or more messages from the front of the queue, without removing them, and decrypts their text.
+    ///
+    /// Messages that aren't encrypted are returned as is, unless the client requires encryption.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Optional configuration for the request. Use `number_of_messages`
+    ///   to specify how many messages to peek (up to 32).
+    pub async fn peek_messages(
+        &self,
+        options: Option<QueueClientPeekMessagesOptions<'_>>,
+    ) -> Result<Vec<PeekedMessage>> {
+        let mut messages = self
+            .queue_client
+            .peek_messages(options)
+            .await?
+            .into_model()?
+            .items
+            .unwrap_or_default();
+        for message in &mut messages {
+            self.decrypt(&mut message.message_text).await?;
+        }
+        Ok(messages)
+    }
+
+    /// Replaces the text of an encrypted message with its decrypted text.
+    async fn decrypt(&self, message_text: &mut Option<String>) -> Result<()> {
+        let message = message_text
+            .as_deref()
+            .and_then(|text| json::from_json::<_, EncryptedMessage>(text).ok());
+        let Some(message) = message else {
+            if self.options.require_encryption.unwrap_or_default() {
+                return Err(Error::with_message(
+                    ErrorKind::DataConversion,
+                    "the message isn't encrypted, but the client requires encryption",
+                ));
+            }
+            return Ok(());
+        };
+
+        let decryptor = ContentDecryptor::new(&self.options, &message.encryption_data).await?;
+        let plaintext = decryptor.decrypt(&message.encrypted_message_contents)?;
+        *message_text = Some(String::from_utf8(plaintext).map_err(|_| {
+            Error::with_message(
+                ErrorKind::DataConversion,
+                "the decrypted message isn't valid UTF-8",
+            )
+        })?);
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for EncryptedQueueClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedQueueClient")
+            .field("queue_name", &self.queue_client.queue_name())
+            .field("options", &self.options)
+            .finish()
+    }
+}
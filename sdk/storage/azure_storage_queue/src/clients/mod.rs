@@ -1,6 +1,11 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+#[cfg(feature = "client_side_encryption")]
+mod encrypted_queue_client;
+#[cfg(feature = "client_side_encryption")]
+pub use encrypted_queue_client::EncryptedQueueClient;
+
 mod queue_client;
 pub use queue_client::QueueClient;
 
@@ -7,16 +7,67 @@ use crate::generated::{
 };
 use azure_core::{
     credentials::TokenCredential,
-    http::{NoFormat, RawResponse, RequestContent, Response, StatusCode, Url, XmlFormat},
-    xml, Result,
+    http::{
+        policies::Policy, NoFormat, Pipeline, RawResponse, RequestContent, Response, StatusCode,
+        Url, UrlExt, XmlFormat,
+    },
+    tracing, xml, Result,
+};
+use azure_storage_common::{
+    sas::{append_sas, QueueSasBuilder},
+    SharedKeyAuthorizationPolicy, SharedKeyCredential,
 };
 use std::{collections::HashMap, sync::Arc};
 
+#[cfg(feature = "client_side_encryption")]
+use crate::EncryptedQueueClient;
+
 /// A client to interact with a specific Azure storage queue, although that queue may not yet exist.
 pub struct QueueClient {
     pub(super) client: GeneratedQueueClient,
 }
 
+impl GeneratedQueueClient {
+    /// Creates a new GeneratedQueueClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.queue.core.windows.net/`
+    /// * `credential` - The name and key of the storage account.
+    /// * `queue_name` - The name of the queue to interact with.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Queues.Queue")]
+    pub fn with_shared_key(
+        endpoint: &str,
+        credential: Arc<SharedKeyCredential>,
+        queue_name: String,
+        options: Option<QueueClientOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let endpoint = Url::parse(endpoint)?;
+        if !endpoint.scheme().starts_with("http") {
+            return Err(azure_core::Error::with_message(
+                azure_core::error::ErrorKind::Other,
+                format!("{endpoint} must use http(s)"),
+            ));
+        }
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+        Ok(Self {
+            endpoint,
+            queue_name,
+            version: options.version,
+            pipeline: Pipeline::new(
+                option_env!("CARGO_PKG_NAME"),
+                option_env!("CARGO_PKG_VERSION"),
+                options.client_options,
+                Vec::default(),
+                vec![auth_policy],
+                None,
+            ),
+        })
+    }
+}
+
 impl QueueClient {
     /// Creates a new QueueClient using Entra ID authentication.
     ///
@@ -47,6 +98,29 @@ impl QueueClient {
         Ok(Self { client })
     }
 
+    /// Creates a new QueueClient using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.queue.core.windows.net/`
+    /// * `queue_name` - The name of the queue to interact with.
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        queue_name: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<QueueClientOptions>,
+    ) -> Result<Self> {
+        let client = GeneratedQueueClient::with_shared_key(
+            endpoint,
+            credential,
+            queue_name.to_string(),
+            options,
+        )?;
+        Ok(Self { client })
+    }
+
     /// Returns the endpoint URL of the Azure storage account this client is associated with.
     pub fn endpoint(&self) -> &Url {
         self.client.endpoint()
@@ -57,6 +131,50 @@ impl QueueClient {
         &self.client.queue_name
     }
 
+    /// Returns the URL of the queue with a service SAS appended, signed with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the SAS. Its queue name is set to this queue's.
+    /// * `credential` - The name and key of the storage account.
+    pub fn generate_sas_url(
+        &self,
+        sas: QueueSasBuilder,
+        credential: &SharedKeyCredential,
+    ) -> Result<Url> {
+        let sas = sas.with_queue_name(self.queue_name()).sign(credential)?;
+        let mut url = self.endpoint().clone();
+        url.append_path(self.queue_name());
+        Ok(append_sas(&url, &sas))
+    }
+
+    /// Returns a client that encrypts messages before sending them and decrypts them after receiving them, with the
+    /// keys of `options`.
+    ///
+    /// Requires the `client_side_encryption` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The keys with which to encrypt and decrypt messages.
+    #[cfg(feature = "client_side_encryption")]
+    pub fn with_client_side_encryption(
+        &self,
+        options: crate::encryption::ClientSideEncryptionOptions,
+    ) -> EncryptedQueueClient {
+        EncryptedQueueClient {
+            queue_client: QueueClient {
+                client: GeneratedQueueClient {
+                    endpoint: self.client.endpoint.clone(),
+                    pipeline: self.client.pipeline.clone(),
+                    queue_name: self.client.queue_name.clone(),
+                    version: self.client.version.clone(),
+                    tracer: self.client.tracer.clone(),
+                },
+            },
+            options,
+        }
+    }
+
     /// Creates a new queue under the given account.
     ///
     /// # Arguments
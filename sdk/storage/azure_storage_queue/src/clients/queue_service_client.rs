@@ -9,9 +9,13 @@ use crate::{
     },
 };
 use azure_core::{
-    credentials::TokenCredential,
-    http::{NoFormat, Pager, RequestContent, Response, Url, XmlFormat},
-    Result,
+    credentials::{Secret, TokenCredential},
+    http::{policies::Policy, NoFormat, Pager, Pipeline, RequestContent, Response, Url, XmlFormat},
+    tracing, Result,
+};
+use azure_storage_common::{
+    sas::{append_sas, AccountSasBuilder},
+    ConnectionString, SharedKeyAuthorizationPolicy, SharedKeyCredential,
 };
 use std::sync::Arc;
 
@@ -20,6 +24,78 @@ pub struct QueueServiceClient {
     pub(super) client: GeneratedQueueClient,
 }
 
+impl GeneratedQueueClient {
+    /// Creates a new GeneratedQueueServiceClient, using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.queue.core.windows.net/`
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Queues")]
+    pub fn with_shared_key(
+        endpoint: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<QueueServiceClientOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let endpoint = Url::parse(endpoint)?;
+        if !endpoint.scheme().starts_with("http") {
+            return Err(azure_core::Error::with_message(
+                azure_core::error::ErrorKind::Other,
+                format!("{endpoint} must use http(s)"),
+            ));
+        }
+        let auth_policy: Arc<dyn Policy> = Arc::new(SharedKeyAuthorizationPolicy::new(credential));
+        Ok(Self {
+            endpoint,
+            version: options.version,
+            pipeline: Pipeline::new(
+                option_env!("CARGO_PKG_NAME"),
+                option_env!("CARGO_PKG_VERSION"),
+                options.client_options,
+                Vec::default(),
+                vec![auth_policy],
+                None,
+            ),
+        })
+    }
+
+    /// Creates a new GeneratedQueueServiceClient that doesn't authorize requests itself, for
+    /// endpoints with a shared access signature or allowing anonymous access.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.queue.core.windows.net/?sv=...`
+    /// * `options` - Optional configuration for the client.
+    #[tracing::new("Storage.Queues")]
+    pub fn with_no_credential(
+        endpoint: &str,
+        options: Option<QueueServiceClientOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let endpoint = Url::parse(endpoint)?;
+        if !endpoint.scheme().starts_with("http") {
+            return Err(azure_core::Error::with_message(
+                azure_core::error::ErrorKind::Other,
+                format!("{endpoint} must use http(s)"),
+            ));
+        }
+        Ok(Self {
+            endpoint,
+            version: options.version,
+            pipeline: Pipeline::new(
+                option_env!("CARGO_PKG_NAME"),
+                option_env!("CARGO_PKG_VERSION"),
+                options.client_options,
+                Vec::default(),
+                Vec::default(),
+                None,
+            ),
+        })
+    }
+}
+
 impl QueueServiceClient {
     /// Creates a new QueueServiceClient using Entra ID authentication.
     ///
@@ -39,11 +115,78 @@ impl QueueServiceClient {
         Ok(Self { client })
     }
 
+    /// Creates a new QueueServiceClient using Shared Key authorization.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The full URL of the Azure storage account, for example `https://myaccount.queue.core.windows.net/`
+    /// * `credential` - The name and key of the storage account.
+    /// * `options` - Optional configuration for the client.
+    pub fn with_shared_key(
+        endpoint: &str,
+        credential: Arc<SharedKeyCredential>,
+        options: Option<QueueServiceClientOptions>,
+    ) -> Result<Self> {
+        let client = GeneratedQueueClient::with_shared_key(endpoint, credential, options)?;
+        Ok(Self { client })
+    }
+
+    /// Creates a new QueueServiceClient from a storage connection string, authorizing requests with
+    /// its account key or shared access signature, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_string` - The connection string, for example `DefaultEndpointsProtocol=https;AccountName=myaccount;AccountKey=...`,
+    ///   or `UseDevelopmentStorage=true` to connect to Azurite.
+    /// * `options` - Optional configuration for the client.
+    pub fn from_connection_string(
+        connection_string: Secret,
+        options: Option<QueueServiceClientOptions>,
+    ) -> Result<Self> {
+        let connection_string = ConnectionString::try_from(&connection_string)?;
+        let endpoint = connection_string.queue_endpoint()?.clone();
+
+        let client = match connection_string {
+            ConnectionString {
+                account_name: Some(account_name),
+                account_key: Some(account_key),
+                ..
+            } => GeneratedQueueClient::with_shared_key(
+                endpoint.as_str(),
+                SharedKeyCredential::new(account_name, account_key)?,
+                options,
+            )?,
+            ConnectionString {
+                shared_access_signature: Some(sas),
+                ..
+            } => GeneratedQueueClient::with_no_credential(
+                append_sas(&endpoint, sas.secret()).as_str(),
+                options,
+            )?,
+            _ => GeneratedQueueClient::with_no_credential(endpoint.as_str(), options)?,
+        };
+        Ok(Self { client })
+    }
+
     /// Returns the endpoint URL of the Azure storage account this client is associated with.
     pub fn endpoint(&self) -> &Url {
         self.client.endpoint()
     }
 
+    /// Returns the URL of the Queue service with an account SAS appended, signed with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `sas` - The settings of the account SAS.
+    /// * `credential` - The name and key of the storage account.
+    pub fn generate_account_sas_url(
+        &self,
+        sas: &AccountSasBuilder,
+        credential: &SharedKeyCredential,
+    ) -> Result<Url> {
+        Ok(append_sas(self.endpoint(), &sas.sign(credential)?))
+    }
+
     /// Returns a new instance of QueueClient.
     ///
     /// # Arguments
@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 #![doc = include_str!("../README.md")]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 
 #[allow(unused_imports)]
 mod generated;
@@ -22,4 +23,10 @@ pub mod models {
 /// queue metadata.
 pub mod clients;
 
+#[cfg(feature = "client_side_encryption")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client_side_encryption")))]
+pub use azure_storage_common::encryption;
+pub use azure_storage_common::{sas, SharedKeyCredential};
+#[cfg(feature = "client_side_encryption")]
+pub use clients::EncryptedQueueClient;
 pub use clients::{QueueClient, QueueClientOptions, QueueServiceClient, QueueServiceClientOptions};
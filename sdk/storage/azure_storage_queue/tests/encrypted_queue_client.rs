@@ -0,0 +1,93 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+#![cfg(feature = "client_side_encryption")]
+
+use azure_core::credentials::Secret;
+use azure_storage_queue::{
+    encryption::{ClientSideEncryptionOptions, LocalKeyEncryptionKey},
+    models::QueueMessage,
+    QueueClient, QueueClientOptions, SharedKeyCredential,
+};
+use azure_storage_test::FakeStorage;
+use std::{error::Error, sync::Arc};
+
+/// Returns a client of `queue` in `storage`.
+fn mock_queue_client(storage: &FakeStorage) -> Result<QueueClient, Box<dyn Error>> {
+    let options = QueueClientOptions {
+        client_options: storage.client_options(),
+        ..Default::default()
+    };
+    Ok(QueueClient::with_shared_key(
+        "https://account.queue.core.windows.net/",
+        "queue",
+        SharedKeyCredential::new("account", Secret::new("a2V5"))?,
+        Some(options),
+    )?)
+}
+
+fn encryption_options(key: [u8; 32]) -> ClientSideEncryptionOptions {
+    ClientSideEncryptionOptions {
+        key_encryption_key: Some(Arc::new(LocalKeyEncryptionKey::new("kek", key))),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_encrypted_send_and_receive() -> Result<(), Box<dyn Error>> {
+    let storage = FakeStorage::new();
+    let queue_client = mock_queue_client(&storage)?;
+    let client = queue_client.with_client_side_encryption(encryption_options([1; 32]));
+
+    client.send_message("secret <message> & more", None).await?;
+    {
+        let state = storage.state();
+        let messages = &state.queues["queue"];
+        assert!(messages[0].contains("EncryptedMessageContents"));
+        assert!(messages[0].contains("AES_GCM_256"));
+        assert!(!messages[0].contains("secret"));
+    }
+
+    let received = client.receive_messages(None).await?;
+    assert_eq!(
+        Some("secret <message> & more"),
+        received[0].message_text.as_deref()
+    );
+    let peeked = client.peek_messages(None).await?;
+    assert_eq!(
+        Some("secret <message> & more"),
+        peeked[0].message_text.as_deref()
+    );
+
+    // A different key can't decrypt the message.
+    let wrong_client = queue_client.with_client_side_encryption(encryption_options([2; 32]));
+    assert!(wrong_client.receive_messages(None).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypted_receive_of_unencrypted_message() -> Result<(), Box<dyn Error>> {
+    let queue_client = mock_queue_client(&FakeStorage::new())?;
+    queue_client
+        .send_message(
+            QueueMessage {
+                message_text: Some("plaintext".to_string()),
+            }
+            .try_into()?,
+            None,
+        )
+        .await?;
+
+    let client = queue_client.with_client_side_encryption(encryption_options([1; 32]));
+    let received = client.receive_messages(None).await?;
+    assert_eq!(Some("plaintext"), received[0].message_text.as_deref());
+
+    let client = queue_client.with_client_side_encryption(ClientSideEncryptionOptions {
+        require_encryption: Some(true),
+        ..encryption_options([1; 32])
+    });
+    assert!(client.receive_messages(None).await.is_err());
+
+    Ok(())
+}
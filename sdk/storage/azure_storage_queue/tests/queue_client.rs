@@ -2,14 +2,19 @@
 // Licensed under the MIT License.
 
 use azure_core::http::{ClientOptions, Response};
-use azure_core::Result;
+use azure_core::{
+    credentials::Secret,
+    time::{Duration, OffsetDateTime},
+    Result,
+};
 use azure_core_test::{recorded, Recording, TestContext};
 use azure_storage_queue::{
     models::{
         QueueClientPeekMessagesOptions, QueueClientReceiveMessagesOptions,
         QueueClientUpdateOptions, QueueMessage,
     },
-    QueueClient, QueueClientOptions,
+    sas::{QueueSasBuilder, QueueSasPermissions},
+    QueueClient, QueueClientOptions, SharedKeyCredential,
 };
 
 use std::collections::HashMap;
@@ -529,3 +534,31 @@ async fn peek_and_assert(
 
     Ok(())
 }
+
+/// Generates a SAS URL for a queue without sending a request.
+#[test]
+fn test_generate_sas_url() -> Result<()> {
+    let credential = SharedKeyCredential::new("account", Secret::new("a2V5"))?;
+    let queue_client = QueueClient::with_shared_key(
+        "https://account.queue.core.windows.net/",
+        "queue",
+        credential.clone(),
+        None,
+    )?;
+
+    let url = queue_client.generate_sas_url(
+        QueueSasBuilder::new()
+            .with_permissions(QueueSasPermissions {
+                process: true,
+                ..Default::default()
+            })
+            .with_expiry(OffsetDateTime::now_utc() + Duration::hours(1)),
+        &credential,
+    )?;
+    let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!("/queue", url.path());
+    assert_eq!("p", params["sp"]);
+    assert!(params.contains_key("sig"));
+
+    Ok(())
+}
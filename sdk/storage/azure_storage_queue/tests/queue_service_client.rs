@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft Corporation. All rights reserved.
 // Licensed under the MIT License.
 
+use azure_core::credentials::Secret;
 use azure_core::http::{ClientOptions, Response};
 use azure_core::time::OffsetDateTime;
 use azure_core::Result;
@@ -250,3 +251,35 @@ fn assert_successful_response<T, F>(response: &Response<T, F>) {
         response.status()
     );
 }
+
+/// Creates clients from connection strings without sending a request.
+#[test]
+fn test_from_connection_string() -> Result<()> {
+    let queue_service_client = QueueServiceClient::from_connection_string(
+        Secret::from("UseDevelopmentStorage=true"),
+        None,
+    )?;
+    assert_eq!(
+        "http://127.0.0.1:10001/devstoreaccount1",
+        queue_service_client.endpoint().as_str()
+    );
+
+    let queue_service_client = QueueServiceClient::from_connection_string(
+        Secret::from(
+            "QueueEndpoint=https://queue.contoso.com/;SharedAccessSignature=sv=2022-11-02&sig=abc",
+        ),
+        None,
+    )?;
+    assert_eq!(
+        "https://queue.contoso.com/?sv=2022-11-02&sig=abc",
+        queue_service_client.endpoint().as_str()
+    );
+
+    assert!(QueueServiceClient::from_connection_string(
+        Secret::from("BlobEndpoint=https://blob.contoso.com/"),
+        None,
+    )
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,807 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! An in-memory fake of the Azure Storage services, for tests of client behavior a recording can't exercise.
+
+use async_trait::async_trait;
+use azure_core::{
+    base64,
+    error::ErrorKind,
+    http::{
+        headers::{HeaderName, HeaderValue, Headers},
+        request::{Body, Request},
+        response::PinnedStream,
+        AsyncRawResponse, ClientOptions, HttpClient, Method, StatusCode, Transport, Url,
+    },
+    Bytes, Error, Result,
+};
+use azure_storage_common::{content_crc64, content_md5};
+use futures::stream;
+use percent_encoding::percent_decode_str;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The most blocks an append blob can hold.
+const MAX_APPEND_BLOCKS: u64 = 50_000;
+
+/// A request [`FakeStorage`] received.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    /// The request method.
+    pub method: Method,
+
+    /// The request URL.
+    pub url: Url,
+
+    /// The request headers.
+    pub headers: Headers,
+
+    /// The request body.
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    /// Returns the value of query parameter `name`, if set.
+    pub fn query(&self, name: &str) -> Option<String> {
+        self.url
+            .query_pairs()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Returns the value of header `name`, if set.
+    pub fn header(&self, name: &'static str) -> Option<&str> {
+        self.headers.get_optional_str(&HeaderName::from(name))
+    }
+
+    /// Returns the decoded path of the blob, container or queue the request addresses, for example `container/dir/blob`.
+    pub fn path(&self) -> String {
+        percent_decode_str(self.url.path().trim_matches('/'))
+            .decode_utf8_lossy()
+            .into_owned()
+    }
+
+    fn comp(&self) -> Option<String> {
+        self.query("comp")
+    }
+
+    /// Returns the first and, if set, last byte of the range in the `x-ms-range` or `Range` header.
+    fn range(&self) -> Option<(usize, Option<usize>)> {
+        let range = self
+            .header("x-ms-range")
+            .or_else(|| self.header("range"))?
+            .strip_prefix("bytes=")?;
+        let (start, end) = range.split_once('-')?;
+        Some((start.parse().ok()?, end.parse().ok()))
+    }
+}
+
+/// A response from [`FakeStorage`].
+#[derive(Clone, Debug)]
+pub struct FakeResponse {
+    /// The response status.
+    pub status: StatusCode,
+
+    /// The response headers.
+    pub headers: Headers,
+
+    /// The response body.
+    pub body: Bytes,
+
+    /// If set, the body is streamed in chunks of this many bytes.
+    pub chunk_size: Option<usize>,
+
+    /// If set, the body stream fails with an I/O error after this many bytes, as when the connection is reset.
+    pub fail_after: Option<usize>,
+}
+
+impl FakeResponse {
+    /// Creates an empty response with `status`.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: Headers::new(),
+            body: Bytes::new(),
+            chunk_size: None,
+            fail_after: None,
+        }
+    }
+
+    /// Creates an error response with `status` and the storage error code `code`.
+    pub fn error(status: StatusCode, code: &str) -> Self {
+        Self::new(status)
+            .with_header("x-ms-error-code", code.to_string())
+            .with_body(format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?><Error><Code>{code}</Code><Message>{code}</Message></Error>"
+            ))
+    }
+
+    /// Adds header `name` with `value`.
+    pub fn with_header(
+        mut self,
+        name: impl Into<HeaderName>,
+        value: impl Into<HeaderValue>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the body.
+    pub fn with_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    fn into_raw_response(self) -> AsyncRawResponse {
+        let mut body = self.body;
+        let mut failure = None;
+        if let Some(len) = self.fail_after.filter(|len| *len < body.len()) {
+            body.truncate(len);
+            failure = Some(Err(Error::with_message(ErrorKind::Io, "connection reset")));
+        }
+        let chunk_size = self.chunk_size.unwrap_or(body.len()).max(1);
+        let chunks: Vec<Result<Bytes>> = (0..body.len())
+            .step_by(chunk_size)
+            .map(|start| Ok(body.slice(start..(start + chunk_size).min(body.len()))))
+            .chain(failure)
+            .collect();
+        let stream: PinnedStream = Box::pin(stream::iter(chunks));
+        AsyncRawResponse::new(self.status, self.headers, stream)
+    }
+}
+
+/// A blob held by [`FakeStorage`].
+#[derive(Clone, Debug, Default)]
+pub struct FakeBlob {
+    /// The blob type: `BlockBlob`, `AppendBlob` or `PageBlob`.
+    pub blob_type: String,
+
+    /// The blob's content.
+    pub data: Vec<u8>,
+
+    /// The blob's metadata headers, such as `("x-ms-meta-name", "value")`.
+    pub metadata: Vec<(String, String)>,
+
+    /// The blob's ETag, which changes whenever the blob is written.
+    pub etag: String,
+
+    /// The IDs and content of a block blob's committed blocks.
+    pub blocks: Vec<(String, Bytes)>,
+
+    /// The number of blocks appended to an append blob.
+    pub committed_block_count: u64,
+
+    /// Whether an append blob is sealed.
+    pub sealed: bool,
+}
+
+/// The blobs and queues of a [`FakeStorage`], and the requests it received.
+#[derive(Debug, Default)]
+pub struct FakeState {
+    /// The blobs, by their path, such as `container/dir/blob`.
+    pub blobs: BTreeMap<String, FakeBlob>,
+
+    /// The XML-escaped text of the messages in each queue, by queue name.
+    pub queues: BTreeMap<String, Vec<String>>,
+
+    /// The requests received, in order.
+    pub requests: Vec<RecordedRequest>,
+
+    /// The most blobs and virtual directories in a page of a listing whose request doesn't set `maxresults`.
+    pub page_size: Option<usize>,
+
+    staged_blocks: HashMap<String, HashMap<String, Bytes>>,
+    last_etag: u64,
+}
+
+impl FakeState {
+    /// Adds or replaces the block blob at `path`, such as `container/dir/blob`, with a single block holding `data`.
+    pub fn insert_blob(&mut self, path: &str, data: impl Into<Vec<u8>>) -> &mut FakeBlob {
+        let data = data.into();
+        let etag = self.next_etag();
+        self.blobs.insert(
+            path.to_string(),
+            FakeBlob {
+                blob_type: "BlockBlob".to_string(),
+                blocks: vec![(String::new(), Bytes::from(data.clone()))],
+                data,
+                etag,
+                ..Default::default()
+            },
+        );
+        self.blobs.get_mut(path).unwrap()
+    }
+
+    /// Answers `request` as the Blob or Queue service would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fake doesn't support the operation `request` invokes.
+    pub fn serve(&mut self, request: &RecordedRequest) -> FakeResponse {
+        if request
+            .url
+            .host_str()
+            .is_some_and(|host| host.contains(".queue."))
+        {
+            return self.serve_queue(request);
+        }
+        let path = request.path();
+        match (request.method, request.comp().as_deref()) {
+            (Method::Put, None) if request.header("x-ms-copy-source").is_none() => {
+                self.put_blob(&path, request)
+            }
+            (Method::Put, Some("block")) => self.put_block(&path, request),
+            (Method::Put, Some("blocklist")) => self.put_block_list(&path, request),
+            (Method::Put, Some("appendblock")) => self.append_block(&path, request),
+            (Method::Put, Some("page")) => self.put_page(&path, request),
+            (Method::Put, Some("seal")) => match self.blobs.get_mut(&path) {
+                Some(blob) => {
+                    blob.sealed = true;
+                    FakeResponse::new(StatusCode::Ok)
+                }
+                None => FakeResponse::error(StatusCode::NotFound, "BlobNotFound"),
+            },
+            (Method::Head, None) => self.get_properties(&path, request),
+            (Method::Get, None) => self.get_blob(&path, request),
+            (Method::Delete, None) => match self.blobs.remove(&path) {
+                Some(_) => FakeResponse::new(StatusCode::Accepted),
+                None => FakeResponse::error(StatusCode::NotFound, "BlobNotFound"),
+            },
+            (Method::Get, Some("list"))
+                if request.query("restype").as_deref() == Some("container") =>
+            {
+                self.list_blobs(&path, request)
+            }
+            _ => panic!(
+                "FakeStorage doesn't support {} {}",
+                request.method, request.url
+            ),
+        }
+    }
+
+    fn next_etag(&mut self) -> String {
+        self.last_etag += 1;
+        format!("\"0x{:X}\"", self.last_etag)
+    }
+
+    /// Returns an error response if `request`'s conditions aren't met by the blob at `path`.
+    fn check_conditions(&self, path: &str, request: &RecordedRequest) -> Option<FakeResponse> {
+        let blob = self.blobs.get(path);
+        if request.header("if-none-match") == Some("*") && blob.is_some() {
+            return Some(FakeResponse::error(
+                StatusCode::Conflict,
+                "BlobAlreadyExists",
+            ));
+        }
+        if let Some(etag) = request.header("if-match") {
+            if blob.is_none_or(|blob| blob.etag != etag) {
+                return Some(FakeResponse::error(
+                    StatusCode::PreconditionFailed,
+                    "ConditionNotMet",
+                ));
+            }
+        }
+        None
+    }
+
+    /// Validates the transactional checksums of `request`'s body, returning the headers echoing them or an error
+    /// response.
+    fn check_content(request: &RecordedRequest) -> std::result::Result<Headers, FakeResponse> {
+        let mut headers = Headers::new();
+        if let Some(md5) = request.header("content-md5") {
+            if md5 != base64::encode(content_md5(&request.body)) {
+                return Err(FakeResponse::error(StatusCode::BadRequest, "Md5Mismatch"));
+            }
+            headers.insert("content-md5", md5.to_string());
+        }
+        if let Some(crc64) = request.header("x-ms-content-crc64") {
+            if crc64 != base64::encode(content_crc64(&request.body)) {
+                return Err(FakeResponse::error(StatusCode::BadRequest, "Crc64Mismatch"));
+            }
+            headers.insert("x-ms-content-crc64", crc64.to_string());
+        }
+        Ok(headers)
+    }
+
+    /// Returns a response with `status`, `headers` and the ETag of the blob at `path`.
+    fn written(&self, path: &str, status: StatusCode, headers: Headers) -> FakeResponse {
+        let mut response = FakeResponse::new(status);
+        response.headers = headers;
+        response.with_header("etag", self.blobs[path].etag.clone())
+    }
+
+    fn put_blob(&mut self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        let headers = match Self::check_content(request) {
+            Ok(headers) => headers,
+            Err(response) => return response,
+        };
+        let blob_type = request.header("x-ms-blob-type").unwrap_or("BlockBlob");
+        let data = match blob_type {
+            "PageBlob" => {
+                let len: usize = request
+                    .header("x-ms-blob-content-length")
+                    .and_then(|len| len.parse().ok())
+                    .unwrap_or_default();
+                vec![0; len]
+            }
+            _ => request.body.to_vec(),
+        };
+        let blob = FakeBlob {
+            blob_type: blob_type.to_string(),
+            blocks: match blob_type {
+                "BlockBlob" => vec![(String::new(), request.body.clone())],
+                _ => Vec::new(),
+            },
+            data,
+            metadata: metadata(&request.headers),
+            etag: self.next_etag(),
+            ..Default::default()
+        };
+        self.blobs.insert(path.to_string(), blob);
+        self.staged_blocks.remove(path);
+        self.written(path, StatusCode::Created, headers)
+    }
+
+    fn put_block(&mut self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        let headers = match Self::check_content(request) {
+            Ok(headers) => headers,
+            Err(response) => return response,
+        };
+        let Some(block_id) = request.query("blockid") else {
+            return FakeResponse::error(StatusCode::BadRequest, "InvalidQueryParameterValue");
+        };
+        self.staged_blocks
+            .entry(path.to_string())
+            .or_default()
+            .insert(block_id, request.body.clone());
+        let mut response = FakeResponse::new(StatusCode::Created);
+        response.headers = headers;
+        response
+    }
+
+    fn put_block_list(&mut self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        let body = String::from_utf8_lossy(&request.body);
+        let staged = self.staged_blocks.get(path);
+        let committed = self.blobs.get(path).map(|blob| blob.blocks.as_slice());
+        let find_committed = |id: &str| {
+            committed
+                .unwrap_or_default()
+                .iter()
+                .find(|(committed_id, _)| committed_id == id)
+                .map(|(_, block)| block.clone())
+        };
+        let mut blocks = Vec::new();
+        for (list, id) in block_list(&body) {
+            let block = match list {
+                "Committed" => find_committed(id),
+                "Uncommitted" => staged.and_then(|staged| staged.get(id).cloned()),
+                _ => staged
+                    .and_then(|staged| staged.get(id).cloned())
+                    .or_else(|| find_committed(id)),
+            };
+            let Some(block) = block else {
+                return FakeResponse::error(StatusCode::BadRequest, "InvalidBlockList");
+            };
+            blocks.push((id.to_string(), block));
+        }
+        let blob = FakeBlob {
+            blob_type: "BlockBlob".to_string(),
+            data: blocks
+                .iter()
+                .flat_map(|(_, block)| block.to_vec())
+                .collect(),
+            metadata: metadata(&request.headers),
+            etag: self.next_etag(),
+            blocks,
+            ..Default::default()
+        };
+        self.blobs.insert(path.to_string(), blob);
+        self.staged_blocks.remove(path);
+        self.written(path, StatusCode::Created, Headers::new())
+    }
+
+    fn append_block(&mut self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        let mut headers = match Self::check_content(request) {
+            Ok(headers) => headers,
+            Err(response) => return response,
+        };
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        let etag = self.next_etag();
+        let Some(blob) = self
+            .blobs
+            .get_mut(path)
+            .filter(|blob| blob.blob_type == "AppendBlob")
+        else {
+            return FakeResponse::error(StatusCode::NotFound, "BlobNotFound");
+        };
+        if blob.sealed {
+            return FakeResponse::error(StatusCode::Conflict, "BlobIsSealed");
+        }
+        if blob.committed_block_count >= MAX_APPEND_BLOCKS {
+            return FakeResponse::error(StatusCode::Conflict, "BlockCountExceedsLimit");
+        }
+        let position = request.header("x-ms-blob-condition-appendpos");
+        if position.is_some_and(|position| position != blob.data.len().to_string()) {
+            return FakeResponse::error(
+                StatusCode::PreconditionFailed,
+                "AppendPositionConditionNotMet",
+            );
+        }
+        headers.insert("x-ms-blob-append-offset", blob.data.len().to_string());
+        blob.data.extend_from_slice(&request.body);
+        blob.committed_block_count += 1;
+        blob.etag = etag;
+        headers.insert(
+            "x-ms-blob-committed-block-count",
+            blob.committed_block_count.to_string(),
+        );
+        self.written(path, StatusCode::Created, headers)
+    }
+
+    fn put_page(&mut self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        let headers = match Self::check_content(request) {
+            Ok(headers) => headers,
+            Err(response) => return response,
+        };
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        let etag = self.next_etag();
+        let Some(blob) = self
+            .blobs
+            .get_mut(path)
+            .filter(|blob| blob.blob_type == "PageBlob")
+        else {
+            return FakeResponse::error(StatusCode::NotFound, "BlobNotFound");
+        };
+        let Some((start, Some(end))) = request.range() else {
+            return FakeResponse::error(StatusCode::BadRequest, "InvalidHeaderValue");
+        };
+        if end >= blob.data.len() {
+            return FakeResponse::error(
+                StatusCode::RequestedRangeNotSatisfiable,
+                "InvalidPageRange",
+            );
+        }
+        let pages = &mut blob.data[start..=end];
+        if request.header("x-ms-page-write") == Some("clear") {
+            pages.fill(0);
+        } else if request.body.len() == pages.len() {
+            pages.copy_from_slice(&request.body);
+        } else {
+            return FakeResponse::error(StatusCode::BadRequest, "InvalidHeaderValue");
+        }
+        blob.etag = etag;
+        self.written(path, StatusCode::Created, headers)
+    }
+
+    /// Returns a response with the properties and metadata of `blob`.
+    fn properties(blob: &FakeBlob, status: StatusCode) -> FakeResponse {
+        let mut response = FakeResponse::new(status)
+            .with_header("etag", blob.etag.clone())
+            .with_header("x-ms-blob-type", blob.blob_type.clone());
+        for (name, value) in &blob.metadata {
+            response = response.with_header(name.clone(), value.clone());
+        }
+        if blob.blob_type == "AppendBlob" {
+            response = response
+                .with_header(
+                    "x-ms-blob-committed-block-count",
+                    blob.committed_block_count.to_string(),
+                )
+                .with_header("x-ms-blob-sealed", blob.sealed.to_string());
+        }
+        response
+    }
+
+    fn get_properties(&self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        match self.blobs.get(path) {
+            Some(blob) => Self::properties(blob, StatusCode::Ok)
+                .with_header("content-length", blob.data.len().to_string()),
+            None => {
+                FakeResponse::error(StatusCode::NotFound, "BlobNotFound").with_body(Bytes::new())
+            }
+        }
+    }
+
+    fn get_blob(&self, path: &str, request: &RecordedRequest) -> FakeResponse {
+        if let Some(response) = self.check_conditions(path, request) {
+            return response;
+        }
+        let Some(blob) = self.blobs.get(path) else {
+            return FakeResponse::error(StatusCode::NotFound, "BlobNotFound");
+        };
+        let Some((start, end)) = request.range() else {
+            return Self::properties(blob, StatusCode::Ok)
+                .with_header("content-length", blob.data.len().to_string())
+                .with_body(blob.data.clone());
+        };
+        if start >= blob.data.len() {
+            return FakeResponse::error(StatusCode::RequestedRangeNotSatisfiable, "InvalidRange");
+        }
+        let end = end.map_or(blob.data.len(), |end| (end + 1).min(blob.data.len()));
+        let body = Bytes::copy_from_slice(&blob.data[start..end]);
+        let mut response = Self::properties(blob, StatusCode::PartialContent)
+            .with_header("content-length", body.len().to_string())
+            .with_header(
+                "content-range",
+                format!("bytes {}-{}/{}", start, end - 1, blob.data.len()),
+            );
+        if request.header("x-ms-range-get-content-md5").is_some() {
+            response = response.with_header("content-md5", base64::encode(content_md5(&body)));
+        }
+        if request.header("x-ms-range-get-content-crc64").is_some() {
+            response =
+                response.with_header("x-ms-content-crc64", base64::encode(content_crc64(&body)));
+        }
+        response.with_body(body)
+    }
+
+    fn list_blobs(&self, container: &str, request: &RecordedRequest) -> FakeResponse {
+        let prefix = request.query("prefix").unwrap_or_default();
+        let delimiter = request.query("delimiter").filter(|d| !d.is_empty());
+        let marker: usize = request
+            .query("marker")
+            .and_then(|marker| marker.parse().ok())
+            .unwrap_or_default();
+        let page_size = request
+            .query("maxresults")
+            .and_then(|max| max.parse().ok())
+            .or(self.page_size)
+            .unwrap_or(5000);
+
+        let mut entries: Vec<String> = Vec::new();
+        for (path, blob) in &self.blobs {
+            let Some(name) = path
+                .strip_prefix(container)
+                .and_then(|name| name.strip_prefix('/'))
+                .filter(|name| name.starts_with(&prefix))
+            else {
+                continue;
+            };
+            let virtual_directory = delimiter.as_ref().and_then(|delimiter| {
+                name[prefix.len()..]
+                    .find(delimiter.as_str())
+                    .map(|i| &name[..prefix.len() + i + delimiter.len()])
+            });
+            let entry = match virtual_directory {
+                Some(virtual_directory) => format!(
+                    "<BlobPrefix><Name>{}</Name></BlobPrefix>",
+                    xml_escape(virtual_directory)
+                ),
+                None => format!(
+                    "<Blob><Name>{}</Name><Properties><Content-Length>{}</Content-Length><BlobType>{}</BlobType><Etag>{}</Etag></Properties></Blob>",
+                    xml_escape(name),
+                    blob.data.len(),
+                    blob.blob_type,
+                    blob.etag
+                ),
+            };
+            if entries.last() != Some(&entry) {
+                entries.push(entry);
+            }
+        }
+        let start = marker.min(entries.len());
+        let end = (start + page_size).min(entries.len());
+        let next_marker = if end < entries.len() {
+            format!("<NextMarker>{end}</NextMarker>")
+        } else {
+            "<NextMarker />".to_string()
+        };
+        let delimiter = delimiter
+            .map(|delimiter| format!("<Delimiter>{}</Delimiter>", xml_escape(&delimiter)))
+            .unwrap_or_default();
+        FakeResponse::new(StatusCode::Ok)
+            .with_header("content-type", "application/xml")
+            .with_body(format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?><EnumerationResults ServiceEndpoint=\"https://{}/\" ContainerName=\"{}\"><Prefix>{}</Prefix>{delimiter}<Blobs>{}</Blobs>{next_marker}</EnumerationResults>",
+                request.url.host_str().unwrap_or_default(),
+                xml_escape(container),
+                xml_escape(&prefix),
+                entries[start..end].concat()
+            ))
+    }
+
+    /// Answers a Queue service request. Received messages stay visible, so every receive returns them again.
+    fn serve_queue(&mut self, request: &RecordedRequest) -> FakeResponse {
+        let path = request.path();
+        let Some(queue) = path.strip_suffix("/messages") else {
+            panic!(
+                "FakeStorage doesn't support {} {}",
+                request.method, request.url
+            );
+        };
+        let messages = self.queues.entry(queue.to_string()).or_default();
+        let body = match request.method {
+            Method::Post => {
+                let body = String::from_utf8_lossy(&request.body);
+                let text = body
+                    .split_once("<MessageText>")
+                    .and_then(|(_, rest)| rest.split_once("</MessageText>"))
+                    .map(|(text, _)| text.to_string())
+                    .unwrap_or_default();
+                messages.push(text);
+                format!(
+                    "<QueueMessagesList><QueueMessage><MessageId>{}</MessageId><PopReceipt>receipt</PopReceipt></QueueMessage></QueueMessagesList>",
+                    messages.len() - 1
+                )
+            }
+            Method::Get => {
+                let count = request
+                    .query("numofmessages")
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(1);
+                let items: String = messages
+                    .iter()
+                    .take(count)
+                    .enumerate()
+                    .map(|(i, text)| {
+                        format!(
+                            "<QueueMessage><MessageId>{i}</MessageId><PopReceipt>receipt</PopReceipt><DequeueCount>1</DequeueCount><MessageText>{text}</MessageText></QueueMessage>"
+                        )
+                    })
+                    .collect();
+                format!("<QueueMessagesList>{items}</QueueMessagesList>")
+            }
+            _ => panic!(
+                "FakeStorage doesn't support {} {}",
+                request.method, request.url
+            ),
+        };
+        let status = match request.method {
+            Method::Post => StatusCode::Created,
+            _ => StatusCode::Ok,
+        };
+        FakeResponse::new(status)
+            .with_header("content-type", "application/xml")
+            .with_body(body)
+    }
+}
+
+type Handler = dyn Fn(&RecordedRequest, &mut FakeState) -> Option<FakeResponse> + Send + Sync;
+
+/// An in-memory fake of the Blob and Queue services, for tests of client behavior a recording can't exercise, such as
+/// concurrent requests and corrupted or interrupted responses.
+///
+/// The fake supports creating, uploading, downloading and deleting blobs, staging and committing blocks, appending
+/// blocks, writing pages, listing blobs, and sending and receiving queue messages. It validates transactional
+/// checksums and `If-Match`/`If-None-Match` conditions. A handler set with [`FakeStorage::with_handler()`] can answer
+/// other requests, or inject faults.
+#[derive(Clone, Default)]
+pub struct FakeStorage {
+    state: Arc<Mutex<FakeState>>,
+    handler: Option<Arc<Handler>>,
+}
+
+impl FakeStorage {
+    /// Creates an empty fake.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty fake whose `handler` answers each request before the fake services, which answer the requests
+    /// for which it returns `None`. The handler can call [`FakeState::serve()`] to get the response it replaces.
+    pub fn with_handler(
+        handler: impl Fn(&RecordedRequest, &mut FakeState) -> Option<FakeResponse>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            handler: Some(Arc::new(handler)),
+            ..Default::default()
+        }
+    }
+
+    /// Locks and returns the fake's blobs, queues and received requests.
+    pub fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Returns the requests received, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state().requests.clone()
+    }
+
+    /// Returns the blob at `path`, such as `container/dir/blob`.
+    pub fn blob(&self, path: &str) -> Option<FakeBlob> {
+        self.state().blobs.get(path).cloned()
+    }
+
+    /// Returns client options whose transport sends requests to this fake.
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            transport: Some(Transport::new(Arc::new(self.clone()))),
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Debug for FakeStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FakeStorage")
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl HttpClient for FakeStorage {
+    async fn execute_request(&self, request: &Request) -> Result<AsyncRawResponse> {
+        let body = match request.body() {
+            Body::Bytes(bytes) => bytes.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Body::SeekableStream(stream) => {
+                use futures::AsyncReadExt as _;
+                let mut stream = stream.clone();
+                stream.reset().await?;
+                let mut body = Vec::new();
+                stream.read_to_end(&mut body).await?;
+                body.into()
+            }
+        };
+        let request = RecordedRequest {
+            method: request.method(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body,
+        };
+        let mut state = self.state();
+        state.requests.push(request.clone());
+        let response = self
+            .handler
+            .as_ref()
+            .and_then(|handler| handler(&request, &mut state))
+            .unwrap_or_else(|| state.serve(&request));
+        Ok(response.into_raw_response())
+    }
+}
+
+/// Returns the metadata headers in `headers`.
+fn metadata(headers: &Headers) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-ms-meta-"))
+        .map(|(name, value)| (name.as_str().to_string(), value.as_str().to_string()))
+        .collect()
+}
+
+/// Returns the list and ID of each block in a `Put Block List` request body.
+fn block_list(body: &str) -> Vec<(&str, &str)> {
+    let mut blocks = Vec::new();
+    let mut rest = body;
+    while let Some((_, tag_start)) = rest.split_once('<') {
+        let Some((tag, content)) = tag_start.split_once('>') else {
+            break;
+        };
+        rest = content;
+        if let list @ ("Latest" | "Committed" | "Uncommitted") = tag {
+            if let Some((id, after)) = content.split_once(&format!("</{list}>")) {
+                blocks.push((list, id));
+                rest = after;
+            }
+        }
+    }
+    blocks
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
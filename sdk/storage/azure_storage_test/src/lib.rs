@@ -0,0 +1,8 @@
+// Copyright (c) Microsoft Corporation. All rights reserved.
+// Licensed under the MIT License.
+
+//! Common utilities for Azure Storage tests.
+
+mod fake_storage;
+
+pub use fake_storage::{FakeBlob, FakeResponse, FakeState, FakeStorage, RecordedRequest};